@@ -29,6 +29,8 @@ macro_rules! move_aborts {
             $(#[$meta])*
             $Error = $num,
         )*}
+
+        impl std::error::Error for MoveAbort {}
     };
 }
 
@@ -192,4 +194,37 @@ mod tests {
         assert_eq!(Ok(MoveAbort::MaxPendingOrdersExceeded), 2000_u64.try_into());
         assert_eq!(Ok(MoveAbort::SelfTrading), 3008_u64.try_into());
     }
+
+    #[test]
+    fn diagnose_abort() {
+        use af_sui_types::execution_failure::{AbortRegistry, ExecutionFailure};
+        use af_sui_types::{ExecutionError, ObjectId};
+        use sui_sdk_types::MoveLocation;
+
+        let package = ObjectId::new([1; 32]);
+        let mut registry = AbortRegistry::new();
+        registry.register::<MoveAbort>(package);
+        let location = MoveLocation {
+            package,
+            module: "orderbook".parse().unwrap(),
+            function: 5,
+            instruction: 21,
+            function_name: Some("place_limit_order".parse().unwrap()),
+        };
+        let failure = ExecutionFailure::new(
+            ExecutionError::MoveAbort {
+                location,
+                code: 3008,
+            },
+            Some(2),
+        );
+        let diagnosis = registry.diagnose(failure);
+        assert_eq!(diagnosis.abort_as(), Some(&MoveAbort::SelfTrading));
+        assert_eq!(
+            diagnosis.to_string(),
+            format!(
+                "SelfTrading (abort code 3008 in {package}::orderbook::place_limit_order) in command 2"
+            )
+        );
+    }
 }
@@ -0,0 +1,79 @@
+//! Serve the keys of a Sui keystore to `RemoteSigner`s, only signing what a policy allows.
+//!
+//! Clients aren't authenticated: prefer `--socket`, whose file permissions decide who may connect.
+//!
+//! Example policy:
+//! ```json
+//! {
+//!   "allowed_calls": ["0x2::coin", "0x3::sui_system::request_add_stake"],
+//!   "max_gas_budget": 100000000,
+//!   "spend_limit": { "amount": 1000000000, "period_secs": 86400 }
+//! }
+//! ```
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use af_keys::daemon::{serve_tcp, serve_unix};
+use af_keys::policy::{Policy, PolicySigner};
+use af_keys::{FileBasedKeystore, ReadOnlyAccountKeystore as _};
+use clap::Parser;
+use color_eyre::Result;
+use color_eyre::eyre::{Context as _, bail};
+
+#[derive(Parser)]
+struct Args {
+    /// Keystore file, e.g., `~/.sui/sui_config/sui.keystore`.
+    #[arg(long)]
+    keystore: PathBuf,
+
+    /// JSON policy file; if unset, everything but publishing packages is signed.
+    #[arg(long)]
+    policy: Option<PathBuf>,
+
+    /// Unix socket to listen on.
+    #[arg(long, conflicts_with = "tcp")]
+    socket: Option<PathBuf>,
+
+    /// Loopback TCP address to listen on, e.g., `127.0.0.1:9000`.
+    ///
+    /// Anyone who can connect gets signatures, so other addresses are refused.
+    #[arg(long)]
+    tcp: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    color_eyre::install()?;
+
+    let Args {
+        keystore,
+        policy,
+        socket,
+        tcp,
+    } = Args::parse();
+    let keystore = FileBasedKeystore::new(keystore)?;
+    let policy: Policy = match policy {
+        Some(path) => serde_json::from_reader(std::fs::File::open(&path)?)
+            .wrap_err_with(|| format!("Reading policy {}", path.display()))?,
+        None => Policy::default(),
+    };
+    for address in keystore.addresses() {
+        println!("Signing for {address}");
+    }
+    let signer = Arc::new(PolicySigner::new(keystore, policy));
+
+    match (socket, tcp) {
+        (Some(path), None) => {
+            let listener = tokio::net::UnixListener::bind(&path)?;
+            println!("Listening on {}", path.display());
+            serve_unix(signer, listener).await?;
+        }
+        (None, Some(addr)) => {
+            let listener = tokio::net::TcpListener::bind(&addr).await?;
+            println!("Listening on {}", listener.local_addr()?);
+            serve_tcp(signer, listener).await?;
+        }
+        _ => bail!("Pass either --socket or --tcp"),
+    }
+    Ok(())
+}
@@ -0,0 +1,229 @@
+//! Local signing daemon, serving any [`TransactionSigner`] to `RemoteSigner`s (feature `remote`).
+//!
+//! Wrap the signer in a [`PolicySigner`](crate::policy::PolicySigner) to restrict what the
+//! daemon signs. The `signing_daemon` example serves a keystore this way.
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use af_sui_types::TransactionData;
+use bytes::Bytes;
+use http::{Method, Request, Response, StatusCode};
+use http_body_util::{BodyExt as _, Full, Limited};
+use hyper::body::Incoming;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, UnixListener};
+
+use crate::signer::{ErrorResponse, SIGN_PATH, SignRequest, SignResponse, TransactionSigner};
+
+/// Largest request body accepted, in bytes.
+pub const MAX_REQUEST_SIZE: usize = 1 << 20;
+
+/// Serve `signer` on TCP connections accepted by `listener`, until accepting fails.
+///
+/// The daemon doesn't authenticate its clients: it signs whatever its signer allows for anyone
+/// who can connect. A Unix socket ([`serve_unix`]) limits that to the users with access to the
+/// socket file, while TCP gives no such control. So `listener` must be bound to a loopback
+/// address, leaving the daemon to local processes, and this fails with
+/// [`InvalidInput`](std::io::ErrorKind::InvalidInput) otherwise. Expose it to other machines
+/// only through something that authenticates them, e.g., an SSH tunnel.
+pub async fn serve_tcp<S>(signer: Arc<S>, listener: TcpListener) -> std::io::Result<()>
+where
+    S: TransactionSigner + Send + Sync + 'static,
+{
+    let local_addr = listener.local_addr()?;
+    if !local_addr.ip().is_loopback() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Not serving unauthenticated signatures on non-loopback address {local_addr}"),
+        ));
+    }
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(serve_connection(signer.clone(), stream));
+    }
+}
+
+/// Serve `signer` on Unix socket connections accepted by `listener`, until accepting fails.
+///
+/// Access to the daemon is controlled by the permissions of the socket file.
+pub async fn serve_unix<S>(signer: Arc<S>, listener: UnixListener) -> std::io::Result<()>
+where
+    S: TransactionSigner + Send + Sync + 'static,
+{
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(serve_connection(signer.clone(), stream));
+    }
+}
+
+async fn serve_connection<S, IO>(signer: Arc<S>, stream: IO)
+where
+    S: TransactionSigner + Send + Sync + 'static,
+    IO: AsyncRead + AsyncWrite + Unpin,
+{
+    let service = service_fn(|request| {
+        let signer = signer.clone();
+        async move { Ok::<_, Infallible>(handle(&*signer, request).await) }
+    });
+    // Errors here are the client's problem, e.g., a dropped connection
+    let _ = hyper::server::conn::http1::Builder::new()
+        .serve_connection(TokioIo::new(stream), service)
+        .await;
+}
+
+async fn handle<S: TransactionSigner + Sync>(
+    signer: &S,
+    request: Request<Incoming>,
+) -> Response<Full<Bytes>> {
+    if request.method() != Method::POST || request.uri().path() != SIGN_PATH {
+        return error(StatusCode::NOT_FOUND, "Not found".into());
+    }
+    let body = match Limited::new(request.into_body(), MAX_REQUEST_SIZE)
+        .collect()
+        .await
+    {
+        Ok(body) => body.to_bytes(),
+        Err(e) => return error(StatusCode::BAD_REQUEST, format!("Reading body: {e}")),
+    };
+    let SignRequest { address, tx_bytes } = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => return error(StatusCode::BAD_REQUEST, format!("Invalid request: {e}")),
+    };
+    let tx_data: TransactionData = match bcs::from_bytes(&tx_bytes) {
+        Ok(tx_data) => tx_data,
+        Err(e) => return error(StatusCode::BAD_REQUEST, format!("Invalid transaction: {e}")),
+    };
+    match signer.sign_transaction(address, &tx_data).await {
+        Ok(signature) => json(StatusCode::OK, &SignResponse::from(&signature)),
+        Err(e) => error(StatusCode::FORBIDDEN, e.to_string()),
+    }
+}
+
+fn error(status: StatusCode, error: String) -> Response<Full<Bytes>> {
+    json(status, &ErrorResponse { error })
+}
+
+fn json(status: StatusCode, body: &impl serde::Serialize) -> Response<Full<Bytes>> {
+    let body = serde_json::to_vec(body).expect("Serializable to JSON");
+    let mut response = Response::new(Full::new(body.into()));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        http::header::CONTENT_TYPE,
+        http::HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+#[cfg(all(test, feature = "remote"))]
+mod tests {
+    use af_sui_types::{Address as SuiAddress, UserSignature};
+
+    use super::*;
+    use crate::crypto::SignatureScheme;
+    use crate::keystore::{AccountKeystore as _, InMemKeystore, ReadOnlyAccountKeystore as _};
+    use crate::multisig::{MultiSigPublicKey, MultiSigSigner};
+    use crate::policy::{Policy, PolicySigner};
+    use crate::remote::{RemoteSigner, RemoteSignerError};
+    use crate::test_utils::{PHRASE, split_gas};
+
+    async fn check(remote: &RemoteSigner, address: SuiAddress) -> eyre::Result<()> {
+        let tx_data = split_gas(address, address, 1_000);
+        let signature = remote.sign_transaction(address, &tx_data).await?;
+        let expected = keystore()?.sign_transaction(address, &tx_data).await?;
+        assert_eq!(signature, expected);
+
+        let result = remote
+            .sign_transaction(address, &split_gas(address, address, 10_000_000))
+            .await;
+        assert!(matches!(
+            result,
+            Err(RemoteSignerError::Rejected { status, message })
+                if status == StatusCode::FORBIDDEN && message.contains("spends")
+        ));
+        Ok(())
+    }
+
+    fn keystore() -> eyre::Result<InMemKeystore> {
+        let mut keystore = InMemKeystore::default();
+        keystore.import_from_mnemonic(PHRASE, SignatureScheme::ED25519, None, None)?;
+        Ok(keystore)
+    }
+
+    /// Signs as a 1-of-1 multisig of its key.
+    struct MultiSigKeystore(InMemKeystore, MultiSigPublicKey);
+
+    impl TransactionSigner for MultiSigKeystore {
+        type Error = signature::Error;
+
+        async fn sign_transaction(
+            &self,
+            _: SuiAddress,
+            tx_data: &TransactionData,
+        ) -> Result<UserSignature, Self::Error> {
+            let signer = MultiSigSigner {
+                multisig_pk: self.1.clone(),
+                signers: vec![0],
+            };
+            let signatures = crate::signatures(tx_data, Some(signer), None, &self.0)
+                .map_err(|e| signature::Error::from_source(e.to_string()))?;
+            Ok(signatures[0].clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn remote_rejects_multisig() -> eyre::Result<()> {
+        let keystore = keystore()?;
+        let multisig_pk = MultiSigPublicKey::new(keystore.keys(), vec![1], 1)?;
+        let address = SuiAddress::from(&multisig_pk);
+        let tx_data = split_gas(address, address, 1_000);
+        let signer = MultiSigKeystore(keystore, multisig_pk);
+        assert!(matches!(
+            signer.sign_transaction(address, &tx_data).await?,
+            UserSignature::Multisig(_)
+        ));
+
+        let dir = tempfile::tempdir()?;
+        let socket = dir.path().join("signer.sock");
+        tokio::spawn(serve_unix(Arc::new(signer), UnixListener::bind(&socket)?));
+        let result = RemoteSigner::unix(&socket)
+            .sign_transaction(address, &tx_data)
+            .await;
+        assert!(matches!(
+            result,
+            Err(RemoteSignerError::InvalidSignature(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn remote_signing() -> eyre::Result<()> {
+        let keystore = keystore()?;
+        let address = keystore.addresses()[0];
+        let policy = Policy {
+            max_spend_per_transaction: Some(5_000_000),
+            ..Default::default()
+        };
+        let signer = Arc::new(PolicySigner::new(keystore, policy));
+
+        let dir = tempfile::tempdir()?;
+        let socket = dir.path().join("signer.sock");
+        tokio::spawn(serve_unix(signer.clone(), UnixListener::bind(&socket)?));
+        check(&RemoteSigner::unix(&socket), address).await?;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        tokio::spawn(serve_tcp(signer, listener));
+        let remote = RemoteSigner::http(&url)?;
+        check(&remote, address).await?;
+
+        let bad_path = reqwest::Client::new().get(&url).send().await?;
+        assert_eq!(bad_path.status(), StatusCode::NOT_FOUND);
+
+        let public = TcpListener::bind("0.0.0.0:0").await?;
+        let result = serve_tcp(Arc::new(self::keystore()?), public).await;
+        assert!(matches!(result, Err(e) if e.kind() == std::io::ErrorKind::InvalidInput));
+        Ok(())
+    }
+}
@@ -0,0 +1,646 @@
+//! Keystore that keeps private keys encrypted at rest.
+//!
+//! Each private key is encrypted with XChaCha20-Poly1305 under a key derived from a password
+//! with Argon2id. Public keys and aliases are stored in the clear, so the keystore can list its
+//! addresses while locked; signing requires [unlocking](EncryptedKeystore::unlock) it first.
+//!
+//! Keystores in the plain Sui CLI format can be migrated with [`EncryptedKeystore::import`].
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use af_sui_types::Address as SuiAddress;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead as _, KeyInit as _, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use eyre::{Context as _, bail, ensure, eyre};
+use fastcrypto::traits::EncodeDecodeBase64 as _;
+use rand::RngCore as _;
+use serde::{Deserialize, Serialize};
+use serde_with::base64::Base64;
+use serde_with::serde_as;
+use zeroize::Zeroizing;
+
+use crate::crypto::{PublicKey, Signature, SuiKeyPair};
+use crate::intent::{Intent, IntentMessage};
+use crate::keystore::{AccountKeystore, Alias, Error, ReadOnlyAccountKeystore, write_atomic};
+
+/// Version of the file format.
+const VERSION: u32 = 1;
+const SALT_LENGTH: usize = 16;
+/// Associated data of the sealed empty message used to check the password.
+const PASSWORD_CHECK: &[u8] = b"af-keys password check";
+
+/// Password-based key derivation function and its parameters.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "algorithm", rename_all = "lowercase")]
+pub enum Kdf {
+    Argon2id {
+        #[serde_as(as = "Base64")]
+        salt: Vec<u8>,
+        /// Memory size in KiB.
+        m_cost: u32,
+        /// Number of iterations.
+        t_cost: u32,
+        /// Degree of parallelism.
+        p_cost: u32,
+    },
+}
+
+impl Kdf {
+    /// Argon2id with a random salt and the given costs.
+    pub fn argon2id(m_cost: u32, t_cost: u32, p_cost: u32) -> Self {
+        let mut salt = vec![0; SALT_LENGTH];
+        rand::rngs::OsRng.fill_bytes(&mut salt);
+        Self::Argon2id {
+            salt,
+            m_cost,
+            t_cost,
+            p_cost,
+        }
+    }
+
+    /// Same parameters with a new random salt.
+    fn resalted(&self) -> Self {
+        let Self::Argon2id {
+            m_cost,
+            t_cost,
+            p_cost,
+            ..
+        } = *self;
+        Self::argon2id(m_cost, t_cost, p_cost)
+    }
+
+    fn derive_key(&self, password: &str) -> Result<Zeroizing<[u8; 32]>, Error> {
+        let Self::Argon2id {
+            salt,
+            m_cost,
+            t_cost,
+            p_cost,
+        } = self;
+        let params = Params::new(*m_cost, *t_cost, *p_cost, Some(32))
+            .map_err(|e| eyre!("Invalid Argon2id parameters: {e}"))?;
+        let mut key = Zeroizing::new([0; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password.as_bytes(), salt, key.as_mut())
+            .map_err(|e| eyre!("Deriving key from password: {e}"))?;
+        Ok(key)
+    }
+}
+
+/// Argon2id with the parameters recommended by OWASP.
+impl Default for Kdf {
+    fn default() -> Self {
+        Self::argon2id(
+            Params::DEFAULT_M_COST,
+            Params::DEFAULT_T_COST,
+            Params::DEFAULT_P_COST,
+        )
+    }
+}
+
+/// File-based keystore whose private keys are encrypted with a password.
+///
+/// Changes made through [`AccountKeystore`] are saved immediately and require the keystore to be
+/// unlocked.
+pub struct EncryptedKeystore {
+    path: PathBuf,
+    kdf: Kdf,
+    password_check: Sealed,
+    entries: BTreeMap<SuiAddress, Entry>,
+    unlocked: Option<Unlocked>,
+}
+
+struct Entry {
+    public_key: PublicKey,
+    alias: Alias,
+    sealed: Sealed,
+}
+
+struct Unlocked {
+    cipher: XChaCha20Poly1305,
+    keys: BTreeMap<SuiAddress, SuiKeyPair>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeystoreFile {
+    version: u32,
+    kdf: Kdf,
+    password_check: Sealed,
+    keys: Vec<FileEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileEntry {
+    #[serde(flatten)]
+    alias: Alias,
+    #[serde(flatten)]
+    sealed: Sealed,
+}
+
+#[serde_as]
+#[derive(Clone, Serialize, Deserialize)]
+struct Sealed {
+    #[serde_as(as = "Base64")]
+    nonce: Vec<u8>,
+    #[serde_as(as = "Base64")]
+    ciphertext: Vec<u8>,
+}
+
+impl EncryptedKeystore {
+    /// Load a keystore from `path`, locked.
+    pub fn new(path: PathBuf) -> Result<Self, Error> {
+        let reader = BufReader::new(
+            File::open(&path)
+                .with_context(|| format!("Cannot open the keystore file: {}", path.display()))?,
+        );
+        let file: KeystoreFile = serde_json::from_reader(reader)
+            .with_context(|| format!("Cannot deserialize the keystore file: {}", path.display()))?;
+        ensure!(
+            file.version == VERSION,
+            "Unsupported keystore version {} in {}",
+            file.version,
+            path.display()
+        );
+
+        let mut entries = BTreeMap::new();
+        for FileEntry { alias, sealed } in file.keys {
+            let public_key = PublicKey::decode_base64(&alias.public_key_base64)
+                .map_err(|e| eyre!("Invalid keystore file: {}. {e}", path.display()))?;
+            let address = public_key.to_sui_address();
+            let entry = Entry {
+                public_key,
+                alias,
+                sealed,
+            };
+            if entries.insert(address, entry).is_some() {
+                bail!("Duplicate key for {address} in {}", path.display());
+            }
+        }
+        Ok(Self {
+            path,
+            kdf: file.kdf,
+            password_check: file.password_check,
+            entries,
+            unlocked: None,
+        })
+    }
+
+    /// Create an empty keystore at `path`, unlocked, with the [default](Kdf::default) key
+    /// derivation.
+    pub fn create(path: PathBuf, password: &str) -> Result<Self, Error> {
+        Self::create_with_kdf(path, password, Kdf::default())
+    }
+
+    /// Like [`Self::create`], with custom key derivation parameters.
+    pub fn create_with_kdf(path: PathBuf, password: &str, kdf: Kdf) -> Result<Self, Error> {
+        ensure!(
+            !path.exists(),
+            "Keystore file already exists: {}",
+            path.display()
+        );
+        let cipher = cipher(&kdf, password)?;
+        let keystore = Self {
+            path,
+            password_check: seal(&cipher, &[], PASSWORD_CHECK)?,
+            kdf,
+            entries: BTreeMap::new(),
+            unlocked: Some(Unlocked {
+                cipher,
+                keys: BTreeMap::new(),
+            }),
+        };
+        keystore.save()?;
+        Ok(keystore)
+    }
+
+    /// Encrypt all keys and aliases of `keystore` into a new keystore at `path`.
+    ///
+    /// Keys without an alias are given a random one. The source keystore is left as is, so
+    /// delete it once the migration is confirmed.
+    pub fn import<K: ReadOnlyAccountKeystore>(
+        keystore: &K,
+        path: PathBuf,
+        password: &str,
+    ) -> Result<Self, Error> {
+        Self::import_with_kdf(keystore, path, password, Kdf::default())
+    }
+
+    /// Like [`Self::import`], with custom key derivation parameters.
+    pub fn import_with_kdf<K: ReadOnlyAccountKeystore>(
+        keystore: &K,
+        path: PathBuf,
+        password: &str,
+        kdf: Kdf,
+    ) -> Result<Self, Error> {
+        let mut encrypted = Self::create_with_kdf(path, password, kdf)?;
+        for address in keystore.addresses() {
+            let alias = keystore.get_alias_by_address(&address).ok();
+            let keypair = SuiKeyPair::from_bytes(&keystore.get_key(&address)?.to_bytes())?;
+            let alias = encrypted.create_alias(alias)?;
+            encrypted.insert(alias, keypair)?;
+        }
+        encrypted.save()?;
+        Ok(encrypted)
+    }
+
+    /// Decrypt the private keys with `password`.
+    pub fn unlock(&mut self, password: &str) -> Result<(), Error> {
+        let cipher = cipher(&self.kdf, password)?;
+        open(&cipher, &self.password_check, PASSWORD_CHECK)
+            .map_err(|_| eyre!("Wrong password for keystore {}", self.path.display()))?;
+
+        let mut keys = BTreeMap::new();
+        for (address, entry) in &self.entries {
+            let bytes = open(&cipher, &entry.sealed, entry.public_key.as_ref())
+                .with_context(|| format!("Decrypting key for {address}"))?;
+            let keypair = SuiKeyPair::from_bytes(&bytes)?;
+            ensure!(
+                keypair.public() == entry.public_key,
+                "Decrypted key doesn't match the public key of {address}"
+            );
+            keys.insert(*address, keypair);
+        }
+        self.unlocked = Some(Unlocked { cipher, keys });
+        Ok(())
+    }
+
+    /// Drop the decrypted private keys from memory.
+    pub fn lock(&mut self) {
+        self.unlocked = None;
+    }
+
+    pub const fn is_unlocked(&self) -> bool {
+        self.unlocked.is_some()
+    }
+
+    /// Re-encrypt all keys under `new_password`, with a new salt.
+    pub fn change_password(&mut self, new_password: &str) -> Result<(), Error> {
+        let keys = &self.unlocked()?.keys;
+        let kdf = self.kdf.resalted();
+        let cipher = cipher(&kdf, new_password)?;
+        let mut sealed = BTreeMap::new();
+        for (address, keypair) in keys {
+            let entry = &self.entries[address];
+            let bytes = Zeroizing::new(keypair.to_bytes());
+            sealed.insert(*address, seal(&cipher, &bytes, entry.public_key.as_ref())?);
+        }
+
+        let password_check = seal(&cipher, &[], PASSWORD_CHECK)?;
+
+        // Only change memory once the disk has the new password
+        self.write(&kdf, &password_check, |address, entry| {
+            sealed.get(address).unwrap_or(&entry.sealed).clone()
+        })?;
+        self.password_check = password_check;
+        self.kdf = kdf;
+        for (address, sealed) in sealed {
+            if let Some(entry) = self.entries.get_mut(&address) {
+                entry.sealed = sealed;
+            }
+        }
+        if let Some(unlocked) = &mut self.unlocked {
+            unlocked.cipher = cipher;
+        }
+        Ok(())
+    }
+
+    /// Write the keystore to its path, replacing the file atomically.
+    pub fn save(&self) -> Result<(), Error> {
+        self.write(&self.kdf, &self.password_check, |_, entry| {
+            entry.sealed.clone()
+        })
+    }
+
+    /// [`Self::save`] with `kdf`, `password_check` and the sealed keys given by `sealed` instead
+    /// of those in memory.
+    fn write(
+        &self,
+        kdf: &Kdf,
+        password_check: &Sealed,
+        sealed: impl Fn(&SuiAddress, &Entry) -> Sealed,
+    ) -> Result<(), Error> {
+        let file = KeystoreFile {
+            version: VERSION,
+            kdf: kdf.clone(),
+            password_check: password_check.clone(),
+            keys: self
+                .entries
+                .iter()
+                .map(|(address, entry)| FileEntry {
+                    alias: entry.alias.clone(),
+                    sealed: sealed(address, entry),
+                })
+                .collect(),
+        };
+        write_atomic(&self.path, &serde_json::to_string_pretty(&file)?)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn unlocked(&self) -> Result<&Unlocked, Error> {
+        self.unlocked
+            .as_ref()
+            .ok_or_else(|| eyre!("Keystore {} is locked", self.path.display()))
+    }
+
+    fn unlocked_key(&self, address: &SuiAddress) -> Result<&SuiKeyPair, signature::Error> {
+        let unlocked = self.unlocked().map_err(signature::Error::from_source)?;
+        unlocked.keys.get(address).ok_or_else(|| {
+            signature::Error::from_source(format!("Cannot find key for address: [{address}]"))
+        })
+    }
+
+    /// Encrypt and add a key pair without saving.
+    fn insert(&mut self, alias: String, keypair: SuiKeyPair) -> Result<(), Error> {
+        let public_key = keypair.public();
+        let address = public_key.to_sui_address();
+        if self.entries.contains_key(&address) {
+            bail!("Key for address {address} already exists");
+        }
+        let unlocked = self
+            .unlocked
+            .as_mut()
+            .ok_or_else(|| eyre!("Keystore {} is locked", self.path.display()))?;
+        let bytes = Zeroizing::new(keypair.to_bytes());
+        let sealed = seal(&unlocked.cipher, &bytes, public_key.as_ref())?;
+        unlocked.keys.insert(address, keypair);
+        self.entries.insert(
+            address,
+            Entry {
+                alias: Alias {
+                    alias,
+                    public_key_base64: public_key.encode_base64(),
+                },
+                public_key,
+                sealed,
+            },
+        );
+        Ok(())
+    }
+}
+
+impl ReadOnlyAccountKeystore for EncryptedKeystore {
+    fn keys(&self) -> Vec<PublicKey> {
+        self.entries
+            .values()
+            .map(|entry| entry.public_key.clone())
+            .collect()
+    }
+
+    fn get_key(&self, address: &SuiAddress) -> Result<&SuiKeyPair, Error> {
+        self.unlocked()?
+            .keys
+            .get(address)
+            .ok_or_else(|| eyre!("Cannot find key for address: [{address}]"))
+    }
+
+    fn sign_hashed(&self, address: &SuiAddress, msg: &[u8]) -> Result<Signature, signature::Error> {
+        Ok(Signature::new_hashed(msg, self.unlocked_key(address)?))
+    }
+
+    fn sign_secure<T>(
+        &self,
+        address: &SuiAddress,
+        msg: &T,
+        intent: Intent,
+    ) -> Result<Signature, signature::Error>
+    where
+        T: Serialize,
+    {
+        Ok(Signature::new_secure(
+            &IntentMessage::new(intent, msg),
+            self.unlocked_key(address)?,
+        ))
+    }
+
+    fn addresses_with_alias(&self) -> Vec<(&SuiAddress, &Alias)> {
+        self.entries
+            .iter()
+            .map(|(address, entry)| (address, &entry.alias))
+            .collect()
+    }
+
+    fn aliases(&self) -> Vec<&Alias> {
+        self.entries.values().map(|entry| &entry.alias).collect()
+    }
+
+    fn get_alias_by_address(&self, address: &SuiAddress) -> Result<String, Error> {
+        match self.entries.get(address) {
+            Some(entry) => Ok(entry.alias.alias.clone()),
+            None => bail!("Cannot find alias for address {address}"),
+        }
+    }
+
+    fn get_address_by_alias(&self, alias: String) -> Result<&SuiAddress, Error> {
+        self.entries
+            .iter()
+            .find(|(_, entry)| entry.alias.alias == alias)
+            .map(|(address, _)| address)
+            .ok_or_else(|| eyre!("Cannot resolve alias {alias} to an address"))
+    }
+}
+
+impl AccountKeystore for EncryptedKeystore {
+    fn add_key(&mut self, alias: Option<String>, keypair: SuiKeyPair) -> Result<(), Error> {
+        let alias = self.create_alias(alias)?;
+        let address = keypair.public().to_sui_address();
+        self.insert(alias, keypair)?;
+        // Keep memory in sync with the disk
+        self.save().inspect_err(|_| {
+            self.entries.remove(&address);
+            if let Some(unlocked) = &mut self.unlocked {
+                unlocked.keys.remove(&address);
+            }
+        })
+    }
+
+    fn remove_key(&mut self, address: &SuiAddress) -> Result<(), Error> {
+        self.unlocked()?;
+        let entry = self
+            .entries
+            .remove(address)
+            .ok_or_else(|| eyre!("Cannot find key for address: [{address}]"))?;
+        let keypair = self
+            .unlocked
+            .as_mut()
+            .and_then(|unlocked| unlocked.keys.remove(address));
+        self.save().inspect_err(|_| {
+            self.entries.insert(*address, entry);
+            if let (Some(unlocked), Some(keypair)) = (&mut self.unlocked, keypair) {
+                unlocked.keys.insert(*address, keypair);
+            }
+        })
+    }
+
+    fn update_alias(&mut self, old_alias: &str, new_alias: Option<&str>) -> Result<String, Error> {
+        self.unlocked()?;
+        let new_alias = self.create_alias(new_alias.map(ToOwned::to_owned))?;
+        let (address, entry) = self
+            .entries
+            .iter_mut()
+            .find(|(_, entry)| entry.alias.alias == old_alias)
+            .ok_or_else(|| eyre!("The provided alias {old_alias} does not exist"))?;
+        let address = *address;
+        entry.alias.alias.clone_from(&new_alias);
+        self.save().inspect_err(|_| {
+            if let Some(entry) = self.entries.get_mut(&address) {
+                old_alias.clone_into(&mut entry.alias.alias);
+            }
+        })?;
+        Ok(new_alias)
+    }
+}
+
+fn cipher(kdf: &Kdf, password: &str) -> Result<XChaCha20Poly1305, Error> {
+    let key = kdf.derive_key(password)?;
+    Ok(XChaCha20Poly1305::new(key.as_ref().into()))
+}
+
+fn seal(cipher: &XChaCha20Poly1305, msg: &[u8], aad: &[u8]) -> Result<Sealed, Error> {
+    let mut nonce = XNonce::default();
+    rand::rngs::OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg, aad })
+        .map_err(|_| eyre!("Encryption failed"))?;
+    Ok(Sealed {
+        nonce: nonce.to_vec(),
+        ciphertext,
+    })
+}
+
+fn open(
+    cipher: &XChaCha20Poly1305,
+    sealed: &Sealed,
+    aad: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let nonce: [u8; 24] = (sealed.nonce.as_slice().try_into())
+        .map_err(|_| eyre!("Invalid nonce length {}", sealed.nonce.len()))?;
+    let plaintext = cipher
+        .decrypt(
+            &XNonce::from(nonce),
+            Payload {
+                msg: &sealed.ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| eyre!("Decryption failed"))?;
+    Ok(Zeroizing::new(plaintext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::SignatureScheme;
+    use crate::keystore::FileBasedKeystore;
+
+    /// Cheap parameters to keep tests fast.
+    fn kdf() -> Kdf {
+        Kdf::argon2id(64, 1, 1)
+    }
+
+    #[test]
+    fn lock_unlock_and_change_password() -> eyre::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("sui.keystore.enc");
+        let mut keystore = EncryptedKeystore::create_with_kdf(path.clone(), "hunter2", kdf())?;
+        let (address, _, _) = keystore.generate_and_add_new_key(
+            SignatureScheme::ED25519,
+            Some("alice".into()),
+            None,
+            None,
+        )?;
+        let private_key = keystore.export_private_key(&address)?;
+        assert!(!std::fs::read_to_string(&path)?.contains(&private_key));
+
+        keystore.lock();
+        assert!(keystore.sign_hashed(&address, &[0; 32]).is_err());
+        assert!(keystore.remove_key(&address).is_err());
+        assert_eq!(keystore.addresses(), [address]);
+
+        let mut reloaded = EncryptedKeystore::new(path.clone())?;
+        assert_eq!(reloaded.get_address_by_alias("alice".into())?, &address);
+        assert!(reloaded.unlock("hunter3").is_err());
+        reloaded.unlock("hunter2")?;
+        assert_eq!(reloaded.export_private_key(&address)?, private_key);
+
+        reloaded.change_password("correct horse")?;
+        let mut reloaded = EncryptedKeystore::new(path)?;
+        assert!(reloaded.unlock("hunter2").is_err());
+        reloaded.unlock("correct horse")?;
+        assert!(reloaded.sign_hashed(&address, &[0; 32]).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn failed_save_keeps_memory() -> eyre::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir = temp_dir.path().join("config");
+        let mut keystore =
+            EncryptedKeystore::create_with_kdf(dir.join("sui.keystore.enc"), "pw", kdf())?;
+        let (address, _, _) = keystore.generate_and_add_new_key(
+            SignatureScheme::ED25519,
+            Some("alice".into()),
+            None,
+            None,
+        )?;
+        // Saving fails from now on
+        std::fs::remove_dir_all(&dir)?;
+        std::fs::write(&dir, "not a directory")?;
+
+        assert!(
+            keystore
+                .generate_and_add_new_key(SignatureScheme::ED25519, None, None, None)
+                .is_err()
+        );
+        assert_eq!(keystore.addresses(), [address]);
+        assert!(keystore.remove_key(&address).is_err());
+        assert!(keystore.sign_hashed(&address, &[0; 32]).is_ok());
+        assert!(keystore.update_alias("alice", Some("bob")).is_err());
+        assert_eq!(keystore.get_alias_by_address(&address)?, "alice");
+
+        // Still under the old password
+        assert!(keystore.change_password("new").is_err());
+        std::fs::remove_file(&dir)?;
+        keystore.save()?;
+        let mut reloaded = EncryptedKeystore::new(dir.join("sui.keystore.enc"))?;
+        reloaded.unlock("pw")?;
+        assert_eq!(reloaded.keys(), keystore.keys());
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_from_plain() -> eyre::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let mut plain = FileBasedKeystore::new(temp_dir.path().join("sui.keystore"))?;
+        plain.generate_and_add_new_key(
+            SignatureScheme::Secp256k1,
+            Some("bob".into()),
+            None,
+            None,
+        )?;
+        plain.generate_and_add_new_key(SignatureScheme::Secp256r1, None, None, None)?;
+
+        let path = temp_dir.path().join("sui.keystore.enc");
+        let mut encrypted = EncryptedKeystore::import_with_kdf(&plain, path.clone(), "pw", kdf())?;
+        assert!(EncryptedKeystore::import_with_kdf(&plain, path, "pw", kdf()).is_err());
+        encrypted.lock();
+        encrypted.unlock("pw")?;
+        assert_eq!(encrypted.keys(), plain.keys());
+        for address in plain.addresses() {
+            assert_eq!(
+                encrypted.get_alias_by_address(&address)?,
+                plain.get_alias_by_address(&address)?
+            );
+            assert_eq!(
+                encrypted.export_private_key(&address)?,
+                plain.export_private_key(&address)?
+            );
+        }
+        Ok(())
+    }
+}
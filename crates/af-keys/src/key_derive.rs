@@ -0,0 +1,193 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Key generation and derivation from BIP-39 mnemonics, compatible with the Sui CLI.
+//!
+//! Ed25519 keys are derived with [SLIP-10] along fully hardened paths `m/44'/784'/{account}'/
+//! {change}'/{address}'`. Secp256k1 and Secp256r1 keys are derived with [BIP-32] along
+//! `m/54'/784'/{account}'/{change}/{address}` and `m/74'/784'/{account}'/{change}/{address}`
+//! respectively.
+//!
+//! [SLIP-10]: https://github.com/satoshilabs/slips/blob/master/slip-0010.md
+//! [BIP-32]: https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+use af_sui_types::Address as SuiAddress;
+pub use bip32::DerivationPath;
+use bip32::{ChildNumber, XPrv};
+use bip39::{Language, Mnemonic};
+use eyre::{Result, bail, eyre};
+use fastcrypto::ed25519::{Ed25519KeyPair, Ed25519PrivateKey};
+use fastcrypto::secp256k1::{Secp256k1KeyPair, Secp256k1PrivateKey};
+use fastcrypto::secp256r1::{Secp256r1KeyPair, Secp256r1PrivateKey};
+use fastcrypto::traits::ToFromBytes;
+
+use crate::crypto::{SignatureScheme, SuiKeyPair};
+
+pub const DERIVATION_PATH_COIN_TYPE: u32 = 784;
+pub const DERIVATION_PATH_PURPOSE_ED25519: u32 = 44;
+pub const DERIVATION_PATH_PURPOSE_SECP256K1: u32 = 54;
+pub const DERIVATION_PATH_PURPOSE_SECP256R1: u32 = 74;
+
+/// Word count of generated mnemonics if none is specified.
+pub const DEFAULT_WORD_COUNT: usize = 12;
+
+/// Derive a key pair from a BIP-39 seed along `derivation_path`.
+///
+/// If no path is given, the first address of the first account is derived (e.g.,
+/// `m/44'/784'/0'/0'/0'` for Ed25519).
+pub fn derive_key_pair_from_path(
+    seed: &[u8],
+    derivation_path: Option<DerivationPath>,
+    key_scheme: &SignatureScheme,
+) -> Result<(SuiAddress, SuiKeyPair)> {
+    let path = validate_path(key_scheme, derivation_path)?;
+    let key_pair = match key_scheme {
+        SignatureScheme::ED25519 => {
+            let indexes: Vec<u32> = path.into_iter().map(ChildNumber::index).collect();
+            let derived = slip10_ed25519::derive_ed25519_private_key(seed, &indexes);
+            let sk = Ed25519PrivateKey::from_bytes(&derived)
+                .map_err(|e| eyre!("Invalid derived Ed25519 key: {e}"))?;
+            SuiKeyPair::Ed25519(Ed25519KeyPair::from(sk))
+        }
+        SignatureScheme::Secp256k1 => {
+            let child_xprv = XPrv::derive_from_path(seed, &path)?;
+            let sk = Secp256k1PrivateKey::from_bytes(&child_xprv.to_bytes())
+                .map_err(|e| eyre!("Invalid derived Secp256k1 key: {e}"))?;
+            SuiKeyPair::Secp256k1(Secp256k1KeyPair::from(sk))
+        }
+        // Same as the Sui CLI, which derives the secret along the secp256k1 curve.
+        SignatureScheme::Secp256r1 => {
+            let child_xprv = XPrv::derive_from_path(seed, &path)?;
+            let sk = Secp256r1PrivateKey::from_bytes(&child_xprv.to_bytes())
+                .map_err(|e| eyre!("Invalid derived Secp256r1 key: {e}"))?;
+            SuiKeyPair::Secp256r1(Secp256r1KeyPair::from(sk))
+        }
+        _ => bail!("Key derivation not supported for {key_scheme}"),
+    };
+    Ok((key_pair.public().to_sui_address(), key_pair))
+}
+
+/// Check that `path` is a standard Sui path for `key_scheme`, or default it.
+pub fn validate_path(
+    key_scheme: &SignatureScheme,
+    path: Option<DerivationPath>,
+) -> Result<DerivationPath> {
+    let (purpose, hardened_tail) = match key_scheme {
+        SignatureScheme::ED25519 => (DERIVATION_PATH_PURPOSE_ED25519, true),
+        SignatureScheme::Secp256k1 => (DERIVATION_PATH_PURPOSE_SECP256K1, false),
+        SignatureScheme::Secp256r1 => (DERIVATION_PATH_PURPOSE_SECP256R1, false),
+        _ => bail!("Key derivation not supported for {key_scheme}"),
+    };
+    let Some(path) = path else {
+        let tail = if hardened_tail { "'" } else { "" };
+        return Ok(
+            format!("m/{purpose}'/{DERIVATION_PATH_COIN_TYPE}'/0'/0{tail}/0{tail}").parse()?,
+        );
+    };
+
+    let children: Vec<_> = path.iter().collect();
+    let valid = match children[..] {
+        [p, c, account, change, address] => {
+            p == ChildNumber::new(purpose, true)?
+                && c == ChildNumber::new(DERIVATION_PATH_COIN_TYPE, true)?
+                && account.is_hardened()
+                && change.is_hardened() == hardened_tail
+                && address.is_hardened() == hardened_tail
+        }
+        _ => false,
+    };
+    if !valid {
+        let tail = if hardened_tail { "'" } else { "" };
+        bail!(
+            "Invalid derivation path {path} for {key_scheme}, expected \
+             m/{purpose}'/{DERIVATION_PATH_COIN_TYPE}'/[account]'/[change]{tail}/[address]{tail}"
+        );
+    }
+    Ok(path)
+}
+
+/// Generate a new key pair from a fresh mnemonic of `word_count` words.
+///
+/// Returns the address, the key pair, its scheme and the mnemonic phrase, which can later
+/// recover the key with [`derive_key_pair_from_path`] and the same path.
+pub fn generate_new_key(
+    key_scheme: SignatureScheme,
+    derivation_path: Option<DerivationPath>,
+    word_count: Option<usize>,
+) -> Result<(SuiAddress, SuiKeyPair, SignatureScheme, String)> {
+    let mnemonic =
+        Mnemonic::generate_in(Language::English, word_count.unwrap_or(DEFAULT_WORD_COUNT))
+            .map_err(|e| eyre!("Generating mnemonic: {e}"))?;
+    let seed = mnemonic.to_seed("");
+    let (address, key_pair) = derive_key_pair_from_path(&seed, derivation_path, &key_scheme)?;
+    Ok((address, key_pair, key_scheme, mnemonic.to_string()))
+}
+
+/// Recover a key pair from a mnemonic phrase.
+pub fn key_pair_from_mnemonic(
+    phrase: &str,
+    key_scheme: &SignatureScheme,
+    derivation_path: Option<DerivationPath>,
+) -> Result<(SuiAddress, SuiKeyPair)> {
+    let mnemonic = Mnemonic::parse_in_normalized(Language::English, phrase)
+        .map_err(|e| eyre!("Invalid mnemonic phrase: {e}"))?;
+    derive_key_pair_from_path(&mnemonic.to_seed(""), derivation_path, key_scheme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::PHRASE;
+
+    #[test]
+    fn known_derivations() -> eyre::Result<()> {
+        let (address, _) = key_pair_from_mnemonic(PHRASE, &SignatureScheme::ED25519, None)?;
+        assert_eq!(
+            address,
+            "0xa2d14fad60c56049ecf75246a481934691214ce413e6a8ae2fe6834c173a6133".parse()?
+        );
+        let (address, _) = key_pair_from_mnemonic(PHRASE, &SignatureScheme::Secp256k1, None)?;
+        assert_eq!(
+            address,
+            "0x9e8f732575cc5386f8df3c784cd3ed1b53ce538da79926b2ad54dcc1197d2532".parse()?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn paths() -> eyre::Result<()> {
+        let ed25519 = SignatureScheme::ED25519;
+        let secp256k1 = SignatureScheme::Secp256k1;
+        assert_eq!(
+            validate_path(&ed25519, None)?.to_string(),
+            "m/44'/784'/0'/0'/0'"
+        );
+        assert_eq!(
+            validate_path(&SignatureScheme::Secp256r1, None)?.to_string(),
+            "m/74'/784'/0'/0/0"
+        );
+        assert!(validate_path(&ed25519, Some("m/44'/784'/1'/0'/3'".parse()?)).is_ok());
+        assert!(validate_path(&ed25519, Some("m/44'/784'/0'/0/0".parse()?)).is_err());
+        assert!(validate_path(&secp256k1, Some("m/54'/784'/2'/0/1".parse()?)).is_ok());
+        assert!(validate_path(&secp256k1, Some("m/44'/784'/0'/0/0".parse()?)).is_err());
+        assert!(validate_path(&secp256k1, Some("m/54'/784'/0'/0".parse()?)).is_err());
+        assert!(validate_path(&SignatureScheme::MultiSig, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn generate_and_recover() -> eyre::Result<()> {
+        for scheme in [
+            SignatureScheme::ED25519,
+            SignatureScheme::Secp256k1,
+            SignatureScheme::Secp256r1,
+        ] {
+            let (address, key_pair, _, phrase) = generate_new_key(scheme, None, Some(24))?;
+            assert_eq!(phrase.split_whitespace().count(), 24);
+            let (recovered, recovered_pair) = key_pair_from_mnemonic(&phrase, &scheme, None)?;
+            assert_eq!(address, recovered);
+            assert_eq!(key_pair, recovered_pair);
+        }
+        assert!(generate_new_key(SignatureScheme::ED25519, None, Some(13)).is_err());
+        Ok(())
+    }
+}
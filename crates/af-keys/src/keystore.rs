@@ -4,22 +4,24 @@
 use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter, Write};
 use std::fs::File;
-use std::io::BufReader;
-use std::path::PathBuf;
+use std::io::{BufReader, Write as _};
+use std::path::{Path, PathBuf};
 
 use af_sui_types::Address as SuiAddress;
 use enum_dispatch::enum_dispatch;
 use eyre::{Context as _, bail, eyre};
 use fastcrypto::traits::EncodeDecodeBase64;
+use rand::seq::SliceRandom as _;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::crypto::{PublicKey, Signature, SuiKeyPair};
+use crate::crypto::{PublicKey, Signature, SignatureScheme, SuiKeyPair};
 use crate::intent::{Intent, IntentMessage};
+use crate::key_derive::{self, DerivationPath};
 
 pub type Error = eyre::Report;
 
 #[derive(Serialize, Deserialize)]
-#[enum_dispatch(ReadOnlyAccountKeystore)]
+#[enum_dispatch(ReadOnlyAccountKeystore, AccountKeystore)]
 pub enum Keystore {
     File(FileBasedKeystore),
     InMem(InMemKeystore),
@@ -90,6 +92,88 @@ pub trait ReadOnlyAccountKeystore: Send + Sync {
     }
 }
 
+/// Mutable version of [`ReadOnlyAccountKeystore`].
+///
+/// Mirrors Sui's [AccountKeystore]. File-based keystores are saved after every change, so the
+/// Sui CLI sees the same keys and aliases.
+///
+/// [AccountKeystore]: https://mystenlabs.github.io/sui/sui_keys/keystore/trait.AccountKeystore.html
+#[enum_dispatch]
+pub trait AccountKeystore: ReadOnlyAccountKeystore {
+    /// Add a key pair under `alias`, or a random one if `None`.
+    fn add_key(&mut self, alias: Option<String>, keypair: SuiKeyPair) -> Result<(), Error>;
+
+    /// Remove the key pair of `address` and its alias.
+    fn remove_key(&mut self, address: &SuiAddress) -> Result<(), Error>;
+
+    /// Rename `old_alias` to `new_alias`, or a random one if `None`. Returns the new alias.
+    fn update_alias(&mut self, old_alias: &str, new_alias: Option<&str>) -> Result<String, Error>;
+
+    /// Validate `alias` or, if `None`, generate a random one that isn't taken.
+    fn create_alias(&self, alias: Option<String>) -> Result<String, Error> {
+        let taken = self.alias_names();
+        match alias {
+            Some(alias) => {
+                validate_alias(&alias)?;
+                if taken.contains(&alias.as_str()) {
+                    bail!("Alias {alias} already exists. Please choose another alias.");
+                }
+                Ok(alias)
+            }
+            None => Ok(random_alias(&taken)),
+        }
+    }
+
+    /// Generate a key pair from a fresh mnemonic and add it.
+    ///
+    /// Returns the new address, the mnemonic phrase (the only way to recover the key outside
+    /// this keystore) and the key scheme.
+    fn generate_and_add_new_key(
+        &mut self,
+        key_scheme: SignatureScheme,
+        alias: Option<String>,
+        derivation_path: Option<DerivationPath>,
+        word_count: Option<usize>,
+    ) -> Result<(SuiAddress, String, SignatureScheme), Error> {
+        let (address, keypair, scheme, phrase) =
+            key_derive::generate_new_key(key_scheme, derivation_path, word_count)?;
+        self.add_key(alias, keypair)?;
+        Ok((address, phrase, scheme))
+    }
+
+    /// Recover a key pair from a mnemonic phrase and add it.
+    fn import_from_mnemonic(
+        &mut self,
+        phrase: &str,
+        key_scheme: SignatureScheme,
+        derivation_path: Option<DerivationPath>,
+        alias: Option<String>,
+    ) -> Result<SuiAddress, Error> {
+        let (address, keypair) =
+            key_derive::key_pair_from_mnemonic(phrase, &key_scheme, derivation_path)?;
+        self.add_key(alias, keypair)?;
+        Ok(address)
+    }
+
+    /// Export the private key of `address` in Bech32 format, starting with `suiprivkey`.
+    fn export_private_key(&self, address: &SuiAddress) -> Result<String, Error> {
+        Ok(self.get_key(address)?.encode()?)
+    }
+
+    /// Add a private key in Bech32 format, starting with `suiprivkey`.
+    fn import_private_key(
+        &mut self,
+        private_key: &str,
+        alias: Option<String>,
+    ) -> Result<SuiAddress, Error> {
+        let keypair = SuiKeyPair::decode(private_key)
+            .map_err(|e| eyre!("Invalid Bech32 private key: {e}"))?;
+        let address = keypair.public().to_sui_address();
+        self.add_key(alias, keypair)?;
+        Ok(address)
+    }
+}
+
 #[derive(Default)]
 pub struct FileBasedKeystore {
     keys: BTreeMap<SuiAddress, SuiKeyPair>,
@@ -166,6 +250,26 @@ impl FileBasedKeystore {
     pub fn key_pairs(&self) -> Vec<&SuiKeyPair> {
         self.keys.values().collect()
     }
+
+    /// Write the keys and aliases to disk, in the same format as the Sui CLI.
+    ///
+    /// Keys without an alias are given a random one. Each file is replaced atomically. Does
+    /// nothing if the keystore has no path.
+    pub fn save(&mut self) -> Result<(), Error> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        fill_aliases(&self.keys, &mut self.aliases);
+
+        let keys: Vec<_> = self.keys.values().map(|k| k.encode_base64()).collect();
+        write_atomic(path, &serde_json::to_string_pretty(&keys)?)?;
+
+        let mut aliases_path = path.clone();
+        aliases_path.set_extension("aliases");
+        let aliases: Vec<_> = self.aliases.values().collect();
+        write_atomic(&aliases_path, &serde_json::to_string_pretty(&aliases)?)?;
+        Ok(())
+    }
 }
 
 impl Serialize for FileBasedKeystore {
@@ -259,7 +363,41 @@ impl ReadOnlyAccountKeystore for FileBasedKeystore {
     }
 }
 
-/// Carry-over from the original code, but un-initializable.
+impl AccountKeystore for FileBasedKeystore {
+    fn add_key(&mut self, alias: Option<String>, keypair: SuiKeyPair) -> Result<(), Error> {
+        let alias = self.create_alias(alias)?;
+        let address = keypair.public().to_sui_address();
+        insert_key(&mut self.keys, &mut self.aliases, alias, keypair)?;
+        // Keep memory in sync with the disk
+        self.save().inspect_err(|_| {
+            self.keys.remove(&address);
+            self.aliases.remove(&address);
+        })
+    }
+
+    fn remove_key(&mut self, address: &SuiAddress) -> Result<(), Error> {
+        let (keypair, alias) = remove_key(&mut self.keys, &mut self.aliases, address)?;
+        self.save().inspect_err(|_| {
+            self.keys.insert(*address, keypair);
+            if let Some(alias) = alias {
+                self.aliases.insert(*address, alias);
+            }
+        })
+    }
+
+    fn update_alias(&mut self, old_alias: &str, new_alias: Option<&str>) -> Result<String, Error> {
+        let new_alias = self.create_alias(new_alias.map(ToOwned::to_owned))?;
+        let address = rename_alias(&mut self.aliases, old_alias, new_alias.clone())?;
+        self.save().inspect_err(|_| {
+            if let Some(alias) = self.aliases.get_mut(&address) {
+                old_alias.clone_into(&mut alias.alias);
+            }
+        })?;
+        Ok(new_alias)
+    }
+}
+
+/// In-memory keystore. Start from [`Default`] and add keys with [`AccountKeystore`].
 #[derive(Default, Serialize, Deserialize)]
 pub struct InMemKeystore {
     aliases: BTreeMap<SuiAddress, Alias>,
@@ -331,12 +469,147 @@ impl ReadOnlyAccountKeystore for InMemKeystore {
     }
 }
 
+impl AccountKeystore for InMemKeystore {
+    fn add_key(&mut self, alias: Option<String>, keypair: SuiKeyPair) -> Result<(), Error> {
+        let alias = self.create_alias(alias)?;
+        insert_key(&mut self.keys, &mut self.aliases, alias, keypair)
+    }
+
+    fn remove_key(&mut self, address: &SuiAddress) -> Result<(), Error> {
+        remove_key(&mut self.keys, &mut self.aliases, address)?;
+        Ok(())
+    }
+
+    fn update_alias(&mut self, old_alias: &str, new_alias: Option<&str>) -> Result<String, Error> {
+        let new_alias = self.create_alias(new_alias.map(ToOwned::to_owned))?;
+        rename_alias(&mut self.aliases, old_alias, new_alias.clone())?;
+        Ok(new_alias)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Alias {
     pub alias: String,
     pub public_key_base64: String,
 }
 
+/// Same rules as the Sui CLI.
+fn validate_alias(alias: &str) -> Result<(), Error> {
+    let mut chars = alias.chars();
+    if !chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        || !chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!(
+            "Invalid alias {alias:?}. A valid alias must start with a letter and can contain only \
+             letters, digits, hyphens (-), or underscores (_)."
+        );
+    }
+    Ok(())
+}
+
+/// Two random words from the BIP-39 English word list, e.g. `brave-otter`.
+fn random_alias(taken: &[&str]) -> String {
+    let words = bip39::Language::English.word_list();
+    let mut rng = rand::thread_rng();
+    loop {
+        let alias = words
+            .choose_multiple(&mut rng, 2)
+            .copied()
+            .collect::<Vec<_>>()
+            .join("-");
+        if !taken.contains(&alias.as_str()) {
+            return alias;
+        }
+    }
+}
+
+fn insert_key(
+    keys: &mut BTreeMap<SuiAddress, SuiKeyPair>,
+    aliases: &mut BTreeMap<SuiAddress, Alias>,
+    alias: String,
+    keypair: SuiKeyPair,
+) -> Result<(), Error> {
+    let public_key = keypair.public();
+    let address = public_key.to_sui_address();
+    if keys.contains_key(&address) {
+        bail!("Key for address {address} already exists");
+    }
+    aliases.insert(
+        address,
+        Alias {
+            alias,
+            public_key_base64: public_key.encode_base64(),
+        },
+    );
+    keys.insert(address, keypair);
+    Ok(())
+}
+
+/// Returns what was removed, to put it back if needed.
+fn remove_key(
+    keys: &mut BTreeMap<SuiAddress, SuiKeyPair>,
+    aliases: &mut BTreeMap<SuiAddress, Alias>,
+    address: &SuiAddress,
+) -> Result<(SuiKeyPair, Option<Alias>), Error> {
+    let keypair = keys
+        .remove(address)
+        .ok_or_else(|| eyre!("Cannot find key for address: [{address}]"))?;
+    Ok((keypair, aliases.remove(address)))
+}
+
+/// Returns the address whose alias was renamed.
+fn rename_alias(
+    aliases: &mut BTreeMap<SuiAddress, Alias>,
+    old_alias: &str,
+    new_alias: String,
+) -> Result<SuiAddress, Error> {
+    let (address, entry) = aliases
+        .iter_mut()
+        .find(|(_, a)| a.alias == old_alias)
+        .ok_or_else(|| eyre!("The provided alias {old_alias} does not exist"))?;
+    entry.alias = new_alias;
+    Ok(*address)
+}
+
+fn fill_aliases(
+    keys: &BTreeMap<SuiAddress, SuiKeyPair>,
+    aliases: &mut BTreeMap<SuiAddress, Alias>,
+) {
+    for (address, keypair) in keys {
+        if aliases.contains_key(address) {
+            continue;
+        }
+        let taken: Vec<_> = aliases.values().map(|a| a.alias.as_str()).collect();
+        let alias = random_alias(&taken);
+        aliases.insert(
+            *address,
+            Alias {
+                alias,
+                public_key_base64: keypair.public().encode_base64(),
+            },
+        );
+    }
+}
+
+/// Replace the file at `path` with `contents` such that readers never see a partial write.
+///
+/// The contents are written to a temporary file (only readable by the owner) in the same
+/// directory, which is then renamed over `path`.
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> Result<(), Error> {
+    let dir = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Creating keystore directory: {}", dir.display()))?;
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    file.write_all(contents.as_bytes())?;
+    file.as_file().sync_all()?;
+    file.persist(path)
+        .with_context(|| format!("Writing {}", path.display()))?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write;
@@ -464,4 +737,103 @@ mod tests {
         assert!(keystore.get_address_by_alias("alice".to_owned()).is_err());
         Ok(())
     }
+
+    #[test]
+    fn writable_file_keystore() -> eyre::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("config/sui.keystore");
+        let mut keystore = FileBasedKeystore::new(path.clone())?;
+
+        let (generated, phrase, _) =
+            keystore.generate_and_add_new_key(SignatureScheme::ED25519, None, None, None)?;
+        assert_eq!(phrase.split_whitespace().count(), 12);
+        let imported = keystore.import_from_mnemonic(
+            crate::test_utils::PHRASE,
+            SignatureScheme::Secp256k1,
+            None,
+            Some("alice".into()),
+        )?;
+        assert_eq!(
+            imported,
+            "0x9e8f732575cc5386f8df3c784cd3ed1b53ce538da79926b2ad54dcc1197d2532".parse()?
+        );
+
+        // Aliases are validated and unique
+        let (_, key, _, _) =
+            crate::key_derive::generate_new_key(SignatureScheme::Secp256r1, None, None)?;
+        let private_key = key.encode()?;
+        assert!(
+            keystore
+                .import_private_key(&private_key, Some("alice".into()))
+                .is_err()
+        );
+        assert!(
+            keystore
+                .import_private_key(&private_key, Some("1bob".into()))
+                .is_err()
+        );
+        let bob = keystore.import_private_key(&private_key, Some("bob".into()))?;
+        assert!(keystore.import_private_key(&private_key, None).is_err());
+        assert_eq!(keystore.export_private_key(&bob)?, private_key);
+
+        assert_eq!(keystore.update_alias("bob", Some("carol"))?, "carol");
+        assert!(keystore.update_alias("bob", None).is_err());
+        keystore.remove_key(&imported)?;
+        assert!(keystore.remove_key(&imported).is_err());
+
+        // Same format as the Sui CLI
+        let keys: Vec<String> = serde_json::from_reader(File::open(&path)?)?;
+        assert_eq!(keys.len(), 2);
+        let aliases: Vec<Alias> =
+            serde_json::from_reader(File::open(path.with_extension("aliases"))?)?;
+        assert_eq!(aliases.len(), 2);
+
+        let reloaded = FileBasedKeystore::new(path)?;
+        assert_eq!(reloaded.keys(), keystore.keys());
+        assert_eq!(reloaded.get_address_by_alias("carol".into())?, &bob);
+        assert!(reloaded.get_alias_by_address(&generated).is_ok());
+        assert!(reloaded.get_key(&imported).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn failed_save_keeps_memory() -> eyre::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir = temp_dir.path().join("config");
+        let mut keystore = FileBasedKeystore::new(dir.join("sui.keystore"))?;
+        let (address, _, _) = keystore.generate_and_add_new_key(
+            SignatureScheme::ED25519,
+            Some("alice".into()),
+            None,
+            None,
+        )?;
+        // Saving fails from now on
+        std::fs::remove_dir_all(&dir)?;
+        std::fs::write(&dir, "not a directory")?;
+
+        let (_, key, _, _) =
+            crate::key_derive::generate_new_key(SignatureScheme::ED25519, None, None)?;
+        assert!(keystore.add_key(Some("bob".into()), key).is_err());
+        assert_eq!(keystore.addresses(), [address]);
+        assert!(!keystore.alias_exists("bob"));
+
+        assert!(keystore.remove_key(&address).is_err());
+        assert_eq!(keystore.addresses(), [address]);
+        assert_eq!(keystore.get_alias_by_address(&address)?, "alice");
+
+        assert!(keystore.update_alias("alice", Some("carol")).is_err());
+        assert_eq!(keystore.get_alias_by_address(&address)?, "alice");
+        Ok(())
+    }
+
+    #[test]
+    fn in_mem_keystore() -> eyre::Result<()> {
+        let mut keystore = InMemKeystore::default();
+        let (address, _, _) =
+            keystore.generate_and_add_new_key(SignatureScheme::Secp256k1, None, None, Some(24))?;
+        let alias = keystore.get_alias_by_address(&address)?;
+        assert_eq!(keystore.get_address_by_alias(alias)?, &address);
+        assert!(keystore.sign_hashed(&address, &[0; 32]).is_ok());
+        Ok(())
+    }
 }
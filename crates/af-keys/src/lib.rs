@@ -1,6 +1,6 @@
 #![cfg_attr(all(doc, not(doctest)), feature(doc_auto_cfg))]
 
-//! Light-weight, read-only version of Sui's file-based keystore.
+//! Light-weight version of Sui's file-based keystore.
 //!
 //! This crate uses [`eyre`] and is meant for applications, not libraries.
 //!
@@ -25,9 +25,22 @@ pub use keystore::{FileBasedKeystore, ReadOnlyAccountKeystore};
 use multisig::{MultiSig, MultiSigSigner, ThresholdUnit};
 
 pub mod crypto;
+#[cfg(feature = "daemon")]
+pub mod daemon;
+#[cfg(feature = "encrypted")]
+pub mod encrypted;
 pub mod intent;
+pub mod key_derive;
 pub mod keystore;
 pub mod multisig;
+#[cfg(feature = "offline")]
+pub mod offline;
+pub mod policy;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod signer;
+#[cfg(test)]
+mod test_utils;
 
 /// Computes the required signatures for a transaction's data.
 ///
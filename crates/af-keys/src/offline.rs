@@ -0,0 +1,477 @@
+//! Portable format for signing transactions on offline (air-gapped) machines.
+//!
+//! The online machine builds a [`SigningRequest`] with everything needed to sign a transaction:
+//! its [`TransactionData`], the [`Intent`], the addresses that have to sign it (and their multisig
+//! public keys, if any) and a human-readable summary. The request is carried over as JSON, with
+//! the transaction encoded as base64 BCS bytes.
+//!
+//! The summary in the request is only informative, as the signing machine may print transactions
+//! differently. Each offline machine reviews the summary it rebuilds from the transaction bytes,
+//! [`SigningRequest::summary`], and calls [`SigningRequest::sign`], which signs for
+//! every required key it holds and produces a [`SigningResponse`]. Back online,
+//! [`SigningRequest::combine`] verifies the responses and merges them into the
+//! [`UserSignature`]s of the transaction, combining partial signatures for multisig addresses
+//! with [`MultiSig::combine`].
+use std::fmt::Write as _;
+
+use af_sui_types::{
+    Address as SuiAddress,
+    GasData,
+    TransactionData,
+    TransactionDataV1,
+    TransactionDigest,
+    TransactionExpiration,
+    TransactionKind,
+    UserSignature,
+};
+use eyre::{Context as _, Result, ensure, eyre};
+use serde::{Deserialize, Serialize};
+use serde_with::base64::Base64;
+use serde_with::{IfIsHumanReadable, serde_as};
+
+use crate::Intent;
+use crate::crypto::{PublicKey, Signature, SuiSignature as _};
+use crate::intent::IntentMessage;
+use crate::keystore::ReadOnlyAccountKeystore;
+use crate::multisig::{MultiSig, MultiSigPublicKey, ThresholdUnit};
+
+/// Everything an offline machine needs to sign a transaction.
+///
+/// Requests are only trusted after being checked against the transaction bytes they carry, which
+/// [`Self::sign`], [`Self::verify`] and [`Self::combine`] do before anything else.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningRequest {
+    /// BCS bytes of the [`TransactionData`], base64-encoded in human-readable formats.
+    #[serde_as(as = "IfIsHumanReadable<Base64>")]
+    tx_bytes: Vec<u8>,
+    intent: Intent,
+    /// The sender first, then the sponsor if different.
+    signers: Box<[RequiredSigner]>,
+    /// Summary of the transaction on the machine that made the request.
+    #[serde(default)]
+    summary: String,
+}
+
+/// An address that has to sign the transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequiredSigner {
+    pub address: SuiAddress,
+    /// The public key of `address` if it's a multisig.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multisig: Option<MultiSigPublicKey>,
+}
+
+/// Signatures produced by an offline machine for a [`SigningRequest`].
+///
+/// For multisig addresses these are the partial signatures of its members.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningResponse {
+    /// Digest of the transaction that was signed.
+    pub tx_digest: TransactionDigest,
+    pub signatures: Vec<Signature>,
+}
+
+impl SigningRequest {
+    /// Request signatures for `tx_data` from its sender and sponsor.
+    ///
+    /// As in [`crate::signatures`], there's no way to tell whether the sender or sponsor are
+    /// multisigs, so their public keys have to be passed in if they are.
+    pub fn new(
+        tx_data: &TransactionData,
+        multisig_sender: Option<MultiSigPublicKey>,
+        multisig_sponsor: Option<MultiSigPublicKey>,
+    ) -> Result<Self> {
+        let TransactionData::V1(TransactionDataV1 {
+            sender,
+            gas_data: GasData { owner: sponsor, .. },
+            ..
+        }) = tx_data;
+
+        let mut signers = vec![RequiredSigner {
+            address: *sender,
+            multisig: multisig_sender,
+        }];
+        if sender != sponsor {
+            signers.push(RequiredSigner {
+                address: *sponsor,
+                multisig: multisig_sponsor,
+            });
+        }
+
+        let request = Self {
+            tx_bytes: bcs::to_bytes(tx_data)?,
+            intent: Intent::sui_transaction(),
+            signers: signers.into(),
+            summary: summary(tx_data),
+        };
+        request.check()?;
+        Ok(request)
+    }
+
+    /// The transaction to be signed, after checking the rest of the request against it.
+    pub fn tx_data(&self) -> Result<TransactionData> {
+        self.check()
+    }
+
+    pub const fn intent(&self) -> &Intent {
+        &self.intent
+    }
+
+    pub const fn signers(&self) -> &[RequiredSigner] {
+        &self.signers
+    }
+
+    /// Human-readable description of the transaction, including its programmable transaction.
+    ///
+    /// Rebuilt from the transaction bytes, so it's the one to review before signing.
+    pub fn summary(&self) -> Result<String> {
+        Ok(summary(&self.check()?))
+    }
+
+    /// The summary made by the machine that requested the signatures.
+    ///
+    /// Not checked against the transaction, as other versions of this crate may print it
+    /// differently; review [`Self::summary`] instead.
+    pub fn sent_summary(&self) -> &str {
+        &self.summary
+    }
+
+    /// Sign for every required key in `keystore`.
+    ///
+    /// That includes the keys of multisig members, so a response may hold only part of the
+    /// signatures a multisig needs. Fails if `keystore` has none of the required keys.
+    pub fn sign<K: ReadOnlyAccountKeystore>(&self, keystore: &K) -> Result<SigningResponse> {
+        let tx_data = self.check()?;
+        let owned = keystore.addresses();
+        let mut signatures: Vec<Signature> = vec![];
+        for address in self.signer_addresses() {
+            if !owned.contains(&address)
+                || signatures.iter().any(|sig| {
+                    sig.to_public_key()
+                        .is_ok_and(|pk| pk.to_sui_address() == address)
+                })
+            {
+                continue;
+            }
+            signatures.push(
+                keystore
+                    .sign_secure(&address, &tx_data, self.intent.clone())
+                    .with_context(|| format!("Signing for {address}"))?,
+            );
+        }
+        ensure!(
+            !signatures.is_empty(),
+            "Keystore has none of the keys required to sign transaction {}",
+            tx_data.digest()
+        );
+        Ok(SigningResponse {
+            tx_digest: tx_data.digest(),
+            signatures,
+        })
+    }
+
+    /// Check that every signature in `response` is valid and comes from a required key.
+    pub fn verify(&self, response: &SigningResponse) -> Result<()> {
+        let tx_data = self.check()?;
+        self.verify_with(&tx_data, response)
+    }
+
+    /// Verify `responses` and combine them into the transaction's signatures, in the order
+    /// expected by the full node.
+    ///
+    /// Fails if a required signature is missing or if the signatures for a multisig don't reach
+    /// its threshold.
+    pub fn combine(
+        &self,
+        responses: impl IntoIterator<Item = SigningResponse>,
+    ) -> Result<Vec<UserSignature>> {
+        let tx_data = self.check()?;
+
+        let mut signatures: Vec<(PublicKey, Signature)> = vec![];
+        for response in responses {
+            self.verify_with(&tx_data, &response)?;
+            for sig in response.signatures {
+                let pk = sig.to_public_key()?;
+                if !signatures.iter().any(|(other, _)| *other == pk) {
+                    signatures.push((pk, sig));
+                }
+            }
+        }
+
+        self.signers
+            .iter()
+            .map(|RequiredSigner { address, multisig }| {
+                let Some(multisig_pk) = multisig else {
+                    let (_, sig) = signatures
+                        .iter()
+                        .find(|(pk, _)| pk.to_sui_address() == *address)
+                        .ok_or_else(|| eyre!("Missing signature for {address}"))?;
+                    return Ok(sig.clone().into());
+                };
+
+                let mut partial: Vec<_> = signatures
+                    .iter()
+                    .filter_map(|(pk, sig)| Some((multisig_pk.get_index(pk)?, sig.clone())))
+                    .collect();
+                partial.sort_by_key(|(idx, _)| *idx);
+                let weight: ThresholdUnit = partial
+                    .iter()
+                    .map(|(idx, _)| multisig_pk.pubkeys()[*idx as usize].1 as ThresholdUnit)
+                    .sum();
+                ensure!(
+                    weight >= *multisig_pk.threshold(),
+                    "Signatures for multisig {address} have weight {weight}, below its threshold \
+                     of {}",
+                    multisig_pk.threshold()
+                );
+                let sigs = partial.into_iter().map(|(_, sig)| sig).collect();
+                Ok(MultiSig::combine(sigs, multisig_pk.clone())
+                    .with_context(|| format!("Combining signatures for multisig {address}"))?
+                    .into())
+            })
+            .collect()
+    }
+
+    /// Decode the transaction and check the other fields against it.
+    fn check(&self) -> Result<TransactionData> {
+        let tx_data: TransactionData =
+            bcs::from_bytes(&self.tx_bytes).context("Deserializing transaction data")?;
+        ensure!(
+            self.intent == Intent::sui_transaction(),
+            "Unsupported intent {:?}",
+            self.intent
+        );
+
+        let TransactionData::V1(TransactionDataV1 {
+            sender,
+            gas_data: GasData { owner: sponsor, .. },
+            ..
+        }) = &tx_data;
+        let expected = if sender == sponsor {
+            vec![*sender]
+        } else {
+            vec![*sender, *sponsor]
+        };
+        let actual: Vec<_> = self.signers.iter().map(|s| s.address).collect();
+        ensure!(
+            actual == expected,
+            "Signers {actual:?} don't match the transaction's sender and sponsor {expected:?}"
+        );
+        for RequiredSigner { address, multisig } in &self.signers {
+            let Some(multisig_pk) = multisig else {
+                continue;
+            };
+            multisig_pk
+                .validate()
+                .map_err(|e| eyre!("Invalid multisig public key for {address}: {e}"))?;
+            let msig_address = SuiAddress::from(multisig_pk);
+            ensure!(
+                msig_address == *address,
+                "Multisig address {msig_address} doesn't match target address {address}"
+            );
+        }
+        Ok(tx_data)
+    }
+
+    fn verify_with(&self, tx_data: &TransactionData, response: &SigningResponse) -> Result<()> {
+        let digest = tx_data.digest();
+        ensure!(
+            response.tx_digest == digest,
+            "Response is for transaction {}, not {digest}",
+            response.tx_digest
+        );
+        let required = self.signer_addresses();
+        let message = IntentMessage::new(self.intent.clone(), tx_data);
+        for sig in &response.signatures {
+            let address = sig.to_public_key()?.to_sui_address();
+            ensure!(
+                required.contains(&address),
+                "Signature from {address}, which is not a required signer"
+            );
+            sig.verify_secure(&message, address, sig.scheme())
+                .with_context(|| format!("Verifying signature from {address}"))?;
+        }
+        Ok(())
+    }
+
+    /// Addresses of the keys that sign directly, i.e., simple signers and multisig members.
+    fn signer_addresses(&self) -> Vec<SuiAddress> {
+        self.signers
+            .iter()
+            .flat_map(|RequiredSigner { address, multisig }| {
+                multisig.as_ref().map_or_else(
+                    || vec![*address],
+                    |multisig_pk| {
+                        multisig_pk
+                            .pubkeys()
+                            .iter()
+                            .map(|(pk, _)| pk.to_sui_address())
+                            .collect()
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// Human-readable description of a transaction.
+///
+/// Programmable transactions are printed with [`af_ptbuilder::text::print`].
+pub fn summary(tx_data: &TransactionData) -> String {
+    let TransactionData::V1(TransactionDataV1 {
+        kind,
+        sender,
+        gas_data,
+        expiration,
+    }) = tx_data;
+
+    let mut summary = String::new();
+    // Writing to a `String` is infallible
+    let _ = writeln!(summary, "Digest: {}", tx_data.digest());
+    let _ = writeln!(summary, "Sender: {sender}");
+    let _ = writeln!(summary, "Gas owner: {}", gas_data.owner);
+    let _ = writeln!(summary, "Gas budget: {}", gas_data.budget);
+    let _ = writeln!(summary, "Gas price: {}", gas_data.price);
+    for (id, version, digest) in &gas_data.payment {
+        let _ = writeln!(
+            summary,
+            "Gas payment: {id} (version {version}, digest {digest})"
+        );
+    }
+    match expiration {
+        TransactionExpiration::None => {
+            let _ = writeln!(summary, "Expiration: none");
+        }
+        TransactionExpiration::Epoch(epoch) => {
+            let _ = writeln!(summary, "Expiration: epoch {epoch}");
+        }
+    }
+    match kind {
+        TransactionKind::ProgrammableTransaction(ptb) => {
+            let _ = writeln!(summary, "Programmable transaction:");
+            summary.push_str(&af_ptbuilder::text::print(ptb));
+        }
+        _ => {
+            let _ = writeln!(summary, "System transaction");
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::path::Path;
+
+    use super::*;
+    use crate::keystore::FileBasedKeystore;
+    use crate::multisig::MultiSigSigner;
+    use crate::test_utils::split_gas;
+
+    const KEYS: [&str; 4] = [
+        "AKd4u480uT0eLUNe7vh2zHHYdbpUXY/fwcL13eJQ5/zs",
+        "AI1TKQ0qPLor32rdLOZiN0/J4qNPyypesT1eE+R/wSCB",
+        "AFHMjegm2IwuiLemXb6o7XvuDL7xn1JTHc66CZefYY+B",
+        "APhbsR3gpjBIRvZm5ZwMZhncejgYH/hGa6wHVtaTat22",
+    ];
+
+    fn keystore(dir: &Path, name: &str, keys: &[&str]) -> eyre::Result<FileBasedKeystore> {
+        let path = dir.join(name);
+        serde_json::to_writer(File::create(&path)?, keys)?;
+        FileBasedKeystore::new(path)
+    }
+
+    #[test]
+    fn multisig_sender_and_sponsor() -> eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let all = keystore(dir.path(), "all.keystore", &KEYS)?;
+        let pks = all.keys();
+        let multisig_pk = MultiSigPublicKey::new(pks[..3].to_vec(), vec![1, 1, 1], 2)?;
+        let sender = SuiAddress::from(&multisig_pk);
+        let sponsor = pks[3].to_sui_address();
+        let tx_data = split_gas(sender, sponsor, 1_000);
+
+        let request = SigningRequest::new(&tx_data, Some(multisig_pk.clone()), None)?;
+        let json = serde_json::to_string(&request)?;
+        let request: SigningRequest = serde_json::from_str(&json)?;
+        assert_eq!(request.tx_data()?, tx_data);
+        assert!(request.summary()?.contains("SplitCoins"));
+        assert_eq!(request.sent_summary(), request.summary()?);
+
+        // Each offline machine holds some of the keys
+        let key = |pk: &PublicKey| {
+            let idx = pks.iter().position(|other| other == pk).expect("Known key");
+            KEYS[idx]
+        };
+        let first = keystore(dir.path(), "first.keystore", &[key(&pks[0]), key(&pks[3])])?;
+        let second = keystore(dir.path(), "second.keystore", &[key(&pks[2])])?;
+        let none = keystore(dir.path(), "none.keystore", &[])?;
+
+        let first = request.sign(&first)?;
+        let second = request.sign(&second)?;
+        assert!(request.sign(&none).is_err());
+        assert_eq!(first.signatures.len(), 2);
+        request.verify(&first)?;
+
+        let responses = [first, second].map(|r| {
+            serde_json::from_str(&serde_json::to_string(&r).expect("Serializable"))
+                .expect("Deserializable")
+        });
+        let combined = request.combine(responses.clone())?;
+
+        let expected = crate::signatures(
+            &tx_data,
+            Some(MultiSigSigner {
+                multisig_pk,
+                signers: vec![0, 2],
+            }),
+            None,
+            &all,
+        )?;
+        assert_eq!(combined, expected);
+
+        // Not enough weight for the multisig
+        assert!(request.combine(responses[..1].to_vec()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_tampering() -> eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let all = keystore(dir.path(), "all.keystore", &KEYS)?;
+        let pks = all.keys();
+        let sender = pks[0].to_sui_address();
+        let tx_data = split_gas(sender, sender, 1_000);
+        let request = SigningRequest::new(&tx_data, None, None)?;
+        assert_eq!(request.signers().len(), 1);
+
+        // The sent summary is informative, the one to review comes from the transaction
+        let mut forged = request.clone();
+        forged.summary = "Nothing to see here".into();
+        assert_eq!(forged.summary()?, summary(&tx_data));
+        forged.sign(&all)?;
+
+        let mut forged = request.clone();
+        forged.signers[0].address = pks[1].to_sui_address();
+        assert!(forged.sign(&all).is_err());
+
+        // Response for another transaction
+        let other = SigningRequest::new(
+            &split_gas(sender, pks[1].to_sui_address(), 1_000),
+            None,
+            None,
+        )?;
+        let response = other.sign(&all)?;
+        assert_eq!(response.signatures.len(), 2);
+        assert!(request.verify(&response).is_err());
+
+        // Signature from a key that isn't required
+        let mut response = request.sign(&all)?;
+        response
+            .signatures
+            .extend(other.sign(&all)?.signatures.into_iter().skip(1));
+        assert!(request.verify(&response).is_err());
+        Ok(())
+    }
+}
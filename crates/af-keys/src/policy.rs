@@ -0,0 +1,630 @@
+//! Rules on what transactions get signed.
+//!
+//! A [`PolicySigner`] wraps another [`TransactionSigner`] and only forwards the transactions
+//! allowed by its [`Policy`]: who it signs for, which Move functions may be called, whether
+//! packages may be published and how much SUI may be spent.
+//!
+//! Spend limits cover the SUI taken from the gas coin (the gas budget plus coins split from it),
+//! the only coin whose use can be checked from the transaction alone. So, with a spend limit or
+//! [`Policy::allowed_calls`] set, built-in commands (transfers, splits, merges and vectors) can't
+//! take owned objects other than the gas coin, except to merge coins into it. Owned objects
+//! passed to Move calls aren't checked: use [`Policy::allowed_calls`] to constrain those.
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::iter::once;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use af_sui_types::{
+    Address as SuiAddress,
+    Argument,
+    Command,
+    GasData,
+    Identifier,
+    MoveCall,
+    ObjectId,
+    ProgrammableTransaction,
+    TransactionData,
+    TransactionDataV1,
+    TransactionKind,
+    UserSignature,
+};
+use serde::{Deserialize, Serialize};
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use sui_sdk_types::Input;
+
+use crate::signer::TransactionSigner;
+
+/// What a [`PolicySigner`] is allowed to sign.
+///
+/// The default policy allows everything except publishing and upgrading packages.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// Addresses to sign for. If unset, any address the inner signer can sign for.
+    pub signers: Option<BTreeSet<SuiAddress>>,
+    /// Move calls allowed. If unset, any call is allowed.
+    pub allowed_calls: Option<Vec<CallPattern>>,
+    /// Whether transactions may publish or upgrade packages.
+    pub allow_publish: bool,
+    /// Maximum gas budget, in MIST.
+    pub max_gas_budget: Option<u64>,
+    /// Maximum SUI taken from the gas coin by a single transaction, in MIST.
+    pub max_spend_per_transaction: Option<u64>,
+    /// Maximum SUI taken from the gas coin over a rolling period.
+    pub spend_limit: Option<SpendLimit>,
+}
+
+/// Maximum amount spent over a rolling period.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendLimit {
+    /// In MIST.
+    pub amount: u64,
+    pub period_secs: u64,
+}
+
+/// Matches Move calls to a package, a module or a single function.
+///
+/// Written as `package`, `package::module` or `package::module::function`.
+#[derive(Clone, Debug, PartialEq, Eq, SerializeDisplay, DeserializeFromStr)]
+pub struct CallPattern {
+    pub package: ObjectId,
+    pub module: Option<Identifier>,
+    pub function: Option<Identifier>,
+}
+
+impl CallPattern {
+    pub fn matches(&self, call: &MoveCall) -> bool {
+        self.package == call.package
+            && self.module.as_ref().is_none_or(|m| *m == call.module)
+            && self.function.as_ref().is_none_or(|f| *f == call.function)
+    }
+}
+
+impl Display for CallPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.package)?;
+        if let Some(module) = &self.module {
+            write!(f, "::{module}")?;
+        }
+        if let Some(function) = &self.function {
+            write!(f, "::{function}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CallPattern {
+    type Err = InvalidCallPattern;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || InvalidCallPattern(s.to_owned());
+        let mut parts = s.split("::");
+        let package = parts.next().and_then(|p| p.parse().ok()).ok_or_else(err)?;
+        let mut identifier = || {
+            parts
+                .next()
+                .map(|part| Identifier::new(part).map_err(|_| err()))
+                .transpose()
+        };
+        let module = identifier()?;
+        let function = identifier()?;
+        if parts.next().is_some() {
+            return Err(err());
+        }
+        Ok(Self {
+            package,
+            module,
+            function,
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid call pattern {0:?}, expected package[::module[::function]]")]
+pub struct InvalidCallPattern(String);
+
+/// Why a transaction isn't allowed by a [`Policy`].
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum PolicyViolation {
+    #[error("Not allowed to sign for {0}")]
+    Signer(SuiAddress),
+    #[error("{0} is neither the sender nor the gas owner of the transaction")]
+    NotInvolved(SuiAddress),
+    #[error("Only programmable transactions can be signed")]
+    NotProgrammable,
+    #[error("Call to {package}::{module}::{function} is not allowed")]
+    Call {
+        package: ObjectId,
+        module: Identifier,
+        function: Identifier,
+    },
+    #[error("Publishing and upgrading packages is not allowed")]
+    Publish,
+    #[error("Gas budget {budget} is over the maximum of {max}")]
+    GasBudget { budget: u64, max: u64 },
+    #[error("Transaction uses the gas coin in a way whose spend can't be bounded")]
+    UnboundedSpend,
+    #[error("Command {command} uses owned object input {input} outside a Move call")]
+    OwnedObject { command: usize, input: u16 },
+    #[error("Transaction spends {amount} MIST, over the maximum of {max}")]
+    Spend { amount: u64, max: u64 },
+    #[error(
+        "Transaction spends {amount} MIST, but only {remaining} are left of the {limit} allowed \
+         every {period_secs}s"
+    )]
+    SpendLimit {
+        amount: u64,
+        remaining: u64,
+        limit: u64,
+        period_secs: u64,
+    },
+}
+
+impl Policy {
+    /// Check that signing `tx_data` as `address` is allowed, except for the rolling
+    /// [spend limit](Self::spend_limit) which depends on previous transactions.
+    ///
+    /// Returns the SUI that `address` spends from the gas coin, in MIST.
+    pub fn check(
+        &self,
+        address: SuiAddress,
+        tx_data: &TransactionData,
+    ) -> Result<u64, PolicyViolation> {
+        let TransactionData::V1(TransactionDataV1 {
+            kind,
+            sender,
+            gas_data: GasData { owner, budget, .. },
+            ..
+        }) = tx_data;
+
+        if self
+            .signers
+            .as_ref()
+            .is_some_and(|signers| !signers.contains(&address))
+        {
+            return Err(PolicyViolation::Signer(address));
+        }
+        if address != *sender && address != *owner {
+            return Err(PolicyViolation::NotInvolved(address));
+        }
+        let TransactionKind::ProgrammableTransaction(ptb) = kind else {
+            return Err(PolicyViolation::NotProgrammable);
+        };
+
+        let limited = self.max_spend_per_transaction.is_some() || self.spend_limit.is_some();
+        if limited || self.allowed_calls.is_some() {
+            check_owned_objects(ptb)?;
+        }
+        for command in &ptb.commands {
+            match command {
+                Command::MoveCall(call) => {
+                    if self
+                        .allowed_calls
+                        .as_ref()
+                        .is_some_and(|allowed| !allowed.iter().any(|p| p.matches(call)))
+                    {
+                        return Err(PolicyViolation::Call {
+                            package: call.package,
+                            module: call.module.clone(),
+                            function: call.function.clone(),
+                        });
+                    }
+                }
+                Command::Publish(_) | Command::Upgrade(_) if !self.allow_publish => {
+                    return Err(PolicyViolation::Publish);
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(max) = self.max_gas_budget {
+            if *budget > max {
+                return Err(PolicyViolation::GasBudget {
+                    budget: *budget,
+                    max,
+                });
+            }
+        }
+
+        // The gas coin belongs to the gas owner
+        if address != *owner {
+            return Ok(0);
+        }
+        let amount = match gas_coin_spend(ptb) {
+            Some(split) => split.saturating_add(*budget),
+            None if limited => return Err(PolicyViolation::UnboundedSpend),
+            None => u64::MAX,
+        };
+        if let Some(max) = self.max_spend_per_transaction {
+            if amount > max {
+                return Err(PolicyViolation::Spend { amount, max });
+            }
+        }
+        Ok(amount)
+    }
+}
+
+/// Check that built-in commands only take owned objects to merge them into the gas coin.
+fn check_owned_objects(ptb: &ProgrammableTransaction) -> Result<(), PolicyViolation> {
+    for (index, command) in ptb.commands.iter().enumerate() {
+        let args: Vec<&Argument> = match command {
+            // Merged coins are SUI and count once split from the gas coin
+            Command::MergeCoins(merge) if merge.coin == Argument::Gas => continue,
+            Command::MergeCoins(merge) => once(&merge.coin).chain(&merge.coins_to_merge).collect(),
+            Command::SplitCoins(split) => vec![&split.coin],
+            Command::TransferObjects(transfer) => transfer.objects.iter().collect(),
+            Command::MakeMoveVector(make) => make.elements.iter().collect(),
+            Command::MoveCall(_) | Command::Publish(_) | Command::Upgrade(_) => continue,
+        };
+        for arg in args {
+            if let Argument::Input(input) = *arg {
+                if let Some(Input::ImmutableOrOwned(_) | Input::Receiving(_)) =
+                    ptb.inputs.get(usize::from(input))
+                {
+                    return Err(PolicyViolation::OwnedObject {
+                        command: index,
+                        input,
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Sum of the amounts split from the gas coin, if that's its only use.
+fn gas_coin_spend(ptb: &ProgrammableTransaction) -> Option<u64> {
+    let pure_u64 = |arg: &Argument| match arg {
+        Argument::Input(i) => match ptb.inputs.get(*i as usize)? {
+            Input::Pure { value } => bcs::from_bytes::<u64>(value).ok(),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let mut total = 0u64;
+    for command in &ptb.commands {
+        let args: Vec<&Argument> = match command {
+            Command::SplitCoins(split) if split.coin == Argument::Gas => {
+                for amount in &split.amounts {
+                    total = total.checked_add(pure_u64(amount)?)?;
+                }
+                continue;
+            }
+            // Merging into the gas coin doesn't take from it
+            Command::MergeCoins(merge) if merge.coin == Argument::Gas => {
+                merge.coins_to_merge.iter().collect()
+            }
+            Command::MoveCall(call) => call.arguments.iter().collect(),
+            Command::TransferObjects(transfer) => transfer
+                .objects
+                .iter()
+                .chain(once(&transfer.address))
+                .collect(),
+            Command::SplitCoins(split) => once(&split.coin).chain(&split.amounts).collect(),
+            Command::MergeCoins(merge) => once(&merge.coin).chain(&merge.coins_to_merge).collect(),
+            Command::MakeMoveVector(make) => make.elements.iter().collect(),
+            Command::Upgrade(upgrade) => vec![&upgrade.ticket],
+            Command::Publish(_) => vec![],
+        };
+        if args.contains(&&Argument::Gas) {
+            return None;
+        }
+    }
+    Some(total)
+}
+
+/// [`TransactionSigner`] that only signs transactions allowed by a [`Policy`].
+pub struct PolicySigner<S> {
+    inner: S,
+    policy: Policy,
+    /// When each recent transaction was signed and how much it spent.
+    spent: Mutex<VecDeque<(Instant, u64)>>,
+}
+
+impl<S> PolicySigner<S> {
+    pub const fn new(inner: S, policy: Policy) -> Self {
+        Self {
+            inner,
+            policy,
+            spent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub const fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Check the policy and reserve `tx_data`'s spend against the rolling limit.
+    fn reserve(
+        &self,
+        address: SuiAddress,
+        tx_data: &TransactionData,
+    ) -> Result<Option<(Instant, u64)>, PolicyViolation> {
+        let amount = self.policy.check(address, tx_data)?;
+        let Some(SpendLimit {
+            amount: limit,
+            period_secs,
+        }) = self.policy.spend_limit
+        else {
+            return Ok(None);
+        };
+
+        let now = Instant::now();
+        let mut spent = self.spent.lock().expect("Not poisoned");
+        let period = Duration::from_secs(period_secs);
+        while spent
+            .front()
+            .is_some_and(|(time, _)| now.duration_since(*time) >= period)
+        {
+            spent.pop_front();
+        }
+        let remaining = limit.saturating_sub(spent.iter().map(|(_, a)| a).sum());
+        if amount > remaining {
+            return Err(PolicyViolation::SpendLimit {
+                amount,
+                remaining,
+                limit,
+                period_secs,
+            });
+        }
+        spent.push_back((now, amount));
+        drop(spent);
+        Ok(Some((now, amount)))
+    }
+
+    /// Undo a reservation whose transaction wasn't signed.
+    fn release(&self, reservation: (Instant, u64)) {
+        let mut spent = self.spent.lock().expect("Not poisoned");
+        if let Some(i) = spent.iter().position(|r| *r == reservation) {
+            spent.remove(i);
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PolicySignerError<E> {
+    #[error(transparent)]
+    Violation(#[from] PolicyViolation),
+    #[error("Signing: {0}")]
+    Signer(#[source] E),
+}
+
+impl<S: TransactionSigner + Sync> TransactionSigner for PolicySigner<S> {
+    type Error = PolicySignerError<S::Error>;
+
+    async fn sign_transaction(
+        &self,
+        address: SuiAddress,
+        tx_data: &TransactionData,
+    ) -> Result<UserSignature, Self::Error> {
+        let reservation = self.reserve(address, tx_data)?;
+        let result = self.inner.sign_transaction(address, tx_data).await;
+        if let (Err(_), Some(reservation)) = (&result, reservation) {
+            self.release(reservation);
+        }
+        result.map_err(PolicySignerError::Signer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use af_sui_types::ObjectDigest;
+    use sui_sdk_types::{MergeCoins, ObjectReference, SplitCoins, TransferObjects};
+
+    use super::*;
+    use crate::crypto::SignatureScheme;
+    use crate::keystore::{AccountKeystore as _, InMemKeystore};
+    use crate::test_utils::{split_gas, tx_data};
+
+    fn pay(sender: SuiAddress, amount: u64) -> TransactionData {
+        tx_data(
+            sender,
+            sender,
+            vec![
+                Input::Pure {
+                    value: bcs::to_bytes(&amount).expect("BCS"),
+                },
+                Input::Pure {
+                    value: bcs::to_bytes(&SuiAddress::ZERO).expect("BCS"),
+                },
+            ],
+            vec![
+                Command::SplitCoins(SplitCoins {
+                    coin: Argument::Gas,
+                    amounts: vec![Argument::Input(0)],
+                }),
+                Command::TransferObjects(TransferObjects {
+                    objects: vec![Argument::Result(0)],
+                    address: Argument::Input(1),
+                }),
+            ],
+        )
+    }
+
+    fn call(sender: SuiAddress, target: &str) -> TransactionData {
+        let pattern: CallPattern = target.parse().expect("Valid");
+        tx_data(
+            sender,
+            sender,
+            vec![],
+            vec![Command::MoveCall(MoveCall {
+                package: pattern.package,
+                module: pattern.module.expect("Module"),
+                function: pattern.function.expect("Function"),
+                type_arguments: vec![],
+                arguments: vec![],
+            })],
+        )
+    }
+
+    #[test]
+    fn call_patterns() -> eyre::Result<()> {
+        for s in ["0x2", "0x2::coin", "0x2::coin::split"] {
+            let pattern: CallPattern = s.parse()?;
+            assert_eq!(pattern.to_string().parse::<CallPattern>()?, pattern);
+        }
+        assert!("0x2::coin::split::extra".parse::<CallPattern>().is_err());
+        assert!("0x2::1coin".parse::<CallPattern>().is_err());
+        assert!("coin".parse::<CallPattern>().is_err());
+
+        let policy: Policy = serde_json::from_value(serde_json::json!({
+            "allowed_calls": ["0x2::coin", "0x3::staking::stake"],
+            "max_gas_budget": 10_000_000,
+        }))?;
+        let sender = SuiAddress::new([7; 32]);
+        assert!(
+            policy
+                .check(sender, &call(sender, "0x2::coin::split"))
+                .is_ok()
+        );
+        assert!(
+            policy
+                .check(sender, &call(sender, "0x3::staking::stake"))
+                .is_ok()
+        );
+        assert!(matches!(
+            policy.check(sender, &call(sender, "0x3::staking::unstake")),
+            Err(PolicyViolation::Call { .. })
+        ));
+        assert_eq!(
+            policy.check(SuiAddress::ZERO, &call(sender, "0x2::coin::split")),
+            Err(PolicyViolation::NotInvolved(SuiAddress::ZERO))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn spend_limits() {
+        let sender = SuiAddress::new([7; 32]);
+        let policy = Policy {
+            max_spend_per_transaction: Some(6_000_000),
+            ..Default::default()
+        };
+        assert_eq!(policy.check(sender, &pay(sender, 5_000_000)), Ok(6_000_000));
+        // Split coins count without being sent anywhere
+        assert_eq!(
+            policy.check(sender, &split_gas(sender, sender, 5_000_000)),
+            Ok(6_000_000)
+        );
+        assert_eq!(
+            policy.check(sender, &pay(sender, 5_000_001)),
+            Err(PolicyViolation::Spend {
+                amount: 6_000_001,
+                max: 6_000_000
+            })
+        );
+
+        // Sending the whole gas coin away
+        let mut send_gas = pay(sender, 0);
+        let TransactionData::V1(TransactionDataV1 {
+            kind: TransactionKind::ProgrammableTransaction(ptb),
+            ..
+        }) = &mut send_gas
+        else {
+            unreachable!()
+        };
+        ptb.commands[1] = Command::TransferObjects(TransferObjects {
+            objects: vec![Argument::Gas],
+            address: Argument::Input(1),
+        });
+        assert_eq!(
+            policy.check(sender, &send_gas),
+            Err(PolicyViolation::UnboundedSpend)
+        );
+        // Not a problem for the sender of a sponsored transaction
+        let TransactionData::V1(data) = &mut send_gas;
+        data.gas_data.owner = SuiAddress::ZERO;
+        assert_eq!(policy.check(sender, &send_gas), Ok(0));
+    }
+
+    #[test]
+    fn owned_coins() {
+        let sender = SuiAddress::new([7; 32]);
+        let coin = Input::ImmutableOrOwned(ObjectReference::new(
+            ObjectId::new([2; 32]),
+            1,
+            ObjectDigest::ZERO,
+        ));
+        let recipient = Input::Pure {
+            value: bcs::to_bytes(&SuiAddress::ZERO).expect("BCS"),
+        };
+        let send_coin = tx_data(
+            sender,
+            sender,
+            vec![coin.clone(), recipient],
+            vec![Command::TransferObjects(TransferObjects {
+                objects: vec![Argument::Input(0)],
+                address: Argument::Input(1),
+            })],
+        );
+        let policy = Policy {
+            max_spend_per_transaction: Some(6_000_000),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.check(sender, &send_coin),
+            Err(PolicyViolation::OwnedObject {
+                command: 0,
+                input: 0
+            })
+        );
+        // Nothing to bound without limits
+        assert!(Policy::default().check(sender, &send_coin).is_ok());
+
+        // Coins merged into the gas coin count once split from it
+        let merge = tx_data(
+            sender,
+            sender,
+            vec![coin],
+            vec![Command::MergeCoins(MergeCoins {
+                coin: Argument::Gas,
+                coins_to_merge: vec![Argument::Input(0)],
+            })],
+        );
+        assert_eq!(policy.check(sender, &merge), Ok(1_000_000));
+    }
+
+    #[tokio::test]
+    async fn rolling_spend_limit() -> eyre::Result<()> {
+        let mut keystore = InMemKeystore::default();
+        let (address, _, _) =
+            keystore.generate_and_add_new_key(SignatureScheme::ED25519, None, None, None)?;
+        let policy = Policy {
+            signers: Some([address].into()),
+            spend_limit: Some(SpendLimit {
+                amount: 5_000_000,
+                period_secs: 3600,
+            }),
+            ..Default::default()
+        };
+        let signer = PolicySigner::new(keystore, policy);
+
+        signer
+            .sign_transaction(address, &pay(address, 2_000_000))
+            .await?;
+        let result = signer
+            .sign_transaction(address, &pay(address, 2_000_000))
+            .await;
+        assert!(matches!(
+            result,
+            Err(PolicySignerError::Violation(PolicyViolation::SpendLimit {
+                amount: 3_000_000,
+                remaining: 2_000_000,
+                ..
+            }))
+        ));
+        signer
+            .sign_transaction(address, &pay(address, 1_000_000))
+            .await?;
+
+        let other = SuiAddress::new([1; 32]);
+        assert!(matches!(
+            signer.sign_transaction(other, &pay(other, 0)).await,
+            Err(PolicySignerError::Violation(PolicyViolation::Signer(_)))
+        ));
+        Ok(())
+    }
+}
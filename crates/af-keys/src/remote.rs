@@ -0,0 +1,162 @@
+//! [`TransactionSigner`] backed by a remote signing service.
+//!
+//! The service may listen on HTTP(S) or on a Unix socket, e.g., one served by the `daemon`
+//! module (feature `daemon`). See [`signer`](crate::signer) for the protocol.
+use std::path::PathBuf;
+
+use af_sui_types::{Address as SuiAddress, TransactionData, UserSignature};
+use bytes::Bytes;
+use fastcrypto::traits::ToFromBytes as _;
+use http_body_util::{BodyExt as _, Full};
+use hyper_util::rt::TokioIo;
+
+use crate::Intent;
+use crate::crypto::{Signature, SuiSignature as _};
+use crate::intent::IntentMessage;
+use crate::signer::{ErrorResponse, SIGN_PATH, SignRequest, SignResponse, TransactionSigner};
+
+/// Signs transactions by sending them to a remote service.
+///
+/// Signatures returned by the service are checked to be from the requested address and valid for
+/// the transaction. Only simple signatures can be checked this way, so multisig and zkLogin ones
+/// are rejected.
+#[derive(Clone, Debug)]
+pub struct RemoteSigner {
+    transport: Transport,
+}
+
+#[derive(Clone, Debug)]
+enum Transport {
+    Http {
+        client: reqwest::Client,
+        url: reqwest::Url,
+    },
+    Unix(PathBuf),
+}
+
+impl RemoteSigner {
+    /// Signer at `base_url`, which the signing path is appended to.
+    pub fn http(base_url: &str) -> Result<Self, RemoteSignerError> {
+        Self::with_client(reqwest::Client::new(), base_url)
+    }
+
+    /// Same as [`Self::http`], with a custom client (e.g., for timeouts or TLS settings).
+    pub fn with_client(client: reqwest::Client, base_url: &str) -> Result<Self, RemoteSignerError> {
+        let url = reqwest::Url::parse(base_url)?.join(SIGN_PATH)?;
+        Ok(Self {
+            transport: Transport::Http { client, url },
+        })
+    }
+
+    /// Signer listening on the Unix socket at `path`.
+    pub fn unix(path: impl Into<PathBuf>) -> Self {
+        Self {
+            transport: Transport::Unix(path.into()),
+        }
+    }
+
+    async fn request(&self, body: Vec<u8>) -> Result<(http::StatusCode, Bytes), RemoteSignerError> {
+        match &self.transport {
+            Transport::Http { client, url } => {
+                let response = client
+                    .post(url.clone())
+                    .header(http::header::CONTENT_TYPE, "application/json")
+                    .body(body)
+                    .send()
+                    .await?;
+                Ok((response.status(), response.bytes().await?))
+            }
+            Transport::Unix(path) => {
+                let stream = tokio::net::UnixStream::connect(path).await?;
+                let (mut sender, connection) =
+                    hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+                tokio::spawn(connection);
+                let request = http::Request::post(SIGN_PATH)
+                    .header(http::header::HOST, "localhost")
+                    .header(http::header::CONTENT_TYPE, "application/json")
+                    .body(Full::new(Bytes::from(body)))?;
+                let response = sender.send_request(request).await?;
+                let status = response.status();
+                Ok((status, response.into_body().collect().await?.to_bytes()))
+            }
+        }
+    }
+}
+
+impl TransactionSigner for RemoteSigner {
+    type Error = RemoteSignerError;
+
+    async fn sign_transaction(
+        &self,
+        address: SuiAddress,
+        tx_data: &TransactionData,
+    ) -> Result<UserSignature, Self::Error> {
+        let body = serde_json::to_vec(&SignRequest::new(address, tx_data))?;
+        let (status, body) = self.request(body).await?;
+        if !status.is_success() {
+            let message = serde_json::from_slice::<ErrorResponse>(&body)
+                .map_or_else(|_| String::from_utf8_lossy(&body).into_owned(), |e| e.error);
+            return Err(RemoteSignerError::Rejected { status, message });
+        }
+
+        let SignResponse { signature } = serde_json::from_slice(&body)?;
+        let signature = UserSignature::from_bytes(&signature)
+            .map_err(|e| RemoteSignerError::InvalidSignature(e.to_string()))?;
+        let UserSignature::Simple(_) = &signature else {
+            return Err(RemoteSignerError::InvalidSignature(format!(
+                "Expected a simple signature, got {:?}",
+                signature.scheme()
+            )));
+        };
+        verify_simple(address, tx_data, &signature)?;
+        Ok(signature)
+    }
+}
+
+fn verify_simple(
+    address: SuiAddress,
+    tx_data: &TransactionData,
+    signature: &UserSignature,
+) -> Result<(), RemoteSignerError> {
+    let invalid = |e: crate::crypto::Error| RemoteSignerError::InvalidSignature(e.to_string());
+    let sig = Signature::from_bytes(&signature.to_bytes())
+        .map_err(|e| RemoteSignerError::InvalidSignature(e.to_string()))?;
+    let signer = sig.to_public_key().map_err(invalid)?.to_sui_address();
+    if signer != address {
+        return Err(RemoteSignerError::WrongSigner {
+            expected: address,
+            actual: signer,
+        });
+    }
+    let message = IntentMessage::new(Intent::sui_transaction(), tx_data);
+    sig.verify_secure(&message, address, sig.scheme())
+        .map_err(invalid)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RemoteSignerError {
+    #[error("Invalid URL: {0}")]
+    Url(#[from] url::ParseError),
+    #[error("HTTP: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Connecting: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("HTTP: {0}")]
+    Hyper(#[from] hyper::Error),
+    #[error("Building request: {0}")]
+    Http(#[from] http::Error),
+    #[error("JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Signer responded with {status}: {message}")]
+    Rejected {
+        status: http::StatusCode,
+        message: String,
+    },
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("Signature is from {actual}, not {expected}")]
+    WrongSigner {
+        expected: SuiAddress,
+        actual: SuiAddress,
+    },
+}
@@ -0,0 +1,94 @@
+//! Signing abstracted from where the keys are.
+//!
+//! A [`TransactionSigner`] may sign in-process with a keystore, go through a
+//! [`PolicySigner`](crate::policy::PolicySigner) that only signs transactions allowed by a
+//! [`Policy`](crate::policy::Policy), or forward the request to a remote service with
+//! `RemoteSigner` (feature `remote`). Any signer can in turn be served to others with the
+//! `daemon` module (feature `daemon`).
+//!
+//! Remote signers speak JSON over HTTP: a `POST` to [`SIGN_PATH`] with a [`SignRequest`] is
+//! answered with a [`SignResponse`] or, on failure, an [`ErrorResponse`].
+use std::future::Future;
+
+use af_sui_types::{Address as SuiAddress, TransactionData, UserSignature};
+use serde::{Deserialize, Serialize};
+use serde_with::base64::Base64;
+use serde_with::serde_as;
+
+use crate::ReadOnlyAccountKeystore;
+
+/// Signs transactions on behalf of addresses.
+///
+/// Implemented for every [`ReadOnlyAccountKeystore`]. Implement it for external signers, e.g.,
+/// hardware wallets or remote services.
+pub trait TransactionSigner {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Sign `tx_data` as `address`.
+    fn sign_transaction(
+        &self,
+        address: SuiAddress,
+        tx_data: &TransactionData,
+    ) -> impl Future<Output = Result<UserSignature, Self::Error>> + Send;
+}
+
+impl<K: ReadOnlyAccountKeystore> TransactionSigner for K {
+    type Error = signature::Error;
+
+    fn sign_transaction(
+        &self,
+        address: SuiAddress,
+        tx_data: &TransactionData,
+    ) -> impl Future<Output = Result<UserSignature, Self::Error>> + Send {
+        let signature = self
+            .sign_hashed(&address, &tx_data.signing_digest())
+            .map(Into::into);
+        std::future::ready(signature)
+    }
+}
+
+/// Path of the signing endpoint of remote signers.
+pub const SIGN_PATH: &str = "/sign";
+
+/// Body of a request to a remote signer.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignRequest {
+    /// Address to sign as.
+    pub address: SuiAddress,
+    /// Base64-encoded BCS bytes of the [`TransactionData`].
+    #[serde_as(as = "Base64")]
+    pub tx_bytes: Vec<u8>,
+}
+
+impl SignRequest {
+    pub fn new(address: SuiAddress, tx_data: &TransactionData) -> Self {
+        Self {
+            address,
+            tx_bytes: bcs::to_bytes(tx_data).expect("TransactionData is BCS-compatible"),
+        }
+    }
+}
+
+/// Body of a successful response from a remote signer.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignResponse {
+    /// Base64-encoded signature, as sent to full nodes.
+    #[serde_as(as = "Base64")]
+    pub signature: Vec<u8>,
+}
+
+impl From<&UserSignature> for SignResponse {
+    fn from(value: &UserSignature) -> Self {
+        Self {
+            signature: value.to_bytes(),
+        }
+    }
+}
+
+/// Body of an error response from a remote signer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
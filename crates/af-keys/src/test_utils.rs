@@ -0,0 +1,54 @@
+//! Fixtures shared by the tests of several modules.
+use af_sui_types::{
+    Address as SuiAddress,
+    Argument,
+    Command,
+    GasData,
+    ObjectDigest,
+    ObjectId,
+    ProgrammableTransaction,
+    TransactionData,
+    TransactionExpiration,
+    TransactionKind,
+};
+use sui_sdk_types::{Input, SplitCoins};
+
+/// Mnemonic phrase of the test keys.
+pub(crate) const PHRASE: &str = "film crazy soon outside stand loop subway crumble thrive popular \
+                                 green nuclear struggle pistol arm wife phrase warfare march \
+                                 wheat nephew ask sunny firm";
+
+/// Programmable transaction of `sender`, with gas paid by `gas_owner`.
+pub(crate) fn tx_data(
+    sender: SuiAddress,
+    gas_owner: SuiAddress,
+    inputs: Vec<Input>,
+    commands: Vec<Command>,
+) -> TransactionData {
+    TransactionData::v1(
+        TransactionKind::ProgrammableTransaction(ProgrammableTransaction { inputs, commands }),
+        sender,
+        GasData {
+            payment: vec![(ObjectId::new([1; 32]), 1, ObjectDigest::ZERO)],
+            owner: gas_owner,
+            price: 1_000,
+            budget: 1_000_000,
+        },
+        TransactionExpiration::None,
+    )
+}
+
+/// [`tx_data`] splitting `amount` off the gas coin.
+pub(crate) fn split_gas(sender: SuiAddress, gas_owner: SuiAddress, amount: u64) -> TransactionData {
+    tx_data(
+        sender,
+        gas_owner,
+        vec![Input::Pure {
+            value: bcs::to_bytes(&amount).expect("BCS"),
+        }],
+        vec![Command::SplitCoins(SplitCoins {
+            coin: Argument::Gas,
+            amounts: vec![Argument::Input(0)],
+        })],
+    )
+}
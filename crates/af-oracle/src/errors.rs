@@ -1,15 +1,72 @@
 //! Error codes for Move aborts.
 #![expect(non_upper_case_globals, reason = "Copied from Move")]
 
-pub const PriceFeedAlreadyExists: u64 = 1;
-pub const PriceFeedDoesNotExist: u64 = 2;
-pub const InvalidSourceObjectForFeed: u64 = 3;
-pub const InvalidPriceValue: u64 = 5;
-pub const SourceAlreadyAuthorized: u64 = 6;
-pub const SourceNotAuthorized: u64 = 7;
-pub const SourceObjectIsNotRegistered: u64 = 8;
-pub const SymbolDoesNotExists: u64 = 9;
-pub const PriceIsTooHigh: u64 = 10;
-pub const SourceTimestampGreaterThanCurrentClockTimestamp: u64 = 11;
-pub const SourceTimestampOlderThanCurrentFeedTimestamp: u64 = 12;
-pub const SourceTimestampOlderThanTolerance: u64 = 13;
+macro_rules! move_aborts {
+    ($(
+        $(#[$meta:meta])*
+        const $Error:ident: u64 = $num:literal;
+    )*) => {
+        $(
+            $(#[$meta])*
+            pub const $Error: u64 = $num;
+        )*
+
+        /// Typed version of the abort codes above, e.g., for
+        /// [`AbortRegistry`](af_sui_types::execution_failure::AbortRegistry).
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, thiserror::Error)]
+        #[repr(u64)]
+        pub enum MoveAbort {$(
+            $(#[$meta])*
+            #[error("{}", stringify!($Error))]
+            $Error = $num,
+        )*}
+
+        impl TryFrom<u64> for MoveAbort {
+            type Error = u64;
+
+            fn try_from(code: u64) -> Result<Self, Self::Error> {
+                match code {
+                    $($num => Ok(Self::$Error),)*
+                    _ => Err(code),
+                }
+            }
+        }
+
+        impl From<MoveAbort> for u64 {
+            fn from(value: MoveAbort) -> Self {
+                value as Self
+            }
+        }
+    };
+}
+
+move_aborts! {
+    const PriceFeedAlreadyExists: u64 = 1;
+    const PriceFeedDoesNotExist: u64 = 2;
+    const InvalidSourceObjectForFeed: u64 = 3;
+    const InvalidPriceValue: u64 = 5;
+    const SourceAlreadyAuthorized: u64 = 6;
+    const SourceNotAuthorized: u64 = 7;
+    const SourceObjectIsNotRegistered: u64 = 8;
+    const SymbolDoesNotExists: u64 = 9;
+    const PriceIsTooHigh: u64 = 10;
+    const SourceTimestampGreaterThanCurrentClockTimestamp: u64 = 11;
+    const SourceTimestampOlderThanCurrentFeedTimestamp: u64 = 12;
+    const SourceTimestampOlderThanTolerance: u64 = 13;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MoveAbort;
+
+    #[test]
+    fn code_conversions() {
+        assert_eq!(u64::from(MoveAbort::PriceIsTooHigh), 10);
+        assert_eq!(MoveAbort::try_from(5), Ok(MoveAbort::InvalidPriceValue));
+        assert_eq!(MoveAbort::try_from(4), Err(4));
+        assert_eq!(
+            MoveAbort::SymbolDoesNotExists.to_string(),
+            "SymbolDoesNotExists"
+        );
+    }
+}
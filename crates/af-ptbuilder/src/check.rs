@@ -0,0 +1,1011 @@
+//! Static type checking of programmable transactions.
+//!
+//! [`ProgrammableTransactionBuilder`] accepts any [`Argument`] anywhere, so a malformed
+//! transaction is only rejected once it's executed. [`check_ptb`] (or
+//! [`ProgrammableTransactionBuilder::type_check`]) replays the commands of a transaction, tracking
+//! the type of every result and whether values were moved, and reports the first command that
+//! would fail:
+//! - arguments referring to inputs or results that don't exist (yet);
+//! - values used after being moved, e.g., a coin after `TransferObjects`;
+//! - type mismatches between arguments and Move parameters, coins of different types and
+//!   `MakeMoveVec` elements;
+//! - pure inputs where objects are expected and vice versa, immutable objects taken by mutable
+//!   reference or by value, and the gas coin taken by value outside of `TransferObjects`;
+//! - with the `move-type` feature, pure inputs added with
+//!   [`move_pure`](ProgrammableTransactionBuilder::move_pure) (or as `input pure<T>`) whose Rust
+//!   type doesn't represent the parameter's Move type. Only the builder knows those, so
+//!   [`check_ptb`] can't tell.
+//!
+//! Move function signatures come from a [`Signatures`] implementation: either a map of
+//! hand-written [`FunctionSignature`]s or, e.g., `sui-jsonrpc`'s cache of normalized modules.
+//!
+//! The types of object inputs aren't known up front; each is inferred from its first use and
+//! checked against the following ones. Neither is whether an `ImmutableOrOwned` input is
+//! immutable; use a [`Checker`] with [`Checker::with_immutable_objects`] to say which are.
+use std::collections::HashMap;
+
+use af_sui_types::{
+    Address,
+    Argument,
+    Command,
+    Identifier,
+    MOVE_STDLIB_ADDRESS,
+    MoveCall,
+    ObjectId,
+    ProgrammableTransaction,
+    SUI_FRAMEWORK_ADDRESS,
+    StructTag,
+    TypeTag,
+};
+use sui_sdk_types::Input;
+
+use crate::ProgrammableTransactionBuilder;
+
+/// A type in a Move function signature.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SignatureType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    Address,
+    Signer,
+    Vector(Box<Self>),
+    Struct {
+        address: Address,
+        module: Identifier,
+        name: Identifier,
+        type_arguments: Vec<Self>,
+    },
+    /// Index into the function's type parameters.
+    TypeParameter(u16),
+    Reference(Box<Self>),
+    MutableReference(Box<Self>),
+}
+
+impl SignatureType {
+    /// The type with its type parameters replaced by `type_arguments`.
+    ///
+    /// References are kept only at the top level, as in parameters and return types.
+    pub(crate) fn instantiate(&self, type_arguments: &[TypeTag]) -> Option<(Reference, TypeTag)> {
+        Some(match self {
+            Self::Reference(inner) => (Reference::Immutable, inner.type_tag(type_arguments)?),
+            Self::MutableReference(inner) => (Reference::Mutable, inner.type_tag(type_arguments)?),
+            _ => (Reference::None, self.type_tag(type_arguments)?),
+        })
+    }
+
+    fn type_tag(&self, type_arguments: &[TypeTag]) -> Option<TypeTag> {
+        Some(match self {
+            Self::Bool => TypeTag::Bool,
+            Self::U8 => TypeTag::U8,
+            Self::U16 => TypeTag::U16,
+            Self::U32 => TypeTag::U32,
+            Self::U64 => TypeTag::U64,
+            Self::U128 => TypeTag::U128,
+            Self::U256 => TypeTag::U256,
+            Self::Address => TypeTag::Address,
+            Self::Signer => TypeTag::Signer,
+            Self::Vector(inner) => TypeTag::Vector(Box::new(inner.type_tag(type_arguments)?)),
+            Self::Struct {
+                address,
+                module,
+                name,
+                type_arguments: params,
+            } => TypeTag::Struct(Box::new(StructTag {
+                address: *address,
+                module: module.clone(),
+                name: name.clone(),
+                type_params: params
+                    .iter()
+                    .map(|p| p.type_tag(type_arguments))
+                    .collect::<Option<_>>()?,
+            })),
+            Self::TypeParameter(i) => type_arguments.get(usize::from(*i))?.clone(),
+            Self::Reference(_) | Self::MutableReference(_) => return None,
+        })
+    }
+
+    /// Whether this is `&TxContext` or `&mut TxContext`, which PTBs pass implicitly.
+    pub(crate) fn is_tx_context(&self) -> bool {
+        let (Self::Reference(inner) | Self::MutableReference(inner)) = self else {
+            return false;
+        };
+        matches!(
+            &**inner,
+            Self::Struct { address, module, name, .. }
+                if *address == SUI_FRAMEWORK_ADDRESS && module == "tx_context" && name == "TxContext"
+        )
+    }
+}
+
+/// The parameters and return types of a Move function.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FunctionSignature {
+    /// Number of type parameters.
+    pub type_parameters: usize,
+    /// Parameters, optionally including a trailing `TxContext` reference.
+    pub parameters: Vec<SignatureType>,
+    pub returns: Vec<SignatureType>,
+}
+
+/// Source of Move function signatures for the checker.
+pub trait Signatures {
+    fn function_signature(
+        &self,
+        package: &ObjectId,
+        module: &Identifier,
+        function: &Identifier,
+    ) -> Option<FunctionSignature>;
+
+    /// Whether values of the struct type have the `copy` ability, if known.
+    ///
+    /// Values whose type isn't known to be copyable are considered moved when passed by value.
+    fn is_copyable(&self, _type: &StructTag) -> Option<bool> {
+        None
+    }
+}
+
+/// Signatures supplied by hand.
+impl Signatures for HashMap<(ObjectId, Identifier, Identifier), FunctionSignature> {
+    fn function_signature(
+        &self,
+        package: &ObjectId,
+        module: &Identifier,
+        function: &Identifier,
+    ) -> Option<FunctionSignature> {
+        self.get(&(*package, module.clone(), function.clone()))
+            .cloned()
+    }
+}
+
+impl<T: Signatures + ?Sized> Signatures for &T {
+    fn function_signature(
+        &self,
+        package: &ObjectId,
+        module: &Identifier,
+        function: &Identifier,
+    ) -> Option<FunctionSignature> {
+        (**self).function_signature(package, module, function)
+    }
+
+    fn is_copyable(&self, type_: &StructTag) -> Option<bool> {
+        (**self).is_copyable(type_)
+    }
+}
+
+impl ProgrammableTransactionBuilder {
+    /// Type check the commands added so far. See the [`check`](crate::check) module.
+    pub fn type_check(&self, signatures: &impl Signatures) -> Result<(), TypeError> {
+        let mut checker = Checker::new(self.inputs.values(), signatures);
+        for (i, checks) in &self.pure_types {
+            checker.inputs[*i].pure_types.clone_from(checks);
+        }
+        checker.check(&self.commands)
+    }
+}
+
+/// Type check a finished transaction. See the [module](crate::check) documentation.
+pub fn check_ptb(
+    ptb: &ProgrammableTransaction,
+    signatures: &impl Signatures,
+) -> Result<(), TypeError> {
+    Checker::new(&ptb.inputs, signatures).check(&ptb.commands)
+}
+
+/// A command that would fail to execute.
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+#[error("In command {command}: {kind}")]
+pub struct TypeError {
+    pub command: usize,
+    pub kind: TypeErrorKind,
+}
+
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+pub enum TypeErrorKind {
+    #[error("No signature for {package}::{module}::{function}")]
+    FunctionNotFound {
+        package: ObjectId,
+        module: Identifier,
+        function: Identifier,
+    },
+    #[error("Expected {expected} type arguments, got {found}")]
+    TypeArgumentCount { expected: usize, found: usize },
+    #[error("Expected {expected} arguments, got {found}")]
+    ArgumentCount { expected: usize, found: usize },
+    #[error("MakeMoveVec without elements needs a type")]
+    UntypedEmptyVector,
+    /// An argument, by its position in the command.
+    #[error("Argument {index}: {error}")]
+    Argument { index: usize, error: ArgumentError },
+}
+
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+pub enum ArgumentError {
+    #[error("Input {0} doesn't exist")]
+    InputOutOfBounds(u16),
+    #[error("Result of command {0} isn't available yet")]
+    FutureResult(u16),
+    #[error("Command {result} has {count} results, so it can't be used as a single value")]
+    NotSingleResult { result: u16, count: usize },
+    #[error("Command {result} has no result {index}")]
+    NestedResultOutOfBounds { result: u16, index: u16 },
+    #[error("Value was moved in command {0}")]
+    UsedAfterMove(usize),
+    #[error("Expected type {expected}, got {found}")]
+    TypeMismatch {
+        expected: Box<TypeTag>,
+        found: Box<TypeTag>,
+    },
+    #[error("Expected a coin, got {0}")]
+    NotACoin(Box<TypeTag>),
+    #[error("Pure input for a parameter of type {0}")]
+    PureForObject(Box<TypeTag>),
+    #[error("Object input for a parameter of type {0}")]
+    ObjectForPure(Box<TypeTag>),
+    #[error("Pure input encoded from a Rust type that doesn't represent {0}")]
+    PureType(Box<TypeTag>),
+    #[error("Immutable object taken by mutable reference or by value")]
+    ImmutableObject,
+    #[error("Expected a receiving input")]
+    ExpectedReceiving,
+    #[error("Receiving input for a parameter that isn't `Receiving`")]
+    UnexpectedReceiving,
+    #[error("The gas coin can only be taken by value by TransferObjects")]
+    GasByValue,
+}
+
+// =============================================================================
+//  Checker
+// =============================================================================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Reference {
+    None,
+    Immutable,
+    Mutable,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Origin {
+    Pure,
+    Object { mutable: bool },
+    Receiving,
+    Gas,
+    Result,
+}
+
+/// Whether a pure input can be passed as a value of the Move type.
+pub(crate) type PureTypeCheck = fn(&TypeTag) -> bool;
+
+/// A value available to commands.
+#[derive(Clone, Debug)]
+struct Value {
+    origin: Origin,
+    /// [`None`] for pure inputs and objects not used yet.
+    type_: Option<TypeTag>,
+    /// The command that took the value.
+    moved_in: Option<usize>,
+    /// For pure inputs, checks from the Rust types that encoded them.
+    pure_types: Vec<PureTypeCheck>,
+}
+
+impl Value {
+    const fn new(origin: Origin, type_: Option<TypeTag>) -> Self {
+        Self {
+            origin,
+            type_,
+            moved_in: None,
+            pure_types: vec![],
+        }
+    }
+}
+
+/// Replays commands one at a time, for when [`check_ptb`] isn't enough.
+pub struct Checker<S> {
+    signatures: S,
+    gas: Value,
+    inputs: Vec<Value>,
+    /// The object ids of `ImmutableOrOwned` inputs, by input index.
+    imm_or_owned: Vec<Option<ObjectId>>,
+    results: Vec<Vec<Value>>,
+}
+
+type ArgResult<T> = Result<T, ArgumentError>;
+
+impl<S: Signatures> Checker<S> {
+    pub fn new<'a>(inputs: impl IntoIterator<Item = &'a Input>, signatures: S) -> Self {
+        let (inputs, imm_or_owned) = inputs
+            .into_iter()
+            .map(|input| {
+                let (origin, id) = match input {
+                    Input::Pure { .. } => (Origin::Pure, None),
+                    Input::ImmutableOrOwned(oref) => {
+                        (Origin::Object { mutable: true }, Some(*oref.object_id()))
+                    }
+                    Input::Shared { mutable, .. } => (Origin::Object { mutable: *mutable }, None),
+                    Input::Receiving(_) => (Origin::Receiving, None),
+                };
+                (Value::new(origin, None), id)
+            })
+            .unzip();
+        Self {
+            signatures,
+            gas: Value::new(Origin::Gas, Some(StructTag::gas_coin().into())),
+            inputs,
+            imm_or_owned,
+            results: vec![],
+        }
+    }
+
+    /// Treat the `ImmutableOrOwned` inputs of these objects as immutable.
+    ///
+    /// An input doesn't say whether the object is immutable or owned, so it's taken to be owned
+    /// unless listed here.
+    pub fn with_immutable_objects(mut self, objects: &[ObjectId]) -> Self {
+        for (value, id) in self.inputs.iter_mut().zip(&self.imm_or_owned) {
+            if id.is_some_and(|id| objects.contains(&id)) {
+                value.origin = Origin::Object { mutable: false };
+            }
+        }
+        self
+    }
+
+    /// Check `commands`, in order, after those checked so far.
+    pub fn check<'a>(
+        &mut self,
+        commands: impl IntoIterator<Item = &'a Command>,
+    ) -> Result<(), TypeError> {
+        for command in commands {
+            self.command(self.results.len(), command)?;
+        }
+        Ok(())
+    }
+
+    fn command(&mut self, i: usize, command: &Command) -> Result<(), TypeError> {
+        let results = self
+            .command_results(i, command)
+            .map_err(|kind| TypeError { command: i, kind })?;
+        self.results.push(results);
+        Ok(())
+    }
+
+    fn command_results(
+        &mut self,
+        i: usize,
+        command: &Command,
+    ) -> Result<Vec<Value>, TypeErrorKind> {
+        let arg_error = |index: usize| move |error| TypeErrorKind::Argument { index, error };
+        Ok(match command {
+            Command::MoveCall(call) => return self.move_call(i, call),
+            Command::TransferObjects(transfer) => {
+                for (index, object) in transfer.objects.iter().enumerate() {
+                    self.transfer(i, *object).map_err(arg_error(index))?;
+                }
+                self.use_as(i, transfer.address, Reference::None, &TypeTag::Address)
+                    .map_err(arg_error(transfer.objects.len()))?;
+                vec![]
+            }
+            Command::SplitCoins(split) => {
+                let coin = self.coin(i, split.coin).map_err(arg_error(0))?;
+                for (index, amount) in split.amounts.iter().enumerate() {
+                    self.use_as(i, *amount, Reference::None, &TypeTag::U64)
+                        .map_err(arg_error(index + 1))?;
+                }
+                let value = Value::new(Origin::Result, coin);
+                vec![value; split.amounts.len()]
+            }
+            Command::MergeCoins(merge) => {
+                let coin = self.coin(i, merge.coin).map_err(arg_error(0))?;
+                for (index, source) in merge.coins_to_merge.iter().enumerate() {
+                    let arg_error = arg_error(index + 1);
+                    match &coin {
+                        Some(type_) => self.use_as(i, *source, Reference::None, type_),
+                        None => self.take_object(i, *source).map(drop),
+                    }
+                    .map_err(arg_error)?;
+                }
+                vec![]
+            }
+            Command::MakeMoveVector(make) => {
+                let mut type_ = make.type_.clone();
+                for (index, element) in make.elements.iter().enumerate() {
+                    match &type_ {
+                        Some(type_) => self.use_as(i, *element, Reference::None, type_),
+                        None => self.take_object(i, *element).map(|t| type_ = t),
+                    }
+                    .map_err(arg_error(index))?;
+                }
+                if make.elements.is_empty() && type_.is_none() {
+                    return Err(TypeErrorKind::UntypedEmptyVector);
+                }
+                let vector = type_.map(|t| TypeTag::Vector(Box::new(t)));
+                vec![Value::new(Origin::Result, vector)]
+            }
+            Command::Publish(_) => {
+                vec![Value::new(Origin::Result, Some(package_type("UpgradeCap")))]
+            }
+            Command::Upgrade(upgrade) => {
+                self.use_as(
+                    i,
+                    upgrade.ticket,
+                    Reference::None,
+                    &package_type("UpgradeTicket"),
+                )
+                .map_err(arg_error(0))?;
+                vec![Value::new(
+                    Origin::Result,
+                    Some(package_type("UpgradeReceipt")),
+                )]
+            }
+        })
+    }
+
+    fn move_call(&mut self, i: usize, call: &MoveCall) -> Result<Vec<Value>, TypeErrorKind> {
+        let MoveCall {
+            package,
+            module,
+            function,
+            type_arguments,
+            arguments,
+        } = call;
+        let mut signature = self
+            .signatures
+            .function_signature(package, module, function)
+            .ok_or_else(|| TypeErrorKind::FunctionNotFound {
+                package: *package,
+                module: module.clone(),
+                function: function.clone(),
+            })?;
+        if signature.type_parameters != type_arguments.len() {
+            return Err(TypeErrorKind::TypeArgumentCount {
+                expected: signature.type_parameters,
+                found: type_arguments.len(),
+            });
+        }
+        if signature
+            .parameters
+            .last()
+            .is_some_and(SignatureType::is_tx_context)
+        {
+            signature.parameters.pop();
+        }
+        if signature.parameters.len() != arguments.len() {
+            return Err(TypeErrorKind::ArgumentCount {
+                expected: signature.parameters.len(),
+                found: arguments.len(),
+            });
+        }
+        // Signatures with out-of-bounds type parameters can't be checked
+        let Some(parameters) = signature
+            .parameters
+            .iter()
+            .map(|p| p.instantiate(type_arguments))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Err(TypeErrorKind::TypeArgumentCount {
+                expected: signature.type_parameters,
+                found: type_arguments.len(),
+            });
+        };
+        for (index, ((reference, type_), argument)) in parameters.iter().zip(arguments).enumerate()
+        {
+            self.use_as(i, *argument, *reference, type_)
+                .map_err(|error| TypeErrorKind::Argument { index, error })?;
+        }
+        Ok(signature
+            .returns
+            .iter()
+            .map(|r| Value::new(Origin::Result, r.type_tag(type_arguments)))
+            .collect())
+    }
+
+    /// Use `argument` as a parameter of type `type_`, passed by `reference`.
+    fn use_as(
+        &mut self,
+        i: usize,
+        argument: Argument,
+        reference: Reference,
+        type_: &TypeTag,
+    ) -> ArgResult<()> {
+        let copyable = self.is_copyable(type_);
+        let value = self.value(argument)?;
+        if let Some(moved_in) = value.moved_in {
+            return Err(ArgumentError::UsedAfterMove(moved_in));
+        }
+        let is_receiving = is_receiving(type_);
+        match value.origin {
+            Origin::Pure if !is_pure(type_) => {
+                return Err(ArgumentError::PureForObject(Box::new(type_.clone())));
+            }
+            Origin::Pure if !value.pure_types.iter().all(|fits| fits(type_)) => {
+                return Err(ArgumentError::PureType(Box::new(type_.clone())));
+            }
+            Origin::Pure => return Ok(()),
+            Origin::Receiving if !is_receiving => return Err(ArgumentError::UnexpectedReceiving),
+            Origin::Object { .. } | Origin::Gas if is_receiving => {
+                return Err(ArgumentError::ExpectedReceiving);
+            }
+            Origin::Object { .. } | Origin::Gas | Origin::Receiving if is_pure(type_) => {
+                return Err(ArgumentError::ObjectForPure(Box::new(type_.clone())));
+            }
+            Origin::Object { mutable: false } if reference != Reference::Immutable => {
+                return Err(ArgumentError::ImmutableObject);
+            }
+            Origin::Gas if reference == Reference::None => return Err(ArgumentError::GasByValue),
+            _ => (),
+        }
+        match &value.type_ {
+            Some(found) if found != type_ => {
+                return Err(ArgumentError::TypeMismatch {
+                    expected: Box::new(type_.clone()),
+                    found: Box::new(found.clone()),
+                });
+            }
+            Some(_) => (),
+            None => value.type_ = Some(type_.clone()),
+        }
+        if reference == Reference::None && !copyable {
+            value.moved_in = Some(i);
+        }
+        Ok(())
+    }
+
+    /// Take an object by value when its type isn't constrained, e.g., by `TransferObjects`.
+    fn take_object(&mut self, i: usize, argument: Argument) -> ArgResult<Option<TypeTag>> {
+        let value = self.value(argument)?;
+        if let Some(moved_in) = value.moved_in {
+            return Err(ArgumentError::UsedAfterMove(moved_in));
+        }
+        match value.origin {
+            Origin::Pure => {
+                let type_ = value.type_.clone().unwrap_or(TypeTag::U8);
+                return Err(ArgumentError::PureForObject(Box::new(type_)));
+            }
+            Origin::Receiving => return Err(ArgumentError::UnexpectedReceiving),
+            Origin::Object { mutable: false } => return Err(ArgumentError::ImmutableObject),
+            Origin::Gas => return Err(ArgumentError::GasByValue),
+            _ => (),
+        }
+        if let Some(type_) = value.type_.as_ref().filter(|t| is_pure(t)) {
+            return Err(ArgumentError::ObjectForPure(Box::new(type_.clone())));
+        }
+        value.moved_in = Some(i);
+        Ok(value.type_.clone())
+    }
+
+    /// Like [`Self::take_object`], but the gas coin may be transferred.
+    fn transfer(&mut self, i: usize, argument: Argument) -> ArgResult<()> {
+        if argument == Argument::Gas {
+            let gas = &mut self.gas;
+            if let Some(moved_in) = gas.moved_in {
+                return Err(ArgumentError::UsedAfterMove(moved_in));
+            }
+            gas.moved_in = Some(i);
+            return Ok(());
+        }
+        self.take_object(i, argument).map(drop)
+    }
+
+    /// Borrow a coin mutably, returning its type if known.
+    fn coin(&mut self, i: usize, argument: Argument) -> ArgResult<Option<TypeTag>> {
+        let value = self.value(argument)?;
+        let type_ = match &value.type_ {
+            Some(TypeTag::Struct(tag)) if tag.is_coin().is_some() => TypeTag::Struct(tag.clone()),
+            Some(other) => return Err(ArgumentError::NotACoin(Box::new(other.clone()))),
+            None if value.origin == Origin::Pure => {
+                return Err(ArgumentError::PureForObject(Box::new(coin_of_any())));
+            }
+            None => {
+                // Any coin will do; the first use of the object fixes its type
+                return self.borrow_untyped(argument).map(|()| None);
+            }
+        };
+        self.use_as(i, argument, Reference::Mutable, &type_)?;
+        Ok(Some(type_))
+    }
+
+    fn borrow_untyped(&mut self, argument: Argument) -> ArgResult<()> {
+        let value = self.value(argument)?;
+        if let Some(moved_in) = value.moved_in {
+            return Err(ArgumentError::UsedAfterMove(moved_in));
+        }
+        match value.origin {
+            Origin::Receiving => Err(ArgumentError::UnexpectedReceiving),
+            Origin::Object { mutable: false } => Err(ArgumentError::ImmutableObject),
+            _ => Ok(()),
+        }
+    }
+
+    fn value(&mut self, argument: Argument) -> ArgResult<&mut Value> {
+        match argument {
+            Argument::Gas => Ok(&mut self.gas),
+            Argument::Input(i) => self
+                .inputs
+                .get_mut(usize::from(i))
+                .ok_or(ArgumentError::InputOutOfBounds(i)),
+            Argument::Result(result) => {
+                let values = self
+                    .results
+                    .get_mut(usize::from(result))
+                    .ok_or(ArgumentError::FutureResult(result))?;
+                let count = values.len();
+                match &mut values[..] {
+                    [value] => Ok(value),
+                    _ => Err(ArgumentError::NotSingleResult { result, count }),
+                }
+            }
+            Argument::NestedResult(result, index) => self
+                .results
+                .get_mut(usize::from(result))
+                .ok_or(ArgumentError::FutureResult(result))?
+                .get_mut(usize::from(index))
+                .ok_or(ArgumentError::NestedResultOutOfBounds { result, index }),
+        }
+    }
+
+    fn is_copyable(&self, type_: &TypeTag) -> bool {
+        match type_ {
+            TypeTag::Signer => false,
+            TypeTag::Vector(inner) => self.is_copyable(inner),
+            TypeTag::Struct(tag) => {
+                if is_pure(type_) {
+                    return true;
+                }
+                self.signatures.is_copyable(tag).unwrap_or(false)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Whether values of the type can be passed as pure inputs.
+pub(crate) fn is_pure(type_: &TypeTag) -> bool {
+    match type_ {
+        TypeTag::Signer => false,
+        TypeTag::Vector(inner) => is_pure(inner),
+        TypeTag::Struct(tag) => {
+            let StructTag {
+                address,
+                module,
+                name,
+                type_params,
+            } = &**tag;
+            let is = |a: Address, m: &str, n: &str| *address == a && module == m && name == n;
+            if is(MOVE_STDLIB_ADDRESS, "option", "Option") {
+                type_params.first().is_some_and(is_pure)
+            } else {
+                is(MOVE_STDLIB_ADDRESS, "string", "String")
+                    || is(MOVE_STDLIB_ADDRESS, "ascii", "String")
+                    || is(SUI_FRAMEWORK_ADDRESS, "object", "ID")
+            }
+        }
+        _ => true,
+    }
+}
+
+fn is_receiving(type_: &TypeTag) -> bool {
+    matches!(
+        type_,
+        TypeTag::Struct(tag)
+            if tag.address == SUI_FRAMEWORK_ADDRESS
+                && tag.module.as_str() == "transfer"
+                && tag.name.as_str() == "Receiving"
+    )
+}
+
+fn package_type(name: &str) -> TypeTag {
+    sui_framework_type("package", name, vec![])
+}
+
+/// Stand-in for `Coin<T>` in errors where `T` isn't known.
+fn coin_of_any() -> TypeTag {
+    sui_framework_type("coin", "Coin", vec![])
+}
+
+fn sui_framework_type(module: &str, name: &str, type_params: Vec<TypeTag>) -> TypeTag {
+    TypeTag::Struct(Box::new(StructTag {
+        address: SUI_FRAMEWORK_ADDRESS,
+        module: Identifier::new(module).expect("Valid identifier"),
+        name: Identifier::new(name).expect("Valid identifier"),
+        type_params,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use af_sui_types::ObjectDigest;
+
+    use super::*;
+    use crate::ObjectArg;
+
+    type Abis = HashMap<(ObjectId, Identifier, Identifier), FunctionSignature>;
+
+    fn package() -> ObjectId {
+        ObjectId::new([7; 32])
+    }
+
+    fn ident(s: &str) -> Identifier {
+        Identifier::new(s).unwrap()
+    }
+
+    fn coin_param(type_param: u16) -> SignatureType {
+        SignatureType::Struct {
+            address: SUI_FRAMEWORK_ADDRESS,
+            module: ident("coin"),
+            name: ident("Coin"),
+            type_arguments: vec![SignatureType::TypeParameter(type_param)],
+        }
+    }
+
+    fn abis() -> Abis {
+        let mut abis = Abis::new();
+        let tx_context = SignatureType::MutableReference(Box::new(SignatureType::Struct {
+            address: SUI_FRAMEWORK_ADDRESS,
+            module: ident("tx_context"),
+            name: ident("TxContext"),
+            type_arguments: vec![],
+        }));
+        abis.insert(
+            (package(), ident("pool"), ident("deposit")),
+            FunctionSignature {
+                type_parameters: 1,
+                parameters: vec![
+                    SignatureType::MutableReference(Box::new(SignatureType::Struct {
+                        address: package().into(),
+                        module: ident("pool"),
+                        name: ident("Pool"),
+                        type_arguments: vec![SignatureType::TypeParameter(0)],
+                    })),
+                    coin_param(0),
+                    SignatureType::U64,
+                    tx_context,
+                ],
+                returns: vec![coin_param(0), SignatureType::U64],
+            },
+        );
+        abis
+    }
+
+    fn usdc() -> TypeTag {
+        "0x2::usdc::USDC".parse().unwrap()
+    }
+
+    fn sui() -> TypeTag {
+        "0x2::sui::SUI".parse().unwrap()
+    }
+
+    fn owned(n: u8) -> ObjectArg {
+        ObjectArg::ImmOrOwnedObject((ObjectId::new([n; 32]), 1, ObjectDigest::ZERO))
+    }
+
+    fn check(ptb: &ProgrammableTransaction) -> Result<(), TypeError> {
+        check_ptb(ptb, &abis())
+    }
+
+    fn kind(result: Result<(), TypeError>) -> (usize, TypeErrorKind) {
+        let TypeError { command, kind } = result.unwrap_err();
+        (command, kind)
+    }
+
+    #[test]
+    fn well_typed() {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let pool = builder.obj(owned(1)).unwrap();
+        let coin = builder.obj(owned(2)).unwrap();
+        let amount = builder.pure(&5u64).unwrap();
+        let recipient = builder.pure(&Address::ZERO).unwrap();
+        builder.command(crate::Command::move_call(
+            package(),
+            ident("pool"),
+            ident("deposit"),
+            vec![usdc()],
+            vec![pool, coin, amount],
+        ));
+        let change = Argument::NestedResult(0, 0);
+        let split = builder.command(crate::Command::SplitCoins(change, vec![amount]));
+        builder.command(crate::Command::MergeCoins(change, vec![split]));
+        builder.command(crate::Command::TransferObjects(vec![change], recipient));
+        builder.type_check(&abis()).unwrap();
+        check(&builder.finish()).unwrap();
+    }
+
+    #[test]
+    fn use_after_move() {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let coin = builder.obj(owned(2)).unwrap();
+        let recipient = builder.pure(&Address::ZERO).unwrap();
+        builder.command(crate::Command::TransferObjects(vec![coin], recipient));
+        builder.command(crate::Command::TransferObjects(vec![coin], recipient));
+        assert_eq!(
+            kind(builder.type_check(&abis())),
+            (
+                1,
+                TypeErrorKind::Argument {
+                    index: 0,
+                    error: ArgumentError::UsedAfterMove(0)
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn out_of_bounds_results() {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let amount = builder.pure(&1u64).unwrap();
+        let coins = builder.command(crate::Command::SplitCoins(Argument::Gas, vec![amount]));
+        builder.command(crate::Command::MergeCoins(
+            Argument::Gas,
+            vec![Argument::NestedResult(0, 1)],
+        ));
+        assert_eq!(
+            kind(builder.type_check(&abis())),
+            (
+                1,
+                TypeErrorKind::Argument {
+                    index: 1,
+                    error: ArgumentError::NestedResultOutOfBounds {
+                        result: 0,
+                        index: 1
+                    }
+                }
+            )
+        );
+
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.command(crate::Command::MergeCoins(coins, vec![]));
+        assert_eq!(
+            kind(builder.type_check(&abis())).1,
+            TypeErrorKind::Argument {
+                index: 0,
+                error: ArgumentError::FutureResult(0)
+            }
+        );
+    }
+
+    #[test]
+    fn type_mismatches() {
+        // A SUI coin split off the gas coin passed as a USDC coin
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let pool = builder.obj(owned(1)).unwrap();
+        let amount = builder.pure(&1u64).unwrap();
+        let coin = builder.command(crate::Command::SplitCoins(Argument::Gas, vec![amount]));
+        builder.command(crate::Command::move_call(
+            package(),
+            ident("pool"),
+            ident("deposit"),
+            vec![usdc()],
+            vec![pool, coin, amount],
+        ));
+        let (command, error) = kind(builder.type_check(&abis()));
+        assert_eq!(command, 1);
+        assert_eq!(
+            error,
+            TypeErrorKind::Argument {
+                index: 1,
+                error: ArgumentError::TypeMismatch {
+                    expected: Box::new(StructTag::coin(usdc()).into()),
+                    found: Box::new(StructTag::coin(sui()).into()),
+                }
+            }
+        );
+
+        // Vector elements of different types
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let pool = builder.obj(owned(1)).unwrap();
+        let coin = builder.obj(owned(2)).unwrap();
+        let amount = builder.pure(&1u64).unwrap();
+        builder.command(crate::Command::move_call(
+            package(),
+            ident("pool"),
+            ident("deposit"),
+            vec![usdc()],
+            vec![pool, coin, amount],
+        ));
+        let sui_coin = builder.command(crate::Command::SplitCoins(Argument::Gas, vec![amount]));
+        builder.command(crate::Command::MakeMoveVec(
+            None,
+            vec![sui_coin, Argument::NestedResult(0, 0)],
+        ));
+        assert_eq!(
+            kind(builder.type_check(&abis())),
+            (
+                2,
+                TypeErrorKind::Argument {
+                    index: 1,
+                    error: ArgumentError::TypeMismatch {
+                        expected: Box::new(StructTag::coin(sui()).into()),
+                        found: Box::new(StructTag::coin(usdc()).into()),
+                    }
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn input_kinds() {
+        // Pure input for an object, gas coin by value and immutable shared object by &mut
+        let call = |pool, coin, amount| {
+            crate::Command::move_call(
+                package(),
+                ident("pool"),
+                ident("deposit"),
+                vec![sui()],
+                vec![pool, coin, amount],
+            )
+        };
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let amount = builder.pure(&1u64).unwrap();
+        builder.command(call(amount, Argument::Gas, amount));
+        assert!(matches!(
+            kind(builder.type_check(&abis())).1,
+            TypeErrorKind::Argument {
+                index: 0,
+                error: ArgumentError::PureForObject(_)
+            }
+        ));
+
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let amount = builder.pure(&1u64).unwrap();
+        let pool = builder.obj(owned(1)).unwrap();
+        builder.command(call(pool, Argument::Gas, amount));
+        assert_eq!(
+            kind(builder.type_check(&abis())).1,
+            TypeErrorKind::Argument {
+                index: 1,
+                error: ArgumentError::GasByValue
+            }
+        );
+
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let amount = builder.pure(&1u64).unwrap();
+        let pool = builder
+            .obj(ObjectArg::SharedObject {
+                id: ObjectId::new([1; 32]),
+                initial_shared_version: 1,
+                mutable: false,
+            })
+            .unwrap();
+        let coin = builder.obj(owned(2)).unwrap();
+        builder.command(call(pool, coin, amount));
+        assert_eq!(
+            kind(builder.type_check(&abis())).1,
+            TypeErrorKind::Argument {
+                index: 0,
+                error: ArgumentError::ImmutableObject
+            }
+        );
+
+        // Immutable owned-or-immutable object by &mut
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let amount = builder.pure(&1u64).unwrap();
+        let pool = builder.obj(owned(1)).unwrap();
+        let coin = builder.obj(owned(2)).unwrap();
+        builder.command(call(pool, coin, amount));
+        let ptb = builder.finish();
+        check(&ptb).unwrap();
+        let abis = abis();
+        let mut checker =
+            Checker::new(&ptb.inputs, &abis).with_immutable_objects(&[ObjectId::new([1; 32])]);
+        assert_eq!(
+            kind(checker.check(&ptb.commands)).1,
+            TypeErrorKind::Argument {
+                index: 0,
+                error: ArgumentError::ImmutableObject
+            }
+        );
+
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.command(call(pool, coin, amount));
+        assert_eq!(
+            kind(builder.type_check(&abis)).1,
+            TypeErrorKind::Argument {
+                index: 0,
+                error: ArgumentError::InputOutOfBounds(1)
+            }
+        );
+    }
+}
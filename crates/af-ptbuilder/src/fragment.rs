@@ -0,0 +1,448 @@
+//! Transaction fragments that can be built on their own and merged into other builders.
+use af_sui_types::Command;
+use sui_sdk_types::Input;
+
+use crate::{
+    Argument,
+    BuilderArg,
+    Error,
+    ObjectArg,
+    ProgrammableTransactionBuilder,
+    Result,
+    update_object_input,
+};
+
+/// Part of a transaction, together with the arguments it makes available to the rest of it.
+///
+/// A fragment is built with its own [`ProgrammableTransactionBuilder`], independently of the
+/// transaction it will end up in. [`ProgrammableTransactionBuilder::append`] then adds its inputs
+/// and commands to another builder and returns its `exports` pointing to their new positions.
+///
+/// ```
+/// use af_ptbuilder::{Argument, Command, Fragment, ProgrammableTransactionBuilder};
+///
+/// // A fragment splitting a coin off of the gas coin
+/// let split = Fragment::build(|builder| {
+///     let amount = builder.pure(&1_000u64)?;
+///     Ok(builder.split_coins_into_vec(Argument::Gas, vec![amount]))
+/// })?;
+///
+/// let mut builder = ProgrammableTransactionBuilder::new();
+/// let recipient = builder.pure(&af_sui_types::Address::ZERO)?;
+/// builder.command(Command::MergeCoins(Argument::Gas, vec![]));
+/// let coins = builder.append(split)?;
+/// builder.command(Command::TransferObjects(coins.clone(), recipient));
+/// assert_eq!(coins, [Argument::NestedResult(1, 0)]);
+/// # af_ptbuilder::Result::Ok(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Fragment<T = ()> {
+    builder: ProgrammableTransactionBuilder,
+    exports: T,
+}
+
+impl<T> Fragment<T> {
+    /// A fragment with the contents of `builder`, where `exports` are arguments of `builder`.
+    pub const fn new(builder: ProgrammableTransactionBuilder, exports: T) -> Self {
+        Self { builder, exports }
+    }
+
+    /// Build a fragment with a new builder; `f` returns the arguments to export.
+    pub fn build(f: impl FnOnce(&mut ProgrammableTransactionBuilder) -> Result<T>) -> Result<Self> {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let exports = f(&mut builder)?;
+        Ok(Self { builder, exports })
+    }
+
+    pub const fn builder(&self) -> &ProgrammableTransactionBuilder {
+        &self.builder
+    }
+
+    /// The exported arguments, relative to the fragment's own builder.
+    pub const fn exports(&self) -> &T {
+        &self.exports
+    }
+
+    pub fn into_parts(self) -> (ProgrammableTransactionBuilder, T) {
+        (self.builder, self.exports)
+    }
+}
+
+impl From<ProgrammableTransactionBuilder> for Fragment {
+    fn from(builder: ProgrammableTransactionBuilder) -> Self {
+        Self::new(builder, ())
+    }
+}
+
+impl ProgrammableTransactionBuilder {
+    /// Add the inputs and commands of `fragment` after those of `self`.
+    ///
+    /// Inputs are deduplicated like those added with [`Self::pure`] and [`Self::obj`]: a shared
+    /// object used by both is declared once, mutably if either uses it mutably. Fails if both
+    /// declare the same object in incompatible ways.
+    ///
+    /// Returns the fragment's exports, remapped to the arguments of `self`. On error, `self` is
+    /// left unchanged.
+    pub fn append<T: RemapArguments>(&mut self, fragment: Fragment<T>) -> Result<T> {
+        let Fragment {
+            builder,
+            mut exports,
+        } = fragment;
+        let appended = self.append_builder(&builder)?;
+        exports.remap_arguments(&mut |argument| appended.remap(argument));
+        Ok(exports)
+    }
+
+    /// [`Self::append`] for the contents of `fragment`. The result remaps the fragment's arguments
+    /// and can [undo](Self::undo_append) the append.
+    pub(crate) fn append_builder(&mut self, fragment: &Self) -> Result<Appended> {
+        // Check everything that can fail before changing `self`
+        let offset = u16::try_from(self.commands.len())
+            .ok()
+            .filter(|offset| {
+                usize::from(*offset) + fragment.commands.len() <= usize::from(u16::MAX) + 1
+            })
+            .ok_or(Error::TooManyCommands)?;
+        for (key, input) in &fragment.inputs {
+            if let (BuilderArg::Object(id), Some(old_value)) = (key, self.inputs.get(key)) {
+                update_object_input(*id, old_value, input.clone())?;
+            }
+        }
+
+        let mut appended = Appended {
+            offset,
+            input_map: Vec::with_capacity(fragment.inputs.len()),
+            inputs: self.inputs.len(),
+            commands: self.commands.len(),
+            replaced: vec![],
+            pure_types: vec![],
+        };
+        for (key, input) in &fragment.inputs {
+            if let (BuilderArg::Object(_), Some((i, _, old_value))) =
+                (key, self.inputs.get_full(key))
+            {
+                appended.replaced.push((i, old_value.clone()));
+            }
+            let argument = match (key, input.clone()) {
+                (BuilderArg::Pure(_), Input::Pure { value }) => self.pure_bytes(value, false),
+                (_, Input::Pure { value }) => self.pure_bytes(value, true),
+                (_, Input::ImmutableOrOwned(oref)) => {
+                    self.obj(ObjectArg::ImmOrOwnedObject(oref.into_parts()))?
+                }
+                (
+                    _,
+                    Input::Shared {
+                        object_id,
+                        initial_shared_version,
+                        mutable,
+                    },
+                ) => self.obj(ObjectArg::SharedObject {
+                    id: object_id,
+                    initial_shared_version,
+                    mutable,
+                })?,
+                (_, Input::Receiving(oref)) => self.obj(ObjectArg::Receiving(oref.into_parts()))?,
+            };
+            appended.input_map.push(argument);
+        }
+        for (i, checks) in &fragment.pure_types {
+            if let Some(Argument::Input(j)) = appended.input_map.get(*i) {
+                let j = usize::from(*j);
+                let types = self.pure_types.entry(j).or_default();
+                if j < appended.inputs {
+                    appended.pure_types.push((j, types.len()));
+                }
+                types.extend(checks);
+            }
+        }
+
+        for command in &fragment.commands {
+            let mut command = command.clone();
+            remap_command(&mut command, &mut |argument| appended.remap(argument));
+            self.commands.push(command);
+        }
+        Ok(appended)
+    }
+
+    /// Take back an append, restoring `self` to what it was before.
+    ///
+    /// Nothing else may have been added since.
+    pub(crate) fn undo_append(&mut self, appended: Appended) {
+        let Appended {
+            inputs,
+            commands,
+            replaced,
+            pure_types,
+            ..
+        } = appended;
+        self.inputs.truncate(inputs);
+        self.commands.truncate(commands);
+        for (i, old_value) in replaced {
+            if let Some((_, input)) = self.inputs.get_index_mut(i) {
+                *input = old_value;
+            }
+        }
+        self.pure_types.retain(|i, _| *i < inputs);
+        for (i, len) in pure_types {
+            if let Some(types) = self.pure_types.get_mut(&i) {
+                types.truncate(len);
+            }
+        }
+        self.pure_types.retain(|_, types| !types.is_empty());
+    }
+}
+
+/// Where [`ProgrammableTransactionBuilder::append_builder`] put the contents of a fragment.
+pub(crate) struct Appended {
+    offset: u16,
+    /// Inputs of `self` by fragment input.
+    input_map: Vec<Argument>,
+    /// Number of inputs of `self` before the append.
+    pub(crate) inputs: usize,
+    /// Number of commands of `self` before the append.
+    pub(crate) commands: usize,
+    /// Inputs of `self` that may have been updated, with their previous values.
+    replaced: Vec<(usize, Input)>,
+    /// Number of pure type checks of inputs of `self` that got more.
+    pure_types: Vec<(usize, usize)>,
+}
+
+impl Appended {
+    /// The argument of `self` for an argument of the fragment.
+    pub(crate) fn remap(&self, argument: Argument) -> Argument {
+        // Keep invalid arguments invalid
+        match argument {
+            Argument::Gas => Argument::Gas,
+            Argument::Input(i) => self
+                .input_map
+                .get(usize::from(i))
+                .copied()
+                .unwrap_or(Argument::Input(u16::MAX)),
+            Argument::Result(i) => Argument::Result(i.saturating_add(self.offset)),
+            Argument::NestedResult(i, j) => {
+                Argument::NestedResult(i.saturating_add(self.offset), j)
+            }
+        }
+    }
+}
+
+fn remap_command(command: &mut Command, f: &mut impl FnMut(Argument) -> Argument) {
+    match command {
+        Command::MoveCall(call) => call.arguments.remap_arguments(f),
+        Command::TransferObjects(transfer) => {
+            transfer.objects.remap_arguments(f);
+            transfer.address.remap_arguments(f);
+        }
+        Command::SplitCoins(split) => {
+            split.coin.remap_arguments(f);
+            split.amounts.remap_arguments(f);
+        }
+        Command::MergeCoins(merge) => {
+            merge.coin.remap_arguments(f);
+            merge.coins_to_merge.remap_arguments(f);
+        }
+        Command::Publish(_) => (),
+        Command::MakeMoveVector(make) => make.elements.remap_arguments(f),
+        Command::Upgrade(upgrade) => upgrade.ticket.remap_arguments(f),
+    }
+}
+
+/// Types holding PTB [`Argument`]s, such as the exports of a [`Fragment`].
+///
+/// Implement it for structs of arguments returned by PTB builder extensions so that they can be
+/// exported by fragments.
+pub trait RemapArguments {
+    /// Replace every argument in `self` with the result of `f`.
+    fn remap_arguments(&mut self, f: &mut impl FnMut(Argument) -> Argument);
+}
+
+impl RemapArguments for Argument {
+    fn remap_arguments(&mut self, f: &mut impl FnMut(Self) -> Self) {
+        *self = f(*self);
+    }
+}
+
+impl RemapArguments for () {
+    fn remap_arguments(&mut self, _: &mut impl FnMut(Argument) -> Argument) {}
+}
+
+impl<T: RemapArguments> RemapArguments for Option<T> {
+    fn remap_arguments(&mut self, f: &mut impl FnMut(Argument) -> Argument) {
+        if let Some(inner) = self {
+            inner.remap_arguments(f);
+        }
+    }
+}
+
+impl<T: RemapArguments> RemapArguments for Vec<T> {
+    fn remap_arguments(&mut self, f: &mut impl FnMut(Argument) -> Argument) {
+        for inner in self {
+            inner.remap_arguments(f);
+        }
+    }
+}
+
+impl<T: RemapArguments, const N: usize> RemapArguments for [T; N] {
+    fn remap_arguments(&mut self, f: &mut impl FnMut(Argument) -> Argument) {
+        for inner in self {
+            inner.remap_arguments(f);
+        }
+    }
+}
+
+macro_rules! impl_tuple {
+    ($($T:ident . $i:tt),+) => {
+        impl<$($T: RemapArguments),+> RemapArguments for ($($T,)+) {
+            fn remap_arguments(&mut self, f: &mut impl FnMut(Argument) -> Argument) {
+                $(self.$i.remap_arguments(f);)+
+            }
+        }
+    };
+}
+
+impl_tuple!(A.0);
+impl_tuple!(A.0, B.1);
+impl_tuple!(A.0, B.1, C.2);
+impl_tuple!(A.0, B.1, C.2, D.3);
+
+#[cfg(test)]
+mod tests {
+    use af_sui_types::{ObjectDigest, ObjectId};
+
+    use super::*;
+    use crate::Command;
+
+    fn shared(mutable: bool) -> ObjectArg {
+        ObjectArg::SharedObject {
+            id: ObjectId::new([1; 32]),
+            initial_shared_version: 1,
+            mutable,
+        }
+    }
+
+    #[test]
+    fn append_remaps_arguments() {
+        let fragment = Fragment::build(|builder| {
+            let state = builder.obj(shared(true))?;
+            let amount = builder.pure(&5u64)?;
+            let coins = builder.split_coins_into_vec(Argument::Gas, vec![amount, amount]);
+            builder.command(Command::MergeCoins(coins[0], vec![coins[1], state]));
+            Ok((state, coins[0]))
+        })
+        .unwrap();
+
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let amount = builder.pure(&5u64).unwrap();
+        let state = builder.obj(shared(false)).unwrap();
+        let owned = builder
+            .obj(ObjectArg::ImmOrOwnedObject((
+                ObjectId::new([2; 32]),
+                1,
+                ObjectDigest::ZERO,
+            )))
+            .unwrap();
+        builder.command(Command::MergeCoins(owned, vec![]));
+        let (fragment_state, coin) = builder.append(fragment).unwrap();
+
+        // Same inputs, with the shared object upgraded to mutable
+        assert_eq!(fragment_state, state);
+        assert_eq!(coin, Argument::NestedResult(1, 0));
+        let ptb = builder.finish();
+        assert_eq!(ptb.inputs.len(), 3);
+        assert!(matches!(ptb.inputs[1], Input::Shared { mutable: true, .. }));
+        assert_eq!(
+            crate::Command::from(ptb.commands[1].clone()),
+            Command::SplitCoins(Argument::Gas, vec![amount, amount])
+        );
+        assert_eq!(
+            crate::Command::from(ptb.commands[2].clone()),
+            Command::MergeCoins(coin, vec![Argument::NestedResult(1, 1), state])
+        );
+    }
+
+    #[test]
+    fn undo_append() {
+        let object = ObjectArg::SharedObject {
+            id: ObjectId::new([1; 32]),
+            initial_shared_version: 1,
+            mutable: false,
+        };
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let amount = builder.pure(&1u64).unwrap();
+        builder.obj(object).unwrap();
+        builder.split_coins_into_vec(Argument::Gas, vec![amount]);
+        let before = builder.clone().finish();
+
+        let fragment = Fragment::build(|builder| {
+            let amount = builder.pure(&1u64)?;
+            builder.pure_types.insert(0, vec![|_| true]);
+            builder.obj(ObjectArg::SharedObject {
+                id: ObjectId::new([1; 32]),
+                initial_shared_version: 1,
+                mutable: true,
+            })?;
+            builder.pure(&2u64)?;
+            Ok(builder.split_coins_into_vec(Argument::Gas, vec![amount]))
+        })
+        .unwrap();
+        let appended = builder.append_builder(fragment.builder()).unwrap();
+        assert_eq!(builder.pure_types[&0].len(), 1);
+        builder.undo_append(appended);
+        assert!(builder.pure_types.is_empty());
+        assert_eq!(builder.finish(), before);
+    }
+
+    #[test]
+    fn append_conflicting_inputs() {
+        let object = ObjectId::new([1; 32]);
+        let fragment = Fragment::build(|builder| {
+            builder.pure(&1u64)?;
+            builder.obj(ObjectArg::SharedObject {
+                id: ObjectId::new([2; 32]),
+                initial_shared_version: 1,
+                mutable: true,
+            })?;
+            builder.obj(ObjectArg::ImmOrOwnedObject((object, 1, ObjectDigest::ZERO)))?;
+            Ok(())
+        })
+        .unwrap();
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder
+            .obj(ObjectArg::SharedObject {
+                id: ObjectId::new([2; 32]),
+                initial_shared_version: 1,
+                mutable: false,
+            })
+            .unwrap();
+        builder.obj(shared(true)).unwrap();
+        let before = builder.clone().finish();
+        assert!(matches!(
+            builder.append(fragment),
+            Err(crate::Error::MismatchedObjArgKinds(_))
+        ));
+        // Nothing was added, nor was the shared object made mutable
+        assert_eq!(builder.finish(), before);
+    }
+
+    #[test]
+    fn append_too_many_commands() {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        for _ in 0..u16::MAX {
+            builder.command(Command::MergeCoins(Argument::Gas, vec![]));
+        }
+        let fragment = Fragment::build(|builder| {
+            builder.command(Command::MergeCoins(Argument::Gas, vec![]));
+            Ok(builder.command(Command::MergeCoins(Argument::Gas, vec![])))
+        })
+        .unwrap();
+        assert!(matches!(
+            builder.append(fragment.clone()),
+            Err(crate::Error::TooManyCommands)
+        ));
+        builder.commands.pop();
+        assert_eq!(
+            builder.append(fragment).unwrap(),
+            Argument::Result(u16::MAX)
+        );
+    }
+}
@@ -5,7 +5,11 @@
 //! Check out the [`ptb`](crate::ptb) and [`ptbuilder`](crate::ptbuilder) macros for an ergonomic
 //! way of building transactions, or
 //! [`ProgrammableTransactionBuilder`](crate::ProgrammableTransactionBuilder) for a macro-less
-//! approach.
+//! approach. Parts of a transaction can be built separately as [`Fragment`]s and combined later,
+//! for instance to spread them over several transactions within the protocol [`limits`].
+//! Finished transactions can be rendered to and parsed from a readable [`text`] format.
+
+use std::collections::HashMap;
 
 #[doc(no_inline)]
 pub use af_sui_types::Argument;
@@ -19,17 +23,29 @@ pub use af_sui_types::ObjectArg;
 pub use af_sui_types::ObjectId;
 #[doc(no_inline)]
 pub use af_sui_types::TypeTag;
-use af_sui_types::{Identifier, ProgrammableTransaction};
+use af_sui_types::{Identifier, ObjectRef, ProgrammableTransaction};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use sui_sdk_types::Input;
 
+pub mod check;
+mod fragment;
+pub mod limits;
+#[cfg(feature = "move-type")]
+mod move_type;
+#[cfg(feature = "package")]
+pub mod package;
 #[cfg(test)]
 mod tests;
+pub mod text;
+mod uleb128;
+
+pub use self::fragment::{Fragment, RemapArguments};
 
 pub type Result<T> = ::std::result::Result<T, Error>;
 
 #[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
     #[error("Serializing to BCS: {0}")]
     Bcs(#[from] bcs::Error),
@@ -42,6 +58,22 @@ pub enum Error {
 
     #[error(transparent)]
     MismatchedObjArgKinds(Box<MismatchedObjArgKindsError>),
+
+    #[error("No coins to merge")]
+    NoCoins,
+
+    #[error("More commands than can be referred to by a result argument")]
+    TooManyCommands,
+
+    #[error(transparent)]
+    Limit(#[from] limits::LimitError),
+
+    #[cfg(feature = "move-type")]
+    #[error("Pure value can't be passed as {expected}: {source}")]
+    PureType {
+        expected: TypeTag,
+        source: af_move_type::TypeTagError,
+    },
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -60,6 +92,9 @@ pub struct MismatchedObjArgKindsError {
 pub struct ProgrammableTransactionBuilder {
     inputs: IndexMap<BuilderArg, Input>,
     commands: Vec<af_sui_types::Command>,
+    /// For pure inputs encoded from Move types, whether they can be passed as a given type, by
+    /// input index. Used by [`Self::type_check`].
+    pure_types: HashMap<usize, Vec<check::PureTypeCheck>>,
 }
 
 /// Base API.
@@ -69,7 +104,9 @@ impl ProgrammableTransactionBuilder {
     }
 
     pub fn finish(self) -> ProgrammableTransaction {
-        let Self { inputs, commands } = self;
+        let Self {
+            inputs, commands, ..
+        } = self;
         let inputs = inputs.into_values().collect();
         ProgrammableTransaction { inputs, commands }
     }
@@ -112,50 +149,7 @@ impl ProgrammableTransactionBuilder {
         let mut input_arg = obj_arg.into();
 
         if let Some(old_value) = self.inputs.get(&key) {
-            // Check if the key hash didn't collide with a previous pure input
-            if matches!(old_value, Input::Pure { .. }) {
-                return Err(Error::ObjInvariantViolation);
-            }
-
-            input_arg = match (old_value, input_arg) {
-                // The only update allowed: changing the `mutable` flag for a shared object input
-                (
-                    Input::Shared {
-                        object_id: id1,
-                        initial_shared_version: v1,
-                        mutable: mut1,
-                    },
-                    Input::Shared {
-                        object_id: id2,
-                        initial_shared_version: v2,
-                        mutable: mut2,
-                    },
-                ) if v1 == &v2 => {
-                    if id1 != &id2 {
-                        return Err(Error::InvalidObjArgUpdate);
-                    }
-                    Input::Shared {
-                        object_id: id2,
-                        initial_shared_version: v2,
-                        mutable: *mut1 || mut2,
-                    }
-                }
-
-                // Changing anything else about an existing object input is disallowed
-                (old_value, new_value) if old_value != &new_value => {
-                    return Err(Error::MismatchedObjArgKinds(Box::new(
-                        MismatchedObjArgKindsError {
-                            id,
-                            old_value: old_value.clone(),
-                            new_value,
-                        },
-                    )));
-                }
-
-                // If we already declared this exact same object input in the transaction, it will
-                // be automatically reused
-                (_, new_value) => new_value,
-            };
+            input_arg = update_object_input(id, old_value, input_arg)?;
         }
 
         let (i, _) = self.inputs.insert_full(key, input_arg);
@@ -172,6 +166,54 @@ impl ProgrammableTransactionBuilder {
     }
 }
 
+/// The input replacing `old_value` when object `id` is declared again as `new_value`.
+fn update_object_input(id: ObjectId, old_value: &Input, new_value: Input) -> Result<Input> {
+    // Check if the key hash didn't collide with a previous pure input
+    if matches!(old_value, Input::Pure { .. }) {
+        return Err(Error::ObjInvariantViolation);
+    }
+
+    Ok(match (old_value, new_value) {
+        // The only update allowed: changing the `mutable` flag for a shared object input
+        (
+            Input::Shared {
+                object_id: id1,
+                initial_shared_version: v1,
+                mutable: mut1,
+            },
+            Input::Shared {
+                object_id: id2,
+                initial_shared_version: v2,
+                mutable: mut2,
+            },
+        ) if v1 == &v2 => {
+            if id1 != &id2 {
+                return Err(Error::InvalidObjArgUpdate);
+            }
+            Input::Shared {
+                object_id: id2,
+                initial_shared_version: v2,
+                mutable: *mut1 || mut2,
+            }
+        }
+
+        // Changing anything else about an existing object input is disallowed
+        (old_value, new_value) if old_value != &new_value => {
+            return Err(Error::MismatchedObjArgKinds(Box::new(
+                MismatchedObjArgKindsError {
+                    id,
+                    old_value: old_value.clone(),
+                    new_value,
+                },
+            )));
+        }
+
+        // If we already declared this exact same object input in the transaction, it will be
+        // automatically reused
+        (_, new_value) => new_value,
+    })
+}
+
 /// Extensions to the base API.
 impl ProgrammableTransactionBuilder {
     /// Like `.command(Command::SplitCoins(coin_arg, balances))`, but also takes care of unpacking
@@ -191,6 +233,55 @@ impl ProgrammableTransactionBuilder {
         };
         idxs.map(|i| Argument::NestedResult(coin_vec, i)).collect()
     }
+
+    /// Like [`Self::split_coins_into_vec`], but for a single `amount`.
+    ///
+    /// Returns the argument for the new coin, or [`Error::TooManyCommands`] if a result argument
+    /// can't refer to it.
+    pub fn split_coin(&mut self, coin: Argument, amount: Argument) -> Result<Argument> {
+        let i = u16::try_from(self.commands.len()).map_err(|_| Error::TooManyCommands)?;
+        self.command(Command::SplitCoins(coin, vec![amount]));
+        Ok(Argument::NestedResult(i, 0))
+    }
+
+    /// Merges the owned `coins` into the first one and splits a new coin with exactly `amount`
+    /// off of it.
+    ///
+    /// Returns the argument for the new coin. Any change stays in the first coin, which remains
+    /// owned by the sender. Pairs well with the selections in [`af_sui_types::coin_selection`].
+    pub fn merge_and_split(
+        &mut self,
+        coins: impl IntoIterator<Item = ObjectRef>,
+        amount: u64,
+    ) -> Result<Argument> {
+        let mut coins = coins.into_iter();
+        let primary = self.obj(ObjectArg::ImmOrOwnedObject(
+            coins.next().ok_or(Error::NoCoins)?,
+        ))?;
+        let rest = coins
+            .map(|coin| self.obj(ObjectArg::ImmOrOwnedObject(coin)))
+            .collect::<Result<Vec<_>>>()?;
+        if !rest.is_empty() {
+            self.command(Command::MergeCoins(primary, rest));
+        }
+        let amount = self.pure(&amount)?;
+        self.split_coin(primary, amount)
+    }
+
+    /// Adds the `objects` as inputs and puts them in a `vector<type_>` with `MakeMoveVec`.
+    ///
+    /// Returns the argument for the vector. Since the element type is given, `objects` may be empty.
+    pub fn obj_vec(
+        &mut self,
+        type_: impl Into<TypeTag>,
+        objects: impl IntoIterator<Item = ObjectArg>,
+    ) -> Result<Argument> {
+        let elements = objects
+            .into_iter()
+            .map(|object| self.obj(object))
+            .collect::<Result<_>>()?;
+        Ok(self.command(Command::make_move_vec(Some(type_.into()), elements)))
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -447,6 +538,23 @@ impl Command {
 /// become [`Input::Pure`]s in the transaction payload. Within the macro scope, both variables
 /// are [`Argument::Input`]s and can be used in Move/built-in calls.
 ///
+/// With the `move-type` feature, pure inputs can also be typed by the
+/// [`MoveType`](af_move_type::MoveType) they represent, which then takes care of their encoding
+/// (see [`ProgrammableTransactionBuilder::move_pure`]). When building with [`ptbuilder!`],
+/// [`ProgrammableTransactionBuilder::type_check`] also checks `T` against the types of the
+/// parameters the input is passed to. Vectors of objects of a
+/// [`StaticStructTag`](af_move_type::StaticStructTag) type are declared with `input objs`, which expects
+/// an iterator over [`ObjectArg`]s and adds a `MakeMoveVec` command for them:
+/// ```ignore
+/// af_ptbuilder::ptb!(
+///     input pure<Option<u64>> fee: &Some(30).into();
+///     input pure<String> name;
+///     input objs<Coin<SUI>> coins: coin_args;
+///     // ...
+/// );
+/// ```
+/// `coins` is then the [`Argument::Result`] of the `MakeMoveVec` command.
+///
 /// ## Move calls
 ///
 /// Use the syntax
@@ -587,6 +695,42 @@ macro_rules! ptbuilder {
         $crate::ptbuilder!($builder { $($tt)* });
     };
 
+    ($builder:ident {
+        input pure<$T:ty> $name:ident;
+        $($tt:tt)*
+    }) => {
+        let $name = $builder.move_pure::<$T>($name)?;
+
+        $crate::ptbuilder!($builder { $($tt)* });
+    };
+
+    ($builder:ident {
+        input pure<$T:ty> $name:ident: $value:expr_2021;
+        $($tt:tt)*
+    }) => {
+        let $name = $builder.move_pure::<$T>($value)?;
+
+        $crate::ptbuilder!($builder { $($tt)* });
+    };
+
+    ($builder:ident {
+        input objs<$T:ty> $name:ident;
+        $($tt:tt)*
+    }) => {
+        let $name = $builder.move_obj_vec::<$T>($name)?;
+
+        $crate::ptbuilder!($builder { $($tt)* });
+    };
+
+    ($builder:ident {
+        input objs<$T:ty> $name:ident: $value:expr_2021;
+        $($tt:tt)*
+    }) => {
+        let $name = $builder.move_obj_vec::<$T>($value)?;
+
+        $crate::ptbuilder!($builder { $($tt)* });
+    };
+
     ($builder:ident {
         input pure $name:ident;
         $($tt:tt)*
@@ -0,0 +1,477 @@
+//! Protocol limits on the size of transactions.
+//!
+//! Validators reject transactions exceeding the limits in their protocol config, e.g., a PTB with
+//! too many `place_limit_order` calls. [`TransactionLimits`] holds those limits so that
+//! transactions can be checked before submission with
+//! [`ProgrammableTransactionBuilder::check_limits`], and [`Batches`] spreads work over as many
+//! transactions as needed to stay within them.
+use af_sui_types::{Command, ProgrammableTransaction};
+use sui_sdk_types::Input;
+
+use crate::{Fragment, ProgrammableTransactionBuilder, RemapArguments};
+
+/// Limits from the protocol config that apply to programmable transactions.
+///
+/// The defaults are those of mainnet at protocol version 70.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionLimits {
+    /// `max_tx_size_bytes`: size of the serialized transaction, including signatures.
+    pub max_tx_size_bytes: u64,
+    /// `max_input_objects`: object inputs, including gas coins.
+    pub max_input_objects: u64,
+    /// `max_programmable_tx_commands`
+    pub max_commands: u64,
+    /// `max_pure_argument_size`: size of each pure input.
+    pub max_pure_argument_size: u64,
+    /// `max_arguments`: arguments of each command.
+    pub max_arguments: u64,
+    /// `max_type_arguments`: type arguments of each Move call.
+    pub max_type_arguments: u64,
+    /// `max_tx_gas`: gas budget.
+    pub max_tx_gas: u64,
+    /// Bytes of the transaction left out of the programmable transaction: sender, gas data,
+    /// expiration and signatures.
+    ///
+    /// Not a protocol limit. The default leaves room for a few gas coins and a sponsor.
+    pub reserved_bytes: u64,
+}
+
+impl Default for TransactionLimits {
+    fn default() -> Self {
+        Self {
+            max_tx_size_bytes: 128 * 1024,
+            max_input_objects: 2048,
+            max_commands: 1024,
+            max_pure_argument_size: 16 * 1024,
+            max_arguments: 512,
+            max_type_arguments: 16,
+            max_tx_gas: 50_000_000_000,
+            reserved_bytes: 2048,
+        }
+    }
+}
+
+impl TransactionLimits {
+    /// Check the sizes in `stats` against these limits.
+    pub const fn check(&self, stats: &TransactionStats) -> Result<(), LimitError> {
+        let size = stats.size_bytes.saturating_add(self.reserved_bytes);
+        if size > self.max_tx_size_bytes {
+            return Err(LimitError::TransactionSize {
+                size,
+                max: self.max_tx_size_bytes,
+            });
+        }
+        if stats.input_objects > self.max_input_objects {
+            return Err(LimitError::InputObjects {
+                count: stats.input_objects,
+                max: self.max_input_objects,
+            });
+        }
+        if stats.commands > self.max_commands {
+            return Err(LimitError::Commands {
+                count: stats.commands,
+                max: self.max_commands,
+            });
+        }
+        if let Some((input, size)) = stats.largest_pure_argument {
+            if size > self.max_pure_argument_size {
+                return Err(LimitError::PureArgumentSize {
+                    input,
+                    size,
+                    max: self.max_pure_argument_size,
+                });
+            }
+        }
+        if let Some((command, count)) = stats.most_arguments {
+            if count > self.max_arguments {
+                return Err(LimitError::Arguments {
+                    command,
+                    count,
+                    max: self.max_arguments,
+                });
+            }
+        }
+        if let Some((command, count)) = stats.most_type_arguments {
+            if count > self.max_type_arguments {
+                return Err(LimitError::TypeArguments {
+                    command,
+                    count,
+                    max: self.max_type_arguments,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub const fn check_gas_budget(&self, budget: u64) -> Result<(), LimitError> {
+        if budget > self.max_tx_gas {
+            return Err(LimitError::GasBudget {
+                budget,
+                max: self.max_tx_gas,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Sizes of a programmable transaction that are subject to [`TransactionLimits`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransactionStats {
+    /// Size of the BCS-serialized programmable transaction.
+    pub size_bytes: u64,
+    pub inputs: u64,
+    pub input_objects: u64,
+    pub commands: u64,
+    /// Index and size of the largest pure input.
+    pub largest_pure_argument: Option<(u16, u64)>,
+    /// Index and argument count of the command with the most arguments.
+    pub most_arguments: Option<(u16, u64)>,
+    /// Index and type argument count of the Move call with the most type arguments.
+    pub most_type_arguments: Option<(u16, u64)>,
+}
+
+impl TransactionStats {
+    pub fn new(ptb: &ProgrammableTransaction) -> Self {
+        let inputs: Vec<_> = ptb.inputs.iter().collect();
+        Self::from_parts(&inputs, &ptb.commands)
+    }
+
+    fn from_parts(inputs: &[&Input], commands: &[Command]) -> Self {
+        let mut stats = Self::default();
+        stats.extend(inputs, commands);
+        stats
+    }
+
+    /// Count `inputs` and `commands` added after those already counted.
+    fn extend(&mut self, inputs: &[&Input], commands: &[Command]) {
+        let (first_input, first_command) = (self.inputs, self.commands);
+        for (i, input) in inputs.iter().enumerate() {
+            self.inputs += 1;
+            match input {
+                Input::Pure { value } => {
+                    let size = value.len() as u64;
+                    if self.largest_pure_argument.is_none_or(|(_, max)| size > max) {
+                        self.largest_pure_argument = Some(((first_input + i as u64) as u16, size));
+                    }
+                }
+                _ => self.input_objects += 1,
+            }
+        }
+        for (i, command) in commands.iter().enumerate() {
+            self.commands += 1;
+            let i = (first_command + i as u64) as u16;
+            let (arguments, type_arguments) = match command {
+                Command::MoveCall(call) => (call.arguments.len(), call.type_arguments.len()),
+                Command::TransferObjects(transfer) => (transfer.objects.len() + 1, 0),
+                Command::SplitCoins(split) => (split.amounts.len() + 1, 0),
+                Command::MergeCoins(merge) => (merge.coins_to_merge.len() + 1, 0),
+                Command::MakeMoveVector(make) => (make.elements.len(), 0),
+                Command::Publish(_) => (0, 0),
+                Command::Upgrade(_) => (1, 0),
+            };
+            let (arguments, type_arguments) = (arguments as u64, type_arguments as u64);
+            if self.most_arguments.is_none_or(|(_, max)| arguments > max) {
+                self.most_arguments = Some((i, arguments));
+            }
+            if type_arguments > 0
+                && self
+                    .most_type_arguments
+                    .is_none_or(|(_, max)| type_arguments > max)
+            {
+                self.most_type_arguments = Some((i, type_arguments));
+            }
+        }
+
+        // The two sequence lengths change size along with the items. Default stats haven't
+        // counted them yet, hence the saturation.
+        let old_lengths = uleb128_len(first_input) + uleb128_len(first_command);
+        let new_lengths = uleb128_len(self.inputs) + uleb128_len(self.commands);
+        // Only fails for items too large for any limit
+        let items = inputs
+            .iter()
+            .map(bcs::serialized_size)
+            .chain(commands.iter().map(bcs::serialized_size))
+            .map(|size| size.map_or(u64::MAX, |s| s as u64))
+            .fold(0, u64::saturating_add);
+        self.size_bytes = self
+            .size_bytes
+            .saturating_sub(old_lengths)
+            .saturating_add(new_lengths)
+            .saturating_add(items);
+    }
+}
+
+/// Bytes of `value` as a ULEB128 length.
+const fn uleb128_len(value: u64) -> u64 {
+    let bits = u64::BITS - value.leading_zeros();
+    if bits == 0 {
+        1
+    } else {
+        bits.div_ceil(7) as u64
+    }
+}
+
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+pub enum LimitError {
+    #[error("Transaction of {size} bytes exceeds the maximum of {max}")]
+    TransactionSize { size: u64, max: u64 },
+    #[error("{count} object inputs exceed the maximum of {max}")]
+    InputObjects { count: u64, max: u64 },
+    #[error("{count} commands exceed the maximum of {max}")]
+    Commands { count: u64, max: u64 },
+    #[error("Pure input {input} of {size} bytes exceeds the maximum of {max}")]
+    PureArgumentSize { input: u16, size: u64, max: u64 },
+    #[error("{count} arguments to command {command} exceed the maximum of {max}")]
+    Arguments { command: u16, count: u64, max: u64 },
+    #[error("{count} type arguments to command {command} exceed the maximum of {max}")]
+    TypeArguments { command: u16, count: u64, max: u64 },
+    #[error("Gas budget of {budget} exceeds the maximum of {max}")]
+    GasBudget { budget: u64, max: u64 },
+}
+
+impl ProgrammableTransactionBuilder {
+    pub fn stats(&self) -> TransactionStats {
+        let inputs: Vec<_> = self.inputs.values().collect();
+        TransactionStats::from_parts(&inputs, &self.commands)
+    }
+
+    /// Check the transaction built so far against `limits`.
+    pub fn check_limits(&self, limits: &TransactionLimits) -> Result<(), LimitError> {
+        limits.check(&self.stats())
+    }
+}
+
+/// Builds as many transactions as needed to fit [`Fragment`]s within [`TransactionLimits`].
+///
+/// Fragments are appended to the current transaction until one doesn't fit, which then starts a
+/// new transaction. Fragments are never split, so each must fit in a transaction on its own.
+///
+/// ```
+/// use af_ptbuilder::limits::{Batches, TransactionLimits};
+/// use af_ptbuilder::{Argument, Fragment};
+///
+/// let limits = TransactionLimits {
+///     max_commands: 10,
+///     ..Default::default()
+/// };
+/// let mut batches = Batches::new(limits);
+/// for amount in 0..25u64 {
+///     batches.push(Fragment::build(|builder| {
+///         let amount = builder.pure(&amount)?;
+///         Ok(builder.split_coins_into_vec(Argument::Gas, vec![amount]))
+///     })?)?;
+/// }
+/// let ptbs = batches.finish();
+/// assert_eq!(ptbs.len(), 3);
+/// # af_ptbuilder::Result::Ok(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Batches {
+    limits: TransactionLimits,
+    done: Vec<ProgrammableTransaction>,
+    current: ProgrammableTransactionBuilder,
+    /// Stats of `current`, kept up to date as fragments are appended.
+    stats: TransactionStats,
+}
+
+impl Batches {
+    pub fn new(limits: TransactionLimits) -> Self {
+        Self {
+            limits,
+            done: vec![],
+            current: ProgrammableTransactionBuilder::new(),
+            stats: TransactionStats::default(),
+        }
+    }
+
+    /// Add `fragment` to the current transaction, or to a new one if it doesn't fit.
+    ///
+    /// Returns the fragment's exports, remapped to the transaction it was added to, which is the
+    /// last one until the next push. Fails if the fragment doesn't fit in a transaction by itself.
+    pub fn push<T: RemapArguments>(&mut self, fragment: Fragment<T>) -> crate::Result<T> {
+        // Nothing to gain from trying the current transaction if it's empty
+        if !self.current.is_empty() {
+            // A fragment that conflicts with the current transaction may still fit in a new one
+            if let Ok(appended) = self.current.append_builder(fragment.builder()) {
+                let mut stats = self.stats.clone();
+                let inputs: Vec<_> = self.current.inputs.values().skip(appended.inputs).collect();
+                stats.extend(&inputs, &self.current.commands[appended.commands..]);
+                if self.limits.check(&stats).is_ok() {
+                    self.stats = stats;
+                    let (_, mut exports) = fragment.into_parts();
+                    exports.remap_arguments(&mut |argument| appended.remap(argument));
+                    return Ok(exports);
+                }
+                self.current.undo_append(appended);
+            }
+        }
+        let mut new = ProgrammableTransactionBuilder::new();
+        let exports = new.append(fragment)?;
+        let stats = new.stats();
+        self.limits.check(&stats)?;
+        self.stats = stats;
+        let previous = std::mem::replace(&mut self.current, new);
+        if !previous.is_empty() {
+            self.done.push(previous.finish());
+        }
+        Ok(exports)
+    }
+
+    /// The number of transactions so far, including the one being built.
+    pub fn len(&self) -> usize {
+        self.done.len() + usize::from(!self.current.is_empty())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The transaction being built, to which the next fragment may be added.
+    pub const fn current(&self) -> &ProgrammableTransactionBuilder {
+        &self.current
+    }
+
+    pub fn finish(self) -> Vec<ProgrammableTransaction> {
+        let Self {
+            mut done, current, ..
+        } = self;
+        if !current.is_empty() {
+            done.push(current.finish());
+        }
+        done
+    }
+}
+
+impl ProgrammableTransactionBuilder {
+    fn is_empty(&self) -> bool {
+        self.inputs.is_empty() && self.commands.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Argument, Error, ObjectArg, ObjectId, Result};
+
+    fn split(amounts: &[u64]) -> Fragment<Vec<Argument>> {
+        Fragment::build(|builder| {
+            let amounts = amounts
+                .iter()
+                .map(|amount| builder.pure(amount))
+                .collect::<Result<_>>()?;
+            Ok(builder.split_coins_into_vec(Argument::Gas, amounts))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn stats() {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.append(split(&[1, 2, 3])).unwrap();
+        builder.obj(ObjectArg::CLOCK_IMM).unwrap();
+        builder.pure(&vec![0u8; 100]).unwrap();
+        let stats = builder.stats();
+        let ptb = builder.finish();
+        assert_eq!(stats, TransactionStats::new(&ptb));
+        assert_eq!(stats.size_bytes, bcs::serialized_size(&ptb).unwrap() as u64);
+        assert_eq!(stats.inputs, 5);
+        assert_eq!(stats.input_objects, 1);
+        assert_eq!(stats.commands, 1);
+        assert_eq!(stats.largest_pure_argument, Some((4, 101)));
+        assert_eq!(stats.most_arguments, Some((0, 4)));
+        assert_eq!(stats.most_type_arguments, None);
+
+        let limits = TransactionLimits {
+            max_pure_argument_size: 100,
+            ..Default::default()
+        };
+        assert_eq!(
+            limits.check(&stats),
+            Err(LimitError::PureArgumentSize {
+                input: 4,
+                size: 101,
+                max: 100
+            })
+        );
+        assert_eq!(TransactionLimits::default().check(&stats), Ok(()));
+    }
+
+    #[test]
+    fn unserializable_size() {
+        // What `extend` counts for items that fail to serialize
+        let stats = TransactionStats {
+            size_bytes: u64::MAX,
+            ..Default::default()
+        };
+        let limits = TransactionLimits::default();
+        assert_eq!(
+            limits.check(&stats),
+            Err(LimitError::TransactionSize {
+                size: u64::MAX,
+                max: limits.max_tx_size_bytes
+            })
+        );
+    }
+
+    #[test]
+    fn extend_stats() {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let mut stats = TransactionStats::default();
+        // Past 127 inputs and commands, their lengths take two bytes
+        for amount in 0..200u64 {
+            let appended = builder.append_builder(split(&[amount]).builder()).unwrap();
+            let inputs: Vec<_> = builder.inputs.values().skip(appended.inputs).collect();
+            stats.extend(&inputs, &builder.commands[appended.commands..]);
+            assert_eq!(stats, builder.stats());
+        }
+        assert_eq!(
+            stats.size_bytes,
+            bcs::serialized_size(&builder.finish()).unwrap() as u64
+        );
+    }
+
+    #[test]
+    fn batches() {
+        let limits = TransactionLimits {
+            max_arguments: 4,
+            max_input_objects: 1,
+            ..Default::default()
+        };
+        let mut batches = Batches::new(limits);
+        assert!(batches.is_empty());
+        assert_eq!(
+            batches.push(split(&[1, 2])).unwrap(),
+            [Argument::NestedResult(0, 0), Argument::NestedResult(0, 1)]
+        );
+        assert_eq!(
+            batches.push(split(&[3])).unwrap(),
+            [Argument::NestedResult(1, 0)]
+        );
+        assert_eq!(batches.len(), 1);
+
+        // Too many input objects
+        let clock = Fragment::build(|builder| builder.obj(ObjectArg::CLOCK_IMM)).unwrap();
+        let object = ObjectArg::SharedObject {
+            id: ObjectId::new([1; 32]),
+            initial_shared_version: 1,
+            mutable: false,
+        };
+        let other = Fragment::build(|builder| builder.obj(object)).unwrap();
+        batches.push(clock.clone()).unwrap();
+        assert_eq!(batches.push(clock).unwrap(), Argument::Input(3));
+        assert_eq!(batches.push(other).unwrap(), Argument::Input(0));
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches.stats, batches.current.stats());
+
+        assert!(matches!(
+            batches.push(split(&[1, 2, 3, 4])),
+            Err(Error::Limit(LimitError::Arguments { .. }))
+        ));
+
+        let ptbs = batches.finish();
+        assert_eq!(ptbs.len(), 2);
+        assert_eq!(ptbs[0].commands.len(), 2);
+        assert_eq!(ptbs[0].inputs.len(), 4);
+        assert_eq!(ptbs[1].inputs.len(), 1);
+    }
+}
@@ -0,0 +1,182 @@
+//! Inputs typed by [`MoveType`].
+use af_move_type::{MoveType, StaticStructTag};
+
+use crate::{Argument, Error, ObjectArg, ProgrammableTransactionBuilder, Result, TypeTag};
+
+impl ProgrammableTransactionBuilder {
+    /// Like [`Self::pure`], for a value representing a Move type.
+    ///
+    /// The value is encoded with [`MoveType::to_bcs`], so it has the layout of the Move type it
+    /// represents; e.g., [`MoveVec<MoveVec<u8>>`](af_move_type::vector::MoveVec) for
+    /// `vector<vector<u8>>`. [`Self::type_check`] then checks that `T` represents the type of
+    /// every parameter the input is passed to.
+    pub fn move_pure<T: MoveType>(&mut self, value: &T) -> Result<Argument> {
+        let argument = self.pure_bytes(value.to_bcs()?, false);
+        if let Argument::Input(i) = argument {
+            self.pure_types
+                .entry(usize::from(i))
+                .or_default()
+                .push(|type_| T::TypeTag::try_from(type_.clone()).is_ok());
+        }
+        Ok(argument)
+    }
+
+    /// Like [`Self::move_pure`], but fails if `T` doesn't represent the Move type `expected`,
+    /// usually that of the parameter the input is passed to.
+    pub fn move_pure_as<T: MoveType>(&mut self, value: &T, expected: &TypeTag) -> Result<Argument> {
+        T::TypeTag::try_from(expected.clone()).map_err(|source| Error::PureType {
+            expected: expected.clone(),
+            source,
+        })?;
+        self.move_pure(value)
+    }
+
+    /// [`Self::obj_vec`] for objects whose Move type is known at compile time.
+    pub fn move_obj_vec<T: StaticStructTag>(
+        &mut self,
+        objects: impl IntoIterator<Item = ObjectArg>,
+    ) -> Result<Argument> {
+        self.obj_vec(T::struct_tag(), objects)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use af_move_type::MoveStruct;
+    use af_move_type::vector::MoveVec;
+    use af_sui_types::{ObjectDigest, ObjectId};
+    use serde::{Deserialize, Serialize};
+    use sui_sdk_types::{Command, Input, MakeMoveVector};
+
+    use super::*;
+
+    #[derive(MoveStruct, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+    #[move_(address = "0x2", module = pool)]
+    struct Pool {
+        id: ObjectId,
+    }
+
+    impl std::fmt::Display for Pool {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Pool({})", self.id)
+        }
+    }
+
+    #[test]
+    fn typed_pure_inputs() -> eyre::Result<()> {
+        let bytes = MoveVec::from(vec![MoveVec::from(vec![1u8, 2]), MoveVec::from(vec![])]);
+        let name = "pool".to_owned();
+        let amount = &5u64;
+        let ptb = crate::ptb!(
+            input pure<MoveVec<MoveVec<u8>>> bytes: &bytes;
+            input pure<String> name: &name;
+            input pure<u64> amount;
+            package pkg: ObjectId::ZERO;
+            pkg::pool::create(bytes, name, amount);
+        );
+        let values: Vec<_> = ptb
+            .inputs
+            .into_iter()
+            .map(|input| match input {
+                Input::Pure { value } => value,
+                _ => panic!("Expected pure input"),
+            })
+            .collect();
+        assert_eq!(values[0], bcs::to_bytes(&vec![vec![1u8, 2], vec![]])?);
+        assert_eq!(values[1], bcs::to_bytes("pool")?);
+        assert_eq!(values[2], bcs::to_bytes(&5u64)?);
+        Ok(())
+    }
+
+    #[test]
+    fn pure_type_mismatch() {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let string: TypeTag = "0x1::string::String".parse().unwrap();
+        assert!(builder.move_pure_as(&"pool".to_owned(), &string).is_ok());
+        assert!(builder.move_pure_as(&5u64, &TypeTag::U64).is_ok());
+        assert!(matches!(
+            builder.move_pure_as(&5u64, &TypeTag::U32),
+            Err(Error::PureType { .. })
+        ));
+        assert!(matches!(
+            builder.move_pure_as(&MoveVec::from(vec![5u64]), &string),
+            Err(Error::PureType { .. })
+        ));
+    }
+
+    #[test]
+    fn pure_types_checked() -> eyre::Result<()> {
+        use std::collections::HashMap;
+
+        use crate::check::{ArgumentError, FunctionSignature, SignatureType, TypeErrorKind};
+
+        let mut abis = HashMap::new();
+        abis.insert(
+            (ObjectId::ZERO, "pool".parse()?, "deposit".parse()?),
+            FunctionSignature {
+                type_parameters: 0,
+                parameters: vec![SignatureType::U64],
+                returns: vec![],
+            },
+        );
+        let amount = 5u64;
+        let mut builder = ProgrammableTransactionBuilder::new();
+        crate::ptbuilder!(builder {
+            input pure<u64> amount: &amount;
+            package pkg: ObjectId::ZERO;
+            pkg::pool::deposit(amount);
+        });
+        builder.type_check(&abis)?;
+
+        let amount = 5u32;
+        let mut builder = ProgrammableTransactionBuilder::new();
+        crate::ptbuilder!(builder {
+            input pure<u32> amount: &amount;
+            package pkg: ObjectId::ZERO;
+            pkg::pool::deposit(amount);
+        });
+        assert_eq!(
+            builder.type_check(&abis).unwrap_err().kind,
+            TypeErrorKind::Argument {
+                index: 0,
+                error: ArgumentError::PureType(Box::new(TypeTag::U64))
+            }
+        );
+
+        // Kept when appended as a fragment
+        let mut appended = ProgrammableTransactionBuilder::new();
+        appended.pure(&1u8)?;
+        appended.append(crate::Fragment::from(builder))?;
+        assert!(appended.type_check(&abis).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn object_vectors() -> eyre::Result<()> {
+        let pools = [1, 2]
+            .map(|i| ObjectArg::ImmOrOwnedObject((ObjectId::new([i; 32]), 1, ObjectDigest::ZERO)));
+        let empty: Vec<ObjectArg> = vec![];
+        let ptb = crate::ptb!(
+            input objs<Pool> pools;
+            input objs<Pool> empty: empty;
+            package pkg: ObjectId::ZERO;
+            pkg::pool::merge(pools, empty);
+        );
+        assert_eq!(ptb.inputs.len(), 2);
+        let pool_type: TypeTag = "0x2::pool::Pool".parse()?;
+        assert_eq!(
+            ptb.commands[..2],
+            [
+                Command::MakeMoveVector(MakeMoveVector {
+                    type_: Some(pool_type.clone()),
+                    elements: vec![Argument::Input(0), Argument::Input(1)],
+                }),
+                Command::MakeMoveVector(MakeMoveVector {
+                    type_: Some(pool_type),
+                    elements: vec![],
+                }),
+            ]
+        );
+        Ok(())
+    }
+}
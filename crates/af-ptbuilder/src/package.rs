@@ -0,0 +1,508 @@
+//! Publishing and upgrading Move packages.
+//!
+//! [`CompiledPackage`] loads the bytecode produced by `sui move build`, and the builder's
+//! [`publish`](ProgrammableTransactionBuilder::publish) and
+//! [`upgrade`](ProgrammableTransactionBuilder::upgrade) add the commands for it to a transaction.
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use af_sui_types::{Address, IdentStr, ObjectArg, ObjectId, SUI_FRAMEWORK_PACKAGE_ID};
+use serde::Deserialize;
+use sui_sdk_types::hash::Hasher;
+
+use crate::uleb128::read_uleb128;
+use crate::{Argument, Command, ProgrammableTransactionBuilder};
+
+/// Bytecode of a Move package and the IDs of the packages it depends on, transitively.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompiledPackage {
+    /// Modules in dependency order, as expected by the `Publish` and `Upgrade` commands.
+    pub modules: Vec<Vec<u8>>,
+    pub dependencies: Vec<ObjectId>,
+}
+
+impl CompiledPackage {
+    /// Load the output of `sui move build --dump-bytecode-as-base64`.
+    pub fn from_bytecode_dump(json: &str) -> Result<Self, PackageError> {
+        #[derive(Deserialize)]
+        struct Dump {
+            modules: Vec<String>,
+            dependencies: Vec<String>,
+        }
+
+        let Dump {
+            modules,
+            dependencies,
+        } = serde_json::from_str(json)?;
+        let modules = modules
+            .iter()
+            .map(af_sui_types::decode_base64_default)
+            .collect::<Result<_, _>>()?;
+        let dependencies = dependencies
+            .iter()
+            .map(|id| id.parse().map_err(|_| PackageError::Address(id.clone())))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            modules,
+            dependencies,
+        })
+    }
+
+    /// Load a package from its build directory, `build/<package name>`, after `sui move build`.
+    ///
+    /// The dependencies are the non-zero addresses that `BuildInfo.yaml` assigns to the named
+    /// addresses of the package and its dependencies, as the file doesn't say which of them are
+    /// packages. A named address that isn't a package's, e.g., an admin account, ends up among the
+    /// dependencies too and must be removed before publishing. The others are the original IDs of
+    /// the dependencies; any that were upgraded must be replaced with the ID of the version to
+    /// link against.
+    pub fn from_build_dir(dir: impl AsRef<Path>) -> Result<Self, PackageError> {
+        let dir = dir.as_ref();
+
+        let path = dir.join("BuildInfo.yaml");
+        let info: BuildInfo = serde_yaml::from_str(&read_to_string(&path)?)?;
+        let mut dependencies = info
+            .compiled_package_info
+            .address_alias_instantiation
+            .values()
+            .map(|hex| {
+                format!("0x{hex}")
+                    .parse()
+                    .map_err(|_| PackageError::Address(hex.clone()))
+            })
+            .filter(|id| id.as_ref().map_or(true, |id| *id != ObjectId::ZERO))
+            .collect::<Result<Vec<ObjectId>, _>>()?;
+        dependencies.sort();
+        dependencies.dedup();
+
+        let modules_dir = dir.join("bytecode_modules");
+        let mut paths = vec![];
+        for entry in std::fs::read_dir(&modules_dir).map_err(|e| io(&modules_dir, e))? {
+            let path = entry.map_err(|e| io(&modules_dir, e))?.path();
+            if path.extension().is_some_and(|ext| ext == "mv") {
+                paths.push(path);
+            }
+        }
+        // Deterministic order among modules that don't depend on each other
+        paths.sort();
+        let modules = paths
+            .iter()
+            .map(|path| std::fs::read(path).map_err(|e| io(path, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if modules.is_empty() {
+            return Err(PackageError::NoModules(modules_dir));
+        }
+
+        Ok(Self {
+            modules: dependency_order(modules)?,
+            dependencies,
+        })
+    }
+
+    /// The digest authorizing an upgrade to this package.
+    ///
+    /// Computed as in `MovePackage::compute_digest_for_modules_and_deps`, from the hashes of the
+    /// modules and the dependency IDs, in any order.
+    pub fn digest(&self) -> [u8; 32] {
+        let module_digests: Vec<_> = self
+            .modules
+            .iter()
+            .map(|module| Hasher::digest(module).into_inner())
+            .collect();
+        let mut components: Vec<&[u8]> = module_digests
+            .iter()
+            .map(|digest| digest.as_slice())
+            .chain(self.dependencies.iter().map(|id| id.as_bytes()))
+            .collect();
+        components.sort();
+        let mut hasher = Hasher::new();
+        for component in components {
+            hasher.update(component);
+        }
+        hasher.finalize().into_inner()
+    }
+}
+
+/// The upgrades allowed by an `UpgradeCap`, from least to most restrictive.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum UpgradePolicy {
+    /// Any change that keeps the public signatures and struct layouts of the package.
+    #[default]
+    Compatible = 0,
+    /// Only new functions and types can be added.
+    Additive = 128,
+    /// Only the dependencies can change.
+    DepOnly = 192,
+}
+
+impl ProgrammableTransactionBuilder {
+    /// Publish `package`, returning the argument for its `UpgradeCap`.
+    ///
+    /// The cap must be transferred or otherwise consumed later in the transaction; see
+    /// [`Self::publish_to`].
+    pub fn publish(&mut self, package: &CompiledPackage) -> Argument {
+        self.command(Command::Publish(
+            package.modules.clone(),
+            package.dependencies.clone(),
+        ))
+    }
+
+    /// Publish `package` and transfer its `UpgradeCap` to `recipient`.
+    pub fn publish_to(
+        &mut self,
+        package: &CompiledPackage,
+        recipient: Address,
+    ) -> crate::Result<()> {
+        let cap = self.publish(package);
+        let recipient = self.pure(&recipient)?;
+        self.command(Command::TransferObjects(vec![cap], recipient));
+        Ok(())
+    }
+
+    /// Upgrade the package with ID `package_id`, the latest version of the one `upgrade_cap`
+    /// controls, to `package`.
+    ///
+    /// Adds the `authorize_upgrade`, `Upgrade` and `commit_upgrade` commands. `policy` must be at
+    /// least as restrictive as that of the cap.
+    pub fn upgrade(
+        &mut self,
+        upgrade_cap: ObjectArg,
+        package_id: ObjectId,
+        policy: UpgradePolicy,
+        package: &CompiledPackage,
+    ) -> crate::Result<()> {
+        let cap = self.obj(upgrade_cap)?;
+        let policy = self.pure(&(policy as u8))?;
+        let digest = self.pure(package.digest().as_slice())?;
+        let ticket = self.command(Command::move_call(
+            SUI_FRAMEWORK_PACKAGE_ID,
+            IdentStr::cast("package").to_owned(),
+            IdentStr::cast("authorize_upgrade").to_owned(),
+            vec![],
+            vec![cap, policy, digest],
+        ));
+        let receipt = self.command(Command::Upgrade(
+            package.modules.clone(),
+            package.dependencies.clone(),
+            package_id,
+            ticket,
+        ));
+        self.command(Command::move_call(
+            SUI_FRAMEWORK_PACKAGE_ID,
+            IdentStr::cast("package").to_owned(),
+            IdentStr::cast("commit_upgrade").to_owned(),
+            vec![],
+            vec![cap, receipt],
+        ));
+        Ok(())
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PackageError {
+    #[error("Reading {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Parsing BuildInfo.yaml: {0}")]
+    BuildInfo(#[from] serde_yaml::Error),
+    #[error("Parsing bytecode dump: {0}")]
+    Dump(#[from] serde_json::Error),
+    #[error("Decoding module: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("Invalid address {0:?}")]
+    Address(String),
+    #[error("No modules in {0}")]
+    NoModules(PathBuf),
+    #[error("Invalid module bytecode")]
+    Bytecode,
+    #[error("Cyclic dependencies between modules")]
+    CyclicDependencies,
+}
+
+fn io(path: &Path, source: std::io::Error) -> PackageError {
+    PackageError::Io {
+        path: path.to_owned(),
+        source,
+    }
+}
+
+fn read_to_string(path: &Path) -> Result<String, PackageError> {
+    std::fs::read_to_string(path).map_err(|e| io(path, e))
+}
+
+#[derive(Deserialize)]
+struct BuildInfo {
+    compiled_package_info: CompiledPackageInfo,
+}
+
+#[derive(Deserialize)]
+struct CompiledPackageInfo {
+    address_alias_instantiation: BTreeMap<String, String>,
+}
+
+// =============================================================================
+//  Module dependencies
+// =============================================================================
+
+/// Sort `modules` so that each comes after the modules of the package it uses.
+fn dependency_order(modules: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, PackageError> {
+    let handles = modules
+        .iter()
+        .map(|bytes| module_handles(bytes).ok_or(PackageError::Bytecode))
+        .collect::<Result<Vec<_>, _>>()?;
+    let index: HashMap<_, _> = handles
+        .iter()
+        .enumerate()
+        .map(|(i, (self_, _))| (self_.clone(), i))
+        .collect();
+
+    // Depth-first, visiting the dependencies of each module before it
+    let mut order = Vec::with_capacity(modules.len());
+    let mut state = vec![Visit::New; modules.len()];
+    fn visit(
+        i: usize,
+        handles: &[(ModuleId, Vec<ModuleId>)],
+        index: &HashMap<ModuleId, usize>,
+        state: &mut [Visit],
+        order: &mut Vec<usize>,
+    ) -> Result<(), PackageError> {
+        match state[i] {
+            Visit::Done => return Ok(()),
+            Visit::InProgress => return Err(PackageError::CyclicDependencies),
+            Visit::New => state[i] = Visit::InProgress,
+        }
+        for dependency in &handles[i].1 {
+            if let Some(&j) = index.get(dependency) {
+                visit(j, handles, index, state, order)?;
+            }
+        }
+        state[i] = Visit::Done;
+        order.push(i);
+        Ok(())
+    }
+    for i in 0..modules.len() {
+        visit(i, &handles, &index, &mut state, &mut order)?;
+    }
+
+    let mut modules: Vec<_> = modules.into_iter().map(Some).collect();
+    Ok(order
+        .into_iter()
+        .filter_map(|i| modules[i].take())
+        .collect())
+}
+
+#[derive(Clone, Copy)]
+enum Visit {
+    New,
+    InProgress,
+    Done,
+}
+
+type ModuleId = (Address, String);
+
+/// The module's own ID and those of the modules it uses, from its bytecode.
+fn module_handles(bytes: &[u8]) -> Option<(ModuleId, Vec<ModuleId>)> {
+    const MAGIC: [u8; 4] = [0xA1, 0x1C, 0xEB, 0x0B];
+    const MODULE_HANDLES: u8 = 0x1;
+    const IDENTIFIERS: u8 = 0x7;
+    const ADDRESS_IDENTIFIERS: u8 = 0x8;
+
+    let mut cursor = bytes.strip_prefix(&MAGIC)?;
+    let (version, rest) = cursor.split_first_chunk::<4>()?;
+    // The upper bytes hold the flavor of the bytecode
+    let version = u32::from_le_bytes(*version) & 0xFFFF;
+    cursor = rest;
+
+    let mut tables = HashMap::new();
+    let mut content_len = 0;
+    for _ in 0..read_uleb128(&mut cursor)? {
+        let (&kind, rest) = cursor.split_first()?;
+        cursor = rest;
+        let offset = read_uleb128(&mut cursor)?;
+        let len = read_uleb128(&mut cursor)?;
+        content_len = content_len.max(offset.checked_add(len)?);
+        tables.insert(kind, (offset, len));
+    }
+    let content_start = bytes.len() - cursor.len();
+    let content = cursor.get(..content_len)?;
+    let table = |kind| {
+        let &(offset, len) = tables.get(&kind)?;
+        content.get(offset..offset + len)
+    };
+
+    let mut identifiers = vec![];
+    let mut cursor = table(IDENTIFIERS)?;
+    while !cursor.is_empty() {
+        let len = read_uleb128(&mut cursor)?;
+        let (name, rest) = cursor.split_at_checked(len)?;
+        identifiers.push(std::str::from_utf8(name).ok()?.to_owned());
+        cursor = rest;
+    }
+    let addresses: Vec<_> = table(ADDRESS_IDENTIFIERS)?
+        .chunks_exact(Address::LENGTH)
+        .map(|chunk| Address::from_bytes(chunk).ok())
+        .collect::<Option<_>>()?;
+
+    let mut handles = vec![];
+    let mut cursor = table(MODULE_HANDLES)?;
+    while !cursor.is_empty() {
+        let address = *addresses.get(read_uleb128(&mut cursor)?)?;
+        let name = identifiers.get(read_uleb128(&mut cursor)?)?.clone();
+        handles.push((address, name));
+    }
+
+    // Since version 5, the index of the module's own handle follows the tables
+    let self_index = if version >= 5 {
+        read_uleb128(&mut bytes.get(content_start + content_len..)?)?
+    } else {
+        0
+    };
+    if self_index >= handles.len() {
+        return None;
+    }
+    let self_ = handles.remove(self_index);
+    Some((self_, handles))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uleb128(value: usize) -> Vec<u8> {
+        let mut out = vec![];
+        crate::uleb128::write_uleb128(&mut out, value);
+        out
+    }
+
+    /// Bytecode with only the tables read by [`module_handles`].
+    fn module(name: &str, uses: &[&str]) -> Vec<u8> {
+        let names: Vec<_> = std::iter::once(name).chain(uses.iter().copied()).collect();
+        let identifiers: Vec<u8> = names
+            .iter()
+            .flat_map(|name| [uleb128(name.len()), name.as_bytes().to_vec()].concat())
+            .collect();
+        let addresses = Address::ZERO.as_bytes().to_vec();
+        let handles: Vec<u8> = (0..names.len()).flat_map(|i| [0, i as u8]).collect();
+
+        let mut bytes = vec![0xA1, 0x1C, 0xEB, 0x0B];
+        bytes.extend(6u32.to_le_bytes());
+        bytes.push(3);
+        let mut offset = 0;
+        for (kind, table) in [(0x1, &handles), (0x7, &identifiers), (0x8, &addresses)] {
+            bytes.push(kind);
+            bytes.extend(uleb128(offset));
+            bytes.extend(uleb128(table.len()));
+            offset += table.len();
+        }
+        bytes.extend([handles, identifiers, addresses].concat());
+        // Self handle index
+        bytes.push(0);
+        bytes
+    }
+
+    #[test]
+    fn modules_in_dependency_order() {
+        let a = module("a", &["b", "c"]);
+        let b = module("b", &["c"]);
+        let c = module("c", &[]);
+        let ordered = dependency_order(vec![a.clone(), b.clone(), c.clone()]).unwrap();
+        assert_eq!(ordered, [c, b.clone(), a.clone()]);
+
+        let cyclic = module("c", &["a"]);
+        assert!(matches!(
+            dependency_order(vec![a, b, cyclic]),
+            Err(PackageError::CyclicDependencies)
+        ));
+        assert!(matches!(
+            dependency_order(vec![vec![0; 10]]),
+            Err(PackageError::Bytecode)
+        ));
+    }
+
+    #[test]
+    fn build_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let modules_dir = dir.join("bytecode_modules");
+        std::fs::create_dir_all(modules_dir.join("dependencies")).unwrap();
+        std::fs::write(
+            dir.join("BuildInfo.yaml"),
+            "\
+---
+compiled_package_info:
+  package_name: Example
+  address_alias_instantiation:
+    example: \"0000000000000000000000000000000000000000000000000000000000000000\"
+    std: \"0000000000000000000000000000000000000000000000000000000000000001\"
+    sui: \"0000000000000000000000000000000000000000000000000000000000000002\"
+  source_digest: 3F6B1C
+dependencies:
+  - MoveStdlib
+  - Sui
+",
+        )
+        .unwrap();
+        std::fs::write(modules_dir.join("a.mv"), module("a", &["b"])).unwrap();
+        std::fs::write(modules_dir.join("b.mv"), module("b", &[])).unwrap();
+
+        let package = CompiledPackage::from_build_dir(dir).unwrap();
+        assert_eq!(package.modules, [module("b", &[]), module("a", &["b"])]);
+        assert_eq!(
+            package.dependencies,
+            [
+                af_sui_types::MOVE_STDLIB_PACKAGE_ID,
+                SUI_FRAMEWORK_PACKAGE_ID
+            ]
+        );
+    }
+
+    #[test]
+    fn upgrade_commands() {
+        let package = CompiledPackage::from_bytecode_dump(
+            r#"{"modules":["oRzrCwYAAAA=","AQID"],"dependencies":["0x1","0x2"],"digest":[]}"#,
+        )
+        .unwrap();
+        assert_eq!(package.modules[1], [1, 2, 3]);
+        assert_eq!(package.dependencies[1], SUI_FRAMEWORK_PACKAGE_ID);
+
+        // Digest doesn't depend on the order of modules and dependencies
+        let mut reversed = package.clone();
+        reversed.modules.reverse();
+        reversed.dependencies.reverse();
+        assert_eq!(package.digest(), reversed.digest());
+
+        let cap = ObjectArg::ImmOrOwnedObject((
+            ObjectId::new([1; 32]),
+            1,
+            af_sui_types::ObjectDigest::ZERO,
+        ));
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder
+            .upgrade(
+                cap,
+                ObjectId::new([2; 32]),
+                UpgradePolicy::Additive,
+                &package,
+            )
+            .unwrap();
+        let ptb = builder.finish();
+        assert_eq!(ptb.inputs.len(), 3);
+        let sui_sdk_types::Input::Pure { value } = &ptb.inputs[2] else {
+            panic!("Expected pure digest");
+        };
+        assert_eq!(value[0], 32, "Digest is a vector<u8>");
+        let commands: Vec<Command> = ptb.commands.into_iter().map(Into::into).collect();
+        assert!(matches!(
+            &commands[1],
+            Command::Upgrade(_, _, id, Argument::Result(0)) if *id == ObjectId::new([2; 32])
+        ));
+        let Command::MoveCall(commit) = &commands[2] else {
+            panic!("Expected commit_upgrade call");
+        };
+        assert_eq!(commit.function.as_str(), "commit_upgrade");
+        assert_eq!(commit.arguments, [Argument::Input(0), Argument::Result(1)]);
+    }
+}
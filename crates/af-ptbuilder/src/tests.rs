@@ -8,3 +8,41 @@ fn ptb_conversion(ptb: ProgrammableTransaction) {
     let ptb_: ProgrammableTransaction = builder.into();
     assert_eq!(ptb, ptb_);
 }
+
+#[test]
+fn merge_and_split() {
+    use af_sui_types::ObjectDigest;
+
+    let coin = |i: u8| (ObjectId::new([i; 32]), 1, ObjectDigest::ZERO);
+    let mut builder = ProgrammableTransactionBuilder::new();
+    let split = builder
+        .merge_and_split([coin(1), coin(2), coin(3)], 10)
+        .unwrap();
+    assert_eq!(split, Argument::NestedResult(1, 0));
+    let ptb = builder.finish();
+    assert_eq!(ptb.inputs.len(), 4);
+    assert_eq!(ptb.commands.len(), 2);
+
+    let mut builder = ProgrammableTransactionBuilder::new();
+    builder.merge_and_split([coin(1)], 10).unwrap();
+    assert_eq!(builder.finish().commands.len(), 1);
+
+    let mut builder = ProgrammableTransactionBuilder::new();
+    assert!(matches!(
+        builder.merge_and_split([], 10),
+        Err(Error::NoCoins)
+    ));
+}
+
+#[test]
+fn split_coin_too_many_commands() {
+    let mut builder = ProgrammableTransactionBuilder::new();
+    let amount = builder.pure(&1u64).unwrap();
+    for _ in 0..=u16::MAX {
+        builder.command(Command::SplitCoins(Argument::Gas, vec![amount]));
+    }
+    assert!(matches!(
+        builder.split_coin(Argument::Gas, amount),
+        Err(Error::TooManyCommands)
+    ));
+}
@@ -0,0 +1,1186 @@
+//! Human-readable text format for programmable transactions.
+//!
+//! [`print`] (or a configured [`Printer`]) renders a [`ProgrammableTransaction`] in a syntax close
+//! to that of [`ptb!`](crate::ptb), and [`parse`] reads it back, so that transactions can be
+//! reviewed, diffed and edited by hand:
+//!
+//! ```text
+//! input obj pool = shared(0x…01, 7, mut);
+//! input obj coin = owned(0x…02, 3, 11111111111111111111111111111111);
+//! input pure amount: u64 = 1000;
+//! input pure recipient: address = 0x…03;
+//! input pure input_4 = 0x0102;
+//! let result_0 = 0x…07::pool::deposit<0x…02::sui::SUI>(pool, coin, amount);
+//! let result_1 = SplitCoins(result_0.0, [amount]);
+//! let result_2 = TransferObjects([result_1.0], recipient);
+//! ```
+//!
+//! Every command is bound to a result name, and nested results are accessed with `.`. Pure inputs
+//! are decoded when their type can be inferred from the commands using them, with the help of
+//! [`Signatures`] for Move calls; otherwise they are shown as hex-encoded BCS bytes. Lines starting
+//! with `//` are comments.
+//!
+//! Printing then parsing gives back the same transaction.
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+
+use af_sui_types::{
+    Address,
+    Argument,
+    Command,
+    Identifier,
+    MOVE_STDLIB_ADDRESS,
+    MoveCall,
+    ObjectDigest,
+    ObjectId,
+    ProgrammableTransaction,
+    SUI_FRAMEWORK_ADDRESS,
+    StructTag,
+    TypeTag,
+};
+use sui_sdk_types::{
+    Input,
+    MakeMoveVector,
+    MergeCoins,
+    ObjectReference,
+    Publish,
+    SplitCoins,
+    TransferObjects,
+    Upgrade,
+};
+
+use crate::check::{Signatures, is_pure};
+use crate::uleb128::{read_uleb128, write_uleb128};
+
+/// Render `ptb` with the default [`Printer`].
+pub fn print(ptb: &ProgrammableTransaction) -> String {
+    Printer::new().print(ptb)
+}
+
+/// Renders transactions in the text format. See the [module](crate::text) documentation.
+#[derive(Default)]
+pub struct Printer<'a> {
+    input_names: HashMap<u16, String>,
+    signatures: Option<&'a dyn Signatures>,
+}
+
+impl<'a> Printer<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name an input instead of the default `input_<index>`.
+    ///
+    /// Names that aren't identifiers, are already taken, start with `result_` or are `gas` are
+    /// ignored.
+    pub fn input_name(mut self, input: u16, name: impl Into<String>) -> Self {
+        self.input_names.insert(input, name.into());
+        self
+    }
+
+    /// Signatures for inferring the types of pure inputs passed to Move calls.
+    pub fn signatures(mut self, signatures: &'a dyn Signatures) -> Self {
+        self.signatures = Some(signatures);
+        self
+    }
+
+    pub fn print(&self, ptb: &ProgrammableTransaction) -> String {
+        let names = self.names(ptb.inputs.len());
+        let types = self.pure_types(ptb);
+        let arg = |argument: &Argument| match *argument {
+            Argument::Gas => "gas".to_owned(),
+            Argument::Input(i) => names
+                .get(usize::from(i))
+                .cloned()
+                .unwrap_or_else(|| format!("input_{i}")),
+            Argument::Result(i) => format!("result_{i}"),
+            Argument::NestedResult(i, j) => format!("result_{i}.{j}"),
+        };
+        let args =
+            |arguments: &[Argument]| arguments.iter().map(arg).collect::<Vec<_>>().join(", ");
+
+        let mut out = String::new();
+        for (i, input) in ptb.inputs.iter().enumerate() {
+            let name = &names[i];
+            let _ = match input {
+                Input::Pure { value } => match types
+                    .get(&(i as u16))
+                    .and_then(|type_| Some((type_, decode(value, type_)?)))
+                {
+                    Some((type_, decoded)) => {
+                        writeln!(out, "input pure {name}: {type_} = {decoded};")
+                    }
+                    None => writeln!(out, "input pure {name} = {};", hex(value)),
+                },
+                Input::ImmutableOrOwned(oref) => {
+                    let (id, version, digest) = oref.clone().into_parts();
+                    writeln!(out, "input obj {name} = owned({id}, {version}, {digest});")
+                }
+                Input::Shared {
+                    object_id,
+                    initial_shared_version,
+                    mutable,
+                } => {
+                    let mutability = if *mutable { "mut" } else { "imm" };
+                    writeln!(
+                        out,
+                        "input obj {name} = shared({object_id}, {initial_shared_version}, \
+                         {mutability});"
+                    )
+                }
+                Input::Receiving(oref) => {
+                    let (id, version, digest) = oref.clone().into_parts();
+                    writeln!(
+                        out,
+                        "input obj {name} = receiving({id}, {version}, {digest});"
+                    )
+                }
+            };
+        }
+        for (i, command) in ptb.commands.iter().enumerate() {
+            let _ = write!(out, "let result_{i} = ");
+            let _ = match command {
+                Command::MoveCall(MoveCall {
+                    package,
+                    module,
+                    function,
+                    type_arguments,
+                    arguments,
+                }) => {
+                    let _ = write!(out, "{package}::{module}::{function}");
+                    if !type_arguments.is_empty() {
+                        let _ = write!(out, "<{}>", join(type_arguments));
+                    }
+                    write!(out, "({})", args(arguments))
+                }
+                Command::TransferObjects(TransferObjects { objects, address }) => {
+                    write!(
+                        out,
+                        "TransferObjects([{}], {})",
+                        args(objects),
+                        arg(address)
+                    )
+                }
+                Command::SplitCoins(SplitCoins { coin, amounts }) => {
+                    write!(out, "SplitCoins({}, [{}])", arg(coin), args(amounts))
+                }
+                Command::MergeCoins(MergeCoins {
+                    coin,
+                    coins_to_merge,
+                }) => write!(out, "MergeCoins({}, [{}])", arg(coin), args(coins_to_merge)),
+                Command::MakeMoveVector(MakeMoveVector { type_, elements }) => {
+                    let _ = write!(out, "MakeMoveVec");
+                    if let Some(type_) = type_ {
+                        let _ = write!(out, "<{type_}>");
+                    }
+                    write!(out, "([{}])", args(elements))
+                }
+                Command::Publish(Publish {
+                    modules,
+                    dependencies,
+                }) => write!(
+                    out,
+                    "Publish([{}], [{}])",
+                    modules
+                        .iter()
+                        .map(|m| hex(m))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    join(dependencies)
+                ),
+                Command::Upgrade(Upgrade {
+                    modules,
+                    dependencies,
+                    package,
+                    ticket,
+                }) => write!(
+                    out,
+                    "Upgrade([{}], [{}], {package}, {})",
+                    modules
+                        .iter()
+                        .map(|m| hex(m))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    join(dependencies),
+                    arg(ticket)
+                ),
+            };
+            let _ = writeln!(out, ";");
+        }
+        out
+    }
+
+    fn names(&self, count: usize) -> Vec<String> {
+        let mut taken = HashSet::new();
+        (0..count)
+            .map(|i| {
+                let custom = self
+                    .input_names
+                    .get(&(i as u16))
+                    .filter(|name| is_valid_name(name) && !taken.contains(*name));
+                let name = custom.cloned().unwrap_or_else(|| format!("input_{i}"));
+                taken.insert(name.clone());
+                name
+            })
+            .collect()
+    }
+
+    /// The types of pure inputs, from the first command using each.
+    fn pure_types(&self, ptb: &ProgrammableTransaction) -> HashMap<u16, TypeTag> {
+        let mut types = HashMap::new();
+        let mut infer = |argument: &Argument, type_: &TypeTag| {
+            let Argument::Input(i) = *argument else {
+                return;
+            };
+            if matches!(ptb.inputs.get(usize::from(i)), Some(Input::Pure { .. })) && is_pure(type_)
+            {
+                types.entry(i).or_insert_with(|| type_.clone());
+            }
+        };
+        for command in &ptb.commands {
+            match command {
+                Command::MoveCall(call) => {
+                    for (argument, type_) in self.parameter_types(call) {
+                        infer(argument, &type_);
+                    }
+                }
+                Command::TransferObjects(transfer) => infer(&transfer.address, &TypeTag::Address),
+                Command::SplitCoins(split) => {
+                    for amount in &split.amounts {
+                        infer(amount, &TypeTag::U64);
+                    }
+                }
+                Command::MakeMoveVector(MakeMoveVector {
+                    type_: Some(type_),
+                    elements,
+                }) => {
+                    for element in elements {
+                        infer(element, type_);
+                    }
+                }
+                _ => (),
+            }
+        }
+        types
+    }
+
+    fn parameter_types<'c>(&self, call: &'c MoveCall) -> Vec<(&'c Argument, TypeTag)> {
+        let Some(signature) = self
+            .signatures
+            .and_then(|s| s.function_signature(&call.package, &call.module, &call.function))
+        else {
+            return vec![];
+        };
+        let mut parameters = &signature.parameters[..];
+        if let [rest @ .., last] = parameters {
+            if last.is_tx_context() {
+                parameters = rest;
+            }
+        }
+        if parameters.len() != call.arguments.len() {
+            return vec![];
+        }
+        call.arguments
+            .iter()
+            .zip(parameters)
+            .filter_map(|(argument, parameter)| {
+                let (_, type_) = parameter.instantiate(&call.type_arguments)?;
+                Some((argument, type_))
+            })
+            .collect()
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    is_identifier(name)
+        && name != "gas"
+        && !name.starts_with("input_")
+        && !name.starts_with("result_")
+}
+
+/// The argument a default name like `input_1` or `result_2` stands for.
+fn default_argument(name: &str) -> Option<Argument> {
+    if let Some(index) = name.strip_prefix("input_") {
+        return index.parse().ok().map(Argument::Input);
+    }
+    name.strip_prefix("result_")?
+        .parse()
+        .ok()
+        .map(Argument::Result)
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn join<T: std::fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 + bytes.len() * 2);
+    out.push_str("0x");
+    for byte in bytes {
+        let _ = write!(out, "{byte:02x}");
+    }
+    out
+}
+
+// =============================================================================
+//  Pure values
+// =============================================================================
+
+enum PureStruct {
+    String,
+    AsciiString,
+    Id,
+    Option,
+}
+
+fn pure_struct(tag: &StructTag) -> Option<PureStruct> {
+    let is = |address: Address, module: &str, name: &str| {
+        tag.address == address && tag.module.as_str() == module && tag.name.as_str() == name
+    };
+    if is(MOVE_STDLIB_ADDRESS, "string", "String") {
+        Some(PureStruct::String)
+    } else if is(MOVE_STDLIB_ADDRESS, "ascii", "String") {
+        Some(PureStruct::AsciiString)
+    } else if is(SUI_FRAMEWORK_ADDRESS, "object", "ID") {
+        Some(PureStruct::Id)
+    } else if is(MOVE_STDLIB_ADDRESS, "option", "Option") && tag.type_params.len() == 1 {
+        Some(PureStruct::Option)
+    } else {
+        None
+    }
+}
+
+/// Decode BCS `bytes` as a value of `type_`, if they are one.
+fn decode(bytes: &[u8], type_: &TypeTag) -> Option<String> {
+    let mut reader = bytes;
+    let mut out = String::new();
+    decode_value(&mut reader, type_, &mut out)?;
+    reader.is_empty().then_some(out)
+}
+
+fn decode_value(reader: &mut &[u8], type_: &TypeTag, out: &mut String) -> Option<()> {
+    let _ = match type_ {
+        TypeTag::Bool => match take::<1>(reader)? {
+            [0] => write!(out, "false"),
+            [1] => write!(out, "true"),
+            _ => return None,
+        },
+        TypeTag::U8 => write!(out, "{}", u8::from_le_bytes(take(reader)?)),
+        TypeTag::U16 => write!(out, "{}", u16::from_le_bytes(take(reader)?)),
+        TypeTag::U32 => write!(out, "{}", u32::from_le_bytes(take(reader)?)),
+        TypeTag::U64 => write!(out, "{}", u64::from_le_bytes(take(reader)?)),
+        TypeTag::U128 => write!(out, "{}", u128::from_le_bytes(take(reader)?)),
+        TypeTag::U256 => {
+            let mut bytes: [u8; 32] = take(reader)?;
+            bytes.reverse();
+            write!(out, "{}", hex(&bytes))
+        }
+        TypeTag::Address => write!(out, "{}", Address::new(take(reader)?)),
+        TypeTag::Signer => return None,
+        TypeTag::Vector(inner) if **inner == TypeTag::U8 => {
+            let len = read_uleb128(reader)?;
+            write!(out, "{}", hex(take_slice(reader, len)?))
+        }
+        TypeTag::Vector(inner) => {
+            let len = read_uleb128(reader)?;
+            out.push('[');
+            for i in 0..len {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                decode_value(reader, inner, out)?;
+            }
+            write!(out, "]")
+        }
+        TypeTag::Struct(tag) => match pure_struct(tag)? {
+            PureStruct::String | PureStruct::AsciiString => {
+                let len = read_uleb128(reader)?;
+                let string = std::str::from_utf8(take_slice(reader, len)?).ok()?;
+                // The parser only takes ASCII for `ascii::String`
+                if matches!(pure_struct(tag)?, PureStruct::AsciiString) && !string.is_ascii() {
+                    return None;
+                }
+                write!(out, "{string:?}")
+            }
+            PureStruct::Id => write!(out, "{}", Address::new(take(reader)?)),
+            PureStruct::Option => match take::<1>(reader)? {
+                [0] => write!(out, "none"),
+                [1] => {
+                    out.push_str("some(");
+                    decode_value(reader, &tag.type_params[0], out)?;
+                    write!(out, ")")
+                }
+                _ => return None,
+            },
+        },
+    };
+    Some(())
+}
+
+fn take<const N: usize>(reader: &mut &[u8]) -> Option<[u8; N]> {
+    let (bytes, rest) = reader.split_first_chunk::<N>()?;
+    *reader = rest;
+    Some(*bytes)
+}
+
+const fn take_slice<'b>(reader: &mut &'b [u8], len: usize) -> Option<&'b [u8]> {
+    let Some((bytes, rest)) = reader.split_at_checked(len) else {
+        return None;
+    };
+    *reader = rest;
+    Some(bytes)
+}
+
+// =============================================================================
+//  Parser
+// =============================================================================
+
+/// Parse a transaction in the text format. See the [module](crate::text) documentation.
+pub fn parse(text: &str) -> Result<ProgrammableTransaction, ParseError> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        names: HashMap::new(),
+        inputs: vec![],
+        commands: vec![],
+    };
+    parser.program()?;
+    Ok(ProgrammableTransaction {
+        inputs: parser.inputs,
+        commands: parser.commands,
+    })
+}
+
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+#[error("At line {line}, column {column}: {message}")]
+pub struct ParseError {
+    /// Starting at 1.
+    pub line: usize,
+    /// Starting at 1, in characters.
+    pub column: usize,
+    pub message: String,
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+struct Parser<'t> {
+    text: &'t str,
+    pos: usize,
+    names: HashMap<String, Argument>,
+    inputs: Vec<Input>,
+    commands: Vec<Command>,
+}
+
+impl<'t> Parser<'t> {
+    fn program(&mut self) -> ParseResult<()> {
+        loop {
+            self.skip_whitespace();
+            if self.rest().is_empty() {
+                return Ok(());
+            }
+            match self.word()? {
+                "input" => self.input()?,
+                "let" => self.command()?,
+                other => {
+                    return Err(self.error(format!("Expected `input` or `let`, got {other:?}")));
+                }
+            }
+            self.expect(";")?;
+        }
+    }
+
+    fn input(&mut self) -> ParseResult<()> {
+        let kind = self.word()?;
+        self.bind(Argument::Input(self.inputs.len() as u16))?;
+        let input = match kind {
+            "obj" => {
+                self.expect("=")?;
+                self.object()?
+            }
+            "pure" => {
+                let value = if self.eat(":") {
+                    let type_ = self.type_until(&['='])?;
+                    self.expect("=")?;
+                    let mut value = vec![];
+                    self.value(&type_, &mut value)?;
+                    value
+                } else {
+                    self.expect("=")?;
+                    self.hex()?
+                };
+                Input::Pure { value }
+            }
+            other => return Err(self.error(format!("Expected `obj` or `pure`, got {other:?}"))),
+        };
+        self.inputs.push(input);
+        Ok(())
+    }
+
+    fn object(&mut self) -> ParseResult<Input> {
+        let kind = self.word()?;
+        self.expect("(")?;
+        let id: ObjectId = self.parse_word("object id")?;
+        self.expect(",")?;
+        let version = self.parse_word("version")?;
+        self.expect(",")?;
+        let input = match kind {
+            "shared" => {
+                let mutable = match self.word()? {
+                    "mut" => true,
+                    "imm" => false,
+                    other => {
+                        return Err(self.error(format!("Expected `mut` or `imm`, got {other:?}")));
+                    }
+                };
+                Input::Shared {
+                    object_id: id,
+                    initial_shared_version: version,
+                    mutable,
+                }
+            }
+            "owned" | "receiving" => {
+                let digest: ObjectDigest = self.parse_word("object digest")?;
+                let oref = ObjectReference::new(id, version, digest);
+                if kind == "owned" {
+                    Input::ImmutableOrOwned(oref)
+                } else {
+                    Input::Receiving(oref)
+                }
+            }
+            other => {
+                return Err(self.error(format!(
+                    "Expected `shared`, `owned` or `receiving`, got {other:?}"
+                )));
+            }
+        };
+        self.expect(")")?;
+        Ok(input)
+    }
+
+    fn command(&mut self) -> ParseResult<()> {
+        self.bind(Argument::Result(self.commands.len() as u16))?;
+        self.expect("=")?;
+        let word = self.word()?;
+        let command = if word.starts_with(|c: char| c.is_ascii_digit()) {
+            let package = word
+                .parse()
+                .map_err(|_| self.error(format!("Invalid package id {word:?}")))?;
+            self.expect("::")?;
+            let module = self.identifier()?;
+            self.expect("::")?;
+            let function = self.identifier()?;
+            let type_arguments = if self.eat("<") {
+                self.type_list()?
+            } else {
+                vec![]
+            };
+            self.expect("(")?;
+            let arguments = self.arguments(")")?;
+            Command::MoveCall(MoveCall {
+                package,
+                module,
+                function,
+                type_arguments,
+                arguments,
+            })
+        } else {
+            self.builtin(word)?
+        };
+        self.commands.push(command);
+        Ok(())
+    }
+
+    fn builtin(&mut self, name: &str) -> ParseResult<Command> {
+        Ok(match name {
+            "TransferObjects" => {
+                self.expect("(")?;
+                self.expect("[")?;
+                let objects = self.arguments("]")?;
+                self.expect(",")?;
+                let address = self.argument()?;
+                self.expect(")")?;
+                Command::TransferObjects(TransferObjects { objects, address })
+            }
+            "SplitCoins" => {
+                self.expect("(")?;
+                let coin = self.argument()?;
+                self.expect(",")?;
+                self.expect("[")?;
+                let amounts = self.arguments("]")?;
+                self.expect(")")?;
+                Command::SplitCoins(SplitCoins { coin, amounts })
+            }
+            "MergeCoins" => {
+                self.expect("(")?;
+                let coin = self.argument()?;
+                self.expect(",")?;
+                self.expect("[")?;
+                let coins_to_merge = self.arguments("]")?;
+                self.expect(")")?;
+                Command::MergeCoins(MergeCoins {
+                    coin,
+                    coins_to_merge,
+                })
+            }
+            "MakeMoveVec" => {
+                let type_ = if self.eat("<") {
+                    let mut types = self.type_list()?;
+                    if types.len() != 1 {
+                        return Err(self.error("Expected a single type".into()));
+                    }
+                    types.pop()
+                } else {
+                    None
+                };
+                self.expect("(")?;
+                self.expect("[")?;
+                let elements = self.arguments("]")?;
+                self.expect(")")?;
+                Command::MakeMoveVector(MakeMoveVector { type_, elements })
+            }
+            "Publish" => {
+                self.expect("(")?;
+                let modules = self.modules()?;
+                self.expect(",")?;
+                let dependencies = self.object_ids()?;
+                self.expect(")")?;
+                Command::Publish(Publish {
+                    modules,
+                    dependencies,
+                })
+            }
+            "Upgrade" => {
+                self.expect("(")?;
+                let modules = self.modules()?;
+                self.expect(",")?;
+                let dependencies = self.object_ids()?;
+                self.expect(",")?;
+                let package = self.parse_word("package id")?;
+                self.expect(",")?;
+                let ticket = self.argument()?;
+                self.expect(")")?;
+                Command::Upgrade(Upgrade {
+                    modules,
+                    dependencies,
+                    package,
+                    ticket,
+                })
+            }
+            other => return Err(self.error(format!("Unknown command {other:?}"))),
+        })
+    }
+
+    fn modules(&mut self) -> ParseResult<Vec<Vec<u8>>> {
+        self.expect("[")?;
+        self.list("]", Self::hex)
+    }
+
+    fn object_ids(&mut self) -> ParseResult<Vec<ObjectId>> {
+        self.expect("[")?;
+        self.list("]", |p| p.parse_word("object id"))
+    }
+
+    /// Comma-separated arguments up to `end`.
+    fn arguments(&mut self, end: &str) -> ParseResult<Vec<Argument>> {
+        self.list(end, Self::argument)
+    }
+
+    fn list<T>(
+        &mut self,
+        end: &str,
+        mut item: impl FnMut(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<Vec<T>> {
+        let mut items = vec![];
+        if self.eat(end) {
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            if self.eat(end) {
+                return Ok(items);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn argument(&mut self) -> ParseResult<Argument> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let name = self.name()?;
+        if name == "gas" {
+            return Ok(Argument::Gas);
+        }
+        let Some(argument) = self
+            .names
+            .get(name)
+            .copied()
+            .or_else(|| default_argument(name))
+        else {
+            self.pos = start;
+            return Err(self.error(format!("Unknown name {name:?}")));
+        };
+        if !self.eat(".") {
+            return Ok(argument);
+        }
+        let Argument::Result(result) = argument else {
+            return Err(self.error(format!("{name:?} isn't a result")));
+        };
+        let index = self.parse_word("result index")?;
+        Ok(Argument::NestedResult(result, index))
+    }
+
+    /// Declare a name for `argument`.
+    ///
+    /// Default names can only be declared for the argument they stand for.
+    fn bind(&mut self, argument: Argument) -> ParseResult<()> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let name = self.name()?;
+        let error = if name == "gas" || self.names.contains_key(name) {
+            format!("Name {name:?} is already taken")
+        } else if (name.starts_with("input_") || name.starts_with("result_"))
+            && default_argument(name) != Some(argument)
+        {
+            format!("Name {name:?} is reserved")
+        } else {
+            self.names.insert(name.to_owned(), argument);
+            return Ok(());
+        };
+        self.pos = start;
+        Err(self.error(error))
+    }
+
+    /// A pure value of `type_`, BCS-encoded into `out`.
+    fn value(&mut self, type_: &TypeTag, out: &mut Vec<u8>) -> ParseResult<()> {
+        match type_ {
+            TypeTag::Bool => match self.word()? {
+                "true" => out.push(1),
+                "false" => out.push(0),
+                other => return Err(self.error(format!("Expected a bool, got {other:?}"))),
+            },
+            TypeTag::U8 => out.push(self.parse_word("u8")?),
+            TypeTag::U16 => out.extend(self.parse_word::<u16>("u16")?.to_le_bytes()),
+            TypeTag::U32 => out.extend(self.parse_word::<u32>("u32")?.to_le_bytes()),
+            TypeTag::U64 => out.extend(self.parse_word::<u64>("u64")?.to_le_bytes()),
+            TypeTag::U128 => out.extend(self.parse_word::<u128>("u128")?.to_le_bytes()),
+            TypeTag::U256 => {
+                let bytes = self.hex()?;
+                if bytes.len() > 32 {
+                    return Err(self.error("u256 out of range".into()));
+                }
+                out.extend(bytes.iter().rev());
+                out.resize(out.len() + 32 - bytes.len(), 0);
+            }
+            TypeTag::Address => out.extend(self.parse_word::<Address>("address")?.into_inner()),
+            TypeTag::Vector(inner) if **inner == TypeTag::U8 => {
+                let bytes = self.hex()?;
+                write_uleb128(out, bytes.len());
+                out.extend(bytes);
+            }
+            TypeTag::Vector(inner) => {
+                self.expect("[")?;
+                let mut elements = vec![];
+                let count = self.list("]", |p| p.value(inner, &mut elements))?.len();
+                write_uleb128(out, count);
+                out.extend(elements);
+            }
+            TypeTag::Struct(tag) => match pure_struct(tag) {
+                Some(PureStruct::String) => {
+                    let string = self.string()?;
+                    write_uleb128(out, string.len());
+                    out.extend(string.into_bytes());
+                }
+                Some(PureStruct::AsciiString) => {
+                    let string = self.string()?;
+                    if !string.is_ascii() {
+                        return Err(self.error("Expected an ASCII string".into()));
+                    }
+                    write_uleb128(out, string.len());
+                    out.extend(string.into_bytes());
+                }
+                Some(PureStruct::Id) => {
+                    out.extend(self.parse_word::<Address>("ID")?.into_inner());
+                }
+                Some(PureStruct::Option) => match self.word()? {
+                    "none" => out.push(0),
+                    "some" => {
+                        out.push(1);
+                        self.expect("(")?;
+                        self.value(&tag.type_params[0], out)?;
+                        self.expect(")")?;
+                    }
+                    other => {
+                        return Err(self.error(format!("Expected `none` or `some`, got {other:?}")));
+                    }
+                },
+                None => return Err(self.error(format!("{type_} can't be a pure value"))),
+            },
+            TypeTag::Signer => return Err(self.error(format!("{type_} can't be a pure value"))),
+        }
+        Ok(())
+    }
+
+    // =========================================================================
+    //  Tokens
+    // =========================================================================
+
+    fn rest(&self) -> &'t str {
+        &self.text[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if !trimmed.starts_with("//") {
+                return;
+            }
+            self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    /// Consume `token` if it's next.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> ParseResult<()> {
+        if self.eat(token) {
+            return Ok(());
+        }
+        Err(self.error(format!("Expected `{token}`")))
+    }
+
+    /// A run of alphanumeric characters or underscores.
+    fn word(&mut self) -> ParseResult<&'t str> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("Expected a word".into()));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn parse_word<T: std::str::FromStr>(&mut self, what: &str) -> ParseResult<T> {
+        let start = self.pos;
+        let word = self.word()?;
+        word.parse().map_err(|_| {
+            self.pos = start;
+            self.error(format!("Invalid {what} {word:?}"))
+        })
+    }
+
+    fn name(&mut self) -> ParseResult<&'t str> {
+        let start = self.pos;
+        let word = self.word()?;
+        if !is_identifier(word) {
+            self.pos = start;
+            return Err(self.error(format!("Invalid name {word:?}")));
+        }
+        Ok(word)
+    }
+
+    fn identifier(&mut self) -> ParseResult<Identifier> {
+        self.parse_word("identifier")
+    }
+
+    fn hex(&mut self) -> ParseResult<Vec<u8>> {
+        let start = self.pos;
+        let word = self.word()?;
+        let bytes = word
+            .strip_prefix("0x")
+            .filter(|digits| digits.len() % 2 == 0)
+            .and_then(|digits| {
+                (0..digits.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+                    .collect::<Option<Vec<_>>>()
+            });
+        bytes.ok_or_else(|| {
+            self.pos = start;
+            self.error(format!("Invalid hex bytes {word:?}"))
+        })
+    }
+
+    /// A type ending right before one of `ends` that isn't nested in `<>`.
+    fn type_until(&mut self, ends: &[char]) -> ParseResult<TypeTag> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let mut depth = 0usize;
+        let len = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '<' => depth += 1,
+                    '>' if depth > 0 => depth -= 1,
+                    _ if depth == 0 && ends.contains(&c) => return true,
+                    _ => (),
+                }
+                false
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let text = rest[..len].trim();
+        let type_ = text
+            .parse()
+            .map_err(|_| self.error(format!("Invalid type {text:?}")))?;
+        self.pos += len;
+        Ok(type_)
+    }
+
+    /// Comma-separated types after a `<`, up to the matching `>`.
+    fn type_list(&mut self) -> ParseResult<Vec<TypeTag>> {
+        self.list(">", |p| p.type_until(&[',', '>']))
+    }
+
+    /// A string literal, with Rust's escapes.
+    fn string(&mut self) -> ParseResult<String> {
+        self.expect("\"")?;
+        let mut string = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            let c = match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(string);
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('t') => '\t',
+                    Some('0') => '\0',
+                    Some(c @ ('\\' | '"' | '\'')) => c,
+                    Some('u') => {
+                        let rest = chars.as_str();
+                        let code = rest
+                            .strip_prefix('{')
+                            .and_then(|r| r.split_once('}'))
+                            .and_then(|(code, _)| u32::from_str_radix(code, 16).ok())
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| self.error("Invalid unicode escape".into()))?;
+                        let skip = rest.find('}').unwrap_or(0);
+                        chars.nth(skip);
+                        code
+                    }
+                    _ => return Err(self.error("Invalid escape".into())),
+                },
+                c => c,
+            };
+            string.push(c);
+        }
+        Err(self.error("Unterminated string".into()))
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        let before = &self.text[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let column = before[before.rfind('\n').map_or(0, |i| i + 1)..]
+            .chars()
+            .count()
+            + 1;
+        ParseError {
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_strategy::proptest;
+
+    use super::*;
+    use crate::check::{FunctionSignature, SignatureType};
+
+    fn struct_(
+        address: Address,
+        module: &str,
+        name: &str,
+        type_arguments: Vec<SignatureType>,
+    ) -> SignatureType {
+        SignatureType::Struct {
+            address,
+            module: module.parse().unwrap(),
+            name: name.parse().unwrap(),
+            type_arguments,
+        }
+    }
+
+    #[proptest]
+    fn round_trip(ptb: ProgrammableTransaction) {
+        let text = print(&ptb);
+        let parsed = parse(&text).map_err(|e| format!("{e}\n{text}")).unwrap();
+        assert_eq!(parsed, ptb);
+    }
+
+    /// Pure strings decoded for `string::String` and `ascii::String` parameters.
+    #[proptest]
+    fn string_round_trip(string: String) {
+        let package = ObjectId::new([7; 32]);
+        let mut abis = HashMap::new();
+        abis.insert(
+            (package, "pool".parse().unwrap(), "name".parse().unwrap()),
+            FunctionSignature {
+                type_parameters: 0,
+                parameters: vec![
+                    struct_(MOVE_STDLIB_ADDRESS, "string", "String", vec![]),
+                    struct_(MOVE_STDLIB_ADDRESS, "ascii", "String", vec![]),
+                ],
+                returns: vec![],
+            },
+        );
+        let mut builder = crate::ProgrammableTransactionBuilder::new();
+        let bytes = bcs::to_bytes(&string).unwrap();
+        let utf8 = builder.pure_bytes(bytes.clone(), true);
+        let ascii = builder.pure_bytes(bytes, true);
+        builder.command(crate::Command::move_call(
+            package,
+            "pool".parse().unwrap(),
+            "name".parse().unwrap(),
+            vec![],
+            vec![utf8, ascii],
+        ));
+        let ptb = builder.finish();
+
+        let text = Printer::new().signatures(&abis).print(&ptb);
+        let parsed = parse(&text).map_err(|e| format!("{e}\n{text}")).unwrap();
+        assert_eq!(parsed, ptb);
+    }
+
+    #[test]
+    fn named_and_decoded_inputs() {
+        let package = ObjectId::new([7; 32]);
+        let string = struct_(MOVE_STDLIB_ADDRESS, "string", "String", vec![]);
+        let option = struct_(
+            MOVE_STDLIB_ADDRESS,
+            "option",
+            "Option",
+            vec![SignatureType::TypeParameter(0)],
+        );
+        let mut abis = HashMap::new();
+        abis.insert(
+            (package, "pool".parse().unwrap(), "create".parse().unwrap()),
+            FunctionSignature {
+                type_parameters: 1,
+                parameters: vec![
+                    string,
+                    option,
+                    SignatureType::Vector(Box::new(SignatureType::U8)),
+                    SignatureType::Vector(Box::new(SignatureType::Bool)),
+                ],
+                returns: vec![],
+            },
+        );
+
+        let mut builder = crate::ProgrammableTransactionBuilder::new();
+        let name = builder.pure("pool \"A\"").unwrap();
+        let fee = builder.pure(&Some(30u64)).unwrap();
+        let data = builder.pure(&vec![1u8, 2]).unwrap();
+        let flags = builder.pure(&vec![true, false]).unwrap();
+        let unused = builder.pure(&5u16).unwrap();
+        builder.command(crate::Command::move_call(
+            package,
+            "pool".parse().unwrap(),
+            "create".parse().unwrap(),
+            vec![TypeTag::U64],
+            vec![name, fee, data, flags],
+        ));
+        let recipient = builder.pure(&Address::ZERO).unwrap();
+        builder.command(crate::Command::TransferObjects(vec![unused], recipient));
+        let ptb = builder.finish();
+
+        let text = Printer::new()
+            .input_name(0, "name")
+            .input_name(1, "gas")
+            .input_name(2, "name")
+            .signatures(&abis)
+            .print(&ptb);
+        let expected = format!(
+            "\
+input pure name: 0x1::string::String = \"pool \\\"A\\\"\";
+input pure input_1: 0x1::option::Option<u64> = some(30);
+input pure input_2: vector<u8> = 0x0102;
+input pure input_3: vector<bool> = [true, false];
+input pure input_4 = 0x0500;
+input pure input_5: address = {zero};
+let result_0 = {package}::pool::create<u64>(name, input_1, input_2, input_3);
+let result_1 = TransferObjects([input_4], input_5);
+",
+            zero = Address::ZERO,
+        )
+        .replace("0x1::", &format!("{MOVE_STDLIB_ADDRESS}::"));
+        assert_eq!(text, expected);
+        assert_eq!(parse(&text).unwrap(), ptb);
+    }
+
+    #[test]
+    fn non_canonical_length() {
+        let package = ObjectId::new([7; 32]);
+        let mut abis = HashMap::new();
+        abis.insert(
+            (package, "pool".parse().unwrap(), "set".parse().unwrap()),
+            FunctionSignature {
+                type_parameters: 0,
+                parameters: vec![SignatureType::Vector(Box::new(SignatureType::U8))],
+                returns: vec![],
+            },
+        );
+        let mut builder = crate::ProgrammableTransactionBuilder::new();
+        // An empty vector with its length in two bytes
+        let data = builder.pure_bytes(vec![0x80, 0x00], false);
+        builder.command(crate::Command::move_call(
+            package,
+            "pool".parse().unwrap(),
+            "set".parse().unwrap(),
+            vec![],
+            vec![data],
+        ));
+        let ptb = builder.finish();
+
+        let text = Printer::new().signatures(&abis).print(&ptb);
+        assert!(text.starts_with("input pure input_0 = 0x8000;"), "{text}");
+        assert_eq!(parse(&text).unwrap(), ptb);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err =
+            parse("input pure amount: u64 = 5;\nlet result_0 = SplitCoins(gas, [amount, fee]);")
+                .unwrap_err();
+        assert_eq!((err.line, err.column), (2, 41));
+        assert_eq!(err.message, "Unknown name \"fee\"");
+
+        let err = parse("input pure amount: u8 = 256;").unwrap_err();
+        assert_eq!(err.message, "Invalid u8 \"256\"");
+
+        let err = parse("// comment\ninput pure gas = 0x00;").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 12));
+        assert_eq!(err.message, "Name \"gas\" is already taken");
+
+        let err = parse("input pure result_0 = 0x00;").unwrap_err();
+        assert_eq!(err.message, "Name \"result_0\" is reserved");
+    }
+}
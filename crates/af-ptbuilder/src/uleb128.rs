@@ -0,0 +1,70 @@
+//! ULEB128 lengths, as in BCS and Move bytecode.
+
+/// Read a canonically encoded ULEB128 integer from the start of `reader`, advancing it.
+///
+/// [`None`] if `reader` ends early, the encoding has trailing zero bytes (so that reading then
+/// [writing](write_uleb128) gives back the same bytes) or the value doesn't fit a `u64`.
+pub(crate) fn read_uleb128(reader: &mut &[u8]) -> Option<usize> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = reader.split_first()?;
+        *reader = rest;
+        let digit = u64::from(byte & 0x7f);
+        // The last byte holds the top bit of a `u64`
+        if shift == 63 && digit > 1 {
+            return None;
+        }
+        value |= digit << shift;
+        if byte & 0x80 == 0 {
+            if byte == 0 && shift > 0 {
+                return None;
+            }
+            return usize::try_from(value).ok();
+        }
+    }
+    None
+}
+
+pub(crate) fn write_uleb128(out: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(mut bytes: &[u8]) -> Option<usize> {
+        let value = read_uleb128(&mut bytes)?;
+        assert!(bytes.is_empty());
+        Some(value)
+    }
+
+    #[test]
+    fn round_trip() {
+        for value in [0, 1, 127, 128, 300, 16_384, u32::MAX as usize, usize::MAX] {
+            let mut bytes = vec![];
+            write_uleb128(&mut bytes, value);
+            assert_eq!(read(&bytes), Some(value), "{bytes:?}");
+        }
+    }
+
+    #[test]
+    fn non_canonical() {
+        assert_eq!(read(&[0x80, 0x00]), None);
+        assert_eq!(read(&[0x81, 0x80, 0x00]), None);
+        assert_eq!(read(&[0x80]), None);
+        // More than 64 bits
+        let mut too_long = [0xff; 10];
+        too_long[9] = 0x02;
+        assert_eq!(read(&too_long), None);
+        assert_eq!(read(&[0x80; 11]), None);
+    }
+}
@@ -1,4 +1,4 @@
-use af_ptbuilder::{Argument, ProgrammableTransactionBuilder, ptbuilder};
+use af_ptbuilder::{Argument, ProgrammableTransactionBuilder, RemapArguments, ptbuilder};
 use af_sui_pkg_sdk::ObjectId;
 use af_sui_pkg_sdk::af_sui_types::ObjectArg;
 
@@ -12,6 +12,14 @@ pub struct UpdateAfOracleArguments {
     pub pfs_to_source: Vec<(Argument, Argument)>,
 }
 
+impl RemapArguments for UpdateAfOracleArguments {
+    fn remap_arguments(&mut self, f: &mut impl FnMut(Argument) -> Argument) {
+        self.pyth_state.remap_arguments(f);
+        self.pyth_wrapper.remap_arguments(f);
+        self.pfs_to_source.remap_arguments(f);
+    }
+}
+
 #[extension_traits::extension(pub trait ProgrammableTransactionBuilderExt)]
 impl ProgrammableTransactionBuilder {
     /// Construct the PTB arguments to be used in [`update_af_oracle_pyth_feed`].
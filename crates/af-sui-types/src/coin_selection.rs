@@ -0,0 +1,273 @@
+//! Strategies for choosing which coins to use to pay for some amount.
+//!
+//! These are client-agnostic: callers list the candidate coins however they see fit and hand them
+//! to a [`CoinSelector`].
+use std::collections::HashSet;
+
+use crate::{ObjectId, ObjectRef};
+
+/// A coin object and its balance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CoinRef {
+    pub object_ref: ObjectRef,
+    pub balance: u64,
+}
+
+impl CoinRef {
+    pub const fn object_id(&self) -> ObjectId {
+        self.object_ref.0
+    }
+}
+
+/// Coins chosen by a [`CoinSelector`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub coins: Vec<CoinRef>,
+    /// Sum of the balances of `coins`.
+    pub total: u64,
+}
+
+impl Selection {
+    /// How much is left over after taking `amount` from the selected coins.
+    pub const fn change(&self, amount: u64) -> u64 {
+        self.total.saturating_sub(amount)
+    }
+
+    pub fn object_refs(&self) -> impl Iterator<Item = ObjectRef> + '_ {
+        self.coins.iter().map(|c| c.object_ref)
+    }
+}
+
+#[derive(thiserror::Error, Clone, Copy, Debug, PartialEq, Eq)]
+#[error("Insufficient balance: found {found}, requested {requested}")]
+pub struct InsufficientBalanceError {
+    pub found: u64,
+    pub requested: u64,
+}
+
+/// A strategy for choosing coins whose balances add up to at least some amount.
+pub trait CoinSelector {
+    fn select(
+        &self,
+        coins: Vec<CoinRef>,
+        amount: u64,
+    ) -> Result<Selection, InsufficientBalanceError>;
+}
+
+impl<T: CoinSelector + ?Sized> CoinSelector for &T {
+    fn select(
+        &self,
+        coins: Vec<CoinRef>,
+        amount: u64,
+    ) -> Result<Selection, InsufficientBalanceError> {
+        (**self).select(coins, amount)
+    }
+}
+
+/// Takes coins in the order they're given until the amount is covered.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InOrder;
+
+impl CoinSelector for InOrder {
+    fn select(
+        &self,
+        coins: Vec<CoinRef>,
+        amount: u64,
+    ) -> Result<Selection, InsufficientBalanceError> {
+        let mut selection = Selection::default();
+        for coin in coins {
+            if selection.total >= amount {
+                break;
+            }
+            selection.total = selection.total.saturating_add(coin.balance);
+            selection.coins.push(coin);
+        }
+        if selection.total < amount {
+            return Err(InsufficientBalanceError {
+                found: selection.total,
+                requested: amount,
+            });
+        }
+        Ok(selection)
+    }
+}
+
+/// Takes the coins with the largest balances first.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LargestFirst;
+
+impl CoinSelector for LargestFirst {
+    fn select(
+        &self,
+        mut coins: Vec<CoinRef>,
+        amount: u64,
+    ) -> Result<Selection, InsufficientBalanceError> {
+        coins.sort_by_key(|c| std::cmp::Reverse(c.balance));
+        InOrder.select(coins, amount)
+    }
+}
+
+/// Uses as few coins as possible and, among those options, leaves the least change.
+///
+/// If `n` coins are needed, this picks the `n - 1` largest ones and the smallest coin that covers
+/// the remainder.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FewestCoins;
+
+impl CoinSelector for FewestCoins {
+    fn select(
+        &self,
+        mut coins: Vec<CoinRef>,
+        amount: u64,
+    ) -> Result<Selection, InsufficientBalanceError> {
+        coins.sort_by_key(|c| std::cmp::Reverse(c.balance));
+        let Selection {
+            coins: mut selected,
+            total,
+        } = InOrder.select(coins.clone(), amount)?;
+        let Some(last) = selected.pop() else {
+            return Ok(Selection::default());
+        };
+        let remainder = amount - (total - last.balance);
+        // `coins` is in descending order, so the last coin covering the remainder is the smallest
+        let smallest = coins[selected.len()..]
+            .iter()
+            .take_while(|c| c.balance >= remainder)
+            .last()
+            .copied()
+            .unwrap_or(last);
+        selected.push(smallest);
+        Ok(Selection {
+            total: total - last.balance + smallest.balance,
+            coins: selected,
+        })
+    }
+}
+
+/// Uses a single coin with exactly the requested balance if there is one, so that no split is
+/// needed. Otherwise, falls back to another strategy.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ExactMatch<S = FewestCoins> {
+    pub fallback: S,
+}
+
+impl<S: CoinSelector> CoinSelector for ExactMatch<S> {
+    fn select(
+        &self,
+        coins: Vec<CoinRef>,
+        amount: u64,
+    ) -> Result<Selection, InsufficientBalanceError> {
+        if let Some(coin) = coins.iter().find(|c| c.balance == amount) {
+            return Ok(Selection {
+                coins: vec![*coin],
+                total: amount,
+            });
+        }
+        self.fallback.select(coins, amount)
+    }
+}
+
+/// Never selects the `locked` coins, e.g., those already in use by in-flight transactions.
+#[derive(Clone, Debug, Default)]
+pub struct AvoidLocked<S = LargestFirst> {
+    pub locked: HashSet<ObjectId>,
+    pub inner: S,
+}
+
+impl<S> AvoidLocked<S> {
+    pub fn new(locked: impl IntoIterator<Item = ObjectId>, inner: S) -> Self {
+        Self {
+            locked: locked.into_iter().collect(),
+            inner,
+        }
+    }
+}
+
+impl<S: CoinSelector> CoinSelector for AvoidLocked<S> {
+    fn select(
+        &self,
+        mut coins: Vec<CoinRef>,
+        amount: u64,
+    ) -> Result<Selection, InsufficientBalanceError> {
+        coins.retain(|c| !self.locked.contains(&c.object_id()));
+        self.inner.select(coins, amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ObjectDigest;
+
+    fn coins(balances: &[u64]) -> Vec<CoinRef> {
+        balances
+            .iter()
+            .enumerate()
+            .map(|(i, &balance)| CoinRef {
+                object_ref: (ObjectId::new([i as u8; 32]), 1, ObjectDigest::ZERO),
+                balance,
+            })
+            .collect()
+    }
+
+    fn balances(selection: &Selection) -> Vec<u64> {
+        selection.coins.iter().map(|c| c.balance).collect()
+    }
+
+    #[test]
+    fn in_order() {
+        let selection = InOrder.select(coins(&[1, 5, 3, 10]), 6).unwrap();
+        assert_eq!(balances(&selection), [1, 5]);
+        assert_eq!(selection.total, 6);
+    }
+
+    #[test]
+    fn largest_first() {
+        let selection = LargestFirst.select(coins(&[1, 5, 3, 10]), 12).unwrap();
+        assert_eq!(balances(&selection), [10, 5]);
+        assert_eq!(selection.change(12), 3);
+    }
+
+    #[test]
+    fn fewest_coins() {
+        let selection = FewestCoins.select(coins(&[1, 5, 3, 10]), 4).unwrap();
+        assert_eq!(balances(&selection), [5]);
+
+        let selection = FewestCoins.select(coins(&[1, 5, 3, 10]), 12).unwrap();
+        assert_eq!(balances(&selection), [10, 3]);
+        assert_eq!(selection.total, 13);
+
+        let selection = FewestCoins.select(coins(&[1, 5]), 0).unwrap();
+        assert!(selection.coins.is_empty());
+    }
+
+    #[test]
+    fn exact_match() {
+        let selection = ExactMatch::<FewestCoins>::default()
+            .select(coins(&[1, 5, 3, 10]), 3)
+            .unwrap();
+        assert_eq!(balances(&selection), [3]);
+
+        let selection = ExactMatch::<FewestCoins>::default()
+            .select(coins(&[1, 5, 3, 10]), 7)
+            .unwrap();
+        assert_eq!(balances(&selection), [10]);
+    }
+
+    #[test]
+    fn avoid_locked() {
+        let all = coins(&[1, 5, 3, 10]);
+        let selector = AvoidLocked::new([all[3].object_id()], LargestFirst);
+        let selection = selector.select(all.clone(), 6).unwrap();
+        assert_eq!(balances(&selection), [5, 3]);
+
+        let err = selector.select(all, 10).unwrap_err();
+        assert_eq!(
+            err,
+            InsufficientBalanceError {
+                found: 9,
+                requested: 10
+            }
+        );
+    }
+}
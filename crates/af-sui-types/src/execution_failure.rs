@@ -0,0 +1,455 @@
+//! Structured diagnostics for failed transaction executions.
+//!
+//! An [`ExecutionFailure`] is what's left of a transaction's [`ExecutionStatus`] when it failed:
+//! the error and the index of the PTB command during which it happened. Move aborts only carry a
+//! numeric code; an [`AbortRegistry`] maps those codes to the error types of the packages that
+//! raised them, e.g., `af_iperps::errors::MoveAbort`, so that failures can be read and matched on.
+//!
+//! ```
+//! use af_sui_types::execution_failure::{AbortRegistry, ExecutionFailure};
+//! use af_sui_types::{ExecutionError, ObjectId};
+//! use sui_sdk_types::MoveLocation;
+//!
+//! #[derive(Debug, thiserror::Error)]
+//! #[error("Not enough coins")]
+//! struct NotEnough;
+//!
+//! impl TryFrom<u64> for NotEnough {
+//!     type Error = u64;
+//!     fn try_from(code: u64) -> Result<Self, u64> {
+//!         if code == 1 { Ok(Self) } else { Err(code) }
+//!     }
+//! }
+//!
+//! let package = ObjectId::new([1; 32]);
+//! let mut registry = AbortRegistry::new();
+//! registry.register::<NotEnough>(package);
+//!
+//! let failure = ExecutionFailure::new(
+//!     ExecutionError::MoveAbort {
+//!         location: MoveLocation {
+//!             package,
+//!             module: "pool".parse().unwrap(),
+//!             function: 0,
+//!             instruction: 3,
+//!             function_name: Some("swap".parse().unwrap()),
+//!         },
+//!         code: 1,
+//!     },
+//!     Some(2),
+//! );
+//! let diagnosis = registry.diagnose(failure);
+//! assert!(diagnosis.abort_as::<NotEnough>().is_some());
+//! assert!(diagnosis.to_string().starts_with("Not enough coins (abort code 1 in"));
+//! ```
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+
+use sui_sdk_types::MoveLocation;
+
+use crate::{ExecutionError, ExecutionStatus, Identifier, ObjectId};
+
+/// A protocol-specific error decoded from a Move abort code.
+pub type DecodedAbort = Box<dyn StdError + Send + Sync + 'static>;
+
+/// Decodes the abort codes of a package or module.
+pub type AbortDecoder = fn(u64) -> Option<DecodedAbort>;
+
+/// Why a transaction failed to execute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionFailure {
+    /// The typed error, if available.
+    ///
+    /// JSON-RPC nodes only report errors as text, which is parsed back only for Move aborts.
+    pub error: Option<ExecutionError>,
+    /// Human-readable description of the error.
+    ///
+    /// [`AbortRegistry::diagnose`] replaces it with the decoded abort, if any.
+    pub message: String,
+    /// The error exactly as reported by the node, if it was reported as text.
+    pub raw: Option<String>,
+    /// The PTB command during which the error occurred, if any.
+    pub command: Option<u64>,
+}
+
+impl ExecutionFailure {
+    pub fn new(error: ExecutionError, command: Option<u64>) -> Self {
+        Self {
+            message: describe(&error),
+            error: Some(error),
+            raw: None,
+            command,
+        }
+    }
+
+    /// A failure for which only the error message is available.
+    pub const fn unparsed(message: String, command: Option<u64>) -> Self {
+        Self {
+            error: None,
+            message,
+            raw: None,
+            command,
+        }
+    }
+
+    /// Keep the error as originally reported by the node.
+    pub fn with_raw(mut self, raw: String) -> Self {
+        self.raw = Some(raw);
+        self
+    }
+
+    /// The failure in `status`, if any.
+    pub fn from_status(status: ExecutionStatus) -> Option<Self> {
+        match status {
+            ExecutionStatus::Success => None,
+            ExecutionStatus::Failure { error, command } => Some(Self::new(error, command)),
+        }
+    }
+
+    /// Where the Move abort happened and its code, if the failure is one.
+    pub const fn move_abort(&self) -> Option<(&MoveLocation, u64)> {
+        match &self.error {
+            Some(ExecutionError::MoveAbort { location, code }) => Some((location, *code)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ExecutionFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(command) = self.command {
+            write!(f, " in command {command}")?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for ExecutionFailure {}
+
+/// Abort code decoders by the package and, optionally, the module raising them.
+///
+/// Packages are identified by their original id, which is the one in abort locations for all of
+/// their versions.
+#[derive(Clone, Debug, Default)]
+pub struct AbortRegistry {
+    decoders: HashMap<(ObjectId, Option<Identifier>), AbortDecoder>,
+}
+
+impl AbortRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the aborts of every module in `package` as `E`.
+    pub fn register<E>(&mut self, package: ObjectId) -> &mut Self
+    where
+        E: TryFrom<u64> + StdError + Send + Sync + 'static,
+    {
+        self.register_decoder(package, None, decode_as::<E>)
+    }
+
+    /// Decode the aborts of `module` in `package` as `E`.
+    ///
+    /// Takes precedence over decoders registered for the whole package.
+    pub fn register_module<E>(&mut self, package: ObjectId, module: Identifier) -> &mut Self
+    where
+        E: TryFrom<u64> + StdError + Send + Sync + 'static,
+    {
+        self.register_decoder(package, Some(module), decode_as::<E>)
+    }
+
+    pub fn register_decoder(
+        &mut self,
+        package: ObjectId,
+        module: Option<Identifier>,
+        decoder: AbortDecoder,
+    ) -> &mut Self {
+        self.decoders.insert((package, module), decoder);
+        self
+    }
+
+    /// Decode an abort code raised at `location`.
+    pub fn decode(&self, location: &MoveLocation, code: u64) -> Option<DecodedAbort> {
+        let decoder = self
+            .decoders
+            .get(&(location.package, Some(location.module.clone())))
+            .or_else(|| self.decoders.get(&(location.package, None)))?;
+        decoder(code)
+    }
+
+    /// Decode the failure's abort code, if it is a Move abort from a registered package.
+    ///
+    /// The failure's [message](ExecutionFailure::message) becomes the decoded abort followed by
+    /// its code and location.
+    pub fn diagnose(&self, mut failure: ExecutionFailure) -> Diagnosis {
+        let decoded = failure.move_abort().and_then(|(location, code)| {
+            let abort = self.decode(location, code)?;
+            let location = display_location(location);
+            let message = format!("{abort} (abort code {code} in {location})");
+            Some((abort, message))
+        });
+        let abort = decoded.map(|(abort, message)| {
+            failure.message = message;
+            abort
+        });
+        Diagnosis { failure, abort }
+    }
+}
+
+fn decode_as<E>(code: u64) -> Option<DecodedAbort>
+where
+    E: TryFrom<u64> + StdError + Send + Sync + 'static,
+{
+    E::try_from(code).ok().map(|e| Box::new(e) as _)
+}
+
+/// An [`ExecutionFailure`] with its abort code decoded by an [`AbortRegistry`].
+#[derive(Debug)]
+pub struct Diagnosis {
+    pub failure: ExecutionFailure,
+    /// The decoded Move abort, if the failure is one and its package is registered.
+    pub abort: Option<DecodedAbort>,
+}
+
+impl Diagnosis {
+    /// The decoded Move abort as a specific error type.
+    pub fn abort_as<E: StdError + 'static>(&self) -> Option<&E> {
+        self.abort.as_ref()?.downcast_ref()
+    }
+}
+
+impl Display for Diagnosis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.failure)
+    }
+}
+
+impl StdError for Diagnosis {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.abort.as_deref().map(|abort| abort as _)
+    }
+}
+
+/// `package::module::function`, with the function's index if its name is unknown.
+fn display_location(location: &MoveLocation) -> String {
+    let MoveLocation {
+        package,
+        module,
+        function,
+        function_name,
+        ..
+    } = location;
+    function_name.as_ref().map_or_else(
+        || format!("{package}::{module}::<function {function}>"),
+        |name| format!("{package}::{module}::{name}"),
+    )
+}
+
+/// Readable description of an execution error, after the ones used by Sui nodes.
+fn describe(error: &ExecutionError) -> String {
+    use ExecutionError as E;
+    match error {
+        E::InsufficientGas => "Insufficient gas".into(),
+        E::InvalidGasObject => "Invalid gas object".into(),
+        E::InvariantViolation => "Invariant violation".into(),
+        E::FeatureNotYetSupported => "Feature not yet supported".into(),
+        E::ObjectTooBig {
+            object_size,
+            max_object_size,
+        } => format!(
+            "Move object with size {object_size} is larger than the maximum of {max_object_size}"
+        ),
+        E::PackageTooBig {
+            object_size,
+            max_object_size,
+        } => format!(
+            "Move package with size {object_size} is larger than the maximum of {max_object_size}"
+        ),
+        E::CircularObjectOwnership { object } => {
+            format!("Circular ownership of object {object}")
+        }
+        E::InsufficientCoinBalance => "Insufficient coin balance".into(),
+        E::CoinBalanceOverflow => "Coin balance overflows u64".into(),
+        E::PublishErrorNonZeroAddress => {
+            "Published modules must have their self-address set to zero".into()
+        }
+        E::SuiMoveVerificationError => "Sui Move bytecode verification error".into(),
+        E::MovePrimitiveRuntimeError { location } => location.as_ref().map_or_else(
+            || "Move primitive runtime error".into(),
+            |location| {
+                format!(
+                    "Move primitive runtime error in {}",
+                    display_location(location)
+                )
+            },
+        ),
+        E::MoveAbort { location, code } => {
+            format!(
+                "Move abort with code {code} in {}",
+                display_location(location)
+            )
+        }
+        E::VmVerificationOrDeserializationError => {
+            "Move bytecode verification or deserialization error".into()
+        }
+        E::VmInvariantViolation => "Move VM invariant violation".into(),
+        E::FunctionNotFound => "Function not found".into(),
+        E::ArityMismatch => "Wrong number of arguments for Move function".into(),
+        E::TypeArityMismatch => "Wrong number of type arguments for Move function".into(),
+        E::NonEntryFunctionInvoked => "Non-entry function invoked".into(),
+        E::CommandArgumentError { argument, kind } => {
+            format!("Invalid command argument {argument}: {kind:?}")
+        }
+        E::TypeArgumentError {
+            type_argument,
+            kind,
+        } => format!("Invalid type argument {type_argument}: {kind:?}"),
+        E::UnusedValueWithoutDrop { result, subresult } => format!(
+            "Unused value without the drop ability: result {result}, return value {subresult}"
+        ),
+        E::InvalidPublicFunctionReturnType { index } => {
+            format!("Unsupported return type for return value {index} of public function")
+        }
+        E::InvalidTransferObject => "Object does not have public transfer".into(),
+        E::EffectsTooLarge {
+            current_size,
+            max_size,
+        } => format!("Effects of {current_size} bytes are larger than the limit of {max_size}"),
+        E::PublishUpgradeMissingDependency => {
+            "A dependency of the package has no on-chain address".into()
+        }
+        E::PublishUpgradeDependencyDowngrade => {
+            "A transitive dependency of the package is downgraded".into()
+        }
+        E::PackageUpgradeError { kind } => format!("Invalid package upgrade: {kind:?}"),
+        E::WrittenObjectsTooLarge {
+            object_size,
+            max_object_size,
+        } => format!(
+            "Written objects of {object_size} bytes are larger than the limit of \
+             {max_object_size}"
+        ),
+        E::CertificateDenied => "Certificate is on the deny list".into(),
+        E::SuiMoveVerificationTimedout => "Sui Move bytecode verification timed out".into(),
+        E::SharedObjectOperationNotAllowed => "Shared object operation not allowed".into(),
+        E::InputObjectDeleted => "Depends on a deleted shared object".into(),
+        E::ExecutionCanceledDueToSharedObjectCongestion { congested_objects } => format!(
+            "Cancelled due to congestion on shared objects {}",
+            congested_objects
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        E::AddressDeniedForCoin { address, coin_type } => {
+            format!("Address {address} is denied for coin {coin_type}")
+        }
+        E::CoinTypeGlobalPause { coin_type } => format!("Coin type {coin_type} is paused"),
+        E::ExecutionCanceledDueToRandomnessUnavailable => {
+            "Cancelled because randomness is unavailable this epoch".into()
+        }
+        E::MoveVectorElemTooBig {
+            value_size,
+            max_scaled_size,
+        } => format!(
+            "Move vector element with size {value_size} is larger than the maximum of \
+             {max_scaled_size}"
+        ),
+        E::MoveRawValueTooBig {
+            value_size,
+            max_scaled_size,
+        } => format!(
+            "Move value with size {value_size} is larger than the maximum of {max_scaled_size}"
+        ),
+        E::InvalidLinkage => "No valid linkage for the transaction".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, thiserror::Error, PartialEq, Eq)]
+    enum PoolAbort {
+        #[error("Pool is paused")]
+        Paused,
+    }
+
+    impl TryFrom<u64> for PoolAbort {
+        type Error = u64;
+
+        fn try_from(code: u64) -> Result<Self, u64> {
+            match code {
+                1 => Ok(Self::Paused),
+                _ => Err(code),
+            }
+        }
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("Math overflow")]
+    struct MathAbort;
+
+    impl TryFrom<u64> for MathAbort {
+        type Error = ();
+
+        fn try_from(_: u64) -> Result<Self, ()> {
+            Ok(Self)
+        }
+    }
+
+    fn abort(module: &str, code: u64) -> ExecutionFailure {
+        let location = MoveLocation {
+            package: ObjectId::new([1; 32]),
+            module: module.parse().unwrap(),
+            function: 4,
+            instruction: 10,
+            function_name: None,
+        };
+        ExecutionFailure::new(ExecutionError::MoveAbort { location, code }, Some(0))
+    }
+
+    #[test]
+    fn decode_by_package_and_module() {
+        let package = ObjectId::new([1; 32]);
+        let mut registry = AbortRegistry::new();
+        registry
+            .register::<PoolAbort>(package)
+            .register_module::<MathAbort>(package, "math".parse().unwrap());
+
+        let diagnosis = registry.diagnose(abort("pool", 1));
+        assert_eq!(diagnosis.abort_as::<PoolAbort>(), Some(&PoolAbort::Paused));
+        assert_eq!(
+            diagnosis.to_string(),
+            format!("Pool is paused (abort code 1 in {package}::pool::<function 4>) in command 0")
+        );
+
+        let diagnosis = registry.diagnose(abort("math", 1));
+        assert!(diagnosis.abort_as::<MathAbort>().is_some());
+
+        // Unknown code
+        let diagnosis = registry.diagnose(abort("pool", 2));
+        assert!(diagnosis.abort.is_none());
+        assert_eq!(
+            diagnosis.to_string(),
+            format!("Move abort with code 2 in {package}::pool::<function 4> in command 0")
+        );
+    }
+
+    #[test]
+    fn from_status() {
+        assert_eq!(
+            ExecutionFailure::from_status(ExecutionStatus::Success),
+            None
+        );
+        let failure = ExecutionFailure::from_status(ExecutionStatus::Failure {
+            error: ExecutionError::InsufficientGas,
+            command: None,
+        })
+        .unwrap();
+        assert_eq!(failure.to_string(), "Insufficient gas");
+        assert!(failure.move_abort().is_none());
+    }
+}
@@ -88,12 +88,16 @@ pub use sui_sdk_types::{
     Version,
 };
 
+pub mod coin_selection;
 mod const_address;
 pub mod encoding;
+pub mod execution_failure;
 #[cfg(feature = "hash")]
 mod hash;
 /// Aftermath's versions of [`move_core_types`](https://github.com/MystenLabs/sui/tree/main/external-crates/move/crates/move-core-types).
 pub(crate) mod move_core;
+pub mod name_service;
+pub mod object_arg_cache;
 /// Aftermath's versions of [`sui_types`](https://mystenlabs.github.io/sui/sui_types/index.html).
 pub mod sui;
 
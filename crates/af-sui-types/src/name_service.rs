@@ -0,0 +1,457 @@
+//! SuiNS (`.sui`) names and a client-agnostic, caching resolver for them.
+//!
+//! [`AddressOrName`] parses either a hex address or a name, so it can stand in for an [`Address`]
+//! in CLI arguments and configuration files. A [`NameServiceResolver`] queries the name service
+//! (the JSON-RPC and GraphQL clients each implement one) and [`NameServiceCache`] keeps its answers
+//! for a fixed time-to-live.
+//!
+//! ```
+//! use af_sui_types::name_service::{AddressOrName, SuinsName};
+//!
+//! let name: AddressOrName = "example.sui".parse().unwrap();
+//! assert!(matches!(name, AddressOrName::Name(_)));
+//! let address: AddressOrName = "0x2".parse().unwrap();
+//! assert!(matches!(address, AddressOrName::Address(_)));
+//!
+//! // Both formats are normalized to the dot format
+//! let name: SuinsName = "sub@Example".parse().unwrap();
+//! assert_eq!(name.to_string(), "sub.example.sui");
+//! assert_eq!(name.to_at_format(), "sub@example");
+//! ```
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use sui_sdk_types::AddressParseError;
+
+use crate::Address;
+
+const TLD: &str = "sui";
+const MAX_LABEL_LENGTH: usize = 63;
+const MAX_NAME_LENGTH: usize = 235;
+
+// =============================================================================
+//  Names
+// =============================================================================
+
+/// A normalized SuiNS name, e.g., `example.sui` or `sub.example.sui`.
+///
+/// Parses both the dot (`sub.example.sui`) and the at (`sub@example`) formats, case-insensitively.
+#[derive(
+    Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, SerializeDisplay, DeserializeFromStr,
+)]
+pub struct SuinsName {
+    /// Labels from the TLD down, e.g., `["sui", "example", "sub"]`.
+    labels: Vec<String>,
+}
+
+impl SuinsName {
+    /// The labels from the top-level domain down, e.g., `["sui", "example", "sub"]`.
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Whether this is a subname, e.g., `sub.example.sui`.
+    pub fn is_subname(&self) -> bool {
+        self.labels.len() > 2
+    }
+
+    /// The name in the at format, e.g., `sub@example`.
+    pub fn to_at_format(&self) -> String {
+        let (tld_and_sld, subnames) = self.labels.split_at(2);
+        let subnames: Vec<_> = subnames.iter().rev().map(String::as_str).collect();
+        format!("{}@{}", subnames.join("."), tld_and_sld[1])
+    }
+}
+
+impl Display for SuinsName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let labels: Vec<_> = self.labels.iter().rev().map(String::as_str).collect();
+        write!(f, "{}", labels.join("."))
+    }
+}
+
+impl FromStr for SuinsName {
+    type Err = InvalidSuinsName;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lowercase = s.trim().to_ascii_lowercase();
+        let labels: Vec<String> = match lowercase.split_once('@') {
+            Some((subnames, name)) => {
+                let mut labels = vec![TLD.to_owned(), name.to_owned()];
+                if !subnames.is_empty() {
+                    labels.extend(subnames.rsplit('.').map(ToOwned::to_owned));
+                }
+                labels
+            }
+            None => {
+                let labels: Vec<_> = lowercase.rsplit('.').map(ToOwned::to_owned).collect();
+                if labels.first().map(String::as_str) != Some(TLD) {
+                    return Err(InvalidSuinsName::Tld(s.to_owned()));
+                }
+                labels
+            }
+        };
+        if labels.len() < 2 {
+            return Err(InvalidSuinsName::Tld(s.to_owned()));
+        }
+        if lowercase.len() > MAX_NAME_LENGTH {
+            return Err(InvalidSuinsName::TooLong(lowercase.len()));
+        }
+        for label in &labels[1..] {
+            check_label(label)?;
+        }
+        Ok(Self { labels })
+    }
+}
+
+fn check_label(label: &str) -> Result<(), InvalidSuinsName> {
+    let invalid = |reason| InvalidSuinsName::Label {
+        label: label.to_owned(),
+        reason,
+    };
+    if label.is_empty() || label.len() > MAX_LABEL_LENGTH {
+        return Err(invalid("must have between 1 and 63 characters"));
+    }
+    if label.starts_with('-') || label.ends_with('-') {
+        return Err(invalid("can't start or end with a hyphen"));
+    }
+    if !label
+        .bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+    {
+        return Err(invalid("can only have letters, digits and hyphens"));
+    }
+    Ok(())
+}
+
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+pub enum InvalidSuinsName {
+    #[error("Name {0:?} doesn't end with `.sui` nor is in the `@name` format")]
+    Tld(String),
+    #[error("Name is {0} characters long, more than the maximum of 235")]
+    TooLong(usize),
+    #[error("Label {label:?} {reason}")]
+    Label { label: String, reason: &'static str },
+}
+
+/// Either an address or a SuiNS name pointing to one.
+///
+/// Strings starting with `0x` are parsed as addresses, anything else as a [`SuinsName`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SerializeDisplay, DeserializeFromStr)]
+pub enum AddressOrName {
+    Address(Address),
+    Name(SuinsName),
+}
+
+impl Display for AddressOrName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "{address}"),
+            Self::Name(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl FromStr for AddressOrName {
+    type Err = AddressOrNameParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        Ok(if s.starts_with("0x") {
+            Self::Address(s.parse()?)
+        } else {
+            Self::Name(s.parse()?)
+        })
+    }
+}
+
+impl From<Address> for AddressOrName {
+    fn from(value: Address) -> Self {
+        Self::Address(value)
+    }
+}
+
+impl From<SuinsName> for AddressOrName {
+    fn from(value: SuinsName) -> Self {
+        Self::Name(value)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum AddressOrNameParseError {
+    #[error("Invalid address: {0}")]
+    Address(#[from] AddressParseError),
+    #[error(transparent)]
+    Name(#[from] InvalidSuinsName),
+}
+
+// =============================================================================
+//  Resolution
+// =============================================================================
+
+/// Queries the name service.
+pub trait NameServiceResolver {
+    type Error: std::error::Error + Send + 'static;
+
+    /// The address `name` points to, if any.
+    fn resolve_name(
+        &self,
+        name: &SuinsName,
+    ) -> impl Future<Output = Result<Option<Address>, Self::Error>> + Send;
+
+    /// The default name of `address`, if any.
+    fn default_name(
+        &self,
+        address: Address,
+    ) -> impl Future<Output = Result<Option<SuinsName>, Self::Error>> + Send;
+}
+
+/// A [`NameServiceResolver`] that remembers its answers for a fixed time-to-live.
+///
+/// Missing names and addresses without a default name are cached as well. Expired entries are
+/// dropped whenever a new one is added, so the cache only holds what was queried within the TTL.
+#[derive(Debug)]
+pub struct NameServiceCache<R> {
+    resolver: R,
+    ttl: Duration,
+    addresses: Mutex<HashMap<SuinsName, Entry<Address>>>,
+    names: Mutex<HashMap<Address, Entry<SuinsName>>>,
+}
+
+#[derive(Clone, Debug)]
+struct Entry<T> {
+    value: Option<T>,
+    expires: Instant,
+}
+
+impl<R: NameServiceResolver + Sync> NameServiceCache<R> {
+    pub fn new(resolver: R, ttl: Duration) -> Self {
+        Self {
+            resolver,
+            ttl,
+            addresses: Default::default(),
+            names: Default::default(),
+        }
+    }
+
+    pub const fn resolver(&self) -> &R {
+        &self.resolver
+    }
+
+    /// The address for `target`, resolving it if it's a name.
+    pub async fn resolve(&self, target: &AddressOrName) -> Result<Address, ResolveError<R::Error>> {
+        match target {
+            AddressOrName::Address(address) => Ok(*address),
+            AddressOrName::Name(name) => self
+                .resolve_name(name)
+                .await?
+                .ok_or_else(|| ResolveError::NotFound(name.clone())),
+        }
+    }
+
+    /// The address `name` points to, if any.
+    pub async fn resolve_name(&self, name: &SuinsName) -> Result<Option<Address>, R::Error> {
+        if let Some(cached) = cached(&self.addresses, name) {
+            return Ok(cached);
+        }
+        let address = self.resolver.resolve_name(name).await?;
+        self.insert(&self.addresses, name.clone(), address);
+        Ok(address)
+    }
+
+    /// The default name of `address`, if any.
+    pub async fn default_name(&self, address: Address) -> Result<Option<SuinsName>, R::Error> {
+        if let Some(cached) = cached(&self.names, &address) {
+            return Ok(cached);
+        }
+        let name = self.resolver.default_name(address).await?;
+        self.insert(&self.names, address, name.clone());
+        Ok(name)
+    }
+
+    /// Forget everything, e.g., after a name changed hands.
+    pub fn clear(&self) {
+        lock(&self.addresses).clear();
+        lock(&self.names).clear();
+    }
+
+    fn insert<K: std::hash::Hash + Eq, V>(
+        &self,
+        map: &Mutex<HashMap<K, Entry<V>>>,
+        key: K,
+        value: Option<V>,
+    ) {
+        let now = Instant::now();
+        let entry = Entry {
+            value,
+            expires: now + self.ttl,
+        };
+        let mut map = lock(map);
+        map.retain(|_, entry| entry.expires > now);
+        map.insert(key, entry);
+    }
+}
+
+impl<R: NameServiceResolver + Sync> NameServiceResolver for NameServiceCache<R> {
+    type Error = R::Error;
+
+    fn resolve_name(
+        &self,
+        name: &SuinsName,
+    ) -> impl Future<Output = Result<Option<Address>, Self::Error>> + Send {
+        Self::resolve_name(self, name)
+    }
+
+    fn default_name(
+        &self,
+        address: Address,
+    ) -> impl Future<Output = Result<Option<SuinsName>, Self::Error>> + Send {
+        Self::default_name(self, address)
+    }
+}
+
+fn cached<K: std::hash::Hash + Eq, V: Clone>(
+    map: &Mutex<HashMap<K, Entry<V>>>,
+    key: &K,
+) -> Option<Option<V>> {
+    let mut map = lock(map);
+    let entry = map.get(key)?;
+    if entry.expires > Instant::now() {
+        return Some(entry.value.clone());
+    }
+    map.remove(key);
+    None
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    // The maps are always left in a consistent state
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ResolveError<E> {
+    #[error("Resolving name: {0}")]
+    Resolver(#[from] E),
+    #[error("Name {0} doesn't point to an address")]
+    NotFound(SuinsName),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn parse_names() {
+        let name: SuinsName = "Example.sui".parse().unwrap();
+        assert_eq!(name.to_string(), "example.sui");
+        assert_eq!(name.to_at_format(), "@example");
+        assert!(!name.is_subname());
+        assert_eq!("@example".parse::<SuinsName>().unwrap(), name);
+
+        let sub: SuinsName = "a.b@example".parse().unwrap();
+        assert_eq!(sub.to_string(), "a.b.example.sui");
+        assert_eq!(sub.labels(), ["sui", "example", "b", "a"]);
+        assert_eq!(sub.to_at_format(), "a.b@example");
+
+        for invalid in [
+            "example",
+            "example.com",
+            "sui",
+            "-a.sui",
+            "a_b.sui",
+            "a..sui",
+            "@",
+        ] {
+            assert!(invalid.parse::<SuinsName>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn address_or_name() {
+        let parsed: AddressOrName = "0x2".parse().unwrap();
+        assert_eq!(
+            parsed,
+            AddressOrName::Address(Address::from_hex("0x2").unwrap())
+        );
+        assert!("0xnope".parse::<AddressOrName>().is_err());
+
+        let json = serde_json::json!("@example");
+        let parsed: AddressOrName = serde_json::from_value(json).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), "example.sui");
+    }
+
+    #[derive(Default)]
+    struct Counting {
+        calls: AtomicUsize,
+    }
+
+    impl NameServiceResolver for Counting {
+        type Error = std::convert::Infallible;
+
+        async fn resolve_name(&self, name: &SuinsName) -> Result<Option<Address>, Self::Error> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Ok((name.to_string() == "example.sui").then_some(Address::ZERO))
+        }
+
+        async fn default_name(&self, _: Address) -> Result<Option<SuinsName>, Self::Error> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Ok(None)
+        }
+    }
+
+    /// Runs a future that never waits.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        match future.as_mut().poll(&mut cx) {
+            std::task::Poll::Ready(output) => output,
+            std::task::Poll::Pending => unreachable!("Resolver never waits"),
+        }
+    }
+
+    #[test]
+    fn cache_ttl() {
+        let cache = NameServiceCache::new(Counting::default(), Duration::from_secs(60));
+        let name: AddressOrName = "example.sui".parse().unwrap();
+        assert_eq!(block_on(cache.resolve(&name)).unwrap(), Address::ZERO);
+        assert_eq!(block_on(cache.resolve(&name)).unwrap(), Address::ZERO);
+        assert_eq!(cache.resolver().calls.load(Ordering::Relaxed), 1);
+
+        let missing: AddressOrName = "missing.sui".parse().unwrap();
+        assert!(matches!(
+            block_on(cache.resolve(&missing)),
+            Err(ResolveError::NotFound(_))
+        ));
+        assert!(block_on(cache.resolve(&missing)).is_err());
+        assert_eq!(cache.resolver().calls.load(Ordering::Relaxed), 2);
+
+        let expired = NameServiceCache::new(Counting::default(), Duration::ZERO);
+        block_on(expired.default_name(Address::ZERO)).unwrap();
+        block_on(expired.default_name(Address::ZERO)).unwrap();
+        assert_eq!(expired.resolver().calls.load(Ordering::Relaxed), 2);
+
+        // Expired entries are swept when new ones come in
+        for name in ["a.sui", "b.sui", "c.sui"] {
+            block_on(expired.resolve_name(&name.parse().unwrap())).unwrap();
+        }
+        assert_eq!(lock(&expired.addresses).len(), 1);
+    }
+
+    #[test]
+    fn too_long() {
+        let name = format!("  {}.sui", "a".repeat(MAX_NAME_LENGTH));
+        assert_eq!(
+            name.parse::<SuinsName>(),
+            Err(InvalidSuinsName::TooLong(MAX_NAME_LENGTH + 4))
+        );
+    }
+}
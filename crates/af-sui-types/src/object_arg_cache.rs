@@ -0,0 +1,256 @@
+//! Cache of [`ObjectArg`]s kept up to date with the effects of executed transactions.
+//!
+//! Owned and immutable object arguments pin an object version, so after every transaction that
+//! touches them the caller needs the new [`ObjectRef`] before building the next PTB. Instead of
+//! re-querying the node, [`ObjectArgCache::ingest`] applies the object changes in a transaction's
+//! effects directly.
+//!
+//! This is client-agnostic: the caches are filled with whatever client the caller uses.
+use std::collections::HashMap;
+
+use crate::{ObjectArg, ObjectId, ObjectRef, Owner, TransactionEffectsAPI, Version};
+
+/// Latest known [`ObjectArg`]s by object id.
+///
+/// Shared object arguments keep the mutability they were inserted with.
+#[derive(Clone, Debug, Default)]
+pub struct ObjectArgCache {
+    args: HashMap<ObjectId, ObjectArg>,
+}
+
+impl ObjectArgCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, id: &ObjectId) -> Option<ObjectArg> {
+        self.args.get(id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty()
+    }
+
+    /// Cache an object argument, e.g., one that was just queried.
+    ///
+    /// Ignored if the cache already has a later version of the object.
+    pub fn insert(&mut self, arg: ObjectArg) {
+        let id = *arg.id_borrowed();
+        if self.has_later_version(&id, arg_version(&arg)) {
+            return;
+        }
+        self.args.insert(id, arg);
+    }
+
+    pub fn remove(&mut self, id: &ObjectId) -> Option<ObjectArg> {
+        self.args.remove(id)
+    }
+
+    pub fn clear(&mut self) {
+        self.args.clear();
+    }
+
+    /// Apply the object changes of a transaction.
+    ///
+    /// Created, mutated and unwrapped objects get new arguments. Deleted and wrapped objects are
+    /// dropped from the cache since they can't be transaction inputs anymore. Objects for which
+    /// the cache already has a version later than the transaction's
+    /// [lamport version](TransactionEffectsAPI::lamport_version) are left untouched, so effects
+    /// can be ingested out of order.
+    pub fn ingest(&mut self, effects: &impl TransactionEffectsAPI) {
+        let lamport = effects.lamport_version();
+        let written = effects
+            .created()
+            .into_iter()
+            .chain(effects.mutated())
+            .chain(effects.unwrapped());
+        for (oref, owner) in written {
+            let (id, version, digest) = oref.into_parts();
+            if self.has_later_version(&id, Some(version)) {
+                continue;
+            }
+            let mutable = matches!(
+                self.args.get(&id),
+                Some(ObjectArg::SharedObject { mutable: true, .. })
+            );
+            self.args
+                .insert(id, object_arg((id, version, digest), owner, mutable));
+        }
+        for oref in effects.removed_object_refs_post_version() {
+            if !self.has_later_version(oref.object_id(), Some(lamport)) {
+                self.args.remove(oref.object_id());
+            }
+        }
+    }
+
+    fn has_later_version(&self, id: &ObjectId, version: Option<Version>) -> bool {
+        let cached = self.args.get(id).and_then(arg_version);
+        matches!((cached, version), (Some(cached), Some(new)) if cached > new)
+    }
+}
+
+/// The version pinned by an owned or immutable object argument.
+const fn arg_version(arg: &ObjectArg) -> Option<Version> {
+    match arg {
+        ObjectArg::ImmOrOwnedObject((_, version, _)) | ObjectArg::Receiving((_, version, _)) => {
+            Some(*version)
+        }
+        ObjectArg::SharedObject { .. } => None,
+    }
+}
+
+const fn object_arg(oref: ObjectRef, owner: Owner, mutable: bool) -> ObjectArg {
+    match owner {
+        Owner::Address(_) | Owner::Object(_) | Owner::Immutable => {
+            ObjectArg::ImmOrOwnedObject(oref)
+        }
+        Owner::Shared(initial_shared_version)
+        | Owner::ConsensusAddress {
+            start_version: initial_shared_version,
+            ..
+        } => ObjectArg::SharedObject {
+            id: oref.0,
+            initial_shared_version,
+            mutable,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sui_sdk_types::{
+        ChangedObject,
+        ExecutionStatus,
+        GasCostSummary,
+        IdOperation,
+        ObjectDigest,
+        ObjectIn,
+        ObjectOut,
+        TransactionDigest,
+        TransactionEffectsV2,
+    };
+
+    use super::*;
+    use crate::Address;
+
+    const OWNER: Owner = Owner::Address(Address::ZERO);
+
+    fn id(n: u8) -> ObjectId {
+        ObjectId::new([n; 32])
+    }
+
+    fn effects(
+        lamport_version: Version,
+        changed_objects: Vec<ChangedObject>,
+    ) -> TransactionEffectsV2 {
+        TransactionEffectsV2 {
+            status: ExecutionStatus::Success,
+            epoch: 0,
+            gas_used: GasCostSummary::default(),
+            transaction_digest: TransactionDigest::ZERO,
+            gas_object_index: None,
+            events_digest: None,
+            dependencies: vec![],
+            lamport_version,
+            changed_objects,
+            unchanged_shared_objects: vec![],
+            auxiliary_data_digest: None,
+        }
+    }
+
+    fn exists(version: Version, owner: Owner) -> ObjectIn {
+        ObjectIn::Exist {
+            version,
+            digest: ObjectDigest::ZERO,
+            owner,
+        }
+    }
+
+    fn write(owner: Owner) -> ObjectOut {
+        ObjectOut::ObjectWrite {
+            digest: ObjectDigest::ZERO,
+            owner,
+        }
+    }
+
+    #[test]
+    fn ingest_effects() {
+        let mut cache = ObjectArgCache::new();
+        cache.insert(ObjectArg::ImmOrOwnedObject((id(1), 3, ObjectDigest::ZERO)));
+        cache.insert(ObjectArg::ImmOrOwnedObject((id(2), 3, ObjectDigest::ZERO)));
+        cache.insert(ObjectArg::SharedObject {
+            id: id(3),
+            initial_shared_version: 1,
+            mutable: true,
+        });
+
+        let changes = vec![
+            ChangedObject {
+                object_id: id(1),
+                input_state: exists(3, OWNER),
+                output_state: write(OWNER),
+                id_operation: IdOperation::None,
+            },
+            ChangedObject {
+                object_id: id(2),
+                input_state: exists(3, OWNER),
+                output_state: ObjectOut::NotExist,
+                id_operation: IdOperation::Deleted,
+            },
+            ChangedObject {
+                object_id: id(3),
+                input_state: exists(5, Owner::Shared(1)),
+                output_state: write(Owner::Shared(1)),
+                id_operation: IdOperation::None,
+            },
+            ChangedObject {
+                object_id: id(4),
+                input_state: ObjectIn::NotExist,
+                output_state: write(OWNER),
+                id_operation: IdOperation::Created,
+            },
+        ];
+        cache.ingest(&effects(6, changes));
+
+        assert_eq!(
+            cache.get(&id(1)),
+            Some(ObjectArg::ImmOrOwnedObject((id(1), 6, ObjectDigest::ZERO)))
+        );
+        assert_eq!(cache.get(&id(2)), None);
+        assert_eq!(
+            cache.get(&id(3)),
+            Some(ObjectArg::SharedObject {
+                id: id(3),
+                initial_shared_version: 1,
+                mutable: true,
+            })
+        );
+        assert_eq!(
+            cache.get(&id(4)),
+            Some(ObjectArg::ImmOrOwnedObject((id(4), 6, ObjectDigest::ZERO)))
+        );
+    }
+
+    #[test]
+    fn older_versions_are_ignored() {
+        let mut cache = ObjectArgCache::new();
+        let latest = ObjectArg::ImmOrOwnedObject((id(1), 10, ObjectDigest::ZERO));
+        cache.insert(latest);
+        cache.insert(ObjectArg::ImmOrOwnedObject((id(1), 4, ObjectDigest::ZERO)));
+        assert_eq!(cache.get(&id(1)), Some(latest));
+
+        let stale = vec![ChangedObject {
+            object_id: id(1),
+            input_state: exists(3, OWNER),
+            output_state: ObjectOut::NotExist,
+            id_operation: IdOperation::Deleted,
+        }];
+        cache.ingest(&effects(4, stale));
+        assert_eq!(cache.get(&id(1)), Some(latest));
+        assert_eq!(cache.len(), 1);
+    }
+}
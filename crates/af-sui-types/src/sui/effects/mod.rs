@@ -572,6 +572,51 @@ const fn into_parts(r: ObjectReferenceWithOwner) -> (ObjectReference, Owner) {
     (r.reference, r.owner)
 }
 
+/// Use primarily on the gas cost of dry-runs.
+///
+/// Same as used in the Sui CLI.
+///
+/// # Arguments
+/// - `gas_cost_summary`: gas cost breakdown
+/// - `safe_overhead`: balance to add to both the computation and net gas costs to account for
+///   possible fluctuations when the transaction is actually submitted.
+pub fn estimate_gas_budget_from_gas_cost(
+    gas_cost_summary: &GasCostSummary,
+    safe_overhead: u64,
+) -> u64 {
+    let computation_cost_with_overhead = gas_cost_summary.computation_cost + safe_overhead;
+
+    let gas_usage_with_overhead = gas_cost_summary.net_gas_usage() + safe_overhead as i64;
+    computation_cost_with_overhead.max(gas_usage_with_overhead.max(0) as u64)
+}
+
 fn oref(id: ObjectId, version: Version, digest: ObjectDigest) -> ObjectReference {
     ObjectReference::new(id, version, digest)
 }
+
+#[cfg(test)]
+#[test]
+fn budget_from_gas_cost() {
+    let summary = GasCostSummary {
+        computation_cost: 1_000_000,
+        storage_cost: 5_000_000,
+        storage_rebate: 4_000_000,
+        non_refundable_storage_fee: 0,
+    };
+    // Net usage (2M) + overhead dominates computation + overhead
+    assert_eq!(
+        estimate_gas_budget_from_gas_cost(&summary, 750_000),
+        2_750_000
+    );
+
+    let summary = GasCostSummary {
+        computation_cost: 1_000_000,
+        storage_cost: 0,
+        storage_rebate: 4_000_000,
+        non_refundable_storage_fee: 0,
+    };
+    assert_eq!(
+        estimate_gas_budget_from_gas_cost(&summary, 750_000),
+        1_750_000
+    );
+}
@@ -1,4 +1,4 @@
-use af_ptbuilder::{ProgrammableTransactionBuilder, ptbuilder};
+use af_ptbuilder::{ProgrammableTransactionBuilder, RemapArguments, ptbuilder};
 use af_sui_types::{Argument, ObjectArg, ObjectId};
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
@@ -73,6 +73,15 @@ pub struct PtbArguments {
     pub fee_coin: Argument,
 }
 
+impl RemapArguments for PtbArguments {
+    fn remap_arguments(&mut self, f: &mut impl FnMut(Argument) -> Argument) {
+        self.pyth_state.remap_arguments(f);
+        self.wormhole_state.remap_arguments(f);
+        self.price_info_objects.remap_arguments(f);
+        self.fee_coin.remap_arguments(f);
+    }
+}
+
 #[extension_traits::extension(pub trait ProgrammableTransactionBuilderExt)]
 impl ProgrammableTransactionBuilder {
     /// Construct the PTB arguments to be used in [`update_pyth_price_info`].
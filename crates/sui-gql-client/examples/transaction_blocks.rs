@@ -0,0 +1,54 @@
+use af_sui_types::TransactionDataAPI as _;
+use clap::Parser;
+use color_eyre::Result;
+use futures::TryStreamExt as _;
+use sui_gql_client::queries::{GraphQlClientExt as _, TransactionBlockFilter};
+use sui_gql_client::reqwest::ReqwestClient;
+
+// Execute with
+// cargo run --example gql-transaction-blocks
+
+#[derive(Parser)]
+struct Args {
+    #[arg(long, default_value = "https://sui-testnet.mystenlabs.com/graphql")]
+    rpc: String,
+
+    /// Maximum number of transactions to print.
+    #[arg(long, default_value_t = 5)]
+    limit: usize,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    color_eyre::install()?;
+    let Args { rpc, limit } = Args::parse();
+    let client = ReqwestClient::new(reqwest::Client::default(), rpc);
+
+    let tx = client
+        .transaction_block("on9FwM19hquj2tapUCH9WxfVHMFW5YwhK66Hna7TpzP".parse()?)
+        .await?;
+    println!("Transaction: {tx:#?}");
+
+    let filter = TransactionBlockFilter {
+        sent_address: Some(tx.data.sender()),
+        ..Default::default()
+    };
+    tokio::pin!(
+        let stream = client.transaction_blocks(filter, None);
+    );
+    let mut count = 0;
+    while let Some(tx) = stream.try_next().await? {
+        println!(
+            "{}: {} events, {} balance changes, checkpoint {:?}",
+            tx.digest,
+            tx.events.len(),
+            tx.balance_changes.len(),
+            tx.checkpoint
+        );
+        count += 1;
+        if count >= limit {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,524 @@
+//! Sign, submit and wait for transactions to be checkpointed.
+//!
+//! The main item here is [`Executor`], which wraps the
+//! [`executeTransactionBlock`](crate::mutations::ExecuteTxClassic) mutation with retries on
+//! transient failures and classifies failures into an [`Error`] that callers can react to.
+//!
+//! # Equivocation
+//!
+//! Retries only ever resubmit the **same signed transaction bytes**. Sui executes a transaction
+//! digest at most once, so resubmitting can't double-spend the owned objects used as inputs.
+//! Building a *different* transaction with the same owned object references, however, can lock
+//! those objects until the end of the epoch. That's why the executor never rebuilds or re-signs a
+//! transaction on its own; if [`Error::ObjectVersionConflict`] is returned, the caller should
+//! refresh its object references before building a new transaction.
+use std::time::Duration;
+
+use af_keys::ReadOnlyAccountKeystore;
+use af_sui_types::{
+    ExecutionError,
+    ExecutionStatus,
+    TransactionData,
+    TransactionDigest,
+    TransactionEffects,
+    UserSignature,
+};
+pub use sui_sdk_types::MoveLocation;
+
+use crate::mutations::ExecuteTxClassic;
+use crate::mutations::execute_transaction_classic::{ExecutionResult, Variables};
+use crate::queries::GraphQlClientExt as _;
+use crate::{GraphQlClient, GraphQlErrors, GraphQlResponseExt as _};
+
+/// Parameters for [`Executor`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ExecutorOptions {
+    /// How many times to resubmit a transaction after a transient failure.
+    pub max_retries: u32,
+    /// Delay before the first resubmission. Doubled for every subsequent one.
+    pub retry_backoff: Duration,
+    /// Interval between checks for the transaction's checkpoint inclusion.
+    pub poll_interval: Duration,
+    /// How long to wait for the transaction to be included in a checkpoint.
+    pub checkpoint_timeout: Duration,
+}
+
+impl Default for ExecutorOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(500),
+            poll_interval: Duration::from_millis(500),
+            checkpoint_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Signs transactions with a keystore, submits them and waits for their checkpoint inclusion.
+///
+/// See the [module documentation](self) for how retries are made safe.
+#[derive(Clone, Debug)]
+pub struct Executor<C, K> {
+    client: C,
+    keystore: K,
+    options: ExecutorOptions,
+}
+
+impl<C, K> Executor<C, K>
+where
+    C: GraphQlClient,
+    K: ReadOnlyAccountKeystore,
+{
+    pub fn new(client: C, keystore: K) -> Self {
+        Self {
+            client,
+            keystore,
+            options: ExecutorOptions::default(),
+        }
+    }
+
+    pub const fn with_options(mut self, options: ExecutorOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub const fn client(&self) -> &C {
+        &self.client
+    }
+
+    pub const fn keystore(&self) -> &K {
+        &self.keystore
+    }
+
+    /// Sign the transaction for its sender and sponsor, execute it and wait for it to be
+    /// checkpointed.
+    ///
+    /// For multisig senders or sponsors, sign with [`af_keys::signatures`] and use
+    /// [`Self::execute_signed`] instead.
+    pub async fn execute(
+        &self,
+        tx_data: &TransactionData,
+    ) -> Result<TransactionEffects, Error<C::Error>> {
+        let signatures =
+            af_keys::signatures(tx_data, None, None, &self.keystore).map_err(Error::Signing)?;
+        self.execute_signed(tx_data, signatures).await
+    }
+
+    /// Execute an already signed transaction and wait for it to be checkpointed.
+    ///
+    /// Returns the effects only if the transaction executed successfully.
+    pub async fn execute_signed(
+        &self,
+        tx_data: &TransactionData,
+        signatures: Vec<UserSignature>,
+    ) -> Result<TransactionEffects, Error<C::Error>> {
+        let digest = tx_data.digest();
+        let vars = Variables {
+            tx_bytes: tx_data.encode_base64(),
+            signatures: signatures.iter().map(UserSignature::to_base64).collect(),
+        };
+
+        let mut backoff = self.options.retry_backoff;
+        let mut attempt = 0;
+        let effects = loop {
+            let failure = match self.submit(vars.clone()).await {
+                Ok(effects) => break effects,
+                Err(failure) => failure,
+            };
+            let retry = attempt < self.options.max_retries && failure.is_transient();
+            if retry {
+                attempt += 1;
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            // The transaction may still have been executed, e.g., if the response was lost
+            match self.executed_effects(digest).await {
+                Ok(Some(effects)) => break effects,
+                // Unknown if the lookup failed, but resubmitting is safe
+                Ok(None) | Err(_) if retry => (),
+                Ok(None) | Err(_) => return Err(failure.into_error()),
+            }
+        };
+
+        check_status(effects.clone())?;
+        self.wait_for_checkpoint(digest).await?;
+        Ok(effects)
+    }
+
+    /// Poll the transaction's status until it's included in a checkpoint.
+    pub async fn wait_for_checkpoint(
+        &self,
+        digest: TransactionDigest,
+    ) -> Result<(), Error<C::Error>> {
+        let wait = async {
+            loop {
+                let mut statuses = self
+                    .client
+                    .transaction_blocks_status(vec![digest.to_string()])
+                    .await?;
+                if statuses.next().is_some() {
+                    return Ok(());
+                }
+                tokio::time::sleep(self.options.poll_interval).await;
+            }
+        };
+        tokio::time::timeout(self.options.checkpoint_timeout, wait)
+            .await
+            .map_err(|_| Error::Timeout { digest })?
+    }
+
+    async fn submit(&self, vars: Variables) -> Result<TransactionEffects, Failure<C::Error>> {
+        let response = self
+            .client
+            .mutation::<ExecuteTxClassic, _>(vars)
+            .await
+            .map_err(Failure::Client)?;
+        let data = response
+            .try_into_data()
+            .map_err(|GraphQlErrors { errors, .. }| {
+                Failure::Rejected(errors.into_iter().map(|e| e.message).collect())
+            })?
+            .ok_or(Failure::NoData)?;
+        let ExecuteTxClassic {
+            execute_transaction_block: ExecutionResult { effects, errors },
+        } = data;
+        let effects = effects.bcs.into_inner();
+        match errors {
+            // Failures during execution are reported in the effects
+            Some(errors) if effects.status() == &ExecutionStatus::Success => {
+                Err(Failure::Rejected(errors))
+            }
+            _ => Ok(effects),
+        }
+    }
+
+    /// Effects of the transaction if the RPC already knows about it.
+    async fn executed_effects(
+        &self,
+        digest: TransactionDigest,
+    ) -> Result<Option<TransactionEffects>, Error<C::Error>> {
+        let tx = crate::queries::transaction_block::query_opt(&self.client, digest).await?;
+        Ok(tx.map(|tx| tx.effects))
+    }
+}
+
+/// Error for [`Executor`].
+#[derive(thiserror::Error, Debug)]
+pub enum Error<C: std::error::Error> {
+    #[error("Client error: {0:?}")]
+    Client(C),
+    #[error("Querying transaction status: {0}")]
+    Query(#[from] crate::queries::Error<C>),
+    #[error("Signing transaction: {0}")]
+    Signing(eyre::Report),
+    #[error("No data in transaction GQL response")]
+    NoData,
+    /// An owned input object was already used by another transaction or isn't at the referenced
+    /// version anymore. Refresh the object references before building a new transaction.
+    #[error("Object version conflict: {0}")]
+    ObjectVersionConflict(String),
+    /// An input object doesn't exist at the referenced version, e.g., because it was deleted or
+    /// wrapped. If the RPC was lagging behind, it may be found when querying it again.
+    #[error("Object not found: {0}")]
+    ObjectNotFound(String),
+    /// The gas payment doesn't cover the budget or the budget doesn't cover the execution costs.
+    #[error("Insufficient gas: {message}")]
+    InsufficientGas {
+        message: String,
+        /// Present if the failure happened during execution.
+        effects: Option<Box<TransactionEffects>>,
+    },
+    /// A Move function aborted.
+    #[error(
+        "Move abort with code {code} in {}::{}{}{}",
+        location.package,
+        location.module,
+        location.function_name.as_ref().map(|f| format!("::{f}")).unwrap_or_default(),
+        command.map(|c| format!(" (command {c})")).unwrap_or_default(),
+    )]
+    MoveAbort {
+        location: MoveLocation,
+        code: u64,
+        /// Index of the PTB command that aborted.
+        command: Option<u64>,
+        effects: Box<TransactionEffects>,
+    },
+    /// Any other execution failure.
+    #[error("Execution failed: {error:?}{}", command.map(|c| format!(" (command {c})")).unwrap_or_default())]
+    ExecutionFailed {
+        error: ExecutionError,
+        command: Option<u64>,
+        effects: Box<TransactionEffects>,
+    },
+    /// The transaction was rejected before execution for any other reason.
+    #[error("Transaction rejected: {0:?}")]
+    Rejected(Vec<String>),
+    #[error("Transaction {digest} not checkpointed in time")]
+    Timeout { digest: TransactionDigest },
+}
+
+/// Map failed execution effects to an [`Error`].
+fn check_status<C: std::error::Error>(effects: TransactionEffects) -> Result<(), Error<C>> {
+    let ExecutionStatus::Failure { error, command } = effects.status().clone() else {
+        return Ok(());
+    };
+    let effects = Box::new(effects);
+    Err(match error {
+        ExecutionError::MoveAbort { location, code } => Error::MoveAbort {
+            location,
+            code,
+            command,
+            effects,
+        },
+        ExecutionError::InsufficientGas => Error::InsufficientGas {
+            message: "out of gas during execution".into(),
+            effects: Some(effects),
+        },
+        error => Error::ExecutionFailed {
+            error,
+            command,
+            effects,
+        },
+    })
+}
+
+/// A single submission's failure, before deciding whether to retry.
+enum Failure<C> {
+    Client(C),
+    NoData,
+    Rejected(Vec<String>),
+}
+
+impl<C: std::error::Error> Failure<C> {
+    /// Whether resubmitting the same transaction may succeed.
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::Client(_) | Self::NoData => true,
+            Self::Rejected(messages) => messages.iter().any(|m| is_transient_message(m)),
+        }
+    }
+
+    fn into_error(self) -> Error<C> {
+        match self {
+            Self::Client(e) => Error::Client(e),
+            Self::NoData => Error::NoData,
+            Self::Rejected(messages) => classify_rejection(messages),
+        }
+    }
+}
+
+/// Messages from the quorum driver that indicate it may be possible to retry.
+const TRANSIENT_MARKERS: &[&str] = &[
+    "timed out before reaching finality",
+    "failed to reach finality with transient error",
+    "overloaded",
+];
+
+const OBJECT_CONFLICT_MARKERS: &[&str] = &[
+    "not available for consumption",
+    "reserved for another transaction",
+    "equivocated until the next epoch",
+];
+
+const OBJECT_NOT_FOUND_MARKERS: &[&str] =
+    &["could not find the referenced object", "objectnotfound"];
+
+const INSUFFICIENT_GAS_MARKERS: &[&str] = &[
+    "is lower than the needed amount",
+    "insufficientgas",
+    "insufficient gas",
+];
+
+fn contains_any(message: &str, markers: &[&str]) -> bool {
+    let message = message.to_lowercase();
+    markers.iter().any(|m| message.contains(m))
+}
+
+fn is_transient_message(message: &str) -> bool {
+    contains_any(message, TRANSIENT_MARKERS)
+}
+
+fn classify_rejection<C: std::error::Error>(messages: Vec<String>) -> Error<C> {
+    if let Some(m) = messages
+        .iter()
+        .find(|m| contains_any(m, INSUFFICIENT_GAS_MARKERS))
+    {
+        return Error::InsufficientGas {
+            message: m.clone(),
+            effects: None,
+        };
+    }
+    if let Some(m) = messages
+        .iter()
+        .find(|m| contains_any(m, OBJECT_CONFLICT_MARKERS))
+    {
+        return Error::ObjectVersionConflict(m.clone());
+    }
+    if let Some(m) = messages
+        .iter()
+        .find(|m| contains_any(m, OBJECT_NOT_FOUND_MARKERS))
+    {
+        return Error::ObjectNotFound(m.clone());
+    }
+    Error::Rejected(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestError = Error<std::io::Error>;
+
+    #[test]
+    fn object_unavailable_is_conflict() {
+        let message = "Transaction validator signing failed due to issues with transaction inputs, \
+            please review the errors and try again:\n\
+            - Object ID 0xa3b25765e4f7f4524367fa792b608483157bfef919108f0d998c6980493fc7bc \
+              Version 0xb0cb7f7 \
+              Digest FKkELfAR3vP19MrjwEwTapH3JDbdZuxqcC25CoALNUsN \
+              is not available for consumption, current version: 0xb0cb7f8";
+        assert!(!is_transient_message(message));
+        let error: TestError = classify_rejection(vec![message.into()]);
+        assert!(matches!(error, Error::ObjectVersionConflict(_)));
+    }
+
+    #[test]
+    fn missing_object_is_not_found() {
+        let message = "Transaction validator signing failed due to issues with transaction inputs, \
+            please review the errors and try again:\n\
+            - Could not find the referenced object \
+              0x1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f809 at version None";
+        assert!(!is_transient_message(message));
+        let error: TestError = classify_rejection(vec![message.into()]);
+        assert!(matches!(error, Error::ObjectNotFound(_)));
+    }
+
+    #[test]
+    fn gas_balance_is_insufficient_gas() {
+        let message = "Transaction validator signing failed due to issues with transaction inputs, \
+            please review the errors and try again:\n\
+            - Balance of gas object 10 is lower than the needed amount: 100";
+        let error: TestError = classify_rejection(vec![message.into()]);
+        assert!(matches!(
+            error,
+            Error::InsufficientGas { effects: None, .. }
+        ));
+    }
+
+    #[test]
+    fn finality_timeout_is_transient() {
+        assert!(is_transient_message(
+            "Transaction timed out before reaching finality"
+        ));
+    }
+
+    #[test]
+    fn unknown_rejection() {
+        let error: TestError = classify_rejection(vec!["Invalid user signature".into()]);
+        assert!(matches!(error, Error::Rejected(_)));
+    }
+
+    #[cfg(feature = "test-support")]
+    mod retries {
+        use af_keys::keystore::InMemKeystore;
+        use af_sui_types::{
+            Address as SuiAddress,
+            GasData,
+            ObjectDigest,
+            ObjectId,
+            ProgrammableTransaction,
+            TransactionExpiration,
+            TransactionKind,
+        };
+        use serde_json::json;
+
+        use super::*;
+        use crate::mock::{Fixture, MockServer};
+        use crate::reqwest::ReqwestClient;
+
+        fn executor(server: &MockServer) -> Executor<ReqwestClient, InMemKeystore> {
+            Executor::new(server.client(), InMemKeystore::default()).with_options(ExecutorOptions {
+                max_retries: 2,
+                retry_backoff: Duration::ZERO,
+                ..Default::default()
+            })
+        }
+
+        fn tx_data() -> TransactionData {
+            TransactionData::v1(
+                TransactionKind::ProgrammableTransaction(ProgrammableTransaction {
+                    inputs: vec![],
+                    commands: vec![],
+                }),
+                SuiAddress::new([1; 32]),
+                GasData {
+                    payment: vec![(ObjectId::new([2; 32]), 1, ObjectDigest::ZERO)],
+                    owner: SuiAddress::new([1; 32]),
+                    price: 1_000,
+                    budget: 1_000_000,
+                },
+                TransactionExpiration::None,
+            )
+        }
+
+        fn submissions(server: &MockServer) -> usize {
+            server
+                .requests()
+                .iter()
+                .filter(|r| r.root_fields == ["executeTransactionBlock"])
+                .count()
+        }
+
+        #[tokio::test]
+        async fn failed_lookups_dont_stop_retries() {
+            // No fixture for `transactionBlock`, so every lookup fails
+            let server = MockServer::start([Fixture::errors(
+                "executeTransactionBlock",
+                ["Transaction timed out before reaching finality"],
+            )])
+            .await
+            .unwrap();
+            let executor = executor(&server);
+            let err = executor
+                .execute_signed(&tx_data(), vec![])
+                .await
+                .unwrap_err();
+            assert!(matches!(err, Error::Rejected(_)), "{err}");
+            assert_eq!(submissions(&server), 3);
+        }
+
+        #[tokio::test]
+        async fn unknown_digest_is_not_executed() {
+            let server = MockServer::start([
+                Fixture::errors("executeTransactionBlock", ["Invalid user signature"]),
+                Fixture::new("transactionBlock", json!({ "transactionBlock": null })),
+            ])
+            .await
+            .unwrap();
+            let executor = executor(&server);
+            let err = executor
+                .execute_signed(&tx_data(), vec![])
+                .await
+                .unwrap_err();
+            assert!(matches!(err, Error::Rejected(_)), "{err}");
+            assert_eq!(submissions(&server), 1);
+        }
+
+        #[tokio::test]
+        async fn incomplete_lookup_is_an_error() {
+            let server = MockServer::start([Fixture::new(
+                "transactionBlock",
+                json!({ "transactionBlock": { "digest": null, "bcs": null, "effects": null } }),
+            )])
+            .await
+            .unwrap();
+            let executor = executor(&server);
+            let err = executor
+                .executed_effects(tx_data().digest())
+                .await
+                .unwrap_err();
+            assert!(matches!(err, Error::Query(_)), "{err}");
+        }
+    }
+}
@@ -27,12 +27,18 @@
 //!
 //! ## Features
 //!
+//! - `executor`: enables the `executor` submodule for signing, submitting and waiting for
+//!   transactions
 //! - `move-types`: compatibility with `af-move-type` types
 //! - `mutations`: enables the `mutations` submodule
 //! - `queries`: enables the `queries` submodule with pre-made queries
 //! - `reqwest`: enables the `reqwest` submodule with an implementation of
 //!   [`GraphQlClient`](crate::GraphQlClient)
 //! - `scalars`: re-exports the `scalars` module of [`sui_gql_schema`]
+//! - `test-support`: enables the `mock` submodule with an in-process GraphQL server that answers
+//!   from fixtures and validates queries against the schema
+//! - `walker`: enables the `walker` submodule for listing the entries of (nested) tables, bags
+//!   and other `sui` framework containers
 //!
 //! ## Handy links:
 //!
@@ -59,6 +65,10 @@ use extension_traits::extension;
 pub use sui_gql_schema::scalars;
 pub use sui_gql_schema::schema;
 
+#[cfg(feature = "executor")]
+pub mod executor;
+#[cfg(feature = "test-support")]
+pub mod mock;
 #[cfg(feature = "mutations")]
 pub mod mutations;
 #[cfg(feature = "queries")]
@@ -67,6 +77,8 @@ pub mod queries;
 mod raw_client;
 #[cfg(feature = "reqwest")]
 pub mod reqwest;
+#[cfg(feature = "walker")]
+pub mod walker;
 
 #[deprecated(since = "0.14.8", note = "use the graphql-extract crate")]
 pub mod extract;
@@ -0,0 +1,453 @@
+//! In-process stand-in for a Sui GraphQL server, for running tests without a network.
+//!
+//! [`MockServer`] answers operations from a list of [`Fixture`]s over HTTP on a local port, so a
+//! regular [`ReqwestClient`] can connect to it. Like a real server, it first checks each operation
+//! against the schema in [`sui_gql_schema`] and responds with GraphQL errors if it's invalid,
+//! e.g., if it selects a field that doesn't exist. The fixtures' responses are then deserialized
+//! exactly like a real server's would be. This makes
+//! [`GraphQlClientExt`](crate::queries::GraphQlClientExt) methods and anything built on top of
+//! [`GraphQlClient`](crate::GraphQlClient) testable deterministically:
+//!
+//! ```rust
+//! use serde_json::json;
+//! use sui_gql_client::mock::{Fixture, MockServer};
+//! use sui_gql_client::queries::GraphQlClientExt as _;
+//!
+//! # tokio_test::block_on(async {
+//! let server = MockServer::start([Fixture::new(
+//!     "checkpoint",
+//!     json!({ "checkpoint": { "sequenceNumber": 42 } }),
+//! )])
+//! .await
+//! .unwrap();
+//! let client = server.client();
+//! assert_eq!(client.latest_checkpoint().await.unwrap(), 42);
+//! # });
+//! ```
+//!
+//! Fixtures are matched against the fields selected at the root of the operation (e.g.
+//! `object`, `address` or `transactionBlocks`) and, optionally, against its variables. Operations
+//! without a matching fixture get a `500 Internal Server Error` response. Every operation
+//! received is recorded, see [`MockServer::requests`].
+//!
+//! [`sui_gql_schema`]: https://docs.rs/sui-gql-schema/latest/sui_gql_schema/
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use cynic_parser::executable::Selection;
+use http_body_util::{BodyExt as _, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::service::service_fn;
+use hyper::{Method, StatusCode, header};
+use hyper_util::rt::TokioIo;
+use serde::Deserialize;
+use serde_json::{Map, Value as Json, json};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::reqwest::ReqwestClient;
+
+mod validation;
+
+/// A canned response to operations selecting a certain field at the root.
+#[derive(Clone, Debug)]
+pub struct Fixture {
+    field: String,
+    variables: Map<String, Json>,
+    response: Json,
+    once: bool,
+}
+
+impl Fixture {
+    /// Respond with `data` to operations selecting `field` at the root.
+    ///
+    /// `data` is the `data` object of the GraphQL response, so it includes `field` itself, keyed by
+    /// its alias if the operation uses one.
+    pub fn new(field: impl Into<String>, data: Json) -> Self {
+        Self::response(field, json!({ "data": data }))
+    }
+
+    /// Respond with errors to operations selecting `field` at the root.
+    pub fn errors<S: Into<String>>(
+        field: impl Into<String>,
+        messages: impl IntoIterator<Item = S>,
+    ) -> Self {
+        let errors: Vec<_> = messages
+            .into_iter()
+            .map(|message| json!({ "message": message.into() }))
+            .collect();
+        Self::response(field, json!({ "data": null, "errors": errors }))
+    }
+
+    /// Respond with the full GraphQL `response` to operations selecting `field` at the root.
+    pub fn response(field: impl Into<String>, response: Json) -> Self {
+        Self {
+            field: field.into(),
+            variables: Map::new(),
+            response,
+            once: false,
+        }
+    }
+
+    /// Only match operations with these variables.
+    ///
+    /// Variables are named as in the operation, i.e., in camel case. Those not in `variables` can
+    /// take any value; use `null` to match variables that must be unset, e.g., the `after` cursor
+    /// of the first page of a paginated query.
+    ///
+    /// # Panics
+    ///
+    /// If `variables` is not a JSON object.
+    pub fn with_variables(mut self, variables: Json) -> Self {
+        let Json::Object(variables) = variables else {
+            panic!("Fixture variables must be a JSON object");
+        };
+        self.variables = variables;
+        self
+    }
+
+    /// Remove the fixture after it's used once.
+    pub const fn once(mut self) -> Self {
+        self.once = true;
+        self
+    }
+
+    fn matches(&self, root_fields: &[String], variables: &Json) -> bool {
+        root_fields.contains(&self.field)
+            && self
+                .variables
+                .iter()
+                .all(|(name, value)| variables.get(name).unwrap_or(&Json::Null) == value)
+    }
+}
+
+/// An operation received by [`MockServer`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Request {
+    pub operation_name: Option<String>,
+    pub query: String,
+    pub variables: Json,
+    /// Names of the fields selected at the root of the operation, ignoring aliases.
+    pub root_fields: Vec<String>,
+}
+
+/// GraphQL server answering operations from [`Fixture`]s.
+///
+/// The first fixture (in insertion order) matching an operation is used. The server stops when
+/// this is dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<State>,
+    task: JoinHandle<()>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    fixtures: Mutex<Vec<Fixture>>,
+    requests: Mutex<Vec<Request>>,
+}
+
+impl MockServer {
+    /// Serve `fixtures` on a local port.
+    ///
+    /// Must be called within a Tokio runtime.
+    pub async fn start(fixtures: impl IntoIterator<Item = Fixture>) -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(State {
+            fixtures: Mutex::new(fixtures.into_iter().collect()),
+            requests: Mutex::default(),
+        });
+        let task = tokio::spawn(serve(state.clone(), listener));
+        Ok(Self { addr, state, task })
+    }
+
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// HTTP URL of the server.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// A client connected to this server.
+    pub fn client(&self) -> ReqwestClient {
+        ReqwestClient::new_default(self.url())
+    }
+
+    /// Add a fixture, with lower priority than the existing ones.
+    pub fn push(&self, fixture: Fixture) {
+        lock(&self.state.fixtures).push(fixture);
+    }
+
+    /// Operations received so far, in order.
+    pub fn requests(&self) -> Vec<Request> {
+        lock(&self.state.requests).clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// =============================================================================
+//  Server implementation
+// =============================================================================
+
+async fn serve(state: Arc<State>, listener: TcpListener) {
+    // Stop serving if accepting fails; clients will see the connection errors
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(serve_connection(state.clone(), stream));
+    }
+}
+
+async fn serve_connection(state: Arc<State>, stream: TcpStream) {
+    let service = service_fn(|request| {
+        let state = state.clone();
+        async move { Ok::<_, Infallible>(handle(&state, request).await) }
+    });
+    // Errors here are the client's problem, e.g., a dropped connection
+    let _ = hyper::server::conn::http1::Builder::new()
+        .serve_connection(TokioIo::new(stream), service)
+        .await;
+}
+
+/// Body of a GraphQL request over HTTP.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Operation {
+    query: String,
+    #[serde(default)]
+    variables: Json,
+    operation_name: Option<String>,
+}
+
+async fn handle(state: &State, request: hyper::Request<Incoming>) -> hyper::Response<Full<Bytes>> {
+    if request.method() != Method::POST {
+        return response(StatusCode::NOT_FOUND, "Not found".into());
+    }
+    let body = match request.into_body().collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(e) => return response(StatusCode::BAD_REQUEST, format!("Reading body: {e}")),
+    };
+    let Operation {
+        query,
+        variables,
+        operation_name,
+    } = match serde_json::from_slice(&body) {
+        Ok(operation) => operation,
+        Err(e) => return response(StatusCode::BAD_REQUEST, format!("Invalid request: {e}")),
+    };
+
+    let (root_fields, errors) = match cynic_parser::parse_executable_document(&query) {
+        Ok(document) => (root_fields(&document), validation::validate(&document)),
+        Err(e) => (vec![], vec![format!("Parsing query: {e}")]),
+    };
+    let request = Request {
+        operation_name,
+        query,
+        variables,
+        root_fields,
+    };
+    let result = if errors.is_empty() {
+        state.respond(&request)
+    } else {
+        let errors: Vec<_> = errors
+            .into_iter()
+            .map(|message| json!({ "message": message }))
+            .collect();
+        Ok(json!({ "data": null, "errors": errors }))
+    };
+    lock(&state.requests).push(request);
+    match result {
+        Ok(json) => {
+            let mut response = response(StatusCode::OK, json.to_string());
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            );
+            response
+        }
+        Err(message) => response(StatusCode::INTERNAL_SERVER_ERROR, message),
+    }
+}
+
+impl State {
+    fn respond(&self, request: &Request) -> Result<Json, String> {
+        let mut fixtures = lock(&self.fixtures);
+        let i = fixtures
+            .iter()
+            .position(|f| f.matches(&request.root_fields, &request.variables))
+            .ok_or_else(|| {
+                format!(
+                    "No fixture for root fields {:?} with variables {}",
+                    request.root_fields, request.variables
+                )
+            })?;
+        Ok(if fixtures[i].once {
+            fixtures.remove(i).response
+        } else {
+            fixtures[i].response.clone()
+        })
+    }
+}
+
+fn response(status: StatusCode, body: String) -> hyper::Response<Full<Bytes>> {
+    let mut response = hyper::Response::new(Full::new(body.into()));
+    *response.status_mut() = status;
+    response
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Names of the fields in the root selection sets of the operations in `document`.
+fn root_fields(document: &cynic_parser::ExecutableDocument) -> Vec<String> {
+    document
+        .operations()
+        .flat_map(|operation| operation.selection_set())
+        .filter_map(|selection| match selection {
+            Selection::Field(field) => Some(field.name().to_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use af_sui_types::Address as SuiAddress;
+    use cynic::http::CynicReqwestError;
+    use futures::TryStreamExt as _;
+
+    use super::*;
+    use crate::RawClientError;
+    use crate::queries::GraphQlClientExt as _;
+
+    fn is_no_fixture(err: &crate::queries::Error<RawClientError<CynicReqwestError>>) -> bool {
+        matches!(
+            err,
+            crate::queries::Error::Client(RawClientError::Inner(CynicReqwestError::ErrorResponse(
+                status,
+                text,
+            ))) if *status == StatusCode::INTERNAL_SERVER_ERROR && text.starts_with("No fixture")
+        )
+    }
+
+    #[test]
+    fn root_field_names() {
+        let query = r#"
+        query Query($owner: SuiAddress!, $coinType: String) {
+          address(address: $owner) {
+            balance(type: $coinType) {
+              totalBalance
+            }
+          }
+          gas: epoch {
+            referenceGasPrice
+          }
+          checkpoint { sequenceNumber }
+        }
+        "#;
+        let document = cynic_parser::parse_executable_document(query).unwrap();
+        assert_eq!(root_fields(&document), ["address", "epoch", "checkpoint"]);
+    }
+
+    #[tokio::test]
+    async fn answers_from_fixtures() {
+        let server = MockServer::start([Fixture::new(
+            "epoch",
+            json!({ "epoch": { "referenceGasPrice": "750" } }),
+        )])
+        .await
+        .unwrap();
+        let client = server.client();
+        assert_eq!(client.reference_gas_price().await.unwrap(), 750);
+        assert_eq!(client.reference_gas_price().await.unwrap(), 750);
+
+        let err = client.latest_checkpoint().await.unwrap_err();
+        assert!(is_no_fixture(&err), "{err}");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2].root_fields, ["checkpoint"]);
+    }
+
+    #[tokio::test]
+    async fn paginates_with_variables() {
+        let owner = SuiAddress::new([1; 32]);
+        let page = |coin_type: &str, has_next_page: bool, end_cursor: &str| {
+            json!({ "address": { "balances": {
+                "nodes": [{
+                    "coinType": { "repr": coin_type },
+                    "coinObjectCount": 1,
+                    "totalBalance": "10",
+                }],
+                "pageInfo": { "hasNextPage": has_next_page, "endCursor": end_cursor },
+            }}})
+        };
+        let server = MockServer::start([
+            Fixture::new("address", page("0x2::sui::SUI", true, "a"))
+                .with_variables(json!({ "after": null })),
+            Fixture::new("address", page("0x2::usdc::USDC", false, "b"))
+                .with_variables(json!({ "after": "a" }))
+                .once(),
+        ])
+        .await
+        .unwrap();
+        let client = server.client();
+
+        let balances: Vec<_> = client.balances(owner, None).try_collect().await.unwrap();
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[1].total_balance, 10);
+
+        // The second page was used up
+        let err = client
+            .balances(owner, None)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap_err();
+        assert!(is_no_fixture(&err), "{err}");
+    }
+
+    #[tokio::test]
+    async fn server_errors() {
+        let server = MockServer::start([Fixture::errors("checkpoint", ["Oops"])])
+            .await
+            .unwrap();
+        let err = server.client().latest_checkpoint().await.unwrap_err();
+        assert!(matches!(err, crate::queries::Error::Server(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_queries() {
+        let server = MockServer::start([Fixture::new(
+            "checkpoint",
+            json!({ "checkpoint": { "sequenceNumber": 42 } }),
+        )])
+        .await
+        .unwrap();
+        let query = "query { checkpoint { sequenceNumber digest { nope } } }";
+        let response: Json = reqwest::Client::new()
+            .post(server.url())
+            .body(json!({ "query": query }).to_string())
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(
+            response,
+            json!({ "data": null, "errors": [{
+                "message": "Field `Checkpoint.digest` of leaf type `String` can't have a selection set",
+            }]})
+        );
+        assert_eq!(server.requests()[0].root_fields, ["checkpoint"]);
+    }
+}
@@ -0,0 +1,324 @@
+//! Validation of operations against the Sui schema, as a server would do before executing them.
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use cynic_parser::common::OperationType;
+use cynic_parser::executable::{Iter, Selection};
+use cynic_parser::type_system::{Definition, TypeDefinition};
+use cynic_parser::{ExecutableDocument, TypeSystemDocument};
+
+/// Scalars every schema has without defining them.
+const BUILTIN_SCALARS: [&str; 5] = ["Boolean", "Float", "ID", "Int", "String"];
+
+static SCHEMA: LazyLock<TypeSystemDocument> = LazyLock::new(|| {
+    cynic_parser::parse_type_system_document(sui_gql_schema::SCHEMA_SUI)
+        .expect("Bundled schema is valid")
+});
+
+/// Errors found checking `document` against the Sui schema.
+///
+/// Checks that selected fields exist on their parent types and that leaf fields are the only ones
+/// without a selection set, that arguments are declared and required ones are set, that fragments
+/// are defined for existing composite types, and that the variables used are exactly the ones
+/// declared by the operation, with input types.
+pub(super) fn validate(document: &ExecutableDocument) -> Vec<String> {
+    let mut validator = Validator::new(&SCHEMA, document);
+    for operation in document.operations() {
+        let Some(root) = validator.root_type(operation.operation_type()) else {
+            validator.error(format!("Schema has no {} type", operation.operation_type()));
+            continue;
+        };
+        let mut declared = HashSet::new();
+        for variable in operation.variable_definitions() {
+            let ty = variable.ty().name();
+            if !validator.is_input_type(ty) {
+                validator.error(format!(
+                    "Variable `${}` has unknown or non-input type `{ty}`",
+                    variable.name()
+                ));
+            }
+            declared.insert(variable.name());
+        }
+
+        let mut used = HashSet::new();
+        validator.selection_set(root, operation.selection_set(), &mut used, &mut vec![]);
+        for name in used.difference(&declared) {
+            validator.error(format!("Variable `${name}` is not declared"));
+        }
+        for name in declared.difference(&used) {
+            validator.error(format!("Variable `${name}` is never used"));
+        }
+    }
+    validator.errors
+}
+
+struct Validator<'a> {
+    types: HashMap<&'a str, TypeDefinition<'a>>,
+    query: Option<&'a str>,
+    mutation: Option<&'a str>,
+    fragments: HashMap<&'a str, cynic_parser::executable::FragmentDefinition<'a>>,
+    errors: Vec<String>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(schema: &'a TypeSystemDocument, document: &'a ExecutableDocument) -> Self {
+        let mut types = HashMap::new();
+        let (mut query, mut mutation) = (None, None);
+        for definition in schema.definitions() {
+            match definition {
+                Definition::Type(ty) => {
+                    types.insert(ty.name(), ty);
+                }
+                Definition::Schema(schema) => {
+                    query = schema.query_type().map(|ty| ty.named_type());
+                    mutation = schema.mutation_type().map(|ty| ty.named_type());
+                }
+                _ => (),
+            }
+        }
+        Self {
+            types,
+            query,
+            mutation,
+            fragments: document.fragments().map(|f| (f.name(), f)).collect(),
+            errors: vec![],
+        }
+    }
+
+    const fn root_type(&self, operation: OperationType) -> Option<&'a str> {
+        match operation {
+            OperationType::Query => self.query,
+            OperationType::Mutation => self.mutation,
+            OperationType::Subscription => None,
+        }
+    }
+
+    fn error(&mut self, message: String) {
+        self.errors.push(message);
+    }
+
+    fn is_input_type(&self, name: &str) -> bool {
+        BUILTIN_SCALARS.contains(&name)
+            || matches!(
+                self.types.get(name),
+                Some(
+                    TypeDefinition::Scalar(_)
+                        | TypeDefinition::Enum(_)
+                        | TypeDefinition::InputObject(_)
+                )
+            )
+    }
+
+    fn is_leaf_type(&self, name: &str) -> bool {
+        BUILTIN_SCALARS.contains(&name)
+            || matches!(
+                self.types.get(name),
+                Some(TypeDefinition::Scalar(_) | TypeDefinition::Enum(_))
+            )
+    }
+
+    fn is_composite_type(&self, name: &str) -> bool {
+        matches!(
+            self.types.get(name),
+            Some(
+                TypeDefinition::Object(_) | TypeDefinition::Interface(_) | TypeDefinition::Union(_)
+            )
+        )
+    }
+
+    /// Check the `selections` on type `parent`, collecting the variables they use.
+    ///
+    /// `spreads` are the fragments being expanded, to stop at cycles.
+    fn selection_set(
+        &mut self,
+        parent: &'a str,
+        selections: Iter<'a, Selection<'a>>,
+        used: &mut HashSet<&'a str>,
+        spreads: &mut Vec<&'a str>,
+    ) {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => {
+                    for directive in field.directives() {
+                        for argument in directive.arguments() {
+                            used.extend(argument.value().variables_used());
+                        }
+                    }
+                    for argument in field.arguments() {
+                        used.extend(argument.value().variables_used());
+                    }
+                    self.field(parent, field, used, spreads);
+                }
+                Selection::InlineFragment(fragment) => {
+                    let on = fragment.type_condition().unwrap_or(parent);
+                    if !self.is_composite_type(on) {
+                        self.error(format!("Unknown or non-composite type `{on}` in fragment"));
+                        continue;
+                    }
+                    self.selection_set(on, fragment.selection_set(), used, spreads);
+                }
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.fragment_name();
+                    let Some(fragment) = self.fragments.get(name).copied() else {
+                        self.error(format!("Unknown fragment `{name}`"));
+                        continue;
+                    };
+                    if spreads.contains(&name) {
+                        self.error(format!("Fragment `{name}` spreads itself"));
+                        continue;
+                    }
+                    let on = fragment.type_condition();
+                    if !self.is_composite_type(on) {
+                        self.error(format!(
+                            "Unknown or non-composite type `{on}` in fragment `{name}`"
+                        ));
+                        continue;
+                    }
+                    spreads.push(name);
+                    self.selection_set(on, fragment.selection_set(), used, spreads);
+                    spreads.pop();
+                }
+            }
+        }
+    }
+
+    fn field(
+        &mut self,
+        parent: &'a str,
+        field: cynic_parser::executable::FieldSelection<'a>,
+        used: &mut HashSet<&'a str>,
+        spreads: &mut Vec<&'a str>,
+    ) {
+        let name = field.name();
+        if name == "__typename" {
+            if field.selection_set().len() > 0 {
+                self.error(format!(
+                    "Field `{parent}.__typename` can't have a selection set"
+                ));
+            }
+            return;
+        }
+        let mut fields = match self.types.get(parent) {
+            Some(TypeDefinition::Object(object)) => object.fields(),
+            Some(TypeDefinition::Interface(interface)) => interface.fields(),
+            _ => {
+                self.error(format!("Can't select field `{name}` on type `{parent}`"));
+                return;
+            }
+        };
+        let Some(definition) = fields.find(|f| f.name() == name) else {
+            self.error(format!("Unknown field `{name}` on type `{parent}`"));
+            return;
+        };
+
+        for argument in field.arguments() {
+            if !definition.arguments().any(|a| a.name() == argument.name()) {
+                self.error(format!(
+                    "Unknown argument `{}` on field `{parent}.{name}`",
+                    argument.name()
+                ));
+            }
+        }
+        for argument in definition.arguments() {
+            let required = argument.ty().is_non_null() && argument.default_value().is_none();
+            if required && !field.arguments().any(|a| a.name() == argument.name()) {
+                self.error(format!(
+                    "Missing required argument `{}` on field `{parent}.{name}`",
+                    argument.name()
+                ));
+            }
+        }
+
+        let ty = definition.ty().name();
+        let has_selections = field.selection_set().len() > 0;
+        if self.is_leaf_type(ty) {
+            if has_selections {
+                self.error(format!(
+                    "Field `{parent}.{name}` of leaf type `{ty}` can't have a selection set"
+                ));
+            }
+        } else if !has_selections {
+            self.error(format!(
+                "Field `{parent}.{name}` of type `{ty}` must have a selection set"
+            ));
+        } else {
+            self.selection_set(ty, field.selection_set(), used, spreads);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(query: &str) -> Vec<String> {
+        validate(&cynic_parser::parse_executable_document(query).unwrap())
+    }
+
+    #[test]
+    fn valid_query() {
+        let query = r#"
+        query Query($owner: SuiAddress!, $coinType: String) {
+          address(address: $owner) {
+            balance(type: $coinType) {
+              coinType { repr }
+              totalBalance
+            }
+          }
+          gas: epoch { referenceGasPrice }
+        }
+        "#;
+        assert!(errors(query).is_empty());
+    }
+
+    #[test]
+    fn fragments() {
+        let query = r#"
+        query Query($address: SuiAddress!) {
+          object(address: $address) { ...Fields }
+        }
+        fragment Fields on Object {
+          owner {
+            __typename
+            ... on Shared { initialSharedVersion }
+          }
+        }
+        "#;
+        assert!(errors(query).is_empty());
+    }
+
+    #[test]
+    fn invalid_queries() {
+        assert_eq!(
+            errors("query { checkpoint { nope } }"),
+            ["Unknown field `nope` on type `Checkpoint`"]
+        );
+        assert_eq!(
+            errors("query { checkpoint(nope: 1) { sequenceNumber } }"),
+            ["Unknown argument `nope` on field `Query.checkpoint`"]
+        );
+        assert_eq!(
+            errors("query { checkpoint }"),
+            ["Field `Query.checkpoint` of type `Checkpoint` must have a selection set"]
+        );
+        assert_eq!(
+            errors("query { checkpoint { sequenceNumber { x } } }"),
+            ["Field `Checkpoint.sequenceNumber` of leaf type `UInt53` can't have a selection set"]
+        );
+        assert_eq!(
+            errors("query { address { balance { totalBalance } } }"),
+            ["Missing required argument `address` on field `Query.address`"]
+        );
+        assert_eq!(
+            errors("query($a: SuiAddress!) { address(address: $b) { __typename } }"),
+            [
+                "Variable `$b` is not declared",
+                "Variable `$a` is never used"
+            ]
+        );
+        assert_eq!(
+            errors("query { object(address: \"0x1\") { ...Missing } }"),
+            ["Unknown fragment `Missing`"]
+        );
+    }
+}
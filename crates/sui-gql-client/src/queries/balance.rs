@@ -0,0 +1,101 @@
+use af_sui_types::Address as SuiAddress;
+
+use super::fragments::MoveTypeTag;
+use super::outputs::Balance;
+use crate::queries::Error;
+use crate::scalars::BigInt;
+use crate::{GraphQlClient, GraphQlResponseExt as _, schema};
+
+pub(super) async fn query<C: GraphQlClient>(
+    client: &C,
+    owner: SuiAddress,
+    coin_type: Option<String>,
+) -> super::Result<Balance, C> {
+    let data = client
+        .query::<Query, _>(Variables { owner, coin_type })
+        .await
+        .map_err(Error::Client)?
+        .try_into_data()?;
+    graphql_extract::extract!(data => {
+        address? {
+            balance?
+        }
+    });
+    Ok(balance.try_into()?)
+}
+
+impl TryFrom<BalanceGql> for Balance {
+    type Error = &'static str;
+
+    fn try_from(
+        BalanceGql {
+            coin_type,
+            coin_object_count,
+            total_balance,
+        }: BalanceGql,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            coin_type: coin_type.into(),
+            coin_object_count: coin_object_count.ok_or("Balance coin count")?,
+            total_balance: total_balance.ok_or("Total balance")?.into_inner(),
+        })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn gql_output() {
+    use cynic::QueryBuilder as _;
+
+    let vars = Variables {
+        owner: SuiAddress::ZERO,
+        coin_type: None,
+    };
+    let operation = Query::build(vars);
+    insta::assert_snapshot!(operation.query, @r###"
+    query Query($owner: SuiAddress!, $coinType: String) {
+      address(address: $owner) {
+        balance(type: $coinType) {
+          coinType {
+            repr
+          }
+          coinObjectCount
+          totalBalance
+        }
+      }
+    }
+    "###);
+}
+
+// =============================================================================
+//  Inner query fragments
+// =============================================================================
+
+#[derive(cynic::QueryVariables, Clone, Debug)]
+struct Variables {
+    owner: SuiAddress,
+    coin_type: Option<String>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(variables = "Variables")]
+struct Query {
+    #[arguments(address: $owner)]
+    address: Option<Address>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(variables = "Variables")]
+struct Address {
+    #[arguments(type: $coin_type)]
+    balance: Option<BalanceGql>,
+}
+
+/// Balance fragment shared with the `balances` query.
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(graphql_type = "Balance")]
+pub(super) struct BalanceGql {
+    coin_type: MoveTypeTag,
+    coin_object_count: Option<af_sui_types::Version>,
+    total_balance: Option<BigInt<u128>>,
+}
@@ -0,0 +1,119 @@
+use af_sui_types::Address as SuiAddress;
+use futures::Stream;
+
+use super::balance::BalanceGql;
+use super::fragments::{PageInfo, PageInfoForward};
+use super::outputs::Balance;
+use super::stream;
+use crate::queries::Error;
+use crate::{GraphQlClient, GraphQlResponseExt as _, schema};
+
+pub(super) fn query<C: GraphQlClient>(
+    client: &C,
+    owner: SuiAddress,
+    page_size: Option<u32>,
+) -> impl Stream<Item = super::Result<Balance, C>> + '_ {
+    let vars = Variables {
+        owner,
+        first: page_size.map(|v| v.try_into().unwrap_or(i32::MAX)),
+        after: None,
+    };
+    stream::forward(client, vars, request)
+}
+
+async fn request<C: GraphQlClient>(
+    client: &C,
+    vars: Variables,
+) -> super::Result<
+    stream::Page<impl Iterator<Item = super::Result<Balance, C>> + 'static + use<C>>,
+    C,
+> {
+    let data = client
+        .query::<Query, _>(vars)
+        .await
+        .map_err(Error::Client)?
+        .try_into_data()?;
+
+    graphql_extract::extract!(data => {
+        address? {
+            balances {
+                page_info
+                nodes
+            }
+        }
+    });
+
+    Ok(stream::Page::new(
+        page_info,
+        nodes.into_iter().map(|node| Ok(Balance::try_from(node)?)),
+    ))
+}
+
+#[cfg(test)]
+#[test]
+fn gql_output() {
+    use cynic::QueryBuilder as _;
+
+    let vars = Variables {
+        owner: SuiAddress::ZERO,
+        first: None,
+        after: None,
+    };
+    let operation = Query::build(vars);
+    insta::assert_snapshot!(operation.query, @r###"
+    query Query($owner: SuiAddress!, $first: Int, $after: String) {
+      address(address: $owner) {
+        balances(first: $first, after: $after) {
+          nodes {
+            coinType {
+              repr
+            }
+            coinObjectCount
+            totalBalance
+          }
+          pageInfo {
+            hasNextPage
+            endCursor
+          }
+        }
+      }
+    }
+    "###);
+}
+
+// =============================================================================
+//  Inner query fragments
+// =============================================================================
+
+#[derive(cynic::QueryVariables, Clone, Debug)]
+struct Variables {
+    owner: SuiAddress,
+    first: Option<i32>,
+    after: Option<String>,
+}
+
+impl stream::UpdatePageInfo for Variables {
+    fn update_page_info(&mut self, info: &PageInfo) {
+        self.after.clone_from(&info.end_cursor)
+    }
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(variables = "Variables")]
+struct Query {
+    #[arguments(address: $owner)]
+    address: Option<Address>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(variables = "Variables")]
+struct Address {
+    #[arguments(first: $first, after: $after)]
+    balances: BalanceConnection,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct BalanceConnection {
+    nodes: Vec<BalanceGql>,
+    page_info: PageInfoForward,
+}
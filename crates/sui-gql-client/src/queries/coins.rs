@@ -0,0 +1,176 @@
+use af_sui_types::coin_selection::{CoinRef, CoinSelector, InsufficientBalanceError, Selection};
+use af_sui_types::{Address as SuiAddress, ObjectId, Version};
+use futures::{Stream, TryStreamExt as _};
+
+use super::fragments::{PageInfo, PageInfoForward};
+use super::stream;
+use crate::queries::Error as QueryError;
+use crate::scalars::{BigInt, Digest};
+use crate::{GraphQlClient, GraphQlResponseExt as _, missing_data, schema};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error<C: std::error::Error> {
+    #[error(transparent)]
+    Query(#[from] QueryError<C>),
+    #[error("Selecting {coin_type} coins of {owner}: {source}")]
+    InsufficientBalance {
+        owner: SuiAddress,
+        coin_type: String,
+        source: InsufficientBalanceError,
+    },
+}
+
+pub(super) fn query<C: GraphQlClient>(
+    client: &C,
+    owner: SuiAddress,
+    coin_type: Option<String>,
+    page_size: Option<u32>,
+) -> impl Stream<Item = super::Result<CoinRef, C>> + '_ {
+    let vars = Variables {
+        owner,
+        coin_type,
+        first: page_size.map(|v| v.try_into().unwrap_or(i32::MAX)),
+        after: None,
+    };
+    stream::forward(client, vars, request)
+}
+
+pub(super) async fn for_amount<C: GraphQlClient>(
+    client: &C,
+    owner: SuiAddress,
+    coin_type: Option<String>,
+    amount: u64,
+    selector: impl CoinSelector + Send,
+) -> Result<Selection, Error<C::Error>> {
+    let coins: Vec<_> = query(client, owner, coin_type.clone(), None)
+        .try_collect()
+        .await?;
+    selector
+        .select(coins, amount)
+        .map_err(|source| Error::InsufficientBalance {
+            owner,
+            coin_type: coin_type.unwrap_or_else(|| "0x2::sui::SUI".to_owned()),
+            source,
+        })
+}
+
+async fn request<C: GraphQlClient>(
+    client: &C,
+    vars: Variables,
+) -> super::Result<
+    stream::Page<impl Iterator<Item = super::Result<CoinRef, C>> + 'static + use<C>>,
+    C,
+> {
+    let data = client
+        .query::<Query, _>(vars)
+        .await
+        .map_err(QueryError::Client)?
+        .try_into_data()?;
+
+    graphql_extract::extract!(data => {
+        address? {
+            coins {
+                page_info
+                nodes
+            }
+        }
+    });
+
+    Ok(stream::Page::new(
+        page_info,
+        nodes.into_iter().map(
+            |Coin {
+                 object_id,
+                 version,
+                 digest,
+                 coin_balance,
+             }| {
+                let digest = digest.ok_or(missing_data!("Digest of coin {object_id}"))?;
+                let balance = coin_balance.ok_or(missing_data!("Balance of coin {object_id}"))?;
+                Ok(CoinRef {
+                    object_ref: (object_id, version, digest.0.into()),
+                    balance: balance.into_inner(),
+                })
+            },
+        ),
+    ))
+}
+
+#[cfg(test)]
+#[test]
+fn gql_output() {
+    use cynic::QueryBuilder as _;
+
+    let vars = Variables {
+        owner: SuiAddress::ZERO,
+        coin_type: None,
+        first: None,
+        after: None,
+    };
+    let operation = Query::build(vars);
+    insta::assert_snapshot!(operation.query, @r###"
+    query Query($owner: SuiAddress!, $coinType: String, $first: Int, $after: String) {
+      address(address: $owner) {
+        coins(type: $coinType, first: $first, after: $after) {
+          nodes {
+            address
+            version
+            digest
+            coinBalance
+          }
+          pageInfo {
+            hasNextPage
+            endCursor
+          }
+        }
+      }
+    }
+    "###);
+}
+
+// =============================================================================
+//  Inner query fragments
+// =============================================================================
+
+#[derive(cynic::QueryVariables, Clone, Debug)]
+struct Variables {
+    owner: SuiAddress,
+    coin_type: Option<String>,
+    first: Option<i32>,
+    after: Option<String>,
+}
+
+impl stream::UpdatePageInfo for Variables {
+    fn update_page_info(&mut self, info: &PageInfo) {
+        self.after.clone_from(&info.end_cursor)
+    }
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(variables = "Variables")]
+struct Query {
+    #[arguments(address: $owner)]
+    address: Option<Address>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(variables = "Variables")]
+struct Address {
+    #[arguments(type: $coin_type, first: $first, after: $after)]
+    coins: CoinConnection,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct CoinConnection {
+    nodes: Vec<Coin>,
+    page_info: PageInfoForward,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct Coin {
+    #[cynic(rename = "address")]
+    object_id: ObjectId,
+    version: Version,
+    digest: Option<Digest>,
+    coin_balance: Option<BigInt<u64>>,
+}
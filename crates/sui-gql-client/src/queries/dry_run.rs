@@ -0,0 +1,396 @@
+use af_sui_types::sui::effects::estimate_gas_budget_from_gas_cost;
+use af_sui_types::{
+    Address as SuiAddress,
+    Argument,
+    ObjectRef,
+    TransactionData,
+    TransactionEffects,
+    TransactionKind,
+    Version,
+    encode_base64_default,
+};
+use sui_gql_schema::scalars::{self, Base64Bcs};
+
+use super::Error;
+use super::fragments::MoveTypeTag;
+use super::outputs::{DryRunCommandResults, DryRunOutput, RawMoveValue};
+use crate::{GraphQlClient, GraphQlResponseExt as _, missing_data, schema};
+
+/// Maximum possible budget.
+pub const MAX_GAS_BUDGET: u64 = 50_000_000_000;
+
+/// Multiplier on the gas price for computing gas budgets from dry-runs.
+///
+/// Same value as used in the Sui CLI.
+const GAS_SAFE_OVERHEAD_MULTIPLIER: u64 = 1000;
+
+/// Gas parameters for dry-running a [`TransactionKind`].
+///
+/// Fields left unset are filled in by the server, e.g., a mock gas coin is used if `gas_objects`
+/// is `None`.
+#[derive(cynic::InputObject, Clone, Debug, Default)]
+pub struct TransactionMetadata {
+    pub sender: Option<SuiAddress>,
+    pub gas_price: Option<u64>,
+    pub gas_objects: Option<Vec<ObjectRefInput>>,
+    pub gas_budget: Option<u64>,
+    pub gas_sponsor: Option<SuiAddress>,
+}
+
+/// Reference to a gas object for [`TransactionMetadata`].
+#[derive(cynic::InputObject, Clone, Debug)]
+#[cynic(graphql_type = "ObjectRef")]
+pub struct ObjectRefInput {
+    pub address: SuiAddress,
+    pub version: Version,
+    pub digest: String,
+}
+
+impl From<ObjectRef> for ObjectRefInput {
+    fn from((id, version, digest): ObjectRef) -> Self {
+        Self {
+            address: id.into(),
+            version,
+            digest: digest.to_string(),
+        }
+    }
+}
+
+/// Parameters for computing the gas budget for a transaction using a dry-run.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct GasBudgetOptions {
+    /// The gas price. Must be set via [`Self::new`].
+    pub price: u64,
+
+    /// The budget for the dry-run.
+    pub dry_run_budget: u64,
+
+    /// Multiplier on the gas price. The result is a balance to add to both the computation and net
+    /// gas costs to account for possible fluctuations when the transaction is actually submitted.
+    pub safe_overhead_multiplier: u64,
+}
+
+impl GasBudgetOptions {
+    #[expect(
+        clippy::missing_const_for_fn,
+        reason = "We might evolve the defaults to use non-const expressions"
+    )]
+    pub fn new(price: u64) -> Self {
+        Self {
+            price,
+            dry_run_budget: MAX_GAS_BUDGET,
+            safe_overhead_multiplier: GAS_SAFE_OVERHEAD_MULTIPLIER,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DryRunError<C: std::error::Error> {
+    #[error("Error in dry run: {0}")]
+    Execution(String, Box<DryRunOutput>),
+    #[error(transparent)]
+    Query(#[from] Error<C>),
+}
+
+pub(super) async fn query<C: GraphQlClient>(
+    client: &C,
+    tx_kind: &TransactionKind,
+    meta: TransactionMetadata,
+    skip_checks: bool,
+) -> super::Result<DryRunOutput, C> {
+    let tx_bytes = encode_base64_default(
+        bcs::to_bytes(tx_kind).expect("TransactionKind serialization shouldn't fail"),
+    );
+    request(client, tx_bytes, Some(meta), skip_checks).await
+}
+
+pub(super) async fn query_transaction<C: GraphQlClient>(
+    client: &C,
+    tx_data: &TransactionData,
+    skip_checks: bool,
+) -> super::Result<DryRunOutput, C> {
+    request(client, tx_data.encode_base64(), None, skip_checks).await
+}
+
+pub(super) async fn gas_budget<C: GraphQlClient>(
+    client: &C,
+    tx_kind: &TransactionKind,
+    sender: SuiAddress,
+    options: GasBudgetOptions,
+) -> Result<u64, DryRunError<C::Error>> {
+    let meta = TransactionMetadata {
+        sender: Some(sender),
+        gas_price: Some(options.price),
+        gas_budget: Some(options.dry_run_budget),
+        ..Default::default()
+    };
+    let output = query(client, tx_kind, meta, false).await?;
+    if let af_sui_types::ExecutionStatus::Failure { error, .. } = output.effects.status() {
+        let message = output.error.clone().unwrap_or_else(|| format!("{error:?}"));
+        return Err(DryRunError::Execution(message, Box::new(output)));
+    }
+
+    let safe_overhead = options.safe_overhead_multiplier * options.price;
+    Ok(estimate_gas_budget_from_gas_cost(
+        output.effects.gas_summary(),
+        safe_overhead,
+    ))
+}
+
+async fn request<C: GraphQlClient>(
+    client: &C,
+    tx_bytes: String,
+    tx_meta: Option<TransactionMetadata>,
+    skip_checks: bool,
+) -> super::Result<DryRunOutput, C> {
+    let data = client
+        .query::<Query, _>(Variables {
+            tx_bytes,
+            tx_meta,
+            skip_checks: Some(skip_checks),
+        })
+        .await
+        .map_err(Error::Client)?
+        .try_into_data()?;
+    graphql_extract::extract!(data => {
+        dry_run_transaction_block {
+            error
+            results
+            transaction
+        }
+    });
+
+    let Some(TransactionBlock {
+        effects: Some(TransactionBlockEffects { bcs, events }),
+    }) = transaction
+    else {
+        return Err(missing_data!(
+            "Dry run transaction effects; error: {error:?}"
+        ));
+    };
+
+    let results = results
+        .unwrap_or_default()
+        .into_iter()
+        .map(DryRunEffect::try_into)
+        .collect::<Result<_, _>>()?;
+    Ok(DryRunOutput {
+        effects: bcs.into_inner(),
+        events: events
+            .nodes
+            .into_iter()
+            .map(|e| e.bcs.into_inner())
+            .collect(),
+        results,
+        error,
+    })
+}
+
+impl TryFrom<DryRunEffect> for DryRunCommandResults {
+    type Error = &'static str;
+
+    fn try_from(
+        DryRunEffect {
+            mutated_references,
+            return_values,
+        }: DryRunEffect,
+    ) -> Result<Self, Self::Error> {
+        let mutated_references = mutated_references
+            .unwrap_or_default()
+            .into_iter()
+            .map(|DryRunMutation { input, type_, bcs }| {
+                let arg = match input {
+                    TransactionArgument::GasCoin(_) => Argument::Gas,
+                    TransactionArgument::Input(Input { ix }) => {
+                        Argument::Input(ix.try_into().map_err(|_| "Input index overflow")?)
+                    }
+                    TransactionArgument::Result(TxResult { cmd, ix: None }) => {
+                        Argument::Result(cmd.try_into().map_err(|_| "Result index overflow")?)
+                    }
+                    TransactionArgument::Result(TxResult { cmd, ix: Some(ix) }) => {
+                        Argument::NestedResult(
+                            cmd.try_into().map_err(|_| "Result index overflow")?,
+                            ix.try_into().map_err(|_| "Nested result index overflow")?,
+                        )
+                    }
+                    TransactionArgument::Unknown => return Err("Unknown transaction argument"),
+                };
+                Ok((
+                    arg,
+                    RawMoveValue {
+                        type_: type_.into(),
+                        bcs: bcs.into_inner(),
+                    },
+                ))
+            })
+            .collect::<Result<_, _>>()?;
+        let return_values = return_values
+            .unwrap_or_default()
+            .into_iter()
+            .map(|DryRunReturn { type_, bcs }| RawMoveValue {
+                type_: type_.into(),
+                bcs: bcs.into_inner(),
+            })
+            .collect();
+        Ok(Self {
+            mutated_references,
+            return_values,
+        })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn gql_output() {
+    use cynic::QueryBuilder as _;
+
+    let vars = Variables {
+        tx_bytes: String::new(),
+        tx_meta: None,
+        skip_checks: None,
+    };
+    let operation = Query::build(vars);
+    insta::assert_snapshot!(operation.query, @r###"
+    query Query($txBytes: String!, $txMeta: TransactionMetadata, $skipChecks: Boolean) {
+      dryRunTransactionBlock(txBytes: $txBytes, txMeta: $txMeta, skipChecks: $skipChecks) {
+        error
+        results {
+          mutatedReferences {
+            input {
+              __typename
+              ... on GasCoin {
+                _
+              }
+              ... on Input {
+                ix
+              }
+              ... on Result {
+                cmd
+                ix
+              }
+            }
+            type {
+              repr
+            }
+            bcs
+          }
+          returnValues {
+            type {
+              repr
+            }
+            bcs
+          }
+        }
+        transaction {
+          effects {
+            bcs
+            events {
+              nodes {
+                bcs
+              }
+            }
+          }
+        }
+      }
+    }
+    "###);
+}
+
+// =============================================================================
+//  Initial query
+// =============================================================================
+
+#[derive(cynic::QueryVariables, Clone, Debug)]
+struct Variables {
+    tx_bytes: String,
+    tx_meta: Option<TransactionMetadata>,
+    skip_checks: Option<bool>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(variables = "Variables")]
+struct Query {
+    #[arguments(txBytes: $tx_bytes, txMeta: $tx_meta, skipChecks: $skip_checks)]
+    dry_run_transaction_block: DryRunResult,
+}
+
+// =============================================================================
+//  Inner query fragments
+// =============================================================================
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct DryRunResult {
+    error: Option<String>,
+    results: Option<Vec<DryRunEffect>>,
+    transaction: Option<TransactionBlock>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct DryRunEffect {
+    mutated_references: Option<Vec<DryRunMutation>>,
+    return_values: Option<Vec<DryRunReturn>>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct DryRunMutation {
+    input: TransactionArgument,
+    #[cynic(rename = "type")]
+    type_: MoveTypeTag,
+    bcs: scalars::Base64<Vec<u8>>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct DryRunReturn {
+    #[cynic(rename = "type")]
+    type_: MoveTypeTag,
+    bcs: scalars::Base64<Vec<u8>>,
+}
+
+#[derive(cynic::InlineFragments, Clone, Debug)]
+enum TransactionArgument {
+    GasCoin(#[expect(dead_code, reason = "Placeholder field only")] GasCoin),
+    Input(Input),
+    Result(TxResult),
+    #[cynic(fallback)]
+    Unknown,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct GasCoin {
+    #[cynic(rename = "_")]
+    _placeholder: Option<bool>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct Input {
+    ix: i32,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(graphql_type = "Result")]
+struct TxResult {
+    cmd: i32,
+    ix: Option<i32>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct TransactionBlock {
+    effects: Option<TransactionBlockEffects>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct TransactionBlockEffects {
+    bcs: Base64Bcs<TransactionEffects>,
+    events: EventConnection,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct EventConnection {
+    nodes: Vec<Event>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct Event {
+    bcs: Base64Bcs<af_sui_types::Event>,
+}
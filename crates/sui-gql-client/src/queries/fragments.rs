@@ -50,23 +50,40 @@ impl<T: af_move_type::MoveType> TryFrom<af_move_type::MoveInstance<T>> for Dynam
     }
 }
 
+/// Filter for [`GraphQlClientExt::transaction_blocks`](crate::queries::GraphQlClientExt::transaction_blocks).
+///
+/// All set fields are combined with a logical AND.
 #[derive(cynic::InputObject, Clone, Debug, Default)]
-pub(crate) struct TransactionBlockFilter {
-    pub(crate) function: Option<String>,
-    pub(crate) kind: Option<TransactionBlockKindInput>,
-    pub(crate) after_checkpoint: Option<Version>,
-    pub(crate) at_checkpoint: Option<Version>,
-    pub(crate) before_checkpoint: Option<Version>,
-    pub(crate) affected_address: Option<SuiAddress>,
-    pub(crate) sent_address: Option<SuiAddress>,
-    pub(crate) input_object: Option<SuiAddress>,
-    pub(crate) changed_object: Option<SuiAddress>,
-    pub(crate) transaction_ids: Option<Vec<String>>,
-}
-
-#[derive(cynic::Enum, Clone, Debug)]
-pub(crate) enum TransactionBlockKindInput {
+pub struct TransactionBlockFilter {
+    /// Filter transactions by move function called. Calls can be filtered by the `package`,
+    /// `package::module`, or the `package::module::name` of their function.
+    pub function: Option<String>,
+    /// Select for either system or programmable transactions.
+    pub kind: Option<TransactionBlockKindInput>,
+    /// Limit to transactions that occured strictly after the given checkpoint.
+    pub after_checkpoint: Option<Version>,
+    /// Limit to transactions in the given checkpoint.
+    pub at_checkpoint: Option<Version>,
+    /// Limit to transaction that occured strictly before the given checkpoint.
+    pub before_checkpoint: Option<Version>,
+    /// Limit to transactions that interacted with the given address. The address could be a
+    /// sender, sponsor, or recipient of the transaction.
+    pub affected_address: Option<SuiAddress>,
+    /// Limit to transactions that were sent by the given address.
+    pub sent_address: Option<SuiAddress>,
+    /// Limit to transactions that accepted the given object as an input.
+    pub input_object: Option<SuiAddress>,
+    /// Limit to transactions that output a version of this object.
+    pub changed_object: Option<SuiAddress>,
+    /// Select transactions by their digest.
+    pub transaction_ids: Option<Vec<String>>,
+}
+
+#[derive(cynic::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransactionBlockKindInput {
+    /// A system transaction, e.g., genesis, change epoch, consensus commit prologue.
     SystemTx,
+    /// A user submitted programmable transaction.
     ProgrammableTx,
 }
 
@@ -1,5 +1,8 @@
 use std::collections::HashMap;
 
+use af_sui_types::coin_selection::{CoinRef, CoinSelector, Selection};
+use af_sui_types::name_service::SuinsName;
+use af_sui_types::object_arg_cache::ObjectArgCache;
 use af_sui_types::{
     Address as SuiAddress,
     Object,
@@ -8,18 +11,32 @@ use af_sui_types::{
     ObjectRef,
     StructTag,
     TransactionData,
+    TransactionDigest,
+    TransactionKind,
     Version,
 };
 // For `object_args!` macro only
 #[doc(hidden)]
 pub use bimap::BiMap;
 use futures::Stream;
-use outputs::{DynamicField, ObjectKey, RawMoveStruct, RawMoveValue};
+use outputs::{
+    Balance,
+    DryRunOutput,
+    DynamicField,
+    ExecutedTransaction,
+    ObjectKey,
+    RawMoveStruct,
+    RawMoveValue,
+};
 
 use crate::{GraphQlClient, GraphQlErrors};
 
+mod balance;
+mod balances;
 mod coin_metadata;
+mod coins;
 mod current_epoch_id;
+mod dry_run;
 mod epoch_final_checkpoint_num;
 mod events_backward;
 mod filtered_full_objects;
@@ -52,13 +69,26 @@ mod packages_from_original;
 mod packages_published_epoch;
 mod reference_gas_price;
 pub(crate) mod stream;
+mod suins;
+pub(crate) mod transaction_block;
+mod transaction_blocks;
 mod transaction_blocks_status;
 
+pub use self::coins::Error as CoinsForAmountError;
+pub use self::dry_run::{
+    DryRunError,
+    GasBudgetOptions,
+    MAX_GAS_BUDGET,
+    ObjectRefInput,
+    TransactionMetadata,
+};
 pub use self::events_backward::{EventEdge, EventFilter};
+pub use self::fragments::{TransactionBlockFilter, TransactionBlockKindInput};
 pub use self::gas_payment::Error as GasPaymentError;
 pub use self::latest_version_at_checkpoint_v2::Error as LatestVersionAtCheckpointError;
 pub use self::object_args::Error as ObjectArgsError;
 pub use self::object_args_and_content::Error as ObjectArgsAndContentError;
+pub use self::suins::GraphQlNameResolver;
 
 /// Standard query result type to aid in adding new queries.
 type Result<T, C> = std::result::Result<T, Error<<C as GraphQlClient>::Error>>;
@@ -186,6 +216,20 @@ pub trait GraphQlClientExt: GraphQlClient + Sized {
         object_args::query(self, names, page_size)
     }
 
+    /// Object args for `object_ids`, in the same order, taken from `cache` if there and queried
+    /// otherwise.
+    ///
+    /// The queried args are added to `cache`. For shared objects, these aren't mutable. Keep the
+    /// cache current by [ingesting](ObjectArgCache::ingest) the effects of executed transactions.
+    async fn cached_object_args(
+        &self,
+        cache: &mut ObjectArgCache,
+        object_ids: Vec<ObjectId>,
+        page_size: Option<u32>,
+    ) -> std::result::Result<Vec<ObjectArg>, ObjectArgsError<Self::Error>> {
+        object_args::query_cached(self, cache, object_ids, page_size)
+    }
+
     /// Get a sequence of object args and contents corresponding to `object_ids`, but not
     /// necessarily in the same order.
     ///
@@ -249,7 +293,7 @@ pub trait GraphQlClientExt: GraphQlClient + Sized {
         address: SuiAddress,
         root_version: Option<u64>,
         page_size: Option<i32>,
-    ) -> impl Stream<Item = Result<(RawMoveValue, DynamicField), Self>> + '_ {
+    ) -> impl Stream<Item = Result<(RawMoveValue, DynamicField), Self>> + Send + '_ {
         owner_df_contents_stream::query(self, address, root_version, page_size)
     }
 
@@ -300,6 +344,36 @@ pub trait GraphQlClientExt: GraphQlClient + Sized {
         reference_gas_price::query(self)
     }
 
+    /// The address a SuiNS name points to, if any.
+    async fn resolve_suins_address(&self, name: &SuinsName) -> Result<Option<SuiAddress>, Self> {
+        suins::resolve_address(self, name)
+    }
+
+    /// The default SuiNS name of an address, if any.
+    async fn default_suins_name(&self, address: SuiAddress) -> Result<Option<SuinsName>, Self> {
+        suins::default_name(self, address)
+    }
+
+    /// A transaction, its effects, events and balance changes.
+    async fn transaction_block(
+        &self,
+        digest: TransactionDigest,
+    ) -> Result<ExecutedTransaction, Self> {
+        transaction_block::query(self, digest)
+    }
+
+    /// **Streamed** transactions matching the filter, in ascending order of execution.
+    ///
+    /// Use the filter to select transactions by sender, affected address/object or checkpoint
+    /// range, for example.
+    fn transaction_blocks(
+        &self,
+        filter: TransactionBlockFilter,
+        page_size: Option<u32>,
+    ) -> impl Stream<Item = Result<ExecutedTransaction, Self>> + '_ {
+        transaction_blocks::query(self, filter, page_size)
+    }
+
     /// Get execution status for the input transaction digests
     #[expect(deprecated, reason = "Internal module deprecation")]
     async fn transaction_blocks_status(
@@ -324,6 +398,41 @@ pub trait GraphQlClientExt: GraphQlClient + Sized {
         gas_payment::query(self, sponsor, budget, exclude)
     }
 
+    /// Dry-run a transaction kind for its effects, events and the results of each command.
+    ///
+    /// Use `meta` to set the sender and gas parameters; those left unset are chosen by the server.
+    /// `skip_checks` disables the usual transaction validity checks, e.g., to call non-public
+    /// functions.
+    async fn dry_run(
+        &self,
+        tx_kind: &TransactionKind,
+        meta: TransactionMetadata,
+        skip_checks: bool,
+    ) -> Result<DryRunOutput, Self> {
+        dry_run::query(self, tx_kind, meta, skip_checks)
+    }
+
+    /// Dry-run a complete transaction for its effects, events and the results of each command.
+    async fn dry_run_transaction(
+        &self,
+        tx_data: &TransactionData,
+        skip_checks: bool,
+    ) -> Result<DryRunOutput, Self> {
+        dry_run::query_transaction(self, tx_data, skip_checks)
+    }
+
+    /// Estimate a budget for the transaction by dry-running it.
+    ///
+    /// Same estimate as the Sui CLI's.
+    async fn gas_budget(
+        &self,
+        tx_kind: &TransactionKind,
+        sender: SuiAddress,
+        options: GasBudgetOptions,
+    ) -> std::result::Result<u64, DryRunError<Self::Error>> {
+        dry_run::gas_budget(self, tx_kind, sender, options)
+    }
+
     /// The maximum size for pagination allowed by the server.
     async fn max_page_size(&self) -> Result<i32, Self> {
         max_page_size::query(self)
@@ -334,6 +443,48 @@ pub trait GraphQlClientExt: GraphQlClient + Sized {
         object_type::query(self, id)
     }
 
+    /// Total balance of a coin type owned by an address.
+    ///
+    /// Use `None` for the default `Coin<SUI>`.
+    async fn balance(&self, owner: SuiAddress, coin_type: Option<String>) -> Result<Balance, Self> {
+        balance::query(self, owner, coin_type)
+    }
+
+    /// **Streamed** balances of all the coin types owned by an address.
+    fn balances(
+        &self,
+        owner: SuiAddress,
+        page_size: Option<u32>,
+    ) -> impl Stream<Item = Result<Balance, Self>> + '_ {
+        balances::query(self, owner, page_size)
+    }
+
+    /// **Streamed** coins of a certain type owned by an address.
+    ///
+    /// Use `None` for the default `Coin<SUI>`.
+    fn coins(
+        &self,
+        owner: SuiAddress,
+        coin_type: Option<String>,
+        page_size: Option<u32>,
+    ) -> impl Stream<Item = Result<CoinRef, Self>> + '_ {
+        coins::query(self, owner, coin_type, page_size)
+    }
+
+    /// Coins of a certain type owned by an address that cover `amount`, as chosen by `selector`.
+    ///
+    /// All of the owner's coins of that type are listed before selecting. Use `None` for the
+    /// default `Coin<SUI>`.
+    async fn coins_for_amount(
+        &self,
+        owner: SuiAddress,
+        coin_type: Option<String>,
+        amount: u64,
+        selector: impl CoinSelector + Send,
+    ) -> std::result::Result<Selection, CoinsForAmountError<Self::Error>> {
+        coins::for_amount(self, owner, coin_type, amount, selector)
+    }
+
     /// Fetches metadata for the given coin type
     ///
     /// Returns a tuple containing (decimals, name, symbol)
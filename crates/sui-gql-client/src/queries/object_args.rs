@@ -1,4 +1,5 @@
 //! For requesting [`ObjectArg`]s from the server. Defines [`object_args!`](crate::object_args!).
+use af_sui_types::object_arg_cache::ObjectArgCache;
 use af_sui_types::{ObjectArg, ObjectId, Version};
 use bimap::BiMap;
 use futures::TryStreamExt as _;
@@ -25,6 +26,8 @@ pub enum Error<T> {
     NoData,
     #[error("Response missing object args for pairs: {0:?}")]
     MissingNamedArgs(Vec<(String, ObjectId)>),
+    #[error("Response missing object args for: {0:?}")]
+    MissingArgs(Vec<ObjectId>),
 }
 
 /// Turn a bijective map of names and object ids into one of names and object args.
@@ -64,6 +67,47 @@ pub(super) async fn query<C: GraphQlClient>(
     Ok(result)
 }
 
+/// Object args for `ids`, taken from `cache` if there and queried otherwise.
+///
+/// Queried args are added to `cache`.
+pub(super) async fn query_cached<C: GraphQlClient>(
+    client: &C,
+    cache: &mut ObjectArgCache,
+    ids: Vec<ObjectId>,
+    page_size: Option<u32>,
+) -> Res<Vec<ObjectArg>, C> {
+    let missing = ids
+        .iter()
+        .filter(|id| cache.get(id).is_none())
+        .copied()
+        .collect_vec();
+    if !missing.is_empty() {
+        let filter = ObjectFilterV2 {
+            object_ids: Some(&missing),
+            type_: None,
+            owner: None,
+        };
+        let vars = Variables {
+            filter: Some(filter),
+            after: None,
+            first: page_size.map(|n| n as i32),
+        };
+        let mut stream = std::pin::pin!(super::stream::forward(client, vars, request));
+        while let Some(arg) = stream.try_next().await? {
+            cache.insert(arg);
+        }
+    }
+
+    let (args, missing): (Vec<_>, Vec<_>) = ids
+        .into_iter()
+        .map(|id| cache.get(&id).ok_or(id))
+        .partition_result();
+    if !missing.is_empty() {
+        return Err(Error::MissingArgs(missing));
+    }
+    Ok(args)
+}
+
 async fn request<C: GraphQlClient>(
     client: &C,
     vars: Variables<'_>,
@@ -130,6 +174,54 @@ fn gql_output() {
     "###);
 }
 
+#[cfg(all(test, feature = "test-support"))]
+#[tokio::test]
+async fn cached_object_args() {
+    use serde_json::json;
+
+    use crate::mock::{Fixture, MockServer};
+    use crate::queries::GraphQlClientExt as _;
+
+    let (cached, queried) = (ObjectId::new([1; 32]), ObjectId::new([2; 32]));
+    let server = MockServer::start([Fixture::new(
+        "objects",
+        json!({ "objects": {
+            "nodes": [{
+                "address": queried,
+                "version": 3,
+                "digest": "11111111111111111111111111111111",
+                "owner": { "__typename": "Shared", "initialSharedVersion": 2 },
+            }],
+            "pageInfo": { "hasNextPage": false, "endCursor": null },
+        }}),
+    )
+    .once()])
+    .await
+    .unwrap();
+    let client = server.client();
+    let mut cache = ObjectArgCache::new();
+    let cached_arg = ObjectArg::ImmOrOwnedObject((cached, 1, Default::default()));
+    cache.insert(cached_arg);
+
+    let queried_arg = ObjectArg::SharedObject {
+        id: queried,
+        initial_shared_version: 2,
+        mutable: false,
+    };
+    let args = client
+        .cached_object_args(&mut cache, vec![queried, cached], None)
+        .await
+        .unwrap();
+    assert_eq!(args, [queried_arg, cached_arg]);
+    // Served from the cache since the fixture was used up
+    let args = client
+        .cached_object_args(&mut cache, vec![queried], None)
+        .await
+        .unwrap();
+    assert_eq!(args, [queried_arg]);
+    assert_eq!(server.requests().len(), 1);
+}
+
 // =============================================================================
 //  Macro helper
 // =============================================================================
@@ -1,5 +1,16 @@
-use af_sui_types::{ObjectId, StructTag, TypeTag, encode_base64_default};
+use af_sui_types::{
+    Argument,
+    Event,
+    ObjectId,
+    StructTag,
+    TransactionData,
+    TransactionDigest,
+    TransactionEffects,
+    TypeTag,
+    encode_base64_default,
+};
 use derive_more::Display;
+pub use sui_sdk_types::BalanceChange;
 
 /// An instance of a dynamic field or dynamic object.
 #[derive(Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -79,3 +90,47 @@ impl<T: af_move_type::MoveStruct> TryFrom<af_move_type::MoveInstance<T>> for Raw
         })
     }
 }
+
+/// Total balance of a coin type owned by an address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Balance {
+    pub coin_type: TypeTag,
+    /// How many coin objects make up the balance.
+    pub coin_object_count: u64,
+    pub total_balance: u128,
+}
+
+/// A transaction together with the results of its execution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutedTransaction {
+    pub digest: TransactionDigest,
+    pub data: TransactionData,
+    pub effects: TransactionEffects,
+    /// Events emitted by the transaction, in emission order.
+    pub events: Vec<Event>,
+    /// Net coin balance changes per owner and coin type.
+    pub balance_changes: Vec<BalanceChange>,
+    /// Checkpoint that included the transaction, if it has been checkpointed yet.
+    pub checkpoint: Option<u64>,
+}
+
+/// Results of dry-running a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DryRunOutput {
+    pub effects: TransactionEffects,
+    /// Events that would be emitted by the transaction.
+    pub events: Vec<Event>,
+    /// Intermediate results of each command in the transaction, in order.
+    pub results: Vec<DryRunCommandResults>,
+    /// The error that occurred during the dry run, if any.
+    pub error: Option<String>,
+}
+
+/// Intermediate results of a single command in a dry run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DryRunCommandResults {
+    /// Contents of the arguments that were mutably borrowed by the command.
+    pub mutated_references: Vec<(Argument, RawMoveValue)>,
+    /// Values returned by the command.
+    pub return_values: Vec<RawMoveValue>,
+}
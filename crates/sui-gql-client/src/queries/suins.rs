@@ -0,0 +1,165 @@
+use af_sui_types::Address as SuiAddress;
+use af_sui_types::name_service::{NameServiceResolver, SuinsName};
+
+use super::{Error, GraphQlClientExt as _};
+use crate::{GraphQlClient, GraphQlResponseExt as _, missing_data, schema};
+
+pub(super) async fn resolve_address<C: GraphQlClient>(
+    client: &C,
+    name: &SuinsName,
+) -> super::Result<Option<SuiAddress>, C> {
+    let vars = ResolveVariables {
+        domain: name.to_string(),
+    };
+    let data = client
+        .query::<ResolveQuery, _>(vars)
+        .await
+        .map_err(Error::Client)?
+        .try_into_data()?
+        .ok_or_else(|| missing_data!("No data"))?;
+    Ok(data.resolve_suins_address.map(|a| a.address))
+}
+
+pub(super) async fn default_name<C: GraphQlClient>(
+    client: &C,
+    address: SuiAddress,
+) -> super::Result<Option<SuinsName>, C> {
+    let vars = DefaultNameVariables { address };
+    let data = client
+        .query::<DefaultNameQuery, _>(vars)
+        .await
+        .map_err(Error::Client)?
+        .try_into_data()?
+        .ok_or_else(|| missing_data!("No data"))?;
+    data.address
+        .and_then(|a| a.default_suins_name)
+        .map(|name| {
+            name.parse()
+                .map_err(|e| missing_data!("Invalid name {name:?}: {e}"))
+        })
+        .transpose()
+}
+
+/// [`NameServiceResolver`] over a GraphQL client.
+///
+/// Wrap it in a [`NameServiceCache`](af_sui_types::name_service::NameServiceCache) to cache its
+/// answers.
+#[derive(Clone, Debug)]
+pub struct GraphQlNameResolver<C>(pub C);
+
+impl<C: GraphQlClient> NameServiceResolver for GraphQlNameResolver<C> {
+    type Error = Error<C::Error>;
+
+    async fn resolve_name(&self, name: &SuinsName) -> Result<Option<SuiAddress>, Self::Error> {
+        self.0.resolve_suins_address(name).await
+    }
+
+    async fn default_name(&self, address: SuiAddress) -> Result<Option<SuinsName>, Self::Error> {
+        self.0.default_suins_name(address).await
+    }
+}
+
+#[derive(cynic::QueryVariables, Clone, Debug)]
+struct ResolveVariables {
+    domain: String,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query", variables = "ResolveVariables")]
+struct ResolveQuery {
+    #[arguments(domain: $domain)]
+    resolve_suins_address: Option<Address>,
+}
+
+#[derive(cynic::QueryVariables, Clone, Debug)]
+struct DefaultNameVariables {
+    address: SuiAddress,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query", variables = "DefaultNameVariables")]
+struct DefaultNameQuery {
+    #[arguments(address: $address)]
+    address: Option<AddressWithName>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+struct Address {
+    address: SuiAddress,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Address")]
+struct AddressWithName {
+    #[arguments(format: DOT)]
+    default_suins_name: Option<String>,
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[test]
+fn gql_output() {
+    use cynic::QueryBuilder as _;
+
+    let vars = ResolveVariables {
+        domain: "example.sui".into(),
+    };
+    let operation = ResolveQuery::build(vars);
+    insta::assert_snapshot!(operation.query, @r###"
+    query ResolveQuery($domain: String!) {
+      resolveSuinsAddress(domain: $domain) {
+        address
+      }
+    }
+    "###);
+
+    let vars = DefaultNameVariables {
+        address: SuiAddress::ZERO,
+    };
+    let operation = DefaultNameQuery::build(vars);
+    insta::assert_snapshot!(operation.query, @r###"
+    query DefaultNameQuery($address: SuiAddress!) {
+      address(address: $address) {
+        defaultSuinsName(format: DOT)
+      }
+    }
+    "###);
+}
+
+#[cfg(all(test, feature = "test-support"))]
+#[tokio::test]
+async fn name_service() {
+    use std::time::Duration;
+
+    use af_sui_types::name_service::{AddressOrName, NameServiceCache};
+    use serde_json::json;
+
+    use crate::mock::{Fixture, MockServer};
+
+    let address = SuiAddress::new([1; 32]);
+    let server = MockServer::start([
+        Fixture::new(
+            "resolveSuinsAddress",
+            json!({ "resolveSuinsAddress": { "address": address } }),
+        )
+        .with_variables(json!({ "domain": "example.sui" }))
+        .once(),
+        Fixture::new(
+            "address",
+            json!({ "address": { "defaultSuinsName": "example.sui" } }),
+        )
+        .once(),
+    ])
+    .await
+    .unwrap();
+    let cache = NameServiceCache::new(
+        GraphQlNameResolver(server.client()),
+        Duration::from_secs(60),
+    );
+    let name: AddressOrName = "@example".parse().unwrap();
+    assert_eq!(cache.resolve(&name).await.unwrap(), address);
+    assert_eq!(cache.resolve(&name).await.unwrap(), address);
+    let default = cache.default_name(address).await.unwrap().unwrap();
+    assert_eq!(default.to_string(), "example.sui");
+    assert_eq!(server.requests().len(), 2);
+}
@@ -0,0 +1,424 @@
+use af_sui_types::{Address as SuiAddress, TransactionData, TransactionDigest, TransactionEffects};
+use sui_gql_schema::scalars::{self, Base64Bcs, BigInt};
+
+use super::Error;
+use super::fragments::{MoveTypeTag, PageInfoForward};
+use super::outputs::{BalanceChange, ExecutedTransaction};
+use crate::{GraphQlClient, GraphQlResponseExt as _, missing_data, schema};
+
+pub(super) async fn query<C: GraphQlClient>(
+    client: &C,
+    digest: TransactionDigest,
+) -> super::Result<ExecutedTransaction, C> {
+    let data = client
+        .query::<Query, _>(Variables {
+            digest: digest.to_string(),
+        })
+        .await
+        .map_err(Error::Client)?
+        .try_into_data()?;
+    graphql_extract::extract!(data => {
+        transaction_block?
+    });
+    complete(client, transaction_block).await
+}
+
+/// Like [`query`], but [`None`] if the server doesn't know the transaction.
+#[cfg(feature = "executor")]
+pub(crate) async fn query_opt<C: GraphQlClient>(
+    client: &C,
+    digest: TransactionDigest,
+) -> super::Result<Option<ExecutedTransaction>, C> {
+    let data = client
+        .query::<Query, _>(Variables {
+            digest: digest.to_string(),
+        })
+        .await
+        .map_err(Error::Client)?
+        .try_into_data()?;
+    graphql_extract::extract!(data => {
+        transaction_block
+    });
+    match transaction_block {
+        Some(transaction_block) => Ok(Some(complete(client, transaction_block).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Convert the fragment into the output type, fetching any events and balance changes that didn't
+/// fit in the first page of the nested connections.
+pub(super) async fn complete<C: GraphQlClient>(
+    client: &C,
+    TransactionBlock {
+        digest,
+        bcs,
+        effects,
+    }: TransactionBlock,
+) -> super::Result<ExecutedTransaction, C> {
+    let digest: TransactionDigest = digest.ok_or(missing_data!("Transaction digest"))?.0.into();
+    let data = bcs
+        .ok_or(missing_data!("BCS for transaction {digest}"))?
+        .into_inner();
+    let TransactionBlockEffects {
+        bcs,
+        checkpoint,
+        events:
+            EventConnection {
+                page_info: events_page,
+                nodes: events,
+            },
+        balance_changes:
+            BalanceChangeConnection {
+                page_info: balance_changes_page,
+                nodes: balance_changes,
+            },
+    } = effects.ok_or(missing_data!("Effects for transaction {digest}"))?;
+
+    let mut events: Vec<_> = events.into_iter().map(|e| e.bcs.into_inner()).collect();
+    let mut cursor = events_page
+        .has_next_page
+        .then_some(events_page.end_cursor)
+        .flatten();
+    while let Some(after) = cursor {
+        let (page_info, nodes) = events_page_after(client, &digest, after).await?;
+        events.extend(nodes.into_iter().map(|e| e.bcs.into_inner()));
+        cursor = page_info
+            .has_next_page
+            .then_some(page_info.end_cursor)
+            .flatten();
+    }
+
+    let mut balance_changes = balance_changes
+        .into_iter()
+        .map(BalanceChange::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut cursor = balance_changes_page
+        .has_next_page
+        .then_some(balance_changes_page.end_cursor)
+        .flatten();
+    while let Some(after) = cursor {
+        let (page_info, nodes) = balance_changes_page_after(client, &digest, after).await?;
+        for node in nodes {
+            balance_changes.push(node.try_into()?);
+        }
+        cursor = page_info
+            .has_next_page
+            .then_some(page_info.end_cursor)
+            .flatten();
+    }
+
+    Ok(ExecutedTransaction {
+        digest,
+        data,
+        effects: bcs.into_inner(),
+        events,
+        balance_changes,
+        checkpoint: checkpoint.map(|c| c.sequence_number),
+    })
+}
+
+async fn events_page_after<C: GraphQlClient>(
+    client: &C,
+    digest: &TransactionDigest,
+    after: String,
+) -> super::Result<(PageInfoForward, Vec<Event>), C> {
+    let data = client
+        .query::<EventsQuery, _>(NestedPageVariables {
+            digest: digest.to_string(),
+            after: Some(after),
+        })
+        .await
+        .map_err(Error::Client)?
+        .try_into_data()?;
+    graphql_extract::extract!(data => {
+        transaction_block? {
+            effects? {
+                events
+            }
+        }
+    });
+    let EventConnection { page_info, nodes } = events;
+    Ok((page_info, nodes))
+}
+
+async fn balance_changes_page_after<C: GraphQlClient>(
+    client: &C,
+    digest: &TransactionDigest,
+    after: String,
+) -> super::Result<(PageInfoForward, Vec<BalanceChangeGql>), C> {
+    let data = client
+        .query::<BalanceChangesQuery, _>(NestedPageVariables {
+            digest: digest.to_string(),
+            after: Some(after),
+        })
+        .await
+        .map_err(Error::Client)?
+        .try_into_data()?;
+    graphql_extract::extract!(data => {
+        transaction_block? {
+            effects? {
+                balance_changes
+            }
+        }
+    });
+    let BalanceChangeConnection { page_info, nodes } = balance_changes;
+    Ok((page_info, nodes))
+}
+
+impl TryFrom<BalanceChangeGql> for BalanceChange {
+    type Error = &'static str;
+
+    fn try_from(
+        BalanceChangeGql {
+            owner,
+            coin_type,
+            amount,
+        }: BalanceChangeGql,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            address: owner.ok_or("Balance change owner")?.address,
+            coin_type: coin_type.ok_or("Balance change coin type")?.into(),
+            amount: amount.ok_or("Balance change amount")?.into_inner(),
+        })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn gql_output() {
+    use cynic::QueryBuilder as _;
+
+    let vars = Variables {
+        digest: String::new(),
+    };
+    let operation = Query::build(vars);
+    insta::assert_snapshot!(operation.query, @r###"
+    query Query($digest: String!) {
+      transactionBlock(digest: $digest) {
+        digest
+        bcs
+        effects {
+          bcs
+          checkpoint {
+            sequenceNumber
+          }
+          events {
+            pageInfo {
+              hasNextPage
+              endCursor
+            }
+            nodes {
+              bcs
+            }
+          }
+          balanceChanges {
+            pageInfo {
+              hasNextPage
+              endCursor
+            }
+            nodes {
+              owner {
+                address
+              }
+              coinType {
+                repr
+              }
+              amount
+            }
+          }
+        }
+      }
+    }
+    "###);
+}
+
+#[cfg(test)]
+#[test]
+fn events_gql_output() {
+    use cynic::QueryBuilder as _;
+
+    let vars = NestedPageVariables {
+        digest: String::new(),
+        after: None,
+    };
+    let operation = EventsQuery::build(vars);
+    insta::assert_snapshot!(operation.query, @r###"
+    query EventsQuery($digest: String!, $after: String) {
+      transactionBlock(digest: $digest) {
+        effects {
+          events(after: $after) {
+            pageInfo {
+              hasNextPage
+              endCursor
+            }
+            nodes {
+              bcs
+            }
+          }
+        }
+      }
+    }
+    "###);
+}
+
+#[cfg(test)]
+#[test]
+fn balance_changes_gql_output() {
+    use cynic::QueryBuilder as _;
+
+    let vars = NestedPageVariables {
+        digest: String::new(),
+        after: None,
+    };
+    let operation = BalanceChangesQuery::build(vars);
+    insta::assert_snapshot!(operation.query, @r###"
+    query BalanceChangesQuery($digest: String!, $after: String) {
+      transactionBlock(digest: $digest) {
+        effects {
+          balanceChanges(after: $after) {
+            pageInfo {
+              hasNextPage
+              endCursor
+            }
+            nodes {
+              owner {
+                address
+              }
+              coinType {
+                repr
+              }
+              amount
+            }
+          }
+        }
+      }
+    }
+    "###);
+}
+
+// =============================================================================
+//  Initial query
+// =============================================================================
+
+#[derive(cynic::QueryVariables, Clone, Debug)]
+struct Variables {
+    digest: String,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(variables = "Variables")]
+struct Query {
+    #[arguments(digest: $digest)]
+    transaction_block: Option<TransactionBlock>,
+}
+
+// =============================================================================
+//  Nested pages
+// =============================================================================
+
+#[derive(cynic::QueryVariables, Clone, Debug)]
+struct NestedPageVariables {
+    digest: String,
+    after: Option<String>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(graphql_type = "Query", variables = "NestedPageVariables")]
+struct EventsQuery {
+    #[arguments(digest: $digest)]
+    transaction_block: Option<EventsTransactionBlock>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(graphql_type = "TransactionBlock", variables = "NestedPageVariables")]
+struct EventsTransactionBlock {
+    effects: Option<EventsEffects>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(
+    graphql_type = "TransactionBlockEffects",
+    variables = "NestedPageVariables"
+)]
+struct EventsEffects {
+    #[arguments(after: $after)]
+    events: EventConnection,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(graphql_type = "Query", variables = "NestedPageVariables")]
+struct BalanceChangesQuery {
+    #[arguments(digest: $digest)]
+    transaction_block: Option<BalanceChangesTransactionBlock>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(graphql_type = "TransactionBlock", variables = "NestedPageVariables")]
+struct BalanceChangesTransactionBlock {
+    effects: Option<BalanceChangesEffects>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(
+    graphql_type = "TransactionBlockEffects",
+    variables = "NestedPageVariables"
+)]
+struct BalanceChangesEffects {
+    #[arguments(after: $after)]
+    balance_changes: BalanceChangeConnection,
+}
+
+// =============================================================================
+//  Inner query fragments
+// =============================================================================
+
+/// Transaction fragment shared with the `transaction_blocks` query.
+#[derive(cynic::QueryFragment, Clone, Debug)]
+pub(super) struct TransactionBlock {
+    digest: Option<scalars::Digest>,
+    bcs: Option<Base64Bcs<TransactionData>>,
+    effects: Option<TransactionBlockEffects>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct TransactionBlockEffects {
+    bcs: Base64Bcs<TransactionEffects>,
+    checkpoint: Option<Checkpoint>,
+    events: EventConnection,
+    balance_changes: BalanceChangeConnection,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct Checkpoint {
+    sequence_number: af_sui_types::Version,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct EventConnection {
+    page_info: PageInfoForward,
+    nodes: Vec<Event>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct Event {
+    bcs: Base64Bcs<af_sui_types::Event>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct BalanceChangeConnection {
+    page_info: PageInfoForward,
+    nodes: Vec<BalanceChangeGql>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(graphql_type = "BalanceChange")]
+struct BalanceChangeGql {
+    owner: Option<Owner>,
+    coin_type: Option<MoveTypeTag>,
+    amount: Option<BigInt<i128>>,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct Owner {
+    address: SuiAddress,
+}
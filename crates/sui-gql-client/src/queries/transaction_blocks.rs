@@ -0,0 +1,133 @@
+use futures::Stream;
+
+use super::fragments::{PageInfo, PageInfoForward, TransactionBlockFilter};
+use super::outputs::ExecutedTransaction;
+use super::stream;
+use super::transaction_block::{TransactionBlock, complete};
+use crate::queries::Error;
+use crate::{GraphQlClient, GraphQlResponseExt as _, schema};
+
+pub(super) fn query<C: GraphQlClient>(
+    client: &C,
+    filter: TransactionBlockFilter,
+    page_size: Option<u32>,
+) -> impl Stream<Item = super::Result<ExecutedTransaction, C>> + '_ {
+    let vars = Variables {
+        filter: Some(filter),
+        first: page_size.map(|v| v.try_into().unwrap_or(i32::MAX)),
+        after: None,
+    };
+    stream::forward(client, vars, request)
+}
+
+async fn request<C: GraphQlClient>(
+    client: &C,
+    vars: Variables,
+) -> super::Result<
+    stream::Page<impl Iterator<Item = super::Result<ExecutedTransaction, C>> + 'static + use<C>>,
+    C,
+> {
+    let data = client
+        .query::<Query, _>(vars)
+        .await
+        .map_err(Error::Client)?
+        .try_into_data()?;
+
+    graphql_extract::extract!(data => {
+        transaction_blocks {
+            page_info
+            nodes
+        }
+    });
+
+    // Completing a transaction may require further requests for its nested connections, so the
+    // whole page is resolved before being yielded
+    let mut transactions = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        transactions.push(complete(client, node).await);
+    }
+    Ok(stream::Page::new(page_info, transactions.into_iter()))
+}
+
+#[derive(cynic::QueryVariables, Clone, Debug)]
+struct Variables {
+    filter: Option<TransactionBlockFilter>,
+    after: Option<String>,
+    first: Option<i32>,
+}
+
+impl stream::UpdatePageInfo for Variables {
+    fn update_page_info(&mut self, info: &PageInfo) {
+        self.after.clone_from(&info.end_cursor)
+    }
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+#[cynic(variables = "Variables")]
+struct Query {
+    #[arguments(filter: $filter, first: $first, after: $after)]
+    transaction_blocks: TransactionBlockConnection,
+}
+
+#[derive(cynic::QueryFragment, Clone, Debug)]
+struct TransactionBlockConnection {
+    page_info: PageInfoForward,
+    nodes: Vec<TransactionBlock>,
+}
+
+#[cfg(test)]
+#[test]
+fn gql_output() {
+    use cynic::QueryBuilder as _;
+
+    let vars = Variables {
+        filter: None,
+        after: None,
+        first: None,
+    };
+    let operation = Query::build(vars);
+    insta::assert_snapshot!(operation.query, @r###"
+    query Query($filter: TransactionBlockFilter, $after: String, $first: Int) {
+      transactionBlocks(filter: $filter, first: $first, after: $after) {
+        pageInfo {
+          hasNextPage
+          endCursor
+        }
+        nodes {
+          digest
+          bcs
+          effects {
+            bcs
+            checkpoint {
+              sequenceNumber
+            }
+            events {
+              pageInfo {
+                hasNextPage
+                endCursor
+              }
+              nodes {
+                bcs
+              }
+            }
+            balanceChanges {
+              pageInfo {
+                hasNextPage
+                endCursor
+              }
+              nodes {
+                owner {
+                  address
+                }
+                coinType {
+                  repr
+                }
+                amount
+              }
+            }
+          }
+        }
+      }
+    }
+    "###);
+}
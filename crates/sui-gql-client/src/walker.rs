@@ -0,0 +1,566 @@
+//! Typed traversal of the dynamic-field-backed containers in the `sui` framework package.
+//!
+//! The entry point is the [`Container`] trait, implemented for [`Table`], [`ObjectTable`],
+//! [`LinkedTable`], [`TableVec`] and [`VecMap`], as well as for [`Bag`] and [`ObjectBag`] through
+//! the [`Homogeneous`] view. Its [`entries`](Container::entries) stream the typed `(key, value)`
+//! pairs in the container.
+//!
+//! Containers nested inside other containers' values are walked with [`Nested`], which is itself a
+//! [`Container`] and can therefore be nested again, e.g., `Nested<Nested<Table<K1, Table<K2,
+//! Table<K3, V>>>>>` streams `((K1, K2), K3) -> V`. Inner containers are walked concurrently, up to
+//! [`WalkOptions::concurrency`] at a time.
+//!
+//! Protocol crates can implement [`Container`] for their own dynamic field collections on top of
+//! [`fields`].
+use std::marker::PhantomData;
+
+use af_move_type::{FromRawTypeError, MoveInstance, MoveType};
+use af_sui_types::{ObjectId, TypeTag};
+use futures::future::{self, Either};
+use futures::{Stream, StreamExt as _, TryStreamExt as _, stream};
+use sui_framework_sdk::bag::Bag;
+use sui_framework_sdk::linked_table::{LinkedTable, Node};
+use sui_framework_sdk::object_bag::ObjectBag;
+use sui_framework_sdk::object_table::ObjectTable;
+use sui_framework_sdk::table::Table;
+use sui_framework_sdk::table_vec::TableVec;
+use sui_framework_sdk::vec_map::VecMap;
+
+use crate::GraphQlClient;
+use crate::queries::outputs::{DynamicField, RawMoveValue};
+use crate::queries::{Error as QueryError, GraphQlClientExt as _};
+
+/// Result type of the walks.
+pub type Result<T, C> = std::result::Result<T, Error<<C as GraphQlClient>::Error>>;
+
+/// Number of inner containers walked at the same time by default.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Error while walking a container.
+#[derive(thiserror::Error, Debug)]
+pub enum Error<C: std::error::Error> {
+    #[error(transparent)]
+    Query(#[from] QueryError<C>),
+    #[error("Dynamic field of {parent} doesn't match the container's types: {source}")]
+    FromRawType {
+        parent: ObjectId,
+        source: FromRawTypeError,
+    },
+}
+
+/// Parameters shared by all the levels of a walk.
+#[derive(Clone, Copy, Debug)]
+pub struct WalkOptions {
+    /// Version of the root object bounding the versions of the dynamic fields, i.e., the walk
+    /// returns the state of the tree as of this version of the root. Latest if `None`.
+    pub root_version: Option<u64>,
+    /// Page size for the dynamic fields queries. The server's default if `None`.
+    pub page_size: Option<i32>,
+    /// Maximum number of inner containers walked at the same time by [`Nested`].
+    pub concurrency: usize,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            root_version: None,
+            page_size: None,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+/// A collection of key-value pairs that can be listed with a [`GraphQlClient`].
+pub trait Container: Sized {
+    type Key: Clone + Send + 'static;
+    type Value: Send + 'static;
+
+    /// **Streamed** key-value pairs of this container.
+    ///
+    /// For containers backed by dynamic fields the order of the pairs is the server's, not the
+    /// insertion order.
+    fn entries<C: GraphQlClient>(
+        self,
+        client: &C,
+        options: WalkOptions,
+    ) -> impl Stream<Item = Result<(Self::Key, Self::Value), C>> + Send + '_;
+}
+
+/// Walks the containers in the values of `T`, yielding the pairs of keys leading to each inner
+/// value.
+#[derive(Clone, Debug)]
+pub struct Nested<T>(pub T);
+
+impl<T> Container for Nested<T>
+where
+    T: Container,
+    T::Value: Container,
+{
+    type Key = (T::Key, <T::Value as Container>::Key);
+    type Value = <T::Value as Container>::Value;
+
+    fn entries<C: GraphQlClient>(
+        self,
+        client: &C,
+        options: WalkOptions,
+    ) -> impl Stream<Item = Result<(Self::Key, Self::Value), C>> + Send + '_ {
+        self.0
+            .entries(client, options)
+            .map(move |outer| match outer {
+                Ok((key, inner)) => Either::Left(
+                    inner
+                        .entries(client, options)
+                        .map_ok(move |(inner_key, value)| ((key.clone(), inner_key), value))
+                        .boxed(),
+                ),
+                Err(err) => Either::Right(stream::once(future::ready(Err(err)))),
+            })
+            .flatten_unordered(options.concurrency.max(1))
+    }
+}
+
+impl<K: MoveType<TypeTag: Send> + Send + 'static, V: MoveType<TypeTag: Send> + Send + 'static>
+    Container for Table<K, V>
+{
+    type Key = K;
+    type Value = V;
+
+    fn entries<C: GraphQlClient>(
+        self,
+        client: &C,
+        options: WalkOptions,
+    ) -> impl Stream<Item = Result<(K, V), C>> + Send + '_ {
+        fields(client, self.id.into(), options, false).map_ok(|(k, v)| (k.value, v.value))
+    }
+}
+
+impl<K: MoveType<TypeTag: Send> + Send + 'static, V: MoveType<TypeTag: Send> + Send + 'static>
+    Container for ObjectTable<K, V>
+{
+    type Key = K;
+    type Value = V;
+
+    fn entries<C: GraphQlClient>(
+        self,
+        client: &C,
+        options: WalkOptions,
+    ) -> impl Stream<Item = Result<(K, V), C>> + Send + '_ {
+        fields(client, self.id.into(), options, false).map_ok(|(k, v)| (k.value, v.value))
+    }
+}
+
+impl<K: MoveType<TypeTag: Send> + Send + 'static, V: MoveType<TypeTag: Send> + Send + 'static>
+    Container for LinkedTable<K, V>
+{
+    type Key = K;
+    type Value = V;
+
+    fn entries<C: GraphQlClient>(
+        self,
+        client: &C,
+        options: WalkOptions,
+    ) -> impl Stream<Item = Result<(K, V), C>> + Send + '_ {
+        fields::<_, K, Node<K, V>>(client, self.id.into(), options, false)
+            .map_ok(|(k, node)| (k.value, node.value.value))
+    }
+}
+
+impl<V: MoveType<TypeTag: Send> + Send + 'static> Container for TableVec<V> {
+    type Key = u64;
+    type Value = V;
+
+    fn entries<C: GraphQlClient>(
+        self,
+        client: &C,
+        options: WalkOptions,
+    ) -> impl Stream<Item = Result<(u64, V), C>> + Send + '_ {
+        self.contents.entries(client, options)
+    }
+}
+
+impl<K: MoveType<TypeTag: Send> + Send + 'static, V: MoveType<TypeTag: Send> + Send + 'static>
+    Container for VecMap<K, V>
+{
+    type Key = K;
+    type Value = V;
+
+    /// The pairs are stored inline, so no requests are made and they're yielded in insertion
+    /// order.
+    fn entries<C: GraphQlClient>(
+        self,
+        _: &C,
+        _: WalkOptions,
+    ) -> impl Stream<Item = Result<(K, V), C>> + Send + '_ {
+        stream::iter(
+            Vec::from(self.contents)
+                .into_iter()
+                .map(|entry| Ok((entry.key, entry.value))),
+        )
+    }
+}
+
+/// View of a heterogeneous [`Bag`] or [`ObjectBag`] restricted to its entries with keys of type
+/// `K` and values of type `V`.
+///
+/// Entries of other types are skipped.
+#[derive(Clone, Debug)]
+pub struct Homogeneous<B, K, V> {
+    pub bag: B,
+    _types: PhantomData<fn() -> (K, V)>,
+}
+
+impl<B, K, V> Homogeneous<B, K, V> {
+    pub const fn new(bag: B) -> Self {
+        Self {
+            bag,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<K: MoveType<TypeTag: Send> + Send + 'static, V: MoveType<TypeTag: Send> + Send + 'static>
+    Container for Homogeneous<Bag, K, V>
+{
+    type Key = K;
+    type Value = V;
+
+    fn entries<C: GraphQlClient>(
+        self,
+        client: &C,
+        options: WalkOptions,
+    ) -> impl Stream<Item = Result<(K, V), C>> + Send + '_ {
+        fields(client, self.bag.id.into(), options, true).map_ok(|(k, v)| (k.value, v.value))
+    }
+}
+
+impl<K: MoveType<TypeTag: Send> + Send + 'static, V: MoveType<TypeTag: Send> + Send + 'static>
+    Container for Homogeneous<ObjectBag, K, V>
+{
+    type Key = K;
+    type Value = V;
+
+    fn entries<C: GraphQlClient>(
+        self,
+        client: &C,
+        options: WalkOptions,
+    ) -> impl Stream<Item = Result<(K, V), C>> + Send + '_ {
+        fields(client, self.bag.id.into(), options, true).map_ok(|(k, v)| (k.value, v.value))
+    }
+}
+
+/// **Streamed** dynamic fields (or dynamic object fields) of `parent`, typed as `K -> V`.
+///
+/// With `skip_mismatched`, fields whose name or value aren't of types `K` and `V` are silently
+/// skipped. Otherwise, the stream yields an error for them.
+pub fn fields<C, K, V>(
+    client: &C,
+    parent: ObjectId,
+    options: WalkOptions,
+    skip_mismatched: bool,
+) -> impl Stream<Item = Result<(MoveInstance<K>, MoveInstance<V>), C>> + Send + '_
+where
+    C: GraphQlClient,
+    K: MoveType<TypeTag: Send> + Send + 'static,
+    V: MoveType<TypeTag: Send> + Send + 'static,
+{
+    async_stream::try_stream! {
+        let dfs = client
+            .owner_df_contents_stream(parent.into(), options.root_version, options.page_size)
+            .await;
+        for await df in dfs {
+            let (name, value) = df?;
+            match typed(name, value) {
+                Ok(pair) => yield pair,
+                Err(_) if skip_mismatched => continue,
+                Err(source) => Err(Error::FromRawType { parent, source })?,
+            }
+        }
+    }
+}
+
+fn typed<K: MoveType, V: MoveType>(
+    name: RawMoveValue,
+    value: DynamicField,
+) -> std::result::Result<(MoveInstance<K>, MoveInstance<V>), FromRawTypeError> {
+    let value = match value {
+        DynamicField::Field(raw) => raw,
+        DynamicField::Object(_, raw) => RawMoveValue {
+            type_: TypeTag::Struct(Box::new(raw.type_)),
+            bcs: raw.bcs,
+        },
+    };
+    Ok((name.try_into()?, value.try_into()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_field() {
+        let name: RawMoveValue = MoveInstance::from(7_u64).try_into().unwrap();
+        let value = DynamicField::Field(MoveInstance::from(true).try_into().unwrap());
+        let (k, v) = typed::<u64, bool>(name.clone(), value.clone()).unwrap();
+        assert_eq!((k.value, v.value), (7, true));
+
+        // Keys of the wrong type are rejected
+        assert!(typed::<u8, bool>(name.clone(), value.clone()).is_err());
+        // Values of the wrong type are rejected
+        assert!(typed::<u64, u64>(name, value).is_err());
+    }
+
+    #[test]
+    fn typed_rejects_struct_tag_mismatch() {
+        let name: RawMoveValue = MoveInstance::from(7_u64).try_into().unwrap();
+        let value = DynamicField::Field(RawMoveValue {
+            type_: TypeTag::U64,
+            bcs: bcs::to_bytes(&1_u64).unwrap(),
+        });
+        assert!(typed::<u64, Table<u64, u64>>(name, value).is_err());
+    }
+
+    #[cfg(feature = "test-support")]
+    mod walks {
+        use af_sui_types::encode_base64_default;
+        use serde_json::{Value as Json, json};
+        use sui_framework_sdk::object::UID;
+
+        use super::*;
+        use crate::mock::{Fixture, MockServer};
+        use crate::reqwest::ReqwestClient;
+
+        fn move_value<T: MoveType>(instance: MoveInstance<T>) -> Json {
+            let RawMoveValue { type_, bcs } = instance.try_into().unwrap();
+            json!({
+                "__typename": "MoveValue",
+                "type": { "repr": type_.to_string() },
+                "bcs": encode_base64_default(bcs),
+            })
+        }
+
+        fn page(
+            parent: ObjectId,
+            after: Option<&str>,
+            next: Option<&str>,
+            nodes: Vec<Json>,
+        ) -> Fixture {
+            Fixture::new(
+                "owner",
+                json!({ "owner": { "dynamicFields": {
+                    "nodes": nodes,
+                    "pageInfo": {
+                        "hasNextPage": next.is_some(),
+                        "endCursor": next,
+                        "hasPreviousPage": false,
+                        "startCursor": null,
+                    },
+                }}}),
+            )
+            .with_variables(json!({ "address": parent, "after": after }))
+        }
+
+        fn field<K: MoveType, V: MoveType>(name: MoveInstance<K>, value: MoveInstance<V>) -> Json {
+            json!({ "name": move_value(name), "value": move_value(value) })
+        }
+
+        /// BCS of a table with 2 entries.
+        fn table_bcs(id: ObjectId) -> Vec<u8> {
+            bcs::to_bytes(&(UID::new(id), 2_u64)).unwrap()
+        }
+
+        fn table<K: MoveType, V: MoveType>(id: ObjectId) -> Table<K, V> {
+            bcs::from_bytes(&table_bcs(id)).unwrap()
+        }
+
+        /// Field with a `Table<u64, u64>` value.
+        fn table_field(key: u64, id: ObjectId) -> Json {
+            json!({
+                "name": move_value(key.into()),
+                "value": {
+                    "__typename": "MoveValue",
+                    "type": { "repr": "0x2::table::Table<u64, u64>" },
+                    "bcs": encode_base64_default(table_bcs(id)),
+                },
+            })
+        }
+
+        async fn collect<T: Container>(
+            client: &ReqwestClient,
+            container: T,
+            options: WalkOptions,
+        ) -> Vec<(T::Key, T::Value)> {
+            container
+                .entries(client, options)
+                .try_collect()
+                .await
+                .unwrap()
+        }
+
+        #[tokio::test]
+        async fn table_pages() {
+            let id = ObjectId::new([1; 32]);
+            let server = MockServer::start([
+                page(
+                    id,
+                    None,
+                    Some("a"),
+                    vec![field(1_u64.into(), 10_u64.into())],
+                ),
+                page(
+                    id,
+                    Some("a"),
+                    None,
+                    vec![field(2_u64.into(), 20_u64.into())],
+                ),
+            ])
+            .await
+            .unwrap();
+            let client = server.client();
+            let mut entries = collect(&client, table::<u64, u64>(id), WalkOptions::default()).await;
+            entries.sort();
+            assert_eq!(entries, [(1, 10), (2, 20)]);
+            assert_eq!(server.requests().len(), 2);
+        }
+
+        #[tokio::test]
+        async fn table_type_mismatch() {
+            let id = ObjectId::new([1; 32]);
+            let server =
+                MockServer::start([page(id, None, None, vec![field(1_u64.into(), true.into())])])
+                    .await
+                    .unwrap();
+            let err = table::<u64, u64>(id)
+                .entries(&server.client(), WalkOptions::default())
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap_err();
+            assert!(matches!(err, Error::FromRawType { parent, .. } if parent == id));
+        }
+
+        #[tokio::test]
+        async fn homogeneous_bag_skips_other_types() {
+            let id = ObjectId::new([1; 32]);
+            let server = MockServer::start([page(
+                id,
+                None,
+                None,
+                vec![
+                    field(1_u64.into(), 10_u64.into()),
+                    field(2_u8.into(), 20_u64.into()),
+                    field(3_u64.into(), false.into()),
+                ],
+            )])
+            .await
+            .unwrap();
+            let bag = Bag {
+                id: UID::new(id),
+                size: 3,
+            };
+            let entries = collect(
+                &server.client(),
+                Homogeneous::<_, u64, u64>::new(bag),
+                WalkOptions::default(),
+            )
+            .await;
+            assert_eq!(entries, [(1, 10)]);
+        }
+
+        /// Outer table with two inner tables of two entries each, the first one paginated.
+        async fn nested_server(outer: ObjectId, inner: [ObjectId; 2]) -> MockServer {
+            MockServer::start([
+                page(
+                    outer,
+                    None,
+                    None,
+                    vec![table_field(1, inner[0]), table_field(2, inner[1])],
+                ),
+                page(
+                    inner[0],
+                    None,
+                    Some("a"),
+                    vec![field(1_u64.into(), 11_u64.into())],
+                ),
+                page(
+                    inner[0],
+                    Some("a"),
+                    None,
+                    vec![field(2_u64.into(), 12_u64.into())],
+                ),
+                page(
+                    inner[1],
+                    None,
+                    None,
+                    vec![
+                        field(1_u64.into(), 21_u64.into()),
+                        field(2_u64.into(), 22_u64.into()),
+                    ],
+                ),
+            ])
+            .await
+            .unwrap()
+        }
+
+        #[tokio::test]
+        async fn nested_tables() {
+            let outer = ObjectId::new([1; 32]);
+            let inner = [ObjectId::new([2; 32]), ObjectId::new([3; 32])];
+            let server = nested_server(outer, inner).await;
+            let client = server.client();
+            let walk: Nested<Table<u64, Table<u64, u64>>> = Nested(table(outer));
+            let mut entries = collect(&client, walk, WalkOptions::default()).await;
+            entries.sort();
+            assert_eq!(
+                entries,
+                [((1, 1), 11), ((1, 2), 12), ((2, 1), 21), ((2, 2), 22)]
+            );
+            assert_eq!(server.requests().len(), 4);
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn nested_walk_in_spawned_task() {
+            let outer = ObjectId::new([1; 32]);
+            let inner = [ObjectId::new([2; 32]), ObjectId::new([3; 32])];
+            let server = nested_server(outer, inner).await;
+            let client = server.client();
+            let walk: Nested<Table<u64, Table<u64, u64>>> = Nested(table(outer));
+            let entries =
+                tokio::spawn(async move { collect(&client, walk, WalkOptions::default()).await })
+                    .await
+                    .unwrap();
+            assert_eq!(entries.len(), 4);
+        }
+
+        #[tokio::test]
+        async fn nested_walks_one_inner_container_at_a_time() {
+            let outer = ObjectId::new([1; 32]);
+            let inner = [ObjectId::new([2; 32]), ObjectId::new([3; 32])];
+            let server = nested_server(outer, inner).await;
+            let client = server.client();
+            let walk: Nested<Table<u64, Table<u64, u64>>> = Nested(table(outer));
+            let options = WalkOptions {
+                concurrency: 1,
+                ..Default::default()
+            };
+            let entries = collect(&client, walk, options).await;
+            assert_eq!(
+                entries,
+                [((1, 1), 11), ((1, 2), 12), ((2, 1), 21), ((2, 2), 22)]
+            );
+            // Both pages of the first inner table are requested before the second table
+            let parents: Vec<_> = server
+                .requests()
+                .into_iter()
+                .map(|r| r.variables["address"].clone())
+                .collect();
+            assert_eq!(
+                parents,
+                [
+                    json!(outer),
+                    json!(inner[0]),
+                    json!(inner[0]),
+                    json!(inner[1])
+                ]
+            );
+        }
+    }
+}
@@ -10,8 +10,7 @@
 #[cfg(feature = "scalars")]
 pub mod scalars;
 
-#[cfg(feature = "build")]
-#[doc(hidden)]
+#[cfg(feature = "sdl")]
 /// The Sui schema SDL file as a string.
 pub const SCHEMA_SUI: &str = include_str!("../schemas/sui.graphql");
 
@@ -0,0 +1,1020 @@
+//! Cache of normalized Move modules for checking Move calls before submitting them.
+//!
+//! A [`MoveAbiCache`] holds the [`SuiMoveNormalizedModule`]s of whole packages and, optionally,
+//! their upgrade lineage. [`MoveAbiCache::check_ptb`] uses them to catch malformed
+//! [`MoveCall`]s, e.g., from [`ptb!`], without a round trip to a full node:
+//! - the function exists and is callable from a PTB (public or entry);
+//! - the number of type arguments and their abilities;
+//! - the number of arguments, and that inputs are of the kind (pure, object or receiving) their
+//!   parameters take.
+//!
+//! With the `ptbuilder` feature, the cache is also a source of function signatures for
+//! `af_ptbuilder::check`, which checks the argument types and how results are used. The client's
+//! `check_move_calls` fills the cache and runs both checks.
+//!
+//! [`ptb!`]: https://docs.rs/af-ptbuilder/latest/af_ptbuilder/macro.ptb.html
+use std::collections::{BTreeMap, HashMap};
+
+use af_sui_types::{
+    Address,
+    Argument,
+    Command,
+    Identifier,
+    MOVE_STDLIB_ADDRESS,
+    MoveCall,
+    ObjectId,
+    ProgrammableTransaction,
+    SUI_FRAMEWORK_ADDRESS,
+    StructTag,
+    TypeTag,
+};
+use sui_sdk_types::Input;
+
+use crate::msgs::{
+    SuiMoveAbility,
+    SuiMoveAbilitySet,
+    SuiMoveNormalizedFunction,
+    SuiMoveNormalizedModule,
+    SuiMoveNormalizedType,
+    SuiMoveStructTypeParameter,
+    SuiMoveVisibility,
+};
+
+/// Normalized modules by package, as returned by `sui_getNormalizedMoveModulesByPackage`.
+pub type PackageAbi = BTreeMap<String, SuiMoveNormalizedModule>;
+
+/// Normalized Move modules by package id.
+#[derive(Clone, Debug, Default)]
+pub struct MoveAbiCache {
+    packages: HashMap<ObjectId, PackageAbi>,
+    /// Original package id by package id, for every package with a known lineage.
+    originals: HashMap<ObjectId, ObjectId>,
+    /// Package ids by version, by original package id.
+    lineages: HashMap<ObjectId, BTreeMap<u64, ObjectId>>,
+}
+
+impl MoveAbiCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert_package(&mut self, package: ObjectId, modules: PackageAbi) {
+        self.packages.insert(package, modules);
+    }
+
+    pub fn package(&self, package: &ObjectId) -> Option<&PackageAbi> {
+        self.packages.get(package)
+    }
+
+    pub fn contains_package(&self, package: &ObjectId) -> bool {
+        self.packages.contains_key(package)
+    }
+
+    pub fn function(
+        &self,
+        package: &ObjectId,
+        module: &str,
+        function: &str,
+    ) -> Option<&SuiMoveNormalizedFunction> {
+        self.packages
+            .get(package)?
+            .get(module)?
+            .exposed_functions
+            .get(function)
+    }
+
+    /// Record the versions of an upgraded package.
+    ///
+    /// Takes `(package id, version)` pairs for every version of the package, such as those from
+    /// `sui-gql-client`'s `packages_from_original`. The lowest version is taken as the original.
+    pub fn insert_lineage(&mut self, versions: impl IntoIterator<Item = (ObjectId, u64)>) {
+        let versions: BTreeMap<_, _> = versions.into_iter().map(|(id, v)| (v, id)).collect();
+        let Some((_, &original)) = versions.first_key_value() else {
+            return;
+        };
+        for id in versions.values() {
+            self.originals.insert(*id, original);
+        }
+        self.lineages.entry(original).or_default().extend(versions);
+    }
+
+    /// The original id of `package`, if its lineage is known.
+    pub fn original(&self, package: &ObjectId) -> Option<ObjectId> {
+        self.originals.get(package).copied()
+    }
+
+    /// The id of the latest known version of `package`, if its lineage is known.
+    pub fn latest(&self, package: &ObjectId) -> Option<ObjectId> {
+        let original = self.originals.get(package)?;
+        self.lineages
+            .get(original)?
+            .last_key_value()
+            .map(|(_, id)| *id)
+    }
+
+    /// Packages that [`Self::check_ptb`] needs but aren't cached.
+    ///
+    /// Those are the packages of every Move call and of the types in their type arguments. A type
+    /// argument's package counts as cached if any version in its lineage is.
+    pub fn missing_packages(&self, ptb: &ProgrammableTransaction) -> Vec<ObjectId> {
+        let mut missing = vec![];
+        let mut push = |id: ObjectId, in_lineage: bool| {
+            let cached = if in_lineage {
+                self.lineage_contains(&id)
+            } else {
+                self.contains_package(&id)
+            };
+            if !cached && !missing.contains(&id) {
+                missing.push(id);
+            }
+        };
+        for command in &ptb.commands {
+            let Command::MoveCall(call) = command else {
+                continue;
+            };
+            push(call.package, false);
+            for type_ in &call.type_arguments {
+                for_each_struct(type_, &mut |tag| push(tag.address.into(), true));
+            }
+        }
+        missing
+    }
+
+    /// Check that every Move call in `ptb` is to a callable function, with valid type arguments
+    /// and the right number of arguments, and that inputs are passed to parameters of their kind.
+    ///
+    /// Argument types, and the results of earlier commands, are checked by `af_ptbuilder::check`.
+    pub fn check_ptb(&self, ptb: &ProgrammableTransaction) -> Result<(), MoveCallError> {
+        for (index, command) in ptb.commands.iter().enumerate() {
+            if let Command::MoveCall(call) = command {
+                self.check_move_call(call, &ptb.inputs)
+                    .map_err(|kind| MoveCallError {
+                        command: index,
+                        package: call.package,
+                        module: call.module.clone(),
+                        function: call.function.clone(),
+                        kind,
+                    })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_move_call(&self, call: &MoveCall, inputs: &[Input]) -> Result<(), MoveCallErrorKind> {
+        let module = self
+            .packages
+            .get(&call.package)
+            .ok_or(MoveCallErrorKind::PackageNotLoaded)?
+            .get(call.module.as_str())
+            .ok_or(MoveCallErrorKind::ModuleNotFound)?;
+        let function = module
+            .exposed_functions
+            .get(call.function.as_str())
+            .ok_or(MoveCallErrorKind::FunctionNotFound)?;
+        if !function.is_entry && function.visibility != SuiMoveVisibility::Public {
+            return Err(MoveCallErrorKind::NotCallable);
+        }
+
+        if function.type_parameters.len() != call.type_arguments.len() {
+            return Err(MoveCallErrorKind::TypeArgumentCount {
+                expected: function.type_parameters.len(),
+                found: call.type_arguments.len(),
+            });
+        }
+        for (index, (constraints, type_)) in function
+            .type_parameters
+            .iter()
+            .zip(&call.type_arguments)
+            .enumerate()
+        {
+            let abilities = self.abilities(type_)?;
+            let missing: Vec<_> = constraints
+                .abilities
+                .iter()
+                .filter(|a| !abilities.has(**a))
+                .copied()
+                .collect();
+            if !missing.is_empty() {
+                return Err(MoveCallErrorKind::MissingAbilities {
+                    index,
+                    type_: type_.clone(),
+                    missing,
+                });
+            }
+        }
+
+        // PTBs pass the trailing `TxContext` implicitly
+        let mut parameters = &function.parameters[..];
+        if parameters.last().is_some_and(is_tx_context) {
+            parameters = &parameters[..parameters.len() - 1];
+        }
+        if parameters.len() != call.arguments.len() {
+            return Err(MoveCallErrorKind::ArgumentCount {
+                expected: parameters.len(),
+                found: call.arguments.len(),
+            });
+        }
+        for (index, (parameter, argument)) in parameters.iter().zip(&call.arguments).enumerate() {
+            let found = match *argument {
+                Argument::Gas => ArgumentKind::Object,
+                Argument::Input(input) => match inputs.get(usize::from(input)) {
+                    Some(Input::Pure { .. }) => ArgumentKind::Pure,
+                    Some(Input::Receiving(_)) => ArgumentKind::Receiving,
+                    Some(_) => ArgumentKind::Object,
+                    None => return Err(MoveCallErrorKind::InputNotFound { index, input }),
+                },
+                // The types of results are only known to `af_ptbuilder::check`
+                Argument::Result(_) | Argument::NestedResult(..) => continue,
+            };
+            let expected = parameter_kind(parameter, &call.type_arguments);
+            if found != expected {
+                return Err(MoveCallErrorKind::ArgumentKind {
+                    index,
+                    expected,
+                    found,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn lineage_contains(&self, package: &ObjectId) -> bool {
+        self.contains_package(package)
+            || self
+                .originals
+                .get(package)
+                .and_then(|original| self.lineages.get(original))
+                .is_some_and(|versions| versions.values().any(|id| self.contains_package(id)))
+    }
+
+    /// The abilities of a fully instantiated type.
+    fn abilities(&self, type_: &TypeTag) -> Result<AbilitySet, MoveCallErrorKind> {
+        Ok(match type_ {
+            TypeTag::Signer => AbilitySet::DROP,
+            TypeTag::Vector(inner) => self.abilities(inner)?.intersect(AbilitySet::PRIMITIVES),
+            TypeTag::Struct(tag) => self.struct_abilities(tag)?,
+            _ => AbilitySet::PRIMITIVES,
+        })
+    }
+
+    /// The declared abilities of the struct or enum, restricted by those of its type arguments.
+    fn struct_abilities(&self, tag: &StructTag) -> Result<AbilitySet, MoveCallErrorKind> {
+        let not_found = || MoveCallErrorKind::TypeNotFound(TypeTag::Struct(Box::new(tag.clone())));
+        let (declared, parameters) = self.datatype(tag).ok_or_else(not_found)?;
+        if parameters.len() != tag.type_params.len() {
+            return Err(not_found());
+        }
+        let mut abilities = AbilitySet::from(declared);
+        for (parameter, argument) in parameters.iter().zip(&tag.type_params) {
+            if parameter.is_phantom {
+                continue;
+            }
+            abilities = abilities.restrict(self.abilities(argument)?);
+        }
+        Ok(abilities)
+    }
+
+    /// The declaration of a struct or enum in its defining package or a later version of it.
+    fn datatype(
+        &self,
+        tag: &StructTag,
+    ) -> Option<(&SuiMoveAbilitySet, &[SuiMoveStructTypeParameter])> {
+        let defining: ObjectId = tag.address.into();
+        let lineage = self
+            .originals
+            .get(&defining)
+            .and_then(|original| self.lineages.get(original))
+            .into_iter()
+            .flat_map(|versions| versions.values());
+        std::iter::once(&defining)
+            .chain(lineage)
+            .filter_map(|id| self.packages.get(id)?.get(tag.module.as_str()))
+            .find_map(|module| {
+                let name = tag.name.as_str();
+                if let Some(s) = module.structs.get(name) {
+                    return Some((&s.abilities, &s.type_parameters[..]));
+                }
+                let e = module.enums.get(name)?;
+                Some((&e.abilities, &e.type_parameters[..]))
+            })
+    }
+}
+
+/// A [`MoveCall`] that would fail to execute.
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+#[error("In command {command} ({package}::{module}::{function}): {kind}")]
+pub struct MoveCallError {
+    pub command: usize,
+    pub package: ObjectId,
+    pub module: Identifier,
+    pub function: Identifier,
+    pub kind: MoveCallErrorKind,
+}
+
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+pub enum MoveCallErrorKind {
+    #[error("Package ABI not loaded")]
+    PackageNotLoaded,
+    #[error("Module not found in package")]
+    ModuleNotFound,
+    #[error("Function not found in module or not exposed")]
+    FunctionNotFound,
+    #[error("Function is neither public nor entry")]
+    NotCallable,
+    #[error("Expected {expected} type arguments, got {found}")]
+    TypeArgumentCount { expected: usize, found: usize },
+    #[error("Type argument {index} ({type_}) lacks abilities {missing:?}")]
+    MissingAbilities {
+        index: usize,
+        type_: TypeTag,
+        missing: Vec<SuiMoveAbility>,
+    },
+    #[error("Type {0} not found in the loaded packages")]
+    TypeNotFound(TypeTag),
+    #[error("Expected {expected} arguments, got {found}")]
+    ArgumentCount { expected: usize, found: usize },
+    #[error("Argument {index} is input {input}, which doesn't exist")]
+    InputNotFound { index: usize, input: u16 },
+    #[error("Argument {index} should be a {expected} input, got a {found} one")]
+    ArgumentKind {
+        index: usize,
+        expected: ArgumentKind,
+        found: ArgumentKind,
+    },
+}
+
+/// How a PTB input is passed to a Move function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArgumentKind {
+    Pure,
+    Object,
+    Receiving,
+}
+
+impl std::fmt::Display for ArgumentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Pure => "pure",
+            Self::Object => "object",
+            Self::Receiving => "receiving",
+        })
+    }
+}
+
+/// The kind of input a parameter takes, with its type parameters instantiated by `type_arguments`.
+fn parameter_kind(type_: &SuiMoveNormalizedType, type_arguments: &[TypeTag]) -> ArgumentKind {
+    use SuiMoveNormalizedType as T;
+    match type_ {
+        T::Reference(inner) | T::MutableReference(inner) | T::Vector(inner) => {
+            parameter_kind(inner, type_arguments)
+        }
+        T::TypeParameter(i) => type_arguments
+            .get(usize::from(*i))
+            .map_or(ArgumentKind::Object, type_kind),
+        T::Signer => ArgumentKind::Object,
+        T::Struct {
+            address,
+            module,
+            name,
+            type_arguments: params,
+        } => struct_kind(parse_address(address), module, name, || {
+            params
+                .first()
+                .map_or(ArgumentKind::Object, |p| parameter_kind(p, type_arguments))
+        }),
+        _ => ArgumentKind::Pure,
+    }
+}
+
+fn type_kind(type_: &TypeTag) -> ArgumentKind {
+    match type_ {
+        TypeTag::Signer => ArgumentKind::Object,
+        TypeTag::Vector(inner) => type_kind(inner),
+        TypeTag::Struct(tag) => struct_kind(
+            Some(tag.address.into()),
+            tag.module.as_str(),
+            tag.name.as_str(),
+            || {
+                tag.type_params
+                    .first()
+                    .map_or(ArgumentKind::Object, type_kind)
+            },
+        ),
+        _ => ArgumentKind::Pure,
+    }
+}
+
+/// The pure types are those of `af_ptbuilder`'s `is_pure`: `Option<T>` takes the kind of `T`.
+fn struct_kind(
+    address: Option<ObjectId>,
+    module: &str,
+    name: &str,
+    option_param: impl FnOnce() -> ArgumentKind,
+) -> ArgumentKind {
+    let is = |a: Address, m: &str, n: &str| address == Some(a.into()) && module == m && name == n;
+    if is(MOVE_STDLIB_ADDRESS, "option", "Option") {
+        option_param()
+    } else if is(MOVE_STDLIB_ADDRESS, "string", "String")
+        || is(MOVE_STDLIB_ADDRESS, "ascii", "String")
+        || is(SUI_FRAMEWORK_ADDRESS, "object", "ID")
+    {
+        ArgumentKind::Pure
+    } else if is(SUI_FRAMEWORK_ADDRESS, "transfer", "Receiving") {
+        ArgumentKind::Receiving
+    } else {
+        ArgumentKind::Object
+    }
+}
+
+/// Whether this is `&TxContext` or `&mut TxContext`.
+fn is_tx_context(type_: &SuiMoveNormalizedType) -> bool {
+    use SuiMoveNormalizedType as T;
+    let (T::Reference(inner) | T::MutableReference(inner)) = type_ else {
+        return false;
+    };
+    matches!(
+        &**inner,
+        T::Struct { address, module, name, .. }
+            if parse_address(address) == Some(SUI_FRAMEWORK_ADDRESS.into())
+                && module == "tx_context"
+                && name == "TxContext"
+    )
+}
+
+fn for_each_struct(type_: &TypeTag, f: &mut impl FnMut(&StructTag)) {
+    match type_ {
+        TypeTag::Vector(inner) => for_each_struct(inner, f),
+        TypeTag::Struct(tag) => {
+            f(tag);
+            for param in &tag.type_params {
+                for_each_struct(param, f);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(feature = "ptbuilder")]
+impl af_ptbuilder::check::Signatures for MoveAbiCache {
+    fn function_signature(
+        &self,
+        package: &ObjectId,
+        module: &Identifier,
+        function: &Identifier,
+    ) -> Option<af_ptbuilder::check::FunctionSignature> {
+        let function = self.function(package, module.as_str(), function.as_str())?;
+        Some(af_ptbuilder::check::FunctionSignature {
+            type_parameters: function.type_parameters.len(),
+            parameters: function
+                .parameters
+                .iter()
+                .map(signature_type)
+                .collect::<Option<_>>()?,
+            returns: function
+                .return_
+                .iter()
+                .map(signature_type)
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    fn is_copyable(&self, type_: &StructTag) -> Option<bool> {
+        let abilities = self.struct_abilities(type_).ok()?;
+        Some(abilities.has(SuiMoveAbility::Copy))
+    }
+}
+
+/// [`None`] if the type has a malformed address or identifier.
+#[cfg(feature = "ptbuilder")]
+fn signature_type(
+    type_: &crate::msgs::SuiMoveNormalizedType,
+) -> Option<af_ptbuilder::check::SignatureType> {
+    use af_ptbuilder::check::SignatureType as S;
+
+    use crate::msgs::SuiMoveNormalizedType as T;
+    Some(match type_ {
+        T::Bool => S::Bool,
+        T::U8 => S::U8,
+        T::U16 => S::U16,
+        T::U32 => S::U32,
+        T::U64 => S::U64,
+        T::U128 => S::U128,
+        T::U256 => S::U256,
+        T::Address => S::Address,
+        T::Signer => S::Signer,
+        T::Struct {
+            address,
+            module,
+            name,
+            type_arguments,
+        } => S::Struct {
+            address: parse_address(address)?.into(),
+            module: module.parse().ok()?,
+            name: name.parse().ok()?,
+            type_arguments: type_arguments
+                .iter()
+                .map(signature_type)
+                .collect::<Option<_>>()?,
+        },
+        T::Vector(inner) => S::Vector(Box::new(signature_type(inner)?)),
+        T::TypeParameter(i) => S::TypeParameter(*i),
+        T::Reference(inner) => S::Reference(Box::new(signature_type(inner)?)),
+        T::MutableReference(inner) => S::MutableReference(Box::new(signature_type(inner)?)),
+    })
+}
+
+fn parse_address(address: &str) -> Option<ObjectId> {
+    if address.starts_with("0x") {
+        address.parse().ok()
+    } else {
+        format!("0x{address}").parse().ok()
+    }
+}
+
+// =============================================================================
+//  Abilities
+// =============================================================================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct AbilitySet(u8);
+
+impl AbilitySet {
+    const COPY: Self = Self(1);
+    const DROP: Self = Self(1 << 1);
+    const STORE: Self = Self(1 << 2);
+    const KEY: Self = Self(1 << 3);
+    const PRIMITIVES: Self = Self(Self::COPY.0 | Self::DROP.0 | Self::STORE.0);
+
+    const fn single(ability: SuiMoveAbility) -> Self {
+        match ability {
+            SuiMoveAbility::Copy => Self::COPY,
+            SuiMoveAbility::Drop => Self::DROP,
+            SuiMoveAbility::Store => Self::STORE,
+            SuiMoveAbility::Key => Self::KEY,
+        }
+    }
+
+    const fn has(self, ability: SuiMoveAbility) -> bool {
+        self.0 & Self::single(ability).0 != 0
+    }
+
+    const fn intersect(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// The abilities a generic type keeps when instantiated with a type argument having
+    /// `argument` abilities.
+    ///
+    /// `copy`, `drop` and `store` need the same ability in the argument; `key` needs `store`.
+    const fn restrict(self, argument: Self) -> Self {
+        let mut kept = self.0 & argument.0 & Self::PRIMITIVES.0;
+        if self.0 & Self::KEY.0 != 0 && argument.0 & Self::STORE.0 != 0 {
+            kept |= Self::KEY.0;
+        }
+        Self(kept)
+    }
+}
+
+impl From<&SuiMoveAbilitySet> for AbilitySet {
+    fn from(value: &SuiMoveAbilitySet) -> Self {
+        Self(
+            value
+                .abilities
+                .iter()
+                .fold(0, |acc, a| acc | Self::single(*a).0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use af_sui_types::{Argument, ObjectDigest};
+    use sui_sdk_types::{Input, ObjectReference};
+
+    use super::*;
+    use crate::msgs::{SuiMoveNormalizedStruct, SuiMoveNormalizedType, SuiMoveStructTypeParameter};
+
+    const PACKAGE: &str = "0xabc";
+
+    fn abilities(abilities: &[SuiMoveAbility]) -> SuiMoveAbilitySet {
+        SuiMoveAbilitySet {
+            abilities: abilities.to_vec(),
+        }
+    }
+
+    fn struct_type(address: &str, module: &str, name: &str) -> SuiMoveNormalizedType {
+        SuiMoveNormalizedType::Struct {
+            address: address.into(),
+            module: module.into(),
+            name: name.into(),
+            type_arguments: vec![],
+        }
+    }
+
+    /// `pool::swap<T: drop>(pool: &mut Pool, amount: u64, ctx: &mut TxContext)`, plus a private
+    /// `pool::internal()`.
+    fn cache() -> MoveAbiCache {
+        use SuiMoveAbility::*;
+        use SuiMoveNormalizedType as T;
+        let swap = SuiMoveNormalizedFunction {
+            visibility: SuiMoveVisibility::Public,
+            is_entry: false,
+            type_parameters: vec![abilities(&[Drop])],
+            parameters: vec![
+                T::MutableReference(Box::new(struct_type(PACKAGE, "pool", "Pool"))),
+                T::U64,
+                T::MutableReference(Box::new(struct_type("0x2", "tx_context", "TxContext"))),
+            ],
+            return_: vec![],
+        };
+        let internal = SuiMoveNormalizedFunction {
+            visibility: SuiMoveVisibility::Friend,
+            is_entry: false,
+            type_parameters: vec![],
+            parameters: vec![],
+            return_: vec![],
+        };
+        let pool = SuiMoveNormalizedStruct {
+            abilities: abilities(&[Key]),
+            type_parameters: vec![],
+            fields: vec![],
+        };
+        let token = SuiMoveNormalizedStruct {
+            abilities: abilities(&[Drop, Store]),
+            type_parameters: vec![SuiMoveStructTypeParameter {
+                constraints: abilities(&[]),
+                is_phantom: false,
+            }],
+            fields: vec![],
+        };
+        let module = SuiMoveNormalizedModule {
+            file_format_version: 6,
+            address: PACKAGE.into(),
+            name: "pool".into(),
+            friends: vec![],
+            structs: [("Pool".into(), pool), ("Token".into(), token)].into(),
+            enums: BTreeMap::new(),
+            exposed_functions: [("swap".into(), swap), ("internal".into(), internal)].into(),
+        };
+        let mut cache = MoveAbiCache::new();
+        cache.insert_package(
+            PACKAGE.parse().unwrap(),
+            [("pool".to_owned(), module)].into(),
+        );
+        cache
+    }
+
+    fn ptb(
+        function: &str,
+        type_arguments: Vec<TypeTag>,
+        arguments: Vec<Argument>,
+    ) -> ProgrammableTransaction {
+        ProgrammableTransaction {
+            inputs: vec![
+                Input::Shared {
+                    object_id: ObjectId::new([1; 32]),
+                    initial_shared_version: 1,
+                    mutable: true,
+                },
+                Input::Pure {
+                    value: bcs::to_bytes(&1_u64).unwrap(),
+                },
+                Input::Shared {
+                    object_id: ObjectId::new([2; 32]),
+                    initial_shared_version: 1,
+                    mutable: false,
+                },
+                Input::ImmutableOrOwned(ObjectReference::new(
+                    ObjectId::new([3; 32]),
+                    1,
+                    ObjectDigest::ZERO,
+                )),
+            ],
+            commands: vec![Command::MoveCall(MoveCall {
+                package: PACKAGE.parse().unwrap(),
+                module: "pool".parse().unwrap(),
+                function: function.parse().unwrap(),
+                type_arguments,
+                arguments,
+            })],
+        }
+    }
+
+    fn kind(result: Result<(), MoveCallError>) -> MoveCallErrorKind {
+        result.unwrap_err().kind
+    }
+
+    #[test]
+    fn valid_call() {
+        let cache = cache();
+        let ptb = ptb(
+            "swap",
+            vec![TypeTag::U8],
+            vec![Argument::Input(0), Argument::Input(1)],
+        );
+        assert_eq!(cache.missing_packages(&ptb), vec![]);
+        cache.check_ptb(&ptb).unwrap();
+    }
+
+    #[test]
+    fn invalid_calls() {
+        let cache = cache();
+        let args = vec![Argument::Input(0), Argument::Input(1)];
+        assert_eq!(
+            kind(cache.check_ptb(&ptb("missing", vec![TypeTag::U8], args.clone()))),
+            MoveCallErrorKind::FunctionNotFound
+        );
+        assert_eq!(
+            kind(cache.check_ptb(&ptb("internal", vec![], vec![]))),
+            MoveCallErrorKind::NotCallable
+        );
+        assert_eq!(
+            kind(cache.check_ptb(&ptb("swap", vec![], args))),
+            MoveCallErrorKind::TypeArgumentCount {
+                expected: 1,
+                found: 0
+            }
+        );
+    }
+
+    #[test]
+    fn type_argument_abilities() {
+        let cache = cache();
+        let args = vec![Argument::Input(0), Argument::Input(1)];
+        let type_ = |s: &str| -> TypeTag { s.replace("PKG", PACKAGE).parse().unwrap() };
+
+        // `Token<T>` has `drop` only if `T` does
+        let ok = ptb("swap", vec![type_("PKG::pool::Token<u8>")], args.clone());
+        cache.check_ptb(&ok).unwrap();
+
+        let pool = type_("PKG::pool::Pool");
+        let err = ptb(
+            "swap",
+            vec![type_("PKG::pool::Token<PKG::pool::Pool>")],
+            args.clone(),
+        );
+        assert!(matches!(
+            kind(cache.check_ptb(&err)),
+            MoveCallErrorKind::MissingAbilities { index: 0, ref missing, .. }
+                if missing == &[SuiMoveAbility::Drop]
+        ));
+        let err = ptb("swap", vec![pool], args.clone());
+        assert!(matches!(
+            kind(cache.check_ptb(&err)),
+            MoveCallErrorKind::MissingAbilities { .. }
+        ));
+
+        let unknown = ptb("swap", vec![type_("0xdef::coin::COIN")], args);
+        assert_eq!(
+            cache.missing_packages(&unknown),
+            vec!["0xdef".parse().unwrap()]
+        );
+        assert!(matches!(
+            kind(cache.check_ptb(&unknown)),
+            MoveCallErrorKind::TypeNotFound(_)
+        ));
+    }
+
+    #[test]
+    fn argument_counts_and_kinds() {
+        let cache = cache();
+
+        // The trailing `&mut TxContext` isn't an argument
+        let missing = ptb("swap", vec![TypeTag::U8], vec![Argument::Input(0)]);
+        assert_eq!(
+            kind(cache.check_ptb(&missing)),
+            MoveCallErrorKind::ArgumentCount {
+                expected: 2,
+                found: 1
+            }
+        );
+
+        let object_for_pure = ptb(
+            "swap",
+            vec![TypeTag::U8],
+            vec![Argument::Input(0), Argument::Input(3)],
+        );
+        assert_eq!(
+            kind(cache.check_ptb(&object_for_pure)),
+            MoveCallErrorKind::ArgumentKind {
+                index: 1,
+                expected: ArgumentKind::Pure,
+                found: ArgumentKind::Object
+            }
+        );
+        let pure_for_object = ptb(
+            "swap",
+            vec![TypeTag::U8],
+            vec![Argument::Input(1), Argument::Input(1)],
+        );
+        assert_eq!(
+            kind(cache.check_ptb(&pure_for_object)),
+            MoveCallErrorKind::ArgumentKind {
+                index: 0,
+                expected: ArgumentKind::Object,
+                found: ArgumentKind::Pure
+            }
+        );
+
+        let out_of_bounds = ptb(
+            "swap",
+            vec![TypeTag::U8],
+            vec![Argument::Input(0), Argument::Input(9)],
+        );
+        assert_eq!(
+            kind(cache.check_ptb(&out_of_bounds)),
+            MoveCallErrorKind::InputNotFound { index: 1, input: 9 }
+        );
+
+        // Results are left to `af_ptbuilder::check`
+        let result = ptb(
+            "swap",
+            vec![TypeTag::U8],
+            vec![Argument::Input(0), Argument::Result(0)],
+        );
+        cache.check_ptb(&result).unwrap();
+    }
+
+    #[cfg(feature = "ptbuilder")]
+    #[test]
+    fn type_check_signatures() {
+        use af_ptbuilder::check::{ArgumentError, TypeErrorKind, check_ptb};
+
+        let cache = cache();
+        let valid = ptb(
+            "swap",
+            vec![TypeTag::U8],
+            vec![Argument::Input(0), Argument::Input(1)],
+        );
+        check_ptb(&valid, &cache).unwrap();
+
+        let object_for_pure = ptb(
+            "swap",
+            vec![TypeTag::U8],
+            vec![Argument::Input(0), Argument::Input(3)],
+        );
+        let err = check_ptb(&object_for_pure, &cache).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            TypeErrorKind::Argument {
+                index: 1,
+                error: ArgumentError::ObjectForPure(_)
+            }
+        ));
+
+        // The trailing `&mut TxContext` isn't an argument
+        let missing = ptb("swap", vec![TypeTag::U8], vec![Argument::Input(0)]);
+        assert_eq!(
+            check_ptb(&missing, &cache).unwrap_err().kind,
+            TypeErrorKind::ArgumentCount {
+                expected: 2,
+                found: 1
+            }
+        );
+
+        let immutable = ptb(
+            "swap",
+            vec![TypeTag::U8],
+            vec![Argument::Input(2), Argument::Input(1)],
+        );
+        assert_eq!(
+            check_ptb(&immutable, &cache).unwrap_err().kind,
+            TypeErrorKind::Argument {
+                index: 0,
+                error: ArgumentError::ImmutableObject
+            }
+        );
+    }
+
+    #[cfg(all(feature = "ptbuilder", feature = "test-support"))]
+    #[tokio::test]
+    async fn check_move_calls() -> color_eyre::Result<()> {
+        use af_ptbuilder::check::{ArgumentError, TypeError, TypeErrorKind};
+        use af_sui_types::{Object, TransactionDigest};
+        use sui_sdk_types::{MoveStruct, ObjectData, Owner};
+
+        use crate::mock::MockNode;
+
+        let package = ObjectId::new([7; 32]);
+        let vault = || struct_type(&package.to_string(), "vault", "Vault");
+        let function = |parameters| SuiMoveNormalizedFunction {
+            visibility: SuiMoveVisibility::Public,
+            is_entry: false,
+            type_parameters: vec![],
+            parameters,
+            return_: vec![],
+        };
+        let deposit = function(vec![
+            SuiMoveNormalizedType::MutableReference(Box::new(vault())),
+            SuiMoveNormalizedType::U64,
+        ]);
+        let module = SuiMoveNormalizedModule {
+            file_format_version: 6,
+            address: package.to_string(),
+            name: "vault".into(),
+            friends: vec![],
+            structs: BTreeMap::new(),
+            enums: BTreeMap::new(),
+            exposed_functions: [("deposit".to_owned(), deposit)].into(),
+        };
+        let frozen = MoveStruct::new(
+            format!("{package}::vault::Vault").parse()?,
+            false,
+            1,
+            ObjectId::new([1; 32]).into_inner().to_vec(),
+        )
+        .unwrap();
+        let frozen = Object::new(
+            ObjectData::Struct(frozen),
+            Owner::Immutable,
+            TransactionDigest::ZERO,
+            100,
+        );
+        let node = MockNode::new();
+        node.store()
+            .packages
+            .insert(package, [("vault".to_owned(), module)].into());
+        node.store().insert_object(frozen.clone());
+        let server = node.start().await?;
+        let client = server.client().await?;
+
+        let call = |function: &str, vault| -> color_eyre::Result<_> {
+            Ok(Command::MoveCall(MoveCall {
+                package,
+                module: "vault".parse()?,
+                function: function.parse()?,
+                type_arguments: vec![],
+                arguments: vec![Argument::Input(vault), Argument::Input(2)],
+            }))
+        };
+        let mut ptb = ProgrammableTransaction {
+            inputs: vec![
+                Input::Shared {
+                    object_id: ObjectId::new([2; 32]),
+                    initial_shared_version: 1,
+                    mutable: true,
+                },
+                Input::ImmutableOrOwned(ObjectReference::new(
+                    frozen.object_id(),
+                    1,
+                    frozen.digest(),
+                )),
+                Input::Pure {
+                    value: bcs::to_bytes(&10_u64)?,
+                },
+            ],
+            commands: vec![call("deposit", 0)?],
+        };
+        let mut cache = MoveAbiCache::new();
+        client.check_move_calls(&mut cache, &ptb).await.unwrap();
+        assert!(cache.contains_package(&package));
+
+        ptb.commands.push(call("withdraw", 0)?);
+        let err = client.check_move_calls(&mut cache, &ptb).await.unwrap_err();
+        let err = err.downcast_ref::<MoveCallError>().unwrap();
+        assert_eq!(err.command, 1);
+        assert_eq!(err.kind, MoveCallErrorKind::FunctionNotFound);
+
+        // The frozen vault can't be taken by `&mut`
+        ptb.commands[1] = call("deposit", 1)?;
+        let err = client.check_move_calls(&mut cache, &ptb).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TypeError>(),
+            Some(&TypeError {
+                command: 1,
+                kind: TypeErrorKind::Argument {
+                    index: 0,
+                    error: ArgumentError::ImmutableObject
+                }
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lineage() {
+        let mut cache = cache();
+        let original: ObjectId = PACKAGE.parse().unwrap();
+        let upgraded = ObjectId::new([9; 32]);
+        cache.insert_lineage([(upgraded, 2), (original, 1)]);
+        assert_eq!(cache.original(&upgraded), Some(original));
+        assert_eq!(cache.latest(&original), Some(upgraded));
+
+        // Types defined by the original package are found through any loaded version
+        let modules = cache.package(&original).unwrap().clone();
+        let mut cache = MoveAbiCache::new();
+        cache.insert_lineage([(original, 1), (upgraded, 2)]);
+        cache.insert_package(upgraded, modules);
+        let mut ptb = ptb(
+            "swap",
+            vec![format!("{PACKAGE}::pool::Token<u8>").parse().unwrap()],
+            vec![Argument::Input(0), Argument::Input(1)],
+        );
+        let Command::MoveCall(call) = &mut ptb.commands[0] else {
+            unreachable!()
+        };
+        call.package = upgraded;
+        assert_eq!(cache.missing_packages(&ptb), vec![]);
+        cache.check_ptb(&ptb).unwrap();
+    }
+}
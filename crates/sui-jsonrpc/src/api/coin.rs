@@ -2,11 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use af_sui_types::Address as SuiAddress;
+#[cfg(feature = "test-support")]
+use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
 
 use crate::msgs::{Balance, CoinPage, SuiCoinMetadata, Supply};
 
-#[rpc(client, namespace = "suix")]
+#[cfg_attr(not(feature = "test-support"), rpc(client, namespace = "suix"))]
+#[cfg_attr(feature = "test-support", rpc(client, server, namespace = "suix"))]
 pub trait CoinReadApi {
     /// Return all Coin<`coin_type`> objects owned by an address.
     #[method(name = "getCoins")]
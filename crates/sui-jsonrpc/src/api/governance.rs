@@ -2,12 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use af_sui_types::{Address as SuiAddress, ObjectId};
+#[cfg(feature = "test-support")]
+use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
 
 use crate::msgs::{DelegatedStake, SuiCommittee, SuiSystemStateSummary, ValidatorApys};
 use crate::serde::BigInt;
 
-#[rpc(client, namespace = "suix")]
+#[cfg_attr(not(feature = "test-support"), rpc(client, namespace = "suix"))]
+#[cfg_attr(feature = "test-support", rpc(client, server, namespace = "suix"))]
 pub trait GovernanceReadApi {
     /// Return one or more [DelegatedStake]. If a Stake was withdrawn its status will be Unstaked.
     #[method(name = "getStakesByIds")]
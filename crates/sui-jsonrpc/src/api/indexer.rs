@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use af_sui_types::{Address as SuiAddress, ObjectId, TransactionDigest};
+#[cfg(feature = "test-support")]
+use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
 
 use crate::msgs::{
@@ -21,7 +23,8 @@ use crate::msgs::{
     TransactionFilter,
 };
 
-#[rpc(client, namespace = "suix")]
+#[cfg_attr(not(feature = "test-support"), rpc(client, namespace = "suix"))]
+#[cfg_attr(feature = "test-support", rpc(client, server, namespace = "suix"))]
 pub trait IndexerApi {
     /// Return the list of objects owned by an address.
     /// Note that if the address owns more than `QUERY_MAX_RESULT_LIMIT` objects,
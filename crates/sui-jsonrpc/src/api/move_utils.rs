@@ -4,6 +4,8 @@
 use std::collections::BTreeMap;
 
 use af_sui_types::ObjectId;
+#[cfg(feature = "test-support")]
+use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
 
 use crate::msgs::{
@@ -13,7 +15,8 @@ use crate::msgs::{
     SuiMoveNormalizedStruct,
 };
 
-#[rpc(client, namespace = "sui")]
+#[cfg_attr(not(feature = "test-support"), rpc(client, namespace = "sui"))]
+#[cfg_attr(feature = "test-support", rpc(client, server, namespace = "sui"))]
 pub trait MoveUtils {
     /// Return the argument types of a Move function,
     /// based on normalized Type.
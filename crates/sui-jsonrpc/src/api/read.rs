@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use af_sui_types::{ObjectId, TransactionDigest};
+#[cfg(feature = "test-support")]
+use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
 use sui_sdk_types::Version;
 
@@ -20,7 +22,8 @@ use crate::msgs::{
 };
 use crate::serde::BigInt;
 
-#[rpc(client, namespace = "sui")]
+#[cfg_attr(not(feature = "test-support"), rpc(client, namespace = "sui"))]
+#[cfg_attr(feature = "test-support", rpc(client, server, namespace = "sui"))]
 pub trait ReadApi {
     /// Return the transaction response object.
     #[method(name = "getTransactionBlock")]
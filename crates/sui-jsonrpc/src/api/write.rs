@@ -4,6 +4,8 @@
 use std::net::SocketAddr;
 
 use af_sui_types::Address as SuiAddress;
+#[cfg(feature = "test-support")]
+use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
 
 use crate::msgs::{
@@ -16,7 +18,8 @@ use crate::msgs::{
 };
 use crate::serde::BigInt;
 
-#[rpc(client, namespace = "sui")]
+#[cfg_attr(not(feature = "test-support"), rpc(client, namespace = "sui"))]
+#[cfg_attr(feature = "test-support", rpc(client, server, namespace = "sui"))]
 pub trait WriteApi {
     /// Execute the transaction. See [`ExecuteTransactionRequestType`] for details on how it's
     /// handled by the RPC.
@@ -5,9 +5,12 @@ use std::fmt::{Debug, Formatter};
 use std::sync::Arc;
 use std::time::Duration;
 
+use af_sui_types::coin_selection::{CoinSelector, InsufficientBalanceError, Selection};
+use af_sui_types::execution_failure::ExecutionFailure;
+use af_sui_types::object_arg_cache::ObjectArgCache;
+use af_sui_types::sui::effects::estimate_gas_budget_from_gas_cost;
 use af_sui_types::{
     Address as SuiAddress,
-    GasCostSummary,
     GasData,
     Object,
     ObjectArg,
@@ -27,8 +30,32 @@ use jsonrpsee::ws_client::{PingConfig, WsClient, WsClientBuilder};
 use jsonrpsee_http_client::{HeaderMap, HeaderValue, HttpClient, HttpClientBuilder};
 use serde_json::Value;
 
+#[cfg(feature = "gas-pool")]
+mod gas_pool;
+mod name_service;
+mod polling;
+#[cfg(feature = "sponsored")]
+mod sponsored;
+
+#[cfg(feature = "gas-pool")]
+pub use self::gas_pool::{GasCoin, GasLease, GasPool, GasPoolConfig, GasPoolError};
+pub use self::polling::{DEFAULT_POLL_INTERVAL, PollError, PollOptions};
+#[cfg(feature = "sponsored")]
+pub use self::sponsored::{
+    GasStation,
+    LocalGasStation,
+    LocalGasStationError,
+    SponsoredTransaction,
+    SponsoredTxError,
+};
 use super::{CLIENT_SDK_TYPE_HEADER, CLIENT_SDK_VERSION_HEADER, CLIENT_TARGET_API_VERSION_HEADER};
-use crate::api::{CoinReadApiClient, ReadApiClient as _, WriteApiClient as _};
+use crate::abi::MoveAbiCache;
+use crate::api::{
+    CoinReadApiClient,
+    MoveUtilsClient as _,
+    ReadApiClient as _,
+    WriteApiClient as _,
+};
 use crate::error::JsonRpcClientError;
 use crate::msgs::{
     Coin,
@@ -138,6 +165,8 @@ impl SuiClientBuilder {
     }
 
     /// Set the WebSocket URL for the Sui network
+    ///
+    /// For live data, use the polling streams like [`SuiClient::poll_events`] instead.
     #[deprecated = "\
         JSON-RPC subscriptions have been deprecated since at least mainnet-v1.28.3. \
         See <https://github.com/MystenLabs/sui/releases/tag/mainnet-v1.28.3>\
@@ -434,6 +463,102 @@ impl SuiClient {
             .map(|r| Ok(r.into_object()?.object_arg(false)?)))
     }
 
+    /// PTB args for several objects, taken from `cache` if there and queried otherwise.
+    ///
+    /// The queried args are added to `cache`, see [`Self::object_args`] for their mutability. Keep
+    /// the cache current by [ingesting](ObjectArgCache::ingest) the effects of executed
+    /// transactions, e.g., from [`SuiTransactionBlockResponse::sui_effects`].
+    pub async fn cached_object_args<Iter>(
+        &self,
+        cache: &mut ObjectArgCache,
+        ids: Iter,
+    ) -> Result<Vec<ObjectArg>, BoxError>
+    where
+        Iter: IntoIterator<Item = ObjectId> + Send,
+        Iter::IntoIter: Send,
+    {
+        let ids: Vec<_> = ids.into_iter().collect();
+        let missing: Vec<_> = ids
+            .iter()
+            .filter(|id| cache.get(id).is_none())
+            .copied()
+            .collect();
+        if !missing.is_empty() {
+            for arg in self.object_args(missing).await? {
+                cache.insert(arg?);
+            }
+        }
+        Ok(ids
+            .iter()
+            .map(|id| cache.get(id).expect("Inserted above"))
+            .collect())
+    }
+
+    /// Load the normalized Move modules of the `packages` that aren't in `cache` yet.
+    pub async fn load_move_abis<Iter>(
+        &self,
+        cache: &mut MoveAbiCache,
+        packages: Iter,
+    ) -> Result<(), JsonRpcClientError>
+    where
+        Iter: IntoIterator<Item = ObjectId> + Send,
+        Iter::IntoIter: Send,
+    {
+        let missing: Vec<_> = packages
+            .into_iter()
+            .filter(|id| !cache.contains_package(id))
+            .collect();
+        for package in missing {
+            let modules = self
+                .http()
+                .get_normalized_move_modules_by_package(package)
+                .await?;
+            cache.insert_package(package, modules);
+        }
+        Ok(())
+    }
+
+    /// Check the Move calls in `ptb` against the ABIs of their packages, e.g., before submitting
+    /// it.
+    ///
+    /// Packages missing from `cache` are loaded first. The calls are checked by
+    /// [`MoveAbiCache::check_ptb`], then their argument types by [`af_ptbuilder::check`]. The
+    /// owners of the `ImmutableOrOwned` inputs are queried, so that immutable objects taken by
+    /// mutable reference or by value are caught.
+    #[cfg(feature = "ptbuilder")]
+    pub async fn check_move_calls(
+        &self,
+        cache: &mut MoveAbiCache,
+        ptb: &af_sui_types::ProgrammableTransaction,
+    ) -> Result<(), BoxError> {
+        let missing = cache.missing_packages(ptb);
+        self.load_move_abis(cache, missing).await?;
+        cache.check_ptb(ptb)?;
+
+        let imm_or_owned: Vec<_> = ptb
+            .inputs
+            .iter()
+            .filter_map(|input| match input {
+                sui_sdk_types::Input::ImmutableOrOwned(oref) => Some(*oref.object_id()),
+                _ => None,
+            })
+            .collect();
+        let mut immutable = vec![];
+        if !imm_or_owned.is_empty() {
+            let options = SuiObjectDataOptions::new().with_owner();
+            for response in self.multi_get_objects(imm_or_owned, options).await? {
+                let object = response.into_object()?;
+                if matches!(object.owner, Some(crate::msgs::Owner::Immutable)) {
+                    immutable.push(object.object_id);
+                }
+            }
+        }
+        af_ptbuilder::check::Checker::new(&ptb.inputs, &*cache)
+            .with_immutable_objects(&immutable)
+            .check(&ptb.commands)?;
+        Ok(())
+    }
+
     /// Query the full object contents as a standard Sui type.
     pub async fn full_object(&self, id: ObjectId) -> Result<Object, BoxError> {
         let options = SuiObjectDataOptions {
@@ -605,6 +730,22 @@ impl SuiClient {
         Ok(budget)
     }
 
+    /// The limits on programmable transactions at the current protocol version.
+    ///
+    /// Check transactions against them before submission with
+    /// [`ProgrammableTransactionBuilder::check_limits`], or spread work over several transactions
+    /// with [`Batches`].
+    ///
+    /// [`ProgrammableTransactionBuilder::check_limits`]: af_ptbuilder::ProgrammableTransactionBuilder::check_limits
+    /// [`Batches`]: af_ptbuilder::limits::Batches
+    #[cfg(feature = "ptbuilder")]
+    pub async fn transaction_limits(
+        &self,
+    ) -> Result<af_ptbuilder::limits::TransactionLimits, JsonRpcClientError> {
+        let config = self.http().get_protocol_config(None).await?;
+        Ok(config.transaction_limits())
+    }
+
     /// Build the gas data for a transaction by querying the node for gas objects.
     pub async fn get_gas_data(
         &self,
@@ -733,6 +874,35 @@ impl SuiClient {
         })
     }
 
+    /// Coins for the given address that meet or exceed `amount`, as chosen by `selector`.
+    ///
+    /// Unlike [`Self::coins_for_amount`], which takes coins greedily in page order, this lists all
+    /// of the address' coins of that type before selecting. Use `None` for the default
+    /// `Coin<SUI>`.
+    pub async fn select_coins_with(
+        &self,
+        address: SuiAddress,
+        coin_type: Option<String>,
+        amount: u64,
+        selector: impl CoinSelector,
+    ) -> SuiClientResult<Selection> {
+        use futures_util::TryStreamExt as _;
+        let coins: Vec<_> = self
+            .coins_for_address(address, coin_type, None)
+            .map_ok(|c| c.coin_ref())
+            .try_collect()
+            .await?;
+        selector
+            .select(coins, amount)
+            .map_err(|InsufficientBalanceError { found, requested }| {
+                SuiClientError::InsufficientFunds {
+                    address,
+                    found,
+                    requested,
+                }
+            })
+    }
+
     /// Return a stream of coins for the given address, or an error upon failure.
     ///
     /// This simply wraps a paginated query. Use `page_size` to control the inner query's page
@@ -868,6 +1038,16 @@ pub enum DryRunError {
     Client(#[from] JsonRpcClientError),
 }
 
+impl DryRunError {
+    /// The structured execution failure, if the transaction failed in the dry run.
+    pub fn execution_failure(&self) -> Option<ExecutionFailure> {
+        let Self::Execution(_, response) = self else {
+            return None;
+        };
+        response.effects.status().as_execution_failure()
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum GetGasDataError {
     #[error("In JSON-RPC client: {0}")]
@@ -921,21 +1101,6 @@ pub struct NotEnoughGasError {
 /// Same value as used in the Sui CLI.
 const GAS_SAFE_OVERHEAD_MULTIPLIER: u64 = 1000;
 
-/// Use primarily on the gas cost of dry-runs.
-///
-/// Same as used in the Sui CLI.
-///
-/// # Arguments
-/// - `gas_cost_summary`: gas cost breakdown
-/// - `safe_overhead`: balance to add to both the computation and net gas costs to account for
-///   possible fluctuations when the transaction is actually submitted.
-fn estimate_gas_budget_from_gas_cost(gas_cost_summary: &GasCostSummary, safe_overhead: u64) -> u64 {
-    let computation_cost_with_overhead = gas_cost_summary.computation_cost + safe_overhead;
-
-    let gas_usage_with_overhead = gas_cost_summary.net_gas_usage() + safe_overhead as i64;
-    computation_cost_with_overhead.max(gas_usage_with_overhead.max(0) as u64)
-}
-
 fn iter_chunks<I>(iter: I, chunk_size: usize) -> impl Iterator<Item = Vec<I::Item>> + Send
 where
     I: IntoIterator,
@@ -950,3 +1115,50 @@ where
         Some(v)
     })
 }
+
+#[cfg(test)]
+#[cfg(feature = "test-support")]
+mod tests {
+    use af_sui_types::{Object, ObjectArg, StructTag, TransactionDigest};
+    use sui_sdk_types::{MoveStruct, ObjectData, Owner};
+
+    use super::*;
+    use crate::mock::MockNode;
+
+    #[tokio::test]
+    async fn cached_object_args() -> color_eyre::Result<()> {
+        let type_: StructTag = "0x2::clock::Clock".parse()?;
+        let data =
+            MoveStruct::new(type_, true, 1, ObjectId::new([2; 32]).into_inner().to_vec()).unwrap();
+        let owned = Object::new(
+            ObjectData::Struct(data),
+            Owner::Address(SuiAddress::new([1; 32])),
+            TransactionDigest::ZERO,
+            100,
+        );
+        let node = MockNode::new();
+        node.store().insert_object(owned.clone());
+        let server = node.start().await?;
+        let client = server.client().await?;
+
+        let mut cache = ObjectArgCache::new();
+        let newer = ObjectArg::ImmOrOwnedObject((ObjectId::new([3; 32]), 5, owned.digest()));
+        cache.insert(newer);
+        let args = client
+            .cached_object_args(&mut cache, [ObjectId::new([3; 32]), owned.object_id()])
+            .await
+            .unwrap();
+        let queried = ObjectArg::ImmOrOwnedObject((owned.object_id(), 1, owned.digest()));
+        assert_eq!(args, [newer, queried]);
+        assert_eq!(cache.get(&owned.object_id()), Some(queried));
+
+        // Served from the cache
+        node.store().remove_object(owned.object_id());
+        let args = client
+            .cached_object_args(&mut cache, [owned.object_id()])
+            .await
+            .unwrap();
+        assert_eq!(args, [queried]);
+        Ok(())
+    }
+}
@@ -0,0 +1,716 @@
+//! Pool of gas coins for submitting many transactions concurrently from a single address.
+//!
+//! Transactions from the same address can only run concurrently if they use different gas coins,
+//! otherwise all but one are rejected with the coins being unavailable for consumption.
+//! [`SuiClient::get_gas_payment`] has no way of knowing which coins are in use by in-flight
+//! transactions, so concurrent builders end up picking the same ones.
+//!
+//! [`GasPool`] instead keeps track of the SUI coins of an address and *leases* each to a single
+//! transaction at a time. Returning a [`GasLease`] with the transaction's effects updates the coin
+//! reference and balance locally, so the coin can be leased again right away without querying the
+//! node. Coins whose balance drops under [`GasPoolConfig::min_balance`] are set aside and, once
+//! there are no others available, all idle coins are merged and split again into
+//! [`GasPoolConfig::coins`] coins of equal balance.
+//!
+//! Leased coins must only be used for gas, i.e., not as inputs of the transaction nor through
+//! [`Argument::Gas`](sui_sdk_types::Argument::Gas), since the pool can't know their balances
+//! afterwards.
+use std::cmp::Reverse;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use af_keys::signer::TransactionSigner;
+use af_sui_types::{
+    Address as SuiAddress,
+    Argument,
+    Command,
+    GasData,
+    ObjectId,
+    ObjectRef,
+    ProgrammableTransaction,
+    TransactionData,
+    TransactionDataV1,
+    TransactionEffectsAPI,
+    TransactionExpiration,
+    TransactionKind,
+};
+use futures_util::TryStreamExt as _;
+use sui_sdk_types::{Input, SplitCoins, TransferObjects};
+use tokio::sync::Notify;
+
+use super::{BoxError, SuiClient, SuiClientError};
+use crate::api::{GovernanceReadApiClient as _, ReadApiClient as _};
+use crate::error::JsonRpcClientError;
+use crate::msgs::{
+    Owner,
+    SuiExecutionStatus,
+    SuiObjectDataOptions,
+    SuiRawData,
+    SuiTransactionBlockEffectsAPI as _,
+    SuiTransactionBlockResponse,
+    SuiTransactionBlockResponseOptions,
+};
+
+/// Maximum number of coins in a transaction's gas payment.
+const MAX_GAS_PAYMENT_OBJECTS: usize = 256;
+
+/// Configuration for a [`GasPool`].
+#[derive(Clone, Copy, Debug)]
+pub struct GasPoolConfig {
+    /// Number of gas coins to split the balance into, i.e., how many transactions can be in
+    /// flight at once.
+    pub coins: usize,
+    /// Coins with less than this balance aren't leased until the next rebalance.
+    pub min_balance: u64,
+    /// Gas budget of the transactions merging and splitting the coins.
+    pub rebalance_budget: u64,
+}
+
+impl Default for GasPoolConfig {
+    fn default() -> Self {
+        Self {
+            coins: 10,
+            min_balance: 100_000_000,
+            rebalance_budget: 50_000_000,
+        }
+    }
+}
+
+/// A gas coin managed by a [`GasPool`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GasCoin {
+    pub object_ref: ObjectRef,
+    pub balance: u64,
+}
+
+impl GasCoin {
+    const fn id(&self) -> ObjectId {
+        self.object_ref.0
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Coins ready to be leased.
+    idle: VecDeque<GasCoin>,
+    /// Coins under the minimum balance, waiting to be merged.
+    low: Vec<GasCoin>,
+    /// Coins whose reference is unknown since their lease was dropped.
+    stale: Vec<ObjectId>,
+    leased: HashSet<ObjectId>,
+    rebalancing: bool,
+}
+
+impl State {
+    fn put(&mut self, coin: GasCoin, min_balance: u64) {
+        if coin.balance < min_balance {
+            self.low.push(coin);
+        } else {
+            self.idle.push_back(coin);
+        }
+    }
+
+    fn next(&mut self) -> Next {
+        if let Some(coin) = self.idle.pop_front() {
+            self.leased.insert(coin.id());
+            Next::Lease(coin)
+        } else if let Some(id) = self.stale.pop() {
+            Next::Refresh(id)
+        } else if !self.leased.is_empty() || self.rebalancing {
+            Next::Wait
+        } else if self.low.is_empty() {
+            Next::Empty
+        } else {
+            Next::Rebalance
+        }
+    }
+
+    /// Take the coins that aren't leased for merging, largest first.
+    fn take_unleased(&mut self) -> Vec<GasCoin> {
+        let mut coins: Vec<_> = self.idle.drain(..).chain(self.low.drain(..)).collect();
+        coins.sort_by_key(|c| Reverse(c.balance));
+        if coins.len() > MAX_GAS_PAYMENT_OBJECTS {
+            self.low.extend(coins.drain(MAX_GAS_PAYMENT_OBJECTS..));
+        }
+        coins
+    }
+}
+
+/// What [`GasPool::lease`] should do next.
+enum Next {
+    Lease(GasCoin),
+    Refresh(ObjectId),
+    Rebalance,
+    Wait,
+    Empty,
+}
+
+/// Leases the SUI coins of an address to concurrent transactions, see the [module
+/// docs](self).
+pub struct GasPool<S> {
+    client: SuiClient,
+    owner: SuiAddress,
+    signer: S,
+    config: GasPoolConfig,
+    state: Mutex<State>,
+    released: Notify,
+}
+
+impl<S: TransactionSigner + Sync> GasPool<S> {
+    /// An empty pool for the coins of `owner`. Call [`Self::load`] to fill it.
+    ///
+    /// `signer` is used to sign the transactions rebalancing the coins.
+    pub fn new(client: SuiClient, owner: SuiAddress, signer: S, config: GasPoolConfig) -> Self {
+        Self {
+            client,
+            owner,
+            signer,
+            config,
+            state: Mutex::default(),
+            released: Notify::new(),
+        }
+    }
+
+    pub const fn owner(&self) -> SuiAddress {
+        self.owner
+    }
+
+    /// Snapshot of the coins available for leasing.
+    pub fn idle_coins(&self) -> Vec<GasCoin> {
+        self.state().idle.iter().copied().collect()
+    }
+
+    /// Query all SUI coins of the owner, replacing the ones in the pool that aren't leased.
+    ///
+    /// Rebalances if there are fewer coins than [`GasPoolConfig::coins`].
+    pub async fn load(&self) -> Result<(), GasPoolError> {
+        let coins: Vec<_> = self
+            .client
+            .coins_for_address(self.owner, None, None)
+            .try_collect()
+            .await?;
+        let needs_rebalance = {
+            let mut state = self.state();
+            state.idle.clear();
+            state.low.clear();
+            state.stale.clear();
+            for coin in coins {
+                if state.leased.contains(&coin.coin_object_id) {
+                    continue;
+                }
+                let coin = GasCoin {
+                    object_ref: coin.object_ref(),
+                    balance: coin.balance,
+                };
+                state.put(coin, self.config.min_balance);
+            }
+            state.idle.len() + state.leased.len() < self.config.coins
+        };
+        if needs_rebalance {
+            self.rebalance().await?;
+        }
+        self.released.notify_waiters();
+        Ok(())
+    }
+
+    /// Lease a gas coin, waiting for one to be released if all are in use.
+    pub async fn lease(&self) -> Result<GasLease<'_, S>, GasPoolError> {
+        loop {
+            let released = self.released.notified();
+            let next = self.state().next();
+            match next {
+                Next::Lease(coin) => {
+                    return Ok(GasLease {
+                        pool: self,
+                        coin: Some(coin),
+                    });
+                }
+                Next::Refresh(id) => self.refresh(id).await?,
+                Next::Rebalance => self.rebalance().await?,
+                Next::Wait => released.await,
+                Next::Empty => return Err(GasPoolError::Empty(self.owner)),
+            }
+        }
+    }
+
+    /// Merge all coins that aren't leased and split them into equal parts, so that there are
+    /// [`GasPoolConfig::coins`] in the pool.
+    pub async fn rebalance(&self) -> Result<(), GasPoolError> {
+        let (coins, target) = {
+            let mut state = self.state();
+            let target = self.config.coins.saturating_sub(state.leased.len());
+            if state.rebalancing || target == 0 {
+                return Ok(());
+            }
+            state.rebalancing = true;
+            (state.take_unleased(), target)
+        };
+        let result = self.merge_and_split(&coins, target).await;
+        {
+            let mut state = self.state();
+            state.rebalancing = false;
+            match &result {
+                Ok(new) => new
+                    .iter()
+                    .for_each(|c| state.put(*c, self.config.min_balance)),
+                Err(Rebalance::NotSubmitted(_)) => coins
+                    .iter()
+                    .for_each(|c| state.put(*c, self.config.min_balance)),
+                // The gas coins may or may not have been merged
+                Err(Rebalance::Submitted(_)) => {
+                    state.stale.extend(coins.iter().map(GasCoin::id));
+                }
+                Err(Rebalance::Failed { merged, .. }) => {
+                    state.put(*merged, self.config.min_balance);
+                }
+            }
+        }
+        self.released.notify_waiters();
+        result.map(drop).map_err(Rebalance::into_error)
+    }
+
+    async fn merge_and_split(
+        &self,
+        coins: &[GasCoin],
+        target: usize,
+    ) -> Result<Vec<GasCoin>, Rebalance> {
+        use Rebalance::{NotSubmitted, Submitted};
+
+        if coins.is_empty() {
+            return Err(NotSubmitted(GasPoolError::Empty(self.owner)));
+        }
+        let budget = self.config.rebalance_budget;
+        let total: u64 = coins.iter().map(|c| c.balance).sum();
+        let amount = total.saturating_sub(budget) / target as u64;
+        if amount < self.config.min_balance {
+            return Err(NotSubmitted(GasPoolError::InsufficientBalance {
+                total,
+                required: budget + self.config.min_balance * target as u64,
+            }));
+        }
+
+        let price = self
+            .client
+            .http()
+            .get_reference_gas_price()
+            .await
+            .map_err(|e| NotSubmitted(e.into()))?
+            .into_inner();
+        let tx_data = TransactionData::V1(TransactionDataV1 {
+            kind: split_transaction(self.owner, amount, target - 1),
+            sender: self.owner,
+            gas_data: GasData {
+                payment: coins.iter().map(|c| c.object_ref).collect(),
+                owner: self.owner,
+                price,
+                budget,
+            },
+            expiration: TransactionExpiration::None,
+        });
+        let signature = self
+            .signer
+            .sign_transaction(self.owner, &tx_data)
+            .await
+            .map_err(|e| NotSubmitted(GasPoolError::Signing(e.into())))?;
+        let options = SuiTransactionBlockResponseOptions::new().with_effects();
+        let response = self
+            .client
+            .submit_transaction(&tx_data, &[signature], Some(options))
+            .await
+            .map_err(|e| Submitted(e.into()))?;
+        let effects = response
+            .effects
+            .ok_or(Submitted(GasPoolError::MissingEffects(response.digest)))?;
+
+        // Gas coins are always merged into the first one, even if execution fails
+        let spent = effects.gas_cost_summary().net_gas_usage();
+        let gas_ref = effects.gas_object().reference.to_object_ref();
+        if let SuiExecutionStatus::Failure { error } = effects.status() {
+            return Err(Rebalance::Failed {
+                merged: GasCoin {
+                    object_ref: gas_ref,
+                    balance: total.saturating_add_signed(-spent),
+                },
+                error: error.clone(),
+            });
+        }
+        let mut new: Vec<_> = effects
+            .created()
+            .iter()
+            .map(|o| GasCoin {
+                object_ref: o.reference.to_object_ref(),
+                balance: amount,
+            })
+            .collect();
+        new.push(GasCoin {
+            object_ref: gas_ref,
+            balance: (total - amount * (new.len() as u64)).saturating_add_signed(-spent),
+        });
+        Ok(new)
+    }
+
+    /// Query the latest reference and balance of a coin whose lease was dropped.
+    ///
+    /// Coins that no longer exist or changed owners are dropped from the pool.
+    async fn refresh(&self, id: ObjectId) -> Result<(), GasPoolError> {
+        let options = SuiObjectDataOptions::new().with_owner().with_bcs();
+        let response = match self.client.http().get_object(id, Some(options)).await {
+            Ok(response) => response,
+            Err(err) => {
+                self.state().stale.push(id);
+                return Err(err.into());
+            }
+        };
+        let coin = response.into_object().ok().and_then(|data| {
+            if data.owner != Some(Owner::AddressOwner(self.owner)) {
+                return None;
+            }
+            let Some(SuiRawData::MoveObject(object)) = &data.bcs else {
+                return None;
+            };
+            // `Coin<SUI> { id: UID, balance: Balance<SUI> }`
+            let (_, balance): (ObjectId, u64) = bcs::from_bytes(&object.bcs_bytes).ok()?;
+            Some(GasCoin {
+                object_ref: data.object_ref(),
+                balance,
+            })
+        });
+        if let Some(coin) = coin {
+            self.state().put(coin, self.config.min_balance);
+        }
+        Ok(())
+    }
+
+    fn release(&self, coin: Option<GasCoin>, id: ObjectId) {
+        {
+            let mut state = self.state();
+            state.leased.remove(&id);
+            match coin {
+                Some(coin) => state.put(coin, self.config.min_balance),
+                None => state.stale.push(id),
+            }
+        }
+        self.released.notify_one();
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Exclusive use of a gas coin from a [`GasPool`].
+///
+/// Return it to the pool with one of the `release*` methods after the transaction is executed.
+/// If dropped instead, the pool queries the coin's latest state before leasing it again.
+pub struct GasLease<'a, S: TransactionSigner + Sync> {
+    pool: &'a GasPool<S>,
+    coin: Option<GasCoin>,
+}
+
+impl<S: TransactionSigner + Sync> GasLease<'_, S> {
+    pub const fn coin(&self) -> GasCoin {
+        self.coin.expect("Only taken when consumed")
+    }
+
+    /// Gas data paying with the leased coin.
+    pub fn gas_data(&self, price: u64, budget: u64) -> GasData {
+        GasData {
+            payment: vec![self.coin().object_ref],
+            owner: self.pool.owner,
+            price,
+            budget,
+        }
+    }
+
+    /// Return the coin to the pool with its state after a transaction.
+    pub fn release(mut self, coin: GasCoin) {
+        let id = self.coin().id();
+        self.coin = None;
+        self.pool.release(Some(coin), id);
+    }
+
+    /// Return the coin to the pool, updated by the effects of the transaction it paid for.
+    ///
+    /// The response must include the effects, see
+    /// [`SuiTransactionBlockResponseOptions::with_effects`].
+    pub fn release_with_response(self, response: &SuiTransactionBlockResponse) {
+        let Some(effects) = &response.effects else {
+            return drop(self);
+        };
+        let coin = self.coin();
+        let updated = effects
+            .mutated()
+            .iter()
+            .find(|o| o.object_id() == coin.id())
+            .map(|o| GasCoin {
+                object_ref: o.reference.to_object_ref(),
+                balance: coin
+                    .balance
+                    .saturating_add_signed(-effects.gas_cost_summary().net_gas_usage()),
+            });
+        match updated {
+            Some(updated) => self.release(updated),
+            None => drop(self),
+        }
+    }
+
+    /// Return the coin to the pool, updated by the effects of the transaction it paid for.
+    pub fn release_with_effects(self, effects: &impl TransactionEffectsAPI) {
+        let coin = self.coin();
+        let updated = effects
+            .mutated()
+            .into_iter()
+            .find(|(oref, _)| *oref.object_id() == coin.id())
+            .map(|(oref, _)| GasCoin {
+                object_ref: oref.into_parts(),
+                balance: coin
+                    .balance
+                    .saturating_add_signed(-effects.gas_cost_summary().net_gas_usage()),
+            });
+        match updated {
+            Some(updated) => self.release(updated),
+            None => drop(self),
+        }
+    }
+}
+
+impl<S: TransactionSigner + Sync> Drop for GasLease<'_, S> {
+    fn drop(&mut self) {
+        if let Some(coin) = self.coin.take() {
+            self.pool.release(None, coin.id());
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GasPoolError {
+    #[error(transparent)]
+    Client(#[from] SuiClientError),
+    #[error("No SUI coins left for {0}")]
+    Empty(SuiAddress),
+    #[error("Total balance {total} is less than the {required} needed to rebalance")]
+    InsufficientBalance { total: u64, required: u64 },
+    #[error("Signing rebalance transaction: {0}")]
+    Signing(BoxError),
+    #[error("Rebalance transaction {0} response has no effects")]
+    MissingEffects(af_sui_types::TransactionDigest),
+    #[error("Rebalance transaction failed: {0}")]
+    Execution(String),
+}
+
+/// Failed rebalance, by what happened to the coins.
+enum Rebalance {
+    NotSubmitted(GasPoolError),
+    Submitted(GasPoolError),
+    Failed { merged: GasCoin, error: String },
+}
+
+impl Rebalance {
+    fn into_error(self) -> GasPoolError {
+        match self {
+            Self::NotSubmitted(e) | Self::Submitted(e) => e,
+            Self::Failed { error, .. } => GasPoolError::Execution(error),
+        }
+    }
+}
+
+impl From<JsonRpcClientError> for GasPoolError {
+    fn from(value: JsonRpcClientError) -> Self {
+        Self::Client(value.into())
+    }
+}
+
+/// Split `splits` coins of `amount` from the gas coin and send them back to `owner`.
+fn split_transaction(owner: SuiAddress, amount: u64, splits: usize) -> TransactionKind {
+    let amount = bcs::to_bytes(&amount).expect("u64 serialization shouldn't fail");
+    let owner = bcs::to_bytes(&owner).expect("Address serialization shouldn't fail");
+    if splits == 0 {
+        return TransactionKind::ProgrammableTransaction(ProgrammableTransaction {
+            inputs: vec![],
+            commands: vec![],
+        });
+    }
+    let commands = vec![
+        Command::SplitCoins(SplitCoins {
+            coin: Argument::Gas,
+            amounts: vec![Argument::Input(0); splits],
+        }),
+        Command::TransferObjects(TransferObjects {
+            objects: (0..splits as u16)
+                .map(|i| Argument::NestedResult(0, i))
+                .collect(),
+            address: Argument::Input(1),
+        }),
+    ];
+    TransactionKind::ProgrammableTransaction(ProgrammableTransaction {
+        inputs: vec![Input::Pure { value: amount }, Input::Pure { value: owner }],
+        commands,
+    })
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-support")]
+mod tests {
+    use std::time::Duration;
+
+    use af_sui_types::{Object, StructTag, TransactionDigest};
+    use sui_sdk_types::{MoveStruct, ObjectData};
+
+    use super::*;
+    use crate::client::sponsored::tests::{KeyPair, coin};
+    use crate::mock::MockNode;
+
+    const SUI: u64 = 1_000_000_000;
+
+    fn config(coins: usize) -> GasPoolConfig {
+        GasPoolConfig {
+            coins,
+            min_balance: SUI / 10,
+            rebalance_budget: SUI / 20,
+        }
+    }
+
+    #[test]
+    fn split_transaction_shape() {
+        let owner = SuiAddress::new([1; 32]);
+        let TransactionKind::ProgrammableTransaction(ptb) = split_transaction(owner, 5, 3) else {
+            panic!("Not a PTB");
+        };
+        assert_eq!(ptb.inputs.len(), 2);
+        let [
+            Command::SplitCoins(split),
+            Command::TransferObjects(transfer),
+        ] = &ptb.commands[..]
+        else {
+            panic!("Unexpected commands {:?}", ptb.commands);
+        };
+        assert_eq!(split.coin, Argument::Gas);
+        assert_eq!(split.amounts.len(), 3);
+        assert_eq!(transfer.objects[2], Argument::NestedResult(0, 2));
+
+        let TransactionKind::ProgrammableTransaction(ptb) = split_transaction(owner, 5, 0) else {
+            panic!("Not a PTB");
+        };
+        assert!(ptb.inputs.is_empty() && ptb.commands.is_empty());
+    }
+
+    #[tokio::test]
+    async fn leases_are_exclusive() -> color_eyre::Result<()> {
+        let signer = KeyPair::new(1);
+        let owner = signer.address();
+        let node = MockNode::new();
+        {
+            let mut store = node.store();
+            store.insert_object(coin(1, owner, SUI));
+            store.insert_object(coin(2, owner, SUI));
+            store.insert_object(coin(3, owner, SUI / 100));
+        }
+        let server = node.start().await?;
+        let client = server.client().await?;
+        let pool = GasPool::new(client, owner, signer, config(2));
+        pool.load().await?;
+        // The low balance coin is set aside
+        assert_eq!(pool.idle_coins().len(), 2);
+
+        let first = pool.lease().await?;
+        let second = pool.lease().await?;
+        assert_ne!(first.coin(), second.coin());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), pool.lease())
+                .await
+                .is_err()
+        );
+
+        let (id, version, digest) = first.coin().object_ref;
+        let updated = GasCoin {
+            object_ref: (id, version + 1, digest),
+            balance: SUI - 1000,
+        };
+        first.release(updated);
+        let third = pool.lease().await?;
+        assert_eq!(third.coin(), updated);
+        assert!(node.store().submitted.is_empty());
+        drop(second);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn dropped_lease_is_refreshed() -> color_eyre::Result<()> {
+        let signer = KeyPair::new(1);
+        let owner = signer.address();
+        let node = MockNode::new();
+        node.store().insert_object(coin(1, owner, SUI));
+        let server = node.start().await?;
+        let client = server.client().await?;
+        let pool = GasPool::new(client, owner, signer, config(1));
+        pool.load().await?;
+
+        drop(pool.lease().await?);
+        assert!(pool.idle_coins().is_empty());
+
+        // The coin was used by a transaction the pool doesn't know about
+        let mut contents = ObjectId::new([1; 32]).into_inner().to_vec();
+        contents.extend_from_slice(&(SUI / 2).to_le_bytes());
+        let data = MoveStruct::new(StructTag::gas_coin(), true, 7, contents).unwrap();
+        node.store().insert_object(Object::new(
+            ObjectData::Struct(data),
+            sui_sdk_types::Owner::Address(owner),
+            TransactionDigest::ZERO,
+            100,
+        ));
+
+        let lease = pool.lease().await?;
+        assert_eq!(lease.coin().object_ref.1, 7);
+        assert_eq!(lease.coin().balance, SUI / 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rebalance() -> color_eyre::Result<()> {
+        let signer = KeyPair::new(1);
+        let owner = signer.address();
+        let node = MockNode::new();
+        node.store().insert_object(coin(1, owner, SUI));
+        node.store().insert_object(coin(2, owner, SUI / 100));
+        let server = node.start().await?;
+        let client = server.client().await?;
+
+        let pool = GasPool::new(client.clone(), owner, KeyPair::new(1), config(100));
+        let err = pool.load().await.unwrap_err();
+        assert!(
+            matches!(err, GasPoolError::InsufficientBalance { .. }),
+            "{err}"
+        );
+        // Nothing was submitted, so the coins are still usable
+        assert_eq!(pool.idle_coins().len(), 1);
+
+        // The mock node has no execution response to return
+        let pool = GasPool::new(client, owner, signer, config(3));
+        assert!(pool.load().await.is_err());
+        let submitted = node.store().submitted.clone();
+        let [TransactionData::V1(tx)] = &submitted[..] else {
+            panic!("Expected a single transaction, got {submitted:?}");
+        };
+        assert_eq!(tx.gas_data.payment.len(), 2);
+        assert_eq!(tx.gas_data.payment[0].0, ObjectId::new([1; 32]));
+        let TransactionKind::ProgrammableTransaction(ptb) = &tx.kind else {
+            panic!("Not a PTB");
+        };
+        let Command::SplitCoins(split) = &ptb.commands[0] else {
+            panic!("Not a split");
+        };
+        assert_eq!(split.amounts.len(), 2);
+        let amount = (SUI + SUI / 100 - SUI / 20) / 3;
+        assert_eq!(
+            ptb.inputs[0],
+            Input::Pure {
+                value: bcs::to_bytes(&amount)?
+            }
+        );
+
+        // The coins' state is unknown, so they're queried again before leasing
+        assert!(pool.idle_coins().is_empty());
+        assert_eq!(pool.lease().await?.coin().balance, SUI);
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+//! SuiNS resolution through the JSON-RPC indexer API.
+use af_sui_types::Address as SuiAddress;
+use af_sui_types::name_service::{NameServiceResolver, SuinsName};
+
+use super::{SuiClient, SuiClientError};
+use crate::api::IndexerApiClient as _;
+
+impl NameServiceResolver for SuiClient {
+    type Error = SuiClientError;
+
+    async fn resolve_name(&self, name: &SuinsName) -> Result<Option<SuiAddress>, Self::Error> {
+        Ok(self
+            .http()
+            .resolve_name_service_address(name.to_string())
+            .await?)
+    }
+
+    async fn default_name(&self, address: SuiAddress) -> Result<Option<SuinsName>, Self::Error> {
+        let page = self
+            .http()
+            .resolve_name_service_names(address, None, Some(1))
+            .await?;
+        page.data
+            .into_iter()
+            .next()
+            .map(|name| {
+                name.parse()
+                    .map_err(|e| SuiClientError::DataError(format!("Invalid name {name:?}: {e}")))
+            })
+            .transpose()
+    }
+}
+
+#[cfg(all(test, feature = "test-support"))]
+#[tokio::test]
+async fn name_service() -> color_eyre::Result<()> {
+    use std::time::Duration;
+
+    use af_sui_types::name_service::NameServiceCache;
+
+    use crate::mock::MockNode;
+
+    let address = SuiAddress::new([1; 32]);
+    let node = MockNode::new();
+    node.store().names.insert("example.sui".into(), address);
+    let server = node.start().await?;
+    let client = server.client().await?;
+
+    let cache = NameServiceCache::new(client, Duration::from_secs(60));
+    let name = "example.sui".parse()?;
+    assert_eq!(cache.resolve(&name).await?, address);
+    assert_eq!(
+        cache.default_name(address).await?.map(|n| n.to_string()),
+        Some("example.sui".into())
+    );
+    // Served from the cache
+    node.store().names.clear();
+    assert_eq!(cache.resolve(&name).await?, address);
+    assert_eq!(
+        cache.resolver().resolve_name(&"other.sui".parse()?).await?,
+        None
+    );
+    Ok(())
+}
@@ -0,0 +1,470 @@
+//! Polling-based replacements for the deprecated JSON-RPC subscriptions.
+//!
+//! The streams here only request the next page when the caller asks for more items, so a slow
+//! consumer never makes them buffer more than a page. When caught up with the node, they wait for
+//! [`PollOptions::interval`] before asking again.
+//!
+//! Every item carries its own cursor (checkpoint sequence number, [`EventID`] or transaction
+//! digest). Persisting the cursor of the last processed item and passing it back when
+//! re-creating the stream resumes from right after that item, e.g., after an error ends the stream.
+//!
+//! # Consistency checks
+//!
+//! Only [`SuiClient::poll_checkpoints`] can detect skipped items, since checkpoint sequence
+//! numbers are contiguous. The event and transaction streams are filtered by the node, so a
+//! missing item is indistinguishable from one that didn't match. Those streams only check that the
+//! node doesn't go back in time, i.e., that timestamps or checkpoints don't regress.
+use std::time::Duration;
+
+use af_sui_types::TransactionDigest;
+use futures_core::Stream;
+
+use super::{SuiClient, SuiClientError};
+use crate::api::{IndexerApiClient as _, ReadApiClient as _};
+use crate::msgs::{
+    Checkpoint,
+    EventFilter,
+    EventID,
+    SuiEvent,
+    SuiTransactionBlockResponse,
+    SuiTransactionBlockResponseQuery,
+};
+
+/// How long the streams wait before polling again once they've caught up with the node.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configuration for the polling streams.
+#[derive(Clone, Copy, Debug)]
+pub struct PollOptions {
+    /// How long to wait before polling again after reaching the latest data.
+    pub interval: Duration,
+    /// Page size for the inner queries. The node's default if `None`.
+    pub page_size: Option<usize>,
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_POLL_INTERVAL,
+            page_size: None,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PollError {
+    #[error(transparent)]
+    Client(#[from] SuiClientError),
+    #[error("Expected checkpoint {expected}, got {found}")]
+    CheckpointGap { expected: u64, found: u64 },
+    #[error("Event {id:?} is from {found}ms, before the previous one's {previous}ms")]
+    TimestampRegression {
+        id: EventID,
+        previous: u64,
+        found: u64,
+    },
+    #[error(
+        "Transaction {digest} is from checkpoint {found}, before the previous one's {previous}"
+    )]
+    CheckpointRegression {
+        digest: TransactionDigest,
+        previous: u64,
+        found: u64,
+    },
+}
+
+impl PollError {
+    fn rpc(error: crate::error::JsonRpcClientError) -> Self {
+        Self::Client(error.into())
+    }
+}
+
+/// Polling streams.
+impl SuiClient {
+    /// **Streamed** checkpoints in ascending order, starting at `start` or at the latest one if
+    /// `None`.
+    ///
+    /// The stream never ends on its own. It fails if the node skips a checkpoint.
+    pub fn poll_checkpoints(
+        &self,
+        start: Option<u64>,
+        options: PollOptions,
+    ) -> impl Stream<Item = Result<Checkpoint, PollError>> + Send + '_ {
+        async_stream::try_stream! {
+            let mut expected = match start {
+                Some(start) => start,
+                None => self
+                    .http()
+                    .get_latest_checkpoint_sequence_number()
+                    .await
+                    .map_err(PollError::rpc)?
+                    .into_inner(),
+            };
+            loop {
+                let cursor = expected.checked_sub(1).map(Into::into);
+                let page = self
+                    .http()
+                    .get_checkpoints(cursor, options.page_size, false)
+                    .await
+                    .map_err(PollError::rpc)?;
+                for checkpoint in page.data {
+                    let found = checkpoint.sequence_number;
+                    if found != expected {
+                        Err(PollError::CheckpointGap { expected, found })?;
+                    }
+                    expected += 1;
+                    yield checkpoint;
+                }
+                if !page.has_next_page {
+                    tokio::time::sleep(options.interval).await;
+                }
+            }
+        }
+    }
+
+    /// **Streamed** events matching `filter` in ascending order, starting after `cursor` or after
+    /// the latest matching event if `None`.
+    ///
+    /// The stream never ends on its own. It fails if the node returns an event older than the one
+    /// before it, but can't detect skipped events; see the [module documentation](self).
+    pub fn poll_events(
+        &self,
+        filter: EventFilter,
+        cursor: Option<EventID>,
+        options: PollOptions,
+    ) -> impl Stream<Item = Result<SuiEvent, PollError>> + Send + '_ {
+        async_stream::try_stream! {
+            let mut cursor = match cursor {
+                Some(cursor) => Some(cursor),
+                None => self
+                    .http()
+                    .query_events(filter.clone(), None, Some(1), Some(true))
+                    .await
+                    .map_err(PollError::rpc)?
+                    .data
+                    .first()
+                    .map(|event| event.id),
+            };
+            let mut last_timestamp = None;
+            loop {
+                let page = self
+                    .http()
+                    .query_events(filter.clone(), cursor, options.page_size, Some(false))
+                    .await
+                    .map_err(PollError::rpc)?;
+                for event in page.data {
+                    check_order(last_timestamp, event.timestamp_ms, |previous, found| {
+                        PollError::TimestampRegression { id: event.id, previous, found }
+                    })?;
+                    last_timestamp = event.timestamp_ms.or(last_timestamp);
+                    cursor = Some(event.id);
+                    yield event;
+                }
+                if !page.has_next_page {
+                    tokio::time::sleep(options.interval).await;
+                }
+            }
+        }
+    }
+
+    /// **Streamed** transactions matching `query` in ascending order, starting after `cursor` or
+    /// after the latest matching transaction if `None`.
+    ///
+    /// Use the query's options to choose which of the transaction's data (effects, events, etc.)
+    /// to include. The stream never ends on its own. It fails if the node returns a transaction
+    /// from an earlier checkpoint than the one before it, but can't detect skipped transactions;
+    /// see the [module documentation](self).
+    pub fn poll_transaction_blocks(
+        &self,
+        query: SuiTransactionBlockResponseQuery,
+        cursor: Option<TransactionDigest>,
+        options: PollOptions,
+    ) -> impl Stream<Item = Result<SuiTransactionBlockResponse, PollError>> + Send + '_ {
+        async_stream::try_stream! {
+            let mut cursor = match cursor {
+                Some(cursor) => Some(cursor),
+                None => self
+                    .http()
+                    .query_transaction_blocks(query.clone(), None, Some(1), Some(true))
+                    .await
+                    .map_err(PollError::rpc)?
+                    .data
+                    .first()
+                    .map(|tx| tx.digest),
+            };
+            let mut last_checkpoint = None;
+            loop {
+                let page = self
+                    .http()
+                    .query_transaction_blocks(query.clone(), cursor, options.page_size, Some(false))
+                    .await
+                    .map_err(PollError::rpc)?;
+                for tx in page.data {
+                    check_order(last_checkpoint, tx.checkpoint, |previous, found| {
+                        PollError::CheckpointRegression { digest: tx.digest, previous, found }
+                    })?;
+                    last_checkpoint = tx.checkpoint.or(last_checkpoint);
+                    cursor = Some(tx.digest);
+                    yield tx;
+                }
+                if !page.has_next_page {
+                    tokio::time::sleep(options.interval).await;
+                }
+            }
+        }
+    }
+}
+
+/// Fails if `found` is known to come before `previous`.
+fn check_order(
+    previous: Option<u64>,
+    found: Option<u64>,
+    err: impl FnOnce(u64, u64) -> PollError,
+) -> Result<(), PollError> {
+    match (previous, found) {
+        (Some(previous), Some(found)) if found < previous => Err(err(previous, found)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamp_order() {
+        let id = EventID {
+            tx_digest: TransactionDigest::ZERO,
+            event_seq: 0,
+        };
+        let err = |previous, found| PollError::TimestampRegression {
+            id,
+            previous,
+            found,
+        };
+        assert!(check_order(None, Some(1), err).is_ok());
+        assert!(check_order(Some(1), None, err).is_ok());
+        assert!(check_order(Some(1), Some(1), err).is_ok());
+        assert!(check_order(Some(1), Some(2), err).is_ok());
+        assert!(matches!(
+            check_order(Some(2), Some(1), err),
+            Err(PollError::TimestampRegression {
+                previous: 2,
+                found: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn checkpoint_order() {
+        let err = |previous, found| PollError::CheckpointRegression {
+            digest: TransactionDigest::ZERO,
+            previous,
+            found,
+        };
+        assert!(check_order(Some(3), Some(3), err).is_ok());
+        assert!(matches!(
+            check_order(Some(3), Some(2), err),
+            Err(PollError::CheckpointRegression {
+                previous: 3,
+                found: 2,
+                ..
+            })
+        ));
+    }
+
+    #[cfg(feature = "test-support")]
+    mod streams {
+        use std::pin::pin;
+
+        use af_sui_types::{Address as SuiAddress, CheckpointDigest, GasCostSummary, ObjectId};
+        use futures::{StreamExt as _, TryStreamExt as _};
+        use sui_sdk_types::Bls12381Signature;
+
+        use super::*;
+        use crate::mock::{MockNode, MockServer};
+        use crate::msgs::SuiTransactionBlockEvents;
+
+        const OPTIONS: PollOptions = PollOptions {
+            interval: Duration::from_millis(10),
+            page_size: Some(2),
+        };
+
+        /// Long enough for a caught up stream to poll a few times.
+        const IDLE: Duration = Duration::from_millis(50);
+
+        fn checkpoint(sequence_number: u64) -> Checkpoint {
+            Checkpoint {
+                epoch: 0,
+                sequence_number,
+                digest: CheckpointDigest::ZERO,
+                network_total_transactions: sequence_number,
+                previous_digest: None,
+                epoch_rolling_gas_cost_summary: GasCostSummary::default(),
+                timestamp_ms: sequence_number,
+                end_of_epoch_data: None,
+                transactions: vec![],
+                checkpoint_commitments: vec![],
+                validator_signature: Bls12381Signature::new([0; 48]),
+            }
+        }
+
+        fn digest(i: u8) -> TransactionDigest {
+            TransactionDigest::new([i; 32])
+        }
+
+        /// Transaction `i` in checkpoint `checkpoint`, with one event at `checkpoint` ms.
+        fn transaction(i: u8, checkpoint: u64) -> SuiTransactionBlockResponse {
+            let event = SuiEvent {
+                id: EventID {
+                    tx_digest: digest(i),
+                    event_seq: 0,
+                },
+                package_id: ObjectId::new([2; 32]),
+                transaction_module: "pool".parse().unwrap(),
+                sender: SuiAddress::new([1; 32]),
+                type_: "0x2::pool::Swap".parse().unwrap(),
+                parsed_json: serde_json::Value::Null,
+                bcs: vec![],
+                timestamp_ms: Some(checkpoint),
+            };
+            let mut tx = SuiTransactionBlockResponse::new(digest(i));
+            tx.checkpoint = Some(checkpoint);
+            tx.events = Some(SuiTransactionBlockEvents { data: vec![event] });
+            tx
+        }
+
+        async fn start(node: &MockNode) -> (MockServer, SuiClient) {
+            let server = node.start().await.unwrap();
+            let client = server.client().await.unwrap();
+            (server, client)
+        }
+
+        #[tokio::test]
+        async fn checkpoints_across_polls() {
+            let node = MockNode::new();
+            node.store().checkpoints = (0..3).map(checkpoint).collect();
+            let (_server, client) = start(&node).await;
+            let mut stream = pin!(client.poll_checkpoints(Some(1), OPTIONS));
+
+            let mut numbers = vec![];
+            for _ in 0..2 {
+                numbers.push(stream.try_next().await.unwrap().unwrap().sequence_number);
+            }
+            // Caught up: polling again doesn't repeat the last checkpoint
+            assert!(tokio::time::timeout(IDLE, stream.try_next()).await.is_err());
+
+            node.store().checkpoints.extend((3..6).map(checkpoint));
+            for _ in 0..3 {
+                numbers.push(stream.try_next().await.unwrap().unwrap().sequence_number);
+            }
+            assert_eq!(numbers, [1, 2, 3, 4, 5]);
+        }
+
+        #[tokio::test]
+        async fn checkpoints_from_latest() {
+            let node = MockNode::new();
+            node.store().checkpoints = (0..3).map(checkpoint).collect();
+            let (_server, client) = start(&node).await;
+            let mut stream = pin!(client.poll_checkpoints(None, OPTIONS));
+            let first = stream.try_next().await.unwrap().unwrap();
+            assert_eq!(first.sequence_number, 2);
+        }
+
+        #[tokio::test]
+        async fn checkpoint_gap() {
+            let node = MockNode::new();
+            node.store().checkpoints = [0, 1, 3].map(checkpoint).into();
+            let (_server, client) = start(&node).await;
+            let mut stream = pin!(client.poll_checkpoints(Some(0), OPTIONS));
+            stream.try_next().await.unwrap();
+            stream.try_next().await.unwrap();
+            let err = stream.try_next().await.unwrap_err();
+            assert!(matches!(
+                err,
+                PollError::CheckpointGap {
+                    expected: 2,
+                    found: 3
+                }
+            ));
+            // The stream ends after an error
+            assert!(stream.try_next().await.unwrap().is_none());
+        }
+
+        #[tokio::test]
+        async fn client_errors_end_the_stream() {
+            // No checkpoints, so the node can't tell the latest one
+            let (_server, client) = start(&MockNode::new()).await;
+            let mut stream = pin!(client.poll_checkpoints(None, OPTIONS));
+            let err = stream.try_next().await.unwrap_err();
+            assert!(matches!(err, PollError::Client(_)), "{err}");
+            assert!(stream.try_next().await.unwrap().is_none());
+        }
+
+        #[tokio::test]
+        async fn events_resume_from_cursor() {
+            let node = MockNode::new();
+            node.store().transactions = (1..=3).map(|i| transaction(i, i.into())).collect();
+            let (_server, client) = start(&node).await;
+            let filter = EventFilter::All([]);
+
+            // Without a cursor, only events after the latest one are streamed
+            let mut stream = pin!(client.poll_events(filter.clone(), None, OPTIONS));
+            assert!(tokio::time::timeout(IDLE, stream.try_next()).await.is_err());
+            node.store().transactions.push(transaction(4, 4));
+            let event = stream.try_next().await.unwrap().unwrap();
+            assert_eq!(event.id.tx_digest, digest(4));
+
+            // Resuming after the first event streams all the others, once
+            let cursor = EventID {
+                tx_digest: digest(1),
+                event_seq: 0,
+            };
+            let stream = client.poll_events(filter, Some(cursor), OPTIONS);
+            let digests: Vec<_> = stream
+                .map_ok(|event| event.id.tx_digest)
+                .take(3)
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(digests, [digest(2), digest(3), digest(4)]);
+        }
+
+        #[tokio::test]
+        async fn transactions_resume_from_cursor() {
+            let node = MockNode::new();
+            node.store().transactions = (1..=4).map(|i| transaction(i, i.into())).collect();
+            let (_server, client) = start(&node).await;
+            let query = SuiTransactionBlockResponseQuery::default();
+
+            let stream = client.poll_transaction_blocks(query, Some(digest(2)), OPTIONS);
+            let mut stream = pin!(stream.map_ok(|tx| tx.digest));
+            assert_eq!(stream.try_next().await.unwrap(), Some(digest(3)));
+            assert_eq!(stream.try_next().await.unwrap(), Some(digest(4)));
+            assert!(tokio::time::timeout(IDLE, stream.try_next()).await.is_err());
+            node.store().transactions.push(transaction(5, 5));
+            assert_eq!(stream.try_next().await.unwrap(), Some(digest(5)));
+        }
+
+        #[tokio::test]
+        async fn transaction_checkpoint_regression() {
+            let node = MockNode::new();
+            node.store().transactions =
+                vec![transaction(0, 5), transaction(1, 5), transaction(2, 4)];
+            let (_server, client) = start(&node).await;
+            let query = SuiTransactionBlockResponseQuery::default();
+            let mut stream = pin!(client.poll_transaction_blocks(query, Some(digest(0)), OPTIONS));
+            stream.try_next().await.unwrap();
+            let err = stream.try_next().await.unwrap_err();
+            assert!(matches!(
+                err,
+                PollError::CheckpointRegression {
+                    previous: 5,
+                    found: 4,
+                    ..
+                }
+            ));
+        }
+    }
+}
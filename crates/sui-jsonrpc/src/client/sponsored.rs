@@ -0,0 +1,444 @@
+//! Sponsored transactions, where the gas is paid by an address other than the sender.
+//!
+//! A sponsored transaction needs the signatures of both its sender and the owner of its gas coins
+//! (the sponsor). The sponsor is abstracted as a [`GasStation`]: something that reserves gas
+//! coins for a transaction and later co-signs it. [`LocalGasStation`] is a stand-in that pays
+//! with coins of a local key, useful for tests and for services that sponsor their own users.
+//!
+//! [`SuiClient::execute_sponsored`] runs the whole workflow:
+//! 1. reserve gas with the [`GasStation`];
+//! 2. check that the reserved coins are up-to-date SUI coins owned by the sponsor;
+//! 3. sign for the sender with a [`TransactionSigner`] and for the sponsor with the station;
+//! 4. submit the transaction with both signatures.
+use std::future::Future;
+
+use af_keys::signer::TransactionSigner;
+use af_sui_types::{
+    Address as SuiAddress,
+    GasData,
+    ObjectId,
+    ObjectRef,
+    TransactionData,
+    TransactionDataV1,
+    TransactionExpiration,
+    TransactionKind,
+    UserSignature,
+};
+
+use super::{BoxError, GetGasDataError, SuiClient, SuiClientError};
+use crate::api::GovernanceReadApiClient as _;
+use crate::error::JsonRpcClientError;
+use crate::msgs::{
+    ObjectType,
+    Owner,
+    SuiObjectDataOptions,
+    SuiTransactionBlockResponse,
+    SuiTransactionBlockResponseOptions,
+};
+
+/// A service paying for the gas of other addresses' transactions.
+pub trait GasStation {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Reserve gas coins to pay for `tx_kind`, sent by `sender`, with up to `budget` MIST.
+    ///
+    /// The owner of the returned [`GasData`] is the sponsor.
+    fn reserve_gas(
+        &self,
+        tx_kind: &TransactionKind,
+        sender: SuiAddress,
+        budget: u64,
+    ) -> impl Future<Output = Result<GasData, Self::Error>> + Send;
+
+    /// Sign a transaction using previously reserved gas as its sponsor.
+    fn sponsor_signature(
+        &self,
+        tx_data: &TransactionData,
+    ) -> impl Future<Output = Result<UserSignature, Self::Error>> + Send;
+}
+
+/// [`GasStation`] paying with the SUI coins of a single sponsor.
+///
+/// It doesn't keep track of the coins it reserved, so concurrent transactions may end up using the
+/// same coins.
+#[derive(Clone, Debug)]
+pub struct LocalGasStation<S> {
+    client: SuiClient,
+    sponsor: SuiAddress,
+    signer: S,
+    gas_price: Option<u64>,
+}
+
+impl<S> LocalGasStation<S> {
+    /// Sponsor transactions with the coins of `sponsor`, signing with `signer`.
+    ///
+    /// Uses the network's reference gas price, see [`Self::with_gas_price`].
+    pub const fn new(client: SuiClient, sponsor: SuiAddress, signer: S) -> Self {
+        Self {
+            client,
+            sponsor,
+            signer,
+            gas_price: None,
+        }
+    }
+
+    /// Use a fixed gas price instead of querying the reference one for every transaction.
+    pub const fn with_gas_price(mut self, price: u64) -> Self {
+        self.gas_price = Some(price);
+        self
+    }
+
+    pub const fn sponsor(&self) -> SuiAddress {
+        self.sponsor
+    }
+}
+
+impl<S: TransactionSigner + Sync> GasStation for LocalGasStation<S> {
+    type Error = LocalGasStationError;
+
+    async fn reserve_gas(
+        &self,
+        tx_kind: &TransactionKind,
+        _: SuiAddress,
+        budget: u64,
+    ) -> Result<GasData, Self::Error> {
+        let price = match self.gas_price {
+            Some(price) => price,
+            None => self
+                .client
+                .http()
+                .get_reference_gas_price()
+                .await?
+                .into_inner(),
+        };
+        Ok(self
+            .client
+            .get_gas_data(tx_kind, self.sponsor, budget, price)
+            .await?)
+    }
+
+    async fn sponsor_signature(
+        &self,
+        tx_data: &TransactionData,
+    ) -> Result<UserSignature, Self::Error> {
+        let TransactionData::V1(TransactionDataV1 { gas_data, .. }) = tx_data;
+        if gas_data.owner != self.sponsor {
+            return Err(LocalGasStationError::WrongSponsor {
+                expected: self.sponsor,
+                found: gas_data.owner,
+            });
+        }
+        self.signer
+            .sign_transaction(self.sponsor, tx_data)
+            .await
+            .map_err(|e| LocalGasStationError::Signing(e.into()))
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum LocalGasStationError {
+    #[error("In JSON-RPC client: {0}")]
+    Client(#[from] JsonRpcClientError),
+    #[error(transparent)]
+    GasData(#[from] GetGasDataError),
+    #[error("Transaction gas is owned by {found}, not by the sponsor {expected}")]
+    WrongSponsor {
+        expected: SuiAddress,
+        found: SuiAddress,
+    },
+    #[error("Signing as sponsor: {0}")]
+    Signing(BoxError),
+}
+
+/// A transaction to be paid by a [`GasStation`], see [`SuiClient::execute_sponsored`].
+#[derive(Clone, Debug)]
+pub struct SponsoredTransaction {
+    pub kind: TransactionKind,
+    pub sender: SuiAddress,
+    /// Most MIST the sponsor pays for gas.
+    pub budget: u64,
+    /// Use it to make the transaction invalid after an epoch, e.g., so that the reserved gas isn't
+    /// held indefinitely if submission fails.
+    pub expiration: TransactionExpiration,
+}
+
+impl SponsoredTransaction {
+    /// A transaction that never expires, see [`Self::with_expiration`].
+    pub const fn new(kind: TransactionKind, sender: SuiAddress, budget: u64) -> Self {
+        Self {
+            kind,
+            sender,
+            budget,
+            expiration: TransactionExpiration::None,
+        }
+    }
+
+    pub const fn with_expiration(mut self, expiration: TransactionExpiration) -> Self {
+        self.expiration = expiration;
+        self
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SponsoredTxError {
+    #[error("Reserving gas: {0}")]
+    GasStation(BoxError),
+    #[error(transparent)]
+    Client(#[from] SuiClientError),
+    #[error("Gas coin {id} is owned by {owner:?}, not by the sponsor {sponsor}")]
+    GasCoinOwner {
+        id: ObjectId,
+        sponsor: SuiAddress,
+        owner: Option<Owner>,
+    },
+    #[error("Object {id} of type {type_:?} is not a SUI coin")]
+    NotAGasCoin {
+        id: ObjectId,
+        type_: Option<ObjectType>,
+    },
+    #[error("Gas coin reference {expected:?} is outdated, the latest is {found:?}")]
+    StaleGasCoin {
+        expected: ObjectRef,
+        found: ObjectRef,
+    },
+    #[error("Queried {expected} gas coins, but the node returned {found} objects")]
+    GasCoinCount { expected: usize, found: usize },
+    #[error("Signing as sender: {0}")]
+    SenderSignature(BoxError),
+    #[error("Signing as sponsor: {0}")]
+    SponsorSignature(BoxError),
+    #[error("Submitting transaction: {0}")]
+    Submit(#[from] JsonRpcClientError),
+}
+
+/// Sponsored transactions.
+impl SuiClient {
+    /// Build a sponsored transaction, sign it for both the sender and sponsor and submit it.
+    ///
+    /// The gas coins reserved by the `station` are checked with [`Self::check_gas_payment`] before
+    /// anyone signs.
+    pub async fn execute_sponsored<S, G>(
+        &self,
+        tx: SponsoredTransaction,
+        signer: &S,
+        station: &G,
+        options: Option<SuiTransactionBlockResponseOptions>,
+    ) -> Result<SuiTransactionBlockResponse, SponsoredTxError>
+    where
+        S: TransactionSigner + Sync,
+        G: GasStation + Sync,
+    {
+        let SponsoredTransaction {
+            kind,
+            sender,
+            budget,
+            expiration,
+        } = tx;
+        let gas_data = station
+            .reserve_gas(&kind, sender, budget)
+            .await
+            .map_err(|e| SponsoredTxError::GasStation(e.into()))?;
+        self.check_gas_payment(&gas_data).await?;
+
+        let tx_data = TransactionData::V1(TransactionDataV1 {
+            kind,
+            sender,
+            gas_data,
+            expiration,
+        });
+        let sender_signature = signer
+            .sign_transaction(sender, &tx_data)
+            .await
+            .map_err(|e| SponsoredTxError::SenderSignature(e.into()))?;
+        let sponsor_signature = station
+            .sponsor_signature(&tx_data)
+            .await
+            .map_err(|e| SponsoredTxError::SponsorSignature(e.into()))?;
+
+        Ok(self
+            .submit_transaction(&tx_data, &[sender_signature, sponsor_signature], options)
+            .await?)
+    }
+
+    /// Check that the gas payment consists of the latest versions of SUI coins owned by the gas
+    /// owner.
+    pub async fn check_gas_payment(&self, gas_data: &GasData) -> Result<(), SponsoredTxError> {
+        let options = SuiObjectDataOptions::new().with_owner().with_type();
+        let objects = self
+            .multi_get_objects(gas_data.payment.iter().map(|(id, _, _)| *id), options)
+            .await?;
+        if objects.len() != gas_data.payment.len() {
+            return Err(SponsoredTxError::GasCoinCount {
+                expected: gas_data.payment.len(),
+                found: objects.len(),
+            });
+        }
+        for (expected, response) in gas_data.payment.iter().zip(objects) {
+            let object = response.into_object().map_err(SuiClientError::from)?;
+            let found = object.object_ref();
+            if found != *expected {
+                return Err(SponsoredTxError::StaleGasCoin {
+                    expected: *expected,
+                    found,
+                });
+            }
+            if object.owner != Some(Owner::AddressOwner(gas_data.owner)) {
+                return Err(SponsoredTxError::GasCoinOwner {
+                    id: object.object_id,
+                    sponsor: gas_data.owner,
+                    owner: object.owner,
+                });
+            }
+            if !object.is_gas_coin() {
+                return Err(SponsoredTxError::NotAGasCoin {
+                    id: object.object_id,
+                    type_: object.type_,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-support")]
+pub(super) mod tests {
+    use af_keys::crypto::{Signature, SuiKeyPair};
+    use af_sui_types::{Object, ProgrammableTransaction, StructTag, TransactionDigest};
+    use sui_sdk_types::{MoveStruct, ObjectData};
+
+    use super::*;
+    use crate::mock::MockNode;
+
+    pub(in crate::client) struct KeyPair(SuiKeyPair);
+
+    impl KeyPair {
+        pub(in crate::client) fn new(seed: u8) -> Self {
+            let mut bytes = [seed; 33];
+            bytes[0] = 0; // Ed25519 flag
+            Self(SuiKeyPair::from_bytes(&bytes).unwrap())
+        }
+
+        pub(in crate::client) fn address(&self) -> SuiAddress {
+            self.0.public().to_sui_address()
+        }
+    }
+
+    impl TransactionSigner for KeyPair {
+        type Error = signature::Error;
+
+        async fn sign_transaction(
+            &self,
+            address: SuiAddress,
+            tx_data: &TransactionData,
+        ) -> Result<UserSignature, Self::Error> {
+            if address != self.address() {
+                return Err(signature::Error::new());
+            }
+            Ok(Signature::new_hashed(&tx_data.signing_digest(), &self.0).into())
+        }
+    }
+
+    pub(in crate::client) fn coin(id: u8, owner: SuiAddress, balance: u64) -> Object {
+        let mut contents = ObjectId::new([id; 32]).into_inner().to_vec();
+        contents.extend_from_slice(&balance.to_le_bytes());
+        let data = MoveStruct::new(StructTag::gas_coin(), true, 1, contents).unwrap();
+        Object::new(
+            ObjectData::Struct(data),
+            sui_sdk_types::Owner::Address(owner),
+            TransactionDigest::ZERO,
+            100,
+        )
+    }
+
+    pub(in crate::client) const fn empty_ptb() -> TransactionKind {
+        TransactionKind::ProgrammableTransaction(ProgrammableTransaction {
+            inputs: vec![],
+            commands: vec![],
+        })
+    }
+
+    #[tokio::test]
+    async fn execute_sponsored() -> color_eyre::Result<()> {
+        let (sender, sponsor) = (KeyPair::new(1), KeyPair::new(2));
+        let node = MockNode::new();
+        {
+            let mut store = node.store();
+            store.insert_object(coin(1, sponsor.address(), 5_000_000));
+            store.insert_object(coin(2, sender.address(), 5_000_000));
+            store
+                .execution_responses
+                .push_back(SuiTransactionBlockResponse::new(TransactionDigest::ZERO));
+        }
+        let server = node.start().await?;
+        let client = server.client().await?;
+        let station = LocalGasStation::new(client.clone(), sponsor.address(), sponsor);
+
+        client
+            .execute_sponsored(
+                SponsoredTransaction::new(empty_ptb(), sender.address(), 1_000_000)
+                    .with_expiration(TransactionExpiration::Epoch(5)),
+                &sender,
+                &station,
+                None,
+            )
+            .await?;
+
+        let submitted = node.store().submitted.clone();
+        let [TransactionData::V1(tx)] = &submitted[..] else {
+            panic!("Expected a single transaction, got {submitted:?}");
+        };
+        assert_eq!(tx.sender, sender.address());
+        assert_eq!(tx.gas_data.owner, station.sponsor());
+        assert_eq!(tx.gas_data.price, crate::mock::MOCK_REFERENCE_GAS_PRICE);
+        assert_eq!(tx.gas_data.payment.len(), 1);
+        assert_eq!(tx.gas_data.payment[0].0, ObjectId::new([1; 32]));
+        assert_eq!(tx.expiration, TransactionExpiration::Epoch(5));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn invalid_gas_payment() -> color_eyre::Result<()> {
+        let (sponsor, other) = (SuiAddress::new([1; 32]), SuiAddress::new([2; 32]));
+        let node = MockNode::new();
+        let (ours, theirs) = (coin(1, sponsor, 10), coin(2, other, 10));
+        let oref = |o: &Object| (o.object_id(), o.version(), o.digest());
+        let gas_data = |payment| GasData {
+            payment,
+            owner: sponsor,
+            price: 1000,
+            budget: 10,
+        };
+        {
+            let mut store = node.store();
+            store.insert_object(ours.clone());
+            store.insert_object(theirs.clone());
+        }
+        let server = node.start().await?;
+        let client = server.client().await?;
+
+        client
+            .check_gas_payment(&gas_data(vec![oref(&ours)]))
+            .await?;
+
+        let err = client
+            .check_gas_payment(&gas_data(vec![oref(&ours), oref(&theirs)]))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, SponsoredTxError::GasCoinOwner { id, .. } if id == theirs.object_id()),
+            "{err}"
+        );
+
+        let (id, _, digest) = oref(&ours);
+        let err = client
+            .check_gas_payment(&gas_data(vec![(id, 99, digest)]))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, SponsoredTxError::StaleGasCoin { .. }),
+            "{err}"
+        );
+        Ok(())
+    }
+}
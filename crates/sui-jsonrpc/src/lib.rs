@@ -4,14 +4,19 @@
 
 //! A fork of Mysten's `sui-json-rpc-api` and `sui-json-rpc-types` with minimal dependencies for client applications.
 
+pub mod abi;
 #[cfg(feature = "client-api")]
 pub mod api;
 #[cfg(feature = "client")]
 pub mod client;
 #[cfg(feature = "client-api")]
 pub mod error;
+#[cfg(feature = "test-support")]
+pub mod mock;
 pub mod msgs;
 pub mod serde;
+#[cfg(feature = "staking")]
+pub mod staking;
 
 pub const RPC_QUERY_MAX_RESULT_LIMIT: &str = "RPC_QUERY_MAX_RESULT_LIMIT";
 pub const DEFAULT_RPC_QUERY_MAX_RESULT_LIMIT: usize = 50;
@@ -0,0 +1,1329 @@
+//! In-process mock of a Sui JSON-RPC node, for running tests without a network.
+//!
+//! [`MockNode`] serves the [`ReadApi`], [`CoinReadApi`], [`WriteApi`], [`IndexerApi`],
+//! [`GovernanceReadApi`] and [`MoveUtils`] over a [`Store`] that tests populate beforehand (and may keep modifying
+//! while the server runs). [`MockNode::start`] binds the server to a local port so that a regular
+//! [`SuiClient`] can connect to it:
+//!
+//! ```rust,no_run
+//! use sui_jsonrpc::mock::MockNode;
+//!
+//! # async fn example(object: af_sui_types::Object) -> color_eyre::Result<()> {
+//! let node = MockNode::new();
+//! node.store().insert_object(object);
+//! let server = node.start().await?;
+//! let client = server.client().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The mock is not an execution engine. Objects are served as stored, with their type, owner, BCS
+//! contents, previous transaction and storage rebate, but never with parsed `content` or `display`.
+//! Responses for executing, dry-running or dev-inspecting transactions must be set in the
+//! [`Store`] in advance, and the submitted transaction bytes are recorded for later inspection.
+//! Subscriptions are rejected.
+//!
+//! [`ReadApi`]: crate::api::ReadApiServer
+//! [`CoinReadApi`]: crate::api::CoinReadApiServer
+//! [`WriteApi`]: crate::api::WriteApiServer
+//! [`IndexerApi`]: crate::api::IndexerApiServer
+//! [`GovernanceReadApi`]: crate::api::GovernanceReadApiServer
+//! [`MoveUtils`]: crate::api::MoveUtilsServer
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use af_sui_types::{
+    Address as SuiAddress,
+    GAS_MODULE_NAME,
+    GAS_STRUCT_NAME,
+    MoveObjectType,
+    Object,
+    ObjectId,
+    SUI_FRAMEWORK_ADDRESS,
+    StructTag,
+    TransactionData,
+    TransactionDigest,
+    TypeTag,
+};
+use jsonrpsee::PendingSubscriptionSink;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::server::{Server, ServerHandle};
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::types::error::{INVALID_PARAMS_CODE, METHOD_NOT_FOUND_CODE};
+use serde_json::json;
+use sui_sdk_types::{ObjectData, Version};
+
+use crate::DEFAULT_RPC_QUERY_MAX_RESULT_LIMIT;
+use crate::abi::PackageAbi;
+use crate::api::{
+    CoinReadApiServer,
+    GovernanceReadApiServer,
+    IndexerApiServer,
+    MoveUtilsServer,
+    ReadApiServer,
+    WriteApiServer,
+};
+use crate::client::{SuiClient, SuiClientBuilder, SuiClientResult};
+use crate::msgs::{
+    Balance,
+    Checkpoint,
+    CheckpointId,
+    CheckpointPage,
+    Coin,
+    CoinPage,
+    DelegatedStake,
+    DevInspectArgs,
+    DevInspectResults,
+    DryRunTransactionBlockResponse,
+    DynamicFieldInfo,
+    DynamicFieldName,
+    DynamicFieldPage,
+    EventFilter,
+    EventID,
+    EventPage,
+    ExecuteTransactionRequestType,
+    MoveFunctionArgType,
+    ObjectType,
+    ObjectValueKind,
+    ObjectsPage,
+    Page,
+    ProtocolConfigResponse,
+    SuiCoinMetadata,
+    SuiCommittee,
+    SuiEvent,
+    SuiGetPastObjectRequest,
+    SuiMoveNormalizedFunction,
+    SuiMoveNormalizedModule,
+    SuiMoveNormalizedStruct,
+    SuiMoveNormalizedType,
+    SuiObjectData,
+    SuiObjectDataFilter,
+    SuiObjectDataOptions,
+    SuiObjectResponse,
+    SuiObjectResponseError,
+    SuiObjectResponseQuery,
+    SuiPastObjectResponse,
+    SuiRawData,
+    SuiRawMoveObject,
+    SuiRawMovePackage,
+    SuiSystemStateSummary,
+    SuiTransactionBlockDataAPI as _,
+    SuiTransactionBlockEffectsAPI as _,
+    SuiTransactionBlockResponse,
+    SuiTransactionBlockResponseOptions,
+    SuiTransactionBlockResponseQuery,
+    Supply,
+    TransactionBlocksPage,
+    TransactionFilter,
+    ValidatorApys,
+};
+use crate::serde::BigInt;
+
+/// Chain identifier reported by a default [`Store`].
+pub const MOCK_CHAIN_IDENTIFIER: &str = "00000000";
+/// Reference gas price reported by a default [`Store`].
+pub const MOCK_REFERENCE_GAS_PRICE: u64 = 1000;
+
+/// The data served by a [`MockNode`].
+///
+/// Objects are kept with their whole version history; use [`Store::insert_object`] to add a new
+/// version. Everything else is set directly through the public fields.
+#[derive(Debug)]
+pub struct Store {
+    objects: BTreeMap<ObjectId, BTreeMap<Version, Object>>,
+    /// Executed transactions, in execution order. Their events are served by the events queries.
+    pub transactions: Vec<SuiTransactionBlockResponse>,
+    /// Checkpoints, in ascending order.
+    pub checkpoints: Vec<Checkpoint>,
+    /// Dynamic fields by parent object.
+    pub dynamic_fields: BTreeMap<ObjectId, Vec<DynamicFieldInfo>>,
+    /// Coin metadata by coin type.
+    pub coin_metadata: HashMap<TypeTag, SuiCoinMetadata>,
+    /// Total supply by coin type.
+    pub total_supply: HashMap<TypeTag, u64>,
+    /// `.sui` names and the addresses they point to.
+    pub names: BTreeMap<String, SuiAddress>,
+    /// Stakes by owner.
+    pub stakes: HashMap<SuiAddress, Vec<DelegatedStake>>,
+    /// Normalized Move modules by package.
+    pub packages: HashMap<ObjectId, PackageAbi>,
+    pub chain_identifier: String,
+    pub reference_gas_price: u64,
+    pub protocol_config: Option<ProtocolConfigResponse>,
+    pub system_state: Option<SuiSystemStateSummary>,
+    pub committee: Option<SuiCommittee>,
+    pub validators_apy: Option<ValidatorApys>,
+    /// Responses to successive transaction executions. Each response is also appended to
+    /// [`Self::transactions`].
+    pub execution_responses: VecDeque<SuiTransactionBlockResponse>,
+    /// Response to every dry-run.
+    pub dry_run_response: Option<DryRunTransactionBlockResponse>,
+    /// Response to every dev-inspect.
+    pub dev_inspect_results: Option<DevInspectResults>,
+    /// Transactions received by the execution, dry-run and dev-inspect methods, in order.
+    pub submitted: Vec<TransactionData>,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self {
+            objects: Default::default(),
+            transactions: Default::default(),
+            checkpoints: Default::default(),
+            dynamic_fields: Default::default(),
+            coin_metadata: Default::default(),
+            total_supply: Default::default(),
+            names: Default::default(),
+            stakes: Default::default(),
+            packages: Default::default(),
+            chain_identifier: MOCK_CHAIN_IDENTIFIER.to_owned(),
+            reference_gas_price: MOCK_REFERENCE_GAS_PRICE,
+            protocol_config: None,
+            system_state: None,
+            committee: None,
+            validators_apy: None,
+            execution_responses: Default::default(),
+            dry_run_response: None,
+            dev_inspect_results: None,
+            submitted: Default::default(),
+        }
+    }
+}
+
+impl Store {
+    /// Add an object or a new version of an existing one.
+    pub fn insert_object(&mut self, object: Object) {
+        self.objects
+            .entry(object.object_id())
+            .or_default()
+            .insert(object.version(), object);
+    }
+
+    /// Remove every version of an object, returning the latest one.
+    pub fn remove_object(&mut self, id: ObjectId) -> Option<Object> {
+        self.objects.remove(&id)?.pop_last().map(|(_, o)| o)
+    }
+
+    /// The latest version of an object.
+    pub fn object(&self, id: ObjectId) -> Option<&Object> {
+        self.objects.get(&id)?.last_key_value().map(|(_, o)| o)
+    }
+
+    /// Latest versions of all objects, ordered by ID.
+    pub fn objects(&self) -> impl Iterator<Item = &Object> + '_ {
+        self.objects
+            .values()
+            .filter_map(|v| v.last_key_value())
+            .map(|(_, o)| o)
+    }
+
+    fn coins(&self, owner: SuiAddress) -> impl Iterator<Item = (TypeTag, Coin)> + '_ {
+        self.objects().filter_map(move |object| {
+            if *object.owner() != sui_sdk_types::Owner::Address(owner) {
+                return None;
+            }
+            let struct_ = object.as_struct()?;
+            let coin_type =
+                MoveObjectType::from(struct_.object_type().clone()).coin_type_maybe()?;
+            let balance = struct_.contents().get(32..40)?.try_into().ok()?;
+            let coin = Coin {
+                coin_type: coin_type.to_string(),
+                coin_object_id: object.object_id(),
+                version: object.version(),
+                digest: object.digest(),
+                balance: u64::from_le_bytes(balance),
+                previous_transaction: object.previous_transaction(),
+            };
+            Some((coin_type, coin))
+        })
+    }
+
+    fn events(&self) -> impl Iterator<Item = &SuiEvent> + '_ {
+        self.transactions
+            .iter()
+            .filter_map(|tx| tx.events.as_ref())
+            .flat_map(|events| &events.data)
+    }
+
+    fn transaction(&self, digest: TransactionDigest) -> RpcResult<&SuiTransactionBlockResponse> {
+        self.transactions
+            .iter()
+            .find(|tx| tx.digest == digest)
+            .ok_or_else(|| not_found(format!("transaction {digest}")))
+    }
+
+    fn object_response(&self, id: ObjectId, options: &SuiObjectDataOptions) -> SuiObjectResponse {
+        self.object(id).map_or_else(
+            || {
+                SuiObjectResponse::new(
+                    None,
+                    Some(SuiObjectResponseError::NotExists { object_id: id }),
+                )
+            },
+            |object| SuiObjectResponse::new_with_data(object_data(object, options)),
+        )
+    }
+
+    fn owned_objects(
+        &self,
+        address: SuiAddress,
+        filter: Option<&SuiObjectDataFilter>,
+        options: &SuiObjectDataOptions,
+    ) -> RpcResult<Vec<SuiObjectResponse>> {
+        let mut objects = vec![];
+        for object in self.objects() {
+            let owned = match object.owner() {
+                sui_sdk_types::Owner::Address(owner)
+                | sui_sdk_types::Owner::ConsensusAddress { owner, .. } => *owner == address,
+                _ => false,
+            };
+            if owned && filter.map_or(Ok(true), |f| object_matches(f, object))? {
+                objects.push(SuiObjectResponse::new_with_data(object_data(
+                    object, options,
+                )));
+            }
+        }
+        Ok(objects)
+    }
+
+    fn dynamic_field_object(
+        &self,
+        parent_object_id: ObjectId,
+        name: &DynamicFieldName,
+    ) -> SuiObjectResponse {
+        self.dynamic_fields
+            .get(&parent_object_id)
+            .into_iter()
+            .flatten()
+            .find(|field| field.name.type_ == name.type_ && field.name.value == name.value)
+            .map_or_else(
+                || {
+                    SuiObjectResponse::new(
+                        None,
+                        Some(SuiObjectResponseError::DynamicFieldNotFound { parent_object_id }),
+                    )
+                },
+                |field| {
+                    self.object_response(field.object_id, &SuiObjectDataOptions::full_content())
+                },
+            )
+    }
+
+    fn checkpoints_after(&self, cursor: Option<u64>, descending_order: bool) -> Vec<Checkpoint> {
+        let mut checkpoints: Vec<_> = self
+            .checkpoints
+            .iter()
+            .filter(|c| match cursor {
+                Some(cursor) if descending_order => c.sequence_number < cursor,
+                Some(cursor) => c.sequence_number > cursor,
+                None => true,
+            })
+            .cloned()
+            .collect();
+        if descending_order {
+            checkpoints.reverse();
+        }
+        checkpoints
+    }
+
+    fn transactions_matching(
+        &self,
+        filter: Option<&TransactionFilter>,
+    ) -> RpcResult<Vec<SuiTransactionBlockResponse>> {
+        let mut txs = vec![];
+        for tx in &self.transactions {
+            if filter.map_or(Ok(true), |f| transaction_matches(f, tx))? {
+                txs.push(tx.clone());
+            }
+        }
+        Ok(txs)
+    }
+
+    fn events_matching(&self, filter: &EventFilter) -> Vec<SuiEvent> {
+        self.events()
+            .filter(|event| event_matches(filter, event))
+            .cloned()
+            .collect()
+    }
+
+    fn execute(&mut self, tx_bytes: &str) -> RpcResult<SuiTransactionBlockResponse> {
+        self.submit(tx_bytes)?;
+        let response = self
+            .execution_responses
+            .pop_front()
+            .ok_or_else(|| not_found("execution response"))?;
+        self.transactions.push(response.clone());
+        Ok(response)
+    }
+
+    fn past_object(
+        &self,
+        id: ObjectId,
+        version: Version,
+        options: &SuiObjectDataOptions,
+    ) -> SuiPastObjectResponse {
+        let Some(versions) = self.objects.get(&id) else {
+            return SuiPastObjectResponse::ObjectNotExists(id);
+        };
+        if let Some(object) = versions.get(&version) {
+            return SuiPastObjectResponse::VersionFound(object_data(object, options));
+        }
+        match versions.last_key_value() {
+            Some((&latest_version, _)) if latest_version < version => {
+                SuiPastObjectResponse::VersionTooHigh {
+                    object_id: id,
+                    asked_version: version,
+                    latest_version,
+                }
+            }
+            _ => SuiPastObjectResponse::VersionNotFound(id, version),
+        }
+    }
+
+    fn submit(&mut self, tx_bytes: &str) -> RpcResult<()> {
+        let tx = TransactionData::decode_base64(tx_bytes).map_err(invalid_params)?;
+        self.submitted.push(tx);
+        Ok(())
+    }
+}
+
+/// A mock Sui node. Cheap to clone; all clones share the same [`Store`].
+#[derive(Clone, Debug, Default)]
+pub struct MockNode {
+    store: Arc<Mutex<Store>>,
+}
+
+impl MockNode {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_store(store: Store) -> Self {
+        Self {
+            store: Arc::new(Mutex::new(store)),
+        }
+    }
+
+    /// Lock the store to inspect or modify it.
+    ///
+    /// Don't hold the guard across `.await` points, as the server blocks on it.
+    pub fn store(&self) -> MutexGuard<'_, Store> {
+        self.store
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Start serving on a random local port.
+    ///
+    /// The server stops once the returned [`MockServer`] is dropped.
+    pub async fn start(&self) -> std::io::Result<MockServer> {
+        let server = Server::builder().build("127.0.0.1:0").await?;
+        let addr = server.local_addr()?;
+        let handle = server.start(self.clone().into_rpc());
+        Ok(MockServer { addr, handle })
+    }
+
+    /// All the supported methods, including `rpc.discover`.
+    pub fn into_rpc(self) -> jsonrpsee::RpcModule<()> {
+        let mut module = jsonrpsee::RpcModule::new(());
+        module
+            .merge(ReadApiServer::into_rpc(self.clone()))
+            .expect("No duplicate methods");
+        module
+            .merge(CoinReadApiServer::into_rpc(self.clone()))
+            .expect("No duplicate methods");
+        module
+            .merge(WriteApiServer::into_rpc(self.clone()))
+            .expect("No duplicate methods");
+        module
+            .merge(IndexerApiServer::into_rpc(self.clone()))
+            .expect("No duplicate methods");
+        module
+            .merge(GovernanceReadApiServer::into_rpc(self.clone()))
+            .expect("No duplicate methods");
+        module
+            .merge(MoveUtilsServer::into_rpc(self))
+            .expect("No duplicate methods");
+
+        let spec = json!({
+            "info": { "version": env!("CARGO_PKG_VERSION") },
+            "methods": module
+                .method_names()
+                .map(|name| json!({ "name": name }))
+                .collect::<Vec<_>>(),
+        });
+        module
+            .register_method("rpc.discover", move |_, _, _| spec.clone())
+            .expect("No duplicate methods");
+        module
+    }
+}
+
+/// Handle to a running [`MockNode`] server.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    handle: ServerHandle,
+}
+
+impl MockServer {
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// HTTP URL of the server.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// A client connected to this server.
+    pub async fn client(&self) -> SuiClientResult<SuiClient> {
+        SuiClientBuilder::default().build(self.url()).await
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        // Errors if the server already stopped, which is what we want anyway
+        let _ = self.handle.stop();
+    }
+}
+
+// =============================================================================
+//  Server implementations
+// =============================================================================
+
+#[jsonrpsee::core::async_trait]
+impl ReadApiServer for MockNode {
+    async fn get_transaction_block(
+        &self,
+        digest: TransactionDigest,
+        _: Option<SuiTransactionBlockResponseOptions>,
+    ) -> RpcResult<SuiTransactionBlockResponse> {
+        self.store().transaction(digest).cloned()
+    }
+
+    async fn multi_get_transaction_blocks(
+        &self,
+        digests: Vec<TransactionDigest>,
+        _: Option<SuiTransactionBlockResponseOptions>,
+    ) -> RpcResult<Vec<SuiTransactionBlockResponse>> {
+        let store = self.store();
+        digests
+            .into_iter()
+            .map(|digest| store.transaction(digest).cloned())
+            .collect()
+    }
+
+    async fn get_object(
+        &self,
+        object_id: ObjectId,
+        options: Option<SuiObjectDataOptions>,
+    ) -> RpcResult<SuiObjectResponse> {
+        Ok(self
+            .store()
+            .object_response(object_id, &options.unwrap_or_default()))
+    }
+
+    async fn multi_get_objects(
+        &self,
+        object_ids: Vec<ObjectId>,
+        options: Option<SuiObjectDataOptions>,
+    ) -> RpcResult<Vec<SuiObjectResponse>> {
+        let options = options.unwrap_or_default();
+        let store = self.store();
+        Ok(object_ids
+            .into_iter()
+            .map(|id| store.object_response(id, &options))
+            .collect())
+    }
+
+    async fn try_get_past_object(
+        &self,
+        object_id: ObjectId,
+        version: Version,
+        options: Option<SuiObjectDataOptions>,
+    ) -> RpcResult<SuiPastObjectResponse> {
+        Ok(self
+            .store()
+            .past_object(object_id, version, &options.unwrap_or_default()))
+    }
+
+    async fn try_multi_get_past_objects(
+        &self,
+        past_objects: Vec<SuiGetPastObjectRequest>,
+        options: Option<SuiObjectDataOptions>,
+    ) -> RpcResult<Vec<SuiPastObjectResponse>> {
+        let options = options.unwrap_or_default();
+        let store = self.store();
+        Ok(past_objects
+            .into_iter()
+            .map(|req| store.past_object(req.object_id, req.version, &options))
+            .collect())
+    }
+
+    async fn get_checkpoint(&self, id: CheckpointId) -> RpcResult<Checkpoint> {
+        self.store()
+            .checkpoints
+            .iter()
+            .find(|c| match &id {
+                CheckpointId::SequenceNumber(seq) => c.sequence_number == *seq,
+                CheckpointId::Digest(digest) => c.digest == *digest,
+            })
+            .cloned()
+            .ok_or_else(|| not_found(format!("checkpoint {id:?}")))
+    }
+
+    async fn get_checkpoints(
+        &self,
+        cursor: Option<BigInt<u64>>,
+        limit: Option<usize>,
+        descending_order: bool,
+    ) -> RpcResult<CheckpointPage> {
+        let checkpoints = self
+            .store()
+            .checkpoints_after(cursor.map(BigInt::into_inner), descending_order);
+        Ok(page(checkpoints, None, limit, |c| c.sequence_number.into()))
+    }
+
+    async fn get_events(&self, transaction_digest: TransactionDigest) -> RpcResult<Vec<SuiEvent>> {
+        Ok(self
+            .store()
+            .transaction(transaction_digest)?
+            .events
+            .as_ref()
+            .map(|events| events.data.clone())
+            .unwrap_or_default())
+    }
+
+    async fn get_total_transaction_blocks(&self) -> RpcResult<BigInt<u64>> {
+        Ok((self.store().transactions.len() as u64).into())
+    }
+
+    async fn get_latest_checkpoint_sequence_number(&self) -> RpcResult<BigInt<u64>> {
+        self.store()
+            .checkpoints
+            .last()
+            .map(|c| c.sequence_number.into())
+            .ok_or_else(|| not_found("checkpoints"))
+    }
+
+    async fn get_protocol_config(
+        &self,
+        _: Option<BigInt<u64>>,
+    ) -> RpcResult<ProtocolConfigResponse> {
+        self.store()
+            .protocol_config
+            .clone()
+            .ok_or_else(|| not_found("protocol config"))
+    }
+
+    async fn get_chain_identifier(&self) -> RpcResult<String> {
+        Ok(self.store().chain_identifier.clone())
+    }
+}
+
+#[jsonrpsee::core::async_trait]
+impl CoinReadApiServer for MockNode {
+    async fn get_coins(
+        &self,
+        owner: SuiAddress,
+        coin_type: Option<String>,
+        cursor: Option<String>,
+        limit: Option<usize>,
+    ) -> RpcResult<CoinPage> {
+        let coin_type = parse_coin_type(coin_type)?;
+        let coins = self
+            .store()
+            .coins(owner)
+            .filter(|(type_, _)| *type_ == coin_type)
+            .map(|(_, coin)| coin)
+            .collect();
+        Ok(page(coins, cursor, limit, |c| c.coin_object_id.to_string()))
+    }
+
+    async fn get_all_coins(
+        &self,
+        owner: SuiAddress,
+        cursor: Option<String>,
+        limit: Option<usize>,
+    ) -> RpcResult<CoinPage> {
+        let coins = self.store().coins(owner).map(|(_, coin)| coin).collect();
+        Ok(page(coins, cursor, limit, |c| c.coin_object_id.to_string()))
+    }
+
+    async fn get_balance(
+        &self,
+        owner: SuiAddress,
+        coin_type: Option<String>,
+    ) -> RpcResult<Balance> {
+        let coin_type = parse_coin_type(coin_type)?;
+        let mut balance = Balance::zero(coin_type.to_string());
+        for (_, coin) in self.store().coins(owner).filter(|(t, _)| *t == coin_type) {
+            balance.coin_object_count += 1;
+            balance.total_balance += u128::from(coin.balance);
+        }
+        Ok(balance)
+    }
+
+    async fn get_all_balances(&self, owner: SuiAddress) -> RpcResult<Vec<Balance>> {
+        let mut balances = BTreeMap::<String, Balance>::new();
+        for (coin_type, coin) in self.store().coins(owner) {
+            let coin_type = coin_type.to_string();
+            let balance = balances
+                .entry(coin_type.clone())
+                .or_insert_with(|| Balance::zero(coin_type));
+            balance.coin_object_count += 1;
+            balance.total_balance += u128::from(coin.balance);
+        }
+        Ok(balances.into_values().collect())
+    }
+
+    async fn get_coin_metadata(&self, coin_type: String) -> RpcResult<Option<SuiCoinMetadata>> {
+        let coin_type = parse_coin_type(Some(coin_type))?;
+        Ok(self.store().coin_metadata.get(&coin_type).cloned())
+    }
+
+    async fn get_total_supply(&self, coin_type: String) -> RpcResult<Supply> {
+        let coin_type = parse_coin_type(Some(coin_type))?;
+        self.store()
+            .total_supply
+            .get(&coin_type)
+            .map(|&value| Supply { value })
+            .ok_or_else(|| not_found(format!("total supply of {coin_type}")))
+    }
+}
+
+#[jsonrpsee::core::async_trait]
+impl WriteApiServer for MockNode {
+    async fn execute_transaction_block(
+        &self,
+        tx_bytes: String,
+        _: Vec<String>,
+        _: Option<SuiTransactionBlockResponseOptions>,
+        _: Option<ExecuteTransactionRequestType>,
+    ) -> RpcResult<SuiTransactionBlockResponse> {
+        self.store().execute(&tx_bytes)
+    }
+
+    async fn monitored_execute_transaction_block(
+        &self,
+        tx_bytes: String,
+        signatures: Vec<String>,
+        options: Option<SuiTransactionBlockResponseOptions>,
+        request_type: Option<ExecuteTransactionRequestType>,
+        _: Option<SocketAddr>,
+    ) -> RpcResult<SuiTransactionBlockResponse> {
+        self.execute_transaction_block(tx_bytes, signatures, options, request_type)
+            .await
+    }
+
+    async fn dev_inspect_transaction_block(
+        &self,
+        _: SuiAddress,
+        tx_bytes: String,
+        _: Option<BigInt<u64>>,
+        _: Option<BigInt<u64>>,
+        _: Option<DevInspectArgs>,
+    ) -> RpcResult<DevInspectResults> {
+        let mut store = self.store();
+        store.submit(&tx_bytes)?;
+        store
+            .dev_inspect_results
+            .clone()
+            .ok_or_else(|| not_found("dev-inspect results"))
+    }
+
+    async fn dry_run_transaction_block(
+        &self,
+        tx_bytes: String,
+    ) -> RpcResult<DryRunTransactionBlockResponse> {
+        let mut store = self.store();
+        store.submit(&tx_bytes)?;
+        store
+            .dry_run_response
+            .clone()
+            .ok_or_else(|| not_found("dry-run response"))
+    }
+}
+
+#[jsonrpsee::core::async_trait]
+impl IndexerApiServer for MockNode {
+    async fn get_owned_objects(
+        &self,
+        address: SuiAddress,
+        query: Option<SuiObjectResponseQuery>,
+        cursor: Option<ObjectId>,
+        limit: Option<usize>,
+    ) -> RpcResult<ObjectsPage> {
+        let SuiObjectResponseQuery { filter, options } = query.unwrap_or_default();
+        let options = options.unwrap_or_default();
+        let objects = self
+            .store()
+            .owned_objects(address, filter.as_ref(), &options)?;
+        Ok(page(objects, cursor, limit, |o| {
+            o.data.as_ref().map_or(ObjectId::ZERO, |d| d.object_id)
+        }))
+    }
+
+    async fn query_transaction_blocks(
+        &self,
+        query: SuiTransactionBlockResponseQuery,
+        cursor: Option<TransactionDigest>,
+        limit: Option<usize>,
+        descending_order: Option<bool>,
+    ) -> RpcResult<TransactionBlocksPage> {
+        let mut txs = self.store().transactions_matching(query.filter.as_ref())?;
+        if descending_order.unwrap_or_default() {
+            txs.reverse();
+        }
+        Ok(page(txs, cursor, limit, |tx| tx.digest))
+    }
+
+    async fn query_events(
+        &self,
+        query: EventFilter,
+        cursor: Option<EventID>,
+        limit: Option<usize>,
+        descending_order: Option<bool>,
+    ) -> RpcResult<EventPage> {
+        let mut events = self.store().events_matching(&query);
+        if descending_order.unwrap_or_default() {
+            events.reverse();
+        }
+        Ok(page(events, cursor, limit, |event| event.id))
+    }
+
+    /// Dropping the pending sink rejects the subscription.
+    fn subscribe_event(&self, _: PendingSubscriptionSink, _: EventFilter) {}
+
+    fn subscribe_transaction(&self, _: PendingSubscriptionSink, _: TransactionFilter) {}
+
+    async fn get_dynamic_fields(
+        &self,
+        parent_object_id: ObjectId,
+        cursor: Option<ObjectId>,
+        limit: Option<usize>,
+    ) -> RpcResult<DynamicFieldPage> {
+        let fields = self
+            .store()
+            .dynamic_fields
+            .get(&parent_object_id)
+            .cloned()
+            .unwrap_or_default();
+        Ok(page(fields, cursor, limit, |field| field.object_id))
+    }
+
+    async fn get_dynamic_field_object(
+        &self,
+        parent_object_id: ObjectId,
+        name: DynamicFieldName,
+    ) -> RpcResult<SuiObjectResponse> {
+        Ok(self.store().dynamic_field_object(parent_object_id, &name))
+    }
+
+    async fn resolve_name_service_address(&self, name: String) -> RpcResult<Option<SuiAddress>> {
+        Ok(self.store().names.get(&name).copied())
+    }
+
+    async fn resolve_name_service_names(
+        &self,
+        address: SuiAddress,
+        _: Option<ObjectId>,
+        _: Option<usize>,
+    ) -> RpcResult<Page<String, ObjectId>> {
+        let names = self
+            .store()
+            .names
+            .iter()
+            .filter(|(_, a)| **a == address)
+            .map(|(name, _)| name.clone())
+            .collect();
+        Ok(Page {
+            data: names,
+            next_cursor: None,
+            has_next_page: false,
+        })
+    }
+}
+
+#[jsonrpsee::core::async_trait]
+impl GovernanceReadApiServer for MockNode {
+    async fn get_stakes_by_ids(
+        &self,
+        staked_sui_ids: Vec<ObjectId>,
+    ) -> RpcResult<Vec<DelegatedStake>> {
+        let store = self.store();
+        Ok(store
+            .stakes
+            .values()
+            .flatten()
+            .filter_map(|delegated| {
+                let stakes: Vec<_> = delegated
+                    .stakes
+                    .iter()
+                    .filter(|s| staked_sui_ids.contains(&s.staked_sui_id))
+                    .cloned()
+                    .collect();
+                (!stakes.is_empty()).then(|| DelegatedStake {
+                    stakes,
+                    ..delegated.clone()
+                })
+            })
+            .collect())
+    }
+
+    async fn get_stakes(&self, owner: SuiAddress) -> RpcResult<Vec<DelegatedStake>> {
+        Ok(self.store().stakes.get(&owner).cloned().unwrap_or_default())
+    }
+
+    async fn get_committee_info(&self, _: Option<BigInt<u64>>) -> RpcResult<SuiCommittee> {
+        self.store()
+            .committee
+            .clone()
+            .ok_or_else(|| not_found("committee"))
+    }
+
+    async fn get_latest_sui_system_state(&self) -> RpcResult<SuiSystemStateSummary> {
+        self.store()
+            .system_state
+            .clone()
+            .ok_or_else(|| not_found("system state"))
+    }
+
+    async fn get_reference_gas_price(&self) -> RpcResult<BigInt<u64>> {
+        Ok(self.store().reference_gas_price.into())
+    }
+
+    async fn get_validators_apy(&self) -> RpcResult<ValidatorApys> {
+        self.store()
+            .validators_apy
+            .clone()
+            .ok_or_else(|| not_found("validators APY"))
+    }
+}
+
+#[jsonrpsee::core::async_trait]
+impl MoveUtilsServer for MockNode {
+    async fn get_move_function_arg_types(
+        &self,
+        package: ObjectId,
+        module: String,
+        function: String,
+    ) -> RpcResult<Vec<MoveFunctionArgType>> {
+        let function = self
+            .get_normalized_move_function(package, module, function)
+            .await?;
+        Ok(function
+            .parameters
+            .iter()
+            .map(|param| match param {
+                SuiMoveNormalizedType::Struct { .. } => {
+                    MoveFunctionArgType::Object(ObjectValueKind::ByValue)
+                }
+                SuiMoveNormalizedType::Reference(inner)
+                    if matches!(**inner, SuiMoveNormalizedType::Struct { .. }) =>
+                {
+                    MoveFunctionArgType::Object(ObjectValueKind::ByImmutableReference)
+                }
+                SuiMoveNormalizedType::MutableReference(inner)
+                    if matches!(**inner, SuiMoveNormalizedType::Struct { .. }) =>
+                {
+                    MoveFunctionArgType::Object(ObjectValueKind::ByMutableReference)
+                }
+                _ => MoveFunctionArgType::Pure,
+            })
+            .collect())
+    }
+
+    async fn get_normalized_move_modules_by_package(
+        &self,
+        package: ObjectId,
+    ) -> RpcResult<PackageAbi> {
+        self.store()
+            .packages
+            .get(&package)
+            .cloned()
+            .ok_or_else(|| not_found(format!("package {package}")))
+    }
+
+    async fn get_normalized_move_module(
+        &self,
+        package: ObjectId,
+        module_name: String,
+    ) -> RpcResult<SuiMoveNormalizedModule> {
+        self.store()
+            .packages
+            .get(&package)
+            .and_then(|modules| modules.get(&module_name))
+            .cloned()
+            .ok_or_else(|| not_found(format!("module {package}::{module_name}")))
+    }
+
+    async fn get_normalized_move_struct(
+        &self,
+        package: ObjectId,
+        module_name: String,
+        struct_name: String,
+    ) -> RpcResult<SuiMoveNormalizedStruct> {
+        let module = self
+            .get_normalized_move_module(package, module_name.clone())
+            .await?;
+        module
+            .structs
+            .get(&struct_name)
+            .cloned()
+            .ok_or_else(|| not_found(format!("struct {package}::{module_name}::{struct_name}")))
+    }
+
+    async fn get_normalized_move_function(
+        &self,
+        package: ObjectId,
+        module_name: String,
+        function_name: String,
+    ) -> RpcResult<SuiMoveNormalizedFunction> {
+        let module = self
+            .get_normalized_move_module(package, module_name.clone())
+            .await?;
+        module
+            .exposed_functions
+            .get(&function_name)
+            .cloned()
+            .ok_or_else(|| {
+                not_found(format!(
+                    "function {package}::{module_name}::{function_name}"
+                ))
+            })
+    }
+}
+
+// =============================================================================
+//  Helpers
+// =============================================================================
+
+/// The items after `cursor`, up to `limit`.
+fn page<T, C: PartialEq>(
+    items: Vec<T>,
+    cursor: Option<C>,
+    limit: Option<usize>,
+    key: impl Fn(&T) -> C,
+) -> Page<T, C> {
+    let start = cursor.map_or(0, |cursor| {
+        items
+            .iter()
+            .position(|item| key(item) == cursor)
+            .map_or(items.len(), |i| i + 1)
+    });
+    let limit = limit
+        .unwrap_or(DEFAULT_RPC_QUERY_MAX_RESULT_LIMIT)
+        .clamp(1, DEFAULT_RPC_QUERY_MAX_RESULT_LIMIT);
+    let mut data: Vec<_> = items.into_iter().skip(start).collect();
+    let has_next_page = data.len() > limit;
+    data.truncate(limit);
+    Page {
+        next_cursor: data.last().map(key),
+        data,
+        has_next_page,
+    }
+}
+
+fn object_data(object: &Object, options: &SuiObjectDataOptions) -> SuiObjectData {
+    let (type_, bcs) = match object.data() {
+        ObjectData::Struct(s) => (
+            ObjectType::Struct(s.object_type().clone().into()),
+            SuiRawData::MoveObject(SuiRawMoveObject {
+                type_: s.object_type().clone(),
+                has_public_transfer: s.has_public_transfer(),
+                version: s.version(),
+                bcs_bytes: s.contents().to_vec(),
+            }),
+        ),
+        ObjectData::Package(p) => (
+            ObjectType::Package,
+            SuiRawData::Package(SuiRawMovePackage {
+                id: p.id,
+                version: p.version,
+                module_map: p
+                    .modules
+                    .iter()
+                    .map(|(name, bytes)| (name.to_string(), bytes.clone()))
+                    .collect(),
+                type_origin_table: p.type_origin_table.clone(),
+                linkage_table: p.linkage_table.clone(),
+            }),
+        ),
+    };
+    SuiObjectData {
+        object_id: object.object_id(),
+        version: object.version(),
+        digest: object.digest(),
+        type_: options.show_type.then_some(type_),
+        owner: options.show_owner.then(|| (*object.owner()).into()),
+        previous_transaction: options
+            .show_previous_transaction
+            .then(|| object.previous_transaction()),
+        storage_rebate: options.show_storage_rebate.then(|| object.storage_rebate()),
+        display: None,
+        content: None,
+        bcs: options.show_bcs.then_some(bcs),
+    }
+}
+
+fn object_matches(filter: &SuiObjectDataFilter, object: &Object) -> RpcResult<bool> {
+    use SuiObjectDataFilter as F;
+    let struct_tag = object.as_struct().map(|s| s.object_type());
+    Ok(match filter {
+        F::MatchAll(filters) => {
+            for f in filters {
+                if !object_matches(f, object)? {
+                    return Ok(false);
+                }
+            }
+            true
+        }
+        F::MatchAny(filters) => {
+            for f in filters {
+                if object_matches(f, object)? {
+                    return Ok(true);
+                }
+            }
+            false
+        }
+        F::MatchNone(filters) => {
+            for f in filters {
+                if object_matches(f, object)? {
+                    return Ok(false);
+                }
+            }
+            true
+        }
+        F::Package(package) => struct_tag.is_some_and(|t| ObjectId::from(t.address) == *package),
+        F::MoveModule { package, module } => {
+            struct_tag.is_some_and(|t| ObjectId::from(t.address) == *package && t.module == *module)
+        }
+        F::StructType(tag) => struct_tag.is_some_and(|t| {
+            // Like the node, match regardless of type parameters if none are given
+            t.address == tag.address
+                && t.module == tag.module
+                && t.name == tag.name
+                && (tag.type_params.is_empty() || t.type_params == tag.type_params)
+        }),
+        F::AddressOwner(address) => *object.owner() == sui_sdk_types::Owner::Address(*address),
+        F::ObjectOwner(id) => *object.owner() == sui_sdk_types::Owner::Object(*id),
+        F::ObjectId(id) => object.object_id() == *id,
+        F::ObjectIds(ids) => ids.contains(&object.object_id()),
+        F::Version(version) => object.version() == *version,
+    })
+}
+
+fn transaction_matches(
+    filter: &TransactionFilter,
+    tx: &SuiTransactionBlockResponse,
+) -> RpcResult<bool> {
+    use TransactionFilter as F;
+    Ok(match filter {
+        F::Checkpoint(checkpoint) => tx.checkpoint == Some(*checkpoint),
+        F::FromAddress(address) => tx
+            .transaction
+            .as_ref()
+            .is_some_and(|t| t.data.sender() == address),
+        F::ChangedObject(id) => tx.effects.as_ref().is_some_and(|effects| {
+            effects
+                .all_changed_objects()
+                .iter()
+                .any(|(oref, _)| oref.reference.object_id == *id)
+        }),
+        _ => return Err(unsupported(format!("transaction filter {filter:?}"))),
+    })
+}
+
+fn event_matches(filter: &EventFilter, event: &SuiEvent) -> bool {
+    use EventFilter as F;
+    match filter {
+        F::All(_) => true,
+        F::Sender(sender) => event.sender == *sender,
+        F::Transaction(digest) => event.id.tx_digest == *digest,
+        F::MoveModule { package, module } => {
+            event.package_id == *package && event.transaction_module == *module
+        }
+        F::MoveEventType(type_) => event.type_ == *type_,
+        F::MoveEventModule { package, module } => {
+            ObjectId::from(event.type_.address) == *package && event.type_.module == *module
+        }
+        F::TimeRange {
+            start_time,
+            end_time,
+        } => event
+            .timestamp_ms
+            .is_some_and(|t| (*start_time..*end_time).contains(&t)),
+    }
+}
+
+/// `Coin<SUI>` if `None`, like the node.
+fn parse_coin_type(coin_type: Option<String>) -> RpcResult<TypeTag> {
+    coin_type.map_or_else(
+        || {
+            Ok(TypeTag::Struct(Box::new(StructTag {
+                address: SUI_FRAMEWORK_ADDRESS,
+                module: GAS_MODULE_NAME.to_owned(),
+                name: GAS_STRUCT_NAME.to_owned(),
+                type_params: vec![],
+            })))
+        },
+        |s| s.parse().map_err(invalid_params),
+    )
+}
+
+fn not_found(what: impl std::fmt::Display) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(
+        INVALID_PARAMS_CODE,
+        format!("Mock node has no {what}"),
+        None::<()>,
+    )
+}
+
+fn unsupported(what: impl std::fmt::Display) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(
+        METHOD_NOT_FOUND_CODE,
+        format!("Mock node doesn't support {what}"),
+        None::<()>,
+    )
+}
+
+fn invalid_params(err: impl std::fmt::Display) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(INVALID_PARAMS_CODE, err.to_string(), None::<()>)
+}
+
+#[cfg(test)]
+mod tests {
+    use af_sui_types::coin_selection::LargestFirst;
+    use af_sui_types::{ObjectArg, ProgrammableTransaction, TransactionKind};
+    use futures::TryStreamExt as _;
+    use sui_sdk_types::{MoveStruct, Owner};
+
+    use super::*;
+    use crate::api::{CoinReadApiClient as _, ReadApiClient as _};
+    use crate::client::SuiClientError;
+
+    fn object(id: u8, type_: StructTag, owner: Owner, fields: &[u8]) -> Object {
+        let mut contents = ObjectId::new([id; 32]).into_inner().to_vec();
+        contents.extend_from_slice(fields);
+        let data = MoveStruct::new(type_, true, 1, contents).unwrap();
+        Object::new(
+            ObjectData::Struct(data),
+            owner,
+            TransactionDigest::ZERO,
+            100,
+        )
+    }
+
+    fn coin(id: u8, owner: SuiAddress, balance: u64) -> Object {
+        let owner = Owner::Address(owner);
+        object(id, StructTag::gas_coin(), owner, &balance.to_le_bytes())
+    }
+
+    #[tokio::test]
+    async fn coins() -> color_eyre::Result<()> {
+        let owner = SuiAddress::new([1; 32]);
+        let node = MockNode::new();
+        {
+            let mut store = node.store();
+            store.insert_object(coin(1, owner, 5));
+            store.insert_object(coin(2, owner, 10));
+            store.insert_object(coin(3, owner, 3));
+            store.insert_object(coin(4, SuiAddress::new([2; 32]), 100));
+            let usdc = StructTag::coin("0x2::usdc::USDC".parse()?);
+            store.insert_object(object(5, usdc, Owner::Address(owner), &7_u64.to_le_bytes()));
+        }
+        let server = node.start().await?;
+        let client = server.client().await?;
+
+        let coins: Vec<_> = client
+            .coins_for_address(owner, None, Some(1))
+            .try_collect()
+            .await?;
+        assert_eq!(coins.len(), 3);
+
+        let selection = client
+            .select_coins_with(owner, None, 12, LargestFirst)
+            .await?;
+        assert_eq!(selection.total, 15);
+        assert_eq!(selection.coins.len(), 2);
+
+        let err = client
+            .select_coins_with(owner, None, 19, LargestFirst)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            SuiClientError::InsufficientFunds {
+                found: 18,
+                requested: 19,
+                ..
+            }
+        ));
+
+        let balance = client
+            .http()
+            .get_balance(owner, Some("0x2::usdc::USDC".into()))
+            .await?;
+        assert_eq!(balance.total_balance, 7);
+        assert_eq!(client.http().get_all_balances(owner).await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn object_args() -> color_eyre::Result<()> {
+        let type_: StructTag = "0x2::clock::Clock".parse()?;
+        let shared = object(1, type_.clone(), Owner::Shared(1), &[]);
+        let owned = object(2, type_, Owner::Address(SuiAddress::new([1; 32])), &[]);
+        let node = MockNode::new();
+        node.store().insert_object(shared.clone());
+        node.store().insert_object(owned.clone());
+        let server = node.start().await?;
+        let client = server.client().await?;
+
+        assert_eq!(
+            client.get_shared_oarg(shared.object_id(), true).await?,
+            ObjectArg::SharedObject {
+                id: shared.object_id(),
+                initial_shared_version: 1,
+                mutable: true,
+            }
+        );
+        assert_eq!(
+            client.get_imm_or_owned_oarg(owned.object_id()).await?,
+            ObjectArg::ImmOrOwnedObject((owned.object_id(), 1, owned.digest()))
+        );
+        assert_eq!(client.full_object(owned.object_id()).await.unwrap(), owned);
+
+        let missing = client
+            .http()
+            .get_object(ObjectId::new([3; 32]), None)
+            .await?;
+        assert!(missing.data.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn submitted_transactions() -> color_eyre::Result<()> {
+        let node = MockNode::new();
+        let server = node.start().await?;
+        let client = server.client().await?;
+
+        let kind = TransactionKind::ProgrammableTransaction(ProgrammableTransaction {
+            inputs: vec![],
+            commands: vec![],
+        });
+        let sender = SuiAddress::new([1; 32]);
+        // No dry-run response was set
+        assert!(client.gas_budget(&kind, sender, 1000).await.is_err());
+        let submitted = node.store().submitted.clone();
+        assert_eq!(submitted.len(), 1);
+        let TransactionData::V1(tx) = &submitted[0];
+        assert_eq!(tx.sender, sender);
+        assert_eq!(tx.kind, kind);
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@
 
 use std::collections::HashMap;
 
+use af_sui_types::coin_selection::CoinRef;
 use af_sui_types::{EpochId, ObjectDigest, ObjectId, ObjectRef, TransactionDigest};
 use serde::{Deserialize, Serialize};
 use serde_with::{IfIsHumanReadable, serde_as};
@@ -54,6 +55,14 @@ impl Coin {
     pub fn object_ref(&self) -> ObjectRef {
         (self.coin_object_id, self.version, self.digest)
     }
+
+    /// Input for the strategies in [`af_sui_types::coin_selection`].
+    pub fn coin_ref(&self) -> CoinRef {
+        CoinRef {
+            object_ref: self.object_ref(),
+            balance: self.balance,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
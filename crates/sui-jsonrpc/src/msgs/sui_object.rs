@@ -522,6 +522,26 @@ impl From<Owner> for sui_sdk_types::Owner {
         }
     }
 }
+
+impl From<sui_sdk_types::Owner> for Owner {
+    fn from(value: sui_sdk_types::Owner) -> Self {
+        match value {
+            sui_sdk_types::Owner::Address(a) => Self::AddressOwner(a),
+            sui_sdk_types::Owner::Object(o) => Self::ObjectOwner(o.into()),
+            sui_sdk_types::Owner::Shared(initial_shared_version) => Self::Shared {
+                initial_shared_version,
+            },
+            sui_sdk_types::Owner::Immutable => Self::Immutable,
+            sui_sdk_types::Owner::ConsensusAddress {
+                start_version,
+                owner,
+            } => Self::ConsensusAddressOwner {
+                start_version,
+                owner,
+            },
+        }
+    }
+}
 // =============================================================================
 //  ObjectType
 // =============================================================================
@@ -23,6 +23,40 @@ pub struct ProtocolConfigResponse {
     pub attributes: BTreeMap<String, Option<SuiProtocolConfigValue>>,
 }
 
+impl ProtocolConfigResponse {
+    /// The value of an integer attribute, if set.
+    pub fn attribute_u64(&self, name: &str) -> Option<u64> {
+        match self.attributes.get(name)?.as_ref()? {
+            SuiProtocolConfigValue::U16(value) => Some((*value).into()),
+            SuiProtocolConfigValue::U32(value) => Some((*value).into()),
+            SuiProtocolConfigValue::U64(value) => Some(*value),
+            SuiProtocolConfigValue::F64(_) | SuiProtocolConfigValue::Bool(_) => None,
+        }
+    }
+
+    /// The limits on programmable transactions in this config.
+    ///
+    /// Limits missing from the config keep their default values.
+    #[cfg(feature = "ptbuilder")]
+    pub fn transaction_limits(&self) -> af_ptbuilder::limits::TransactionLimits {
+        let mut limits = af_ptbuilder::limits::TransactionLimits::default();
+        for (name, limit) in [
+            ("max_tx_size_bytes", &mut limits.max_tx_size_bytes),
+            ("max_input_objects", &mut limits.max_input_objects),
+            ("max_programmable_tx_commands", &mut limits.max_commands),
+            ("max_pure_argument_size", &mut limits.max_pure_argument_size),
+            ("max_arguments", &mut limits.max_arguments),
+            ("max_type_arguments", &mut limits.max_type_arguments),
+            ("max_tx_gas", &mut limits.max_tx_gas),
+        ] {
+            if let Some(value) = self.attribute_u64(name) {
+                *limit = value;
+            }
+        }
+        limits
+    }
+}
+
 #[serde_as]
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase", rename = "ProtocolConfigValue")]
@@ -33,3 +67,38 @@ pub enum SuiProtocolConfigValue {
     F64(#[serde_as(as = "DisplayFromStr")] f64),
     Bool(#[serde_as(as = "DisplayFromStr")] bool),
 }
+
+#[cfg(all(test, feature = "ptbuilder"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transaction_limits() {
+        let config: ProtocolConfigResponse = serde_json::from_value(serde_json::json!({
+            "minSupportedProtocolVersion": "1",
+            "maxSupportedProtocolVersion": "70",
+            "protocolVersion": "70",
+            "featureFlags": {},
+            "attributes": {
+                "max_tx_size_bytes": { "u64": "131072" },
+                "max_programmable_tx_commands": { "u32": "100" },
+                "max_arguments": { "u32": "64" },
+                "max_pure_argument_size": null,
+                "gas_rounding_step": { "u64": "1000" },
+            },
+        }))
+        .unwrap();
+        assert_eq!(config.attribute_u64("max_arguments"), Some(64));
+        assert_eq!(config.attribute_u64("max_pure_argument_size"), None);
+
+        let limits = config.transaction_limits();
+        let default = af_ptbuilder::limits::TransactionLimits::default();
+        assert_eq!(limits.max_commands, 100);
+        assert_eq!(limits.max_arguments, 64);
+        assert_eq!(limits.max_tx_size_bytes, 131072);
+        assert_eq!(
+            limits.max_pure_argument_size,
+            default.max_pure_argument_size
+        );
+    }
+}
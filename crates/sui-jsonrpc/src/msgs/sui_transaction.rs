@@ -5,11 +5,13 @@ use std::error::Error as StdError;
 use std::fmt::{self, Display, Formatter, Write};
 use std::str::FromStr;
 
+use af_sui_types::execution_failure::ExecutionFailure;
 use af_sui_types::{
     Address as SuiAddress,
     CheckpointDigest,
     ConsensusCommitDigest,
     EpochId,
+    ExecutionError,
     GasCostSummary,
     ObjectDigest,
     ObjectId,
@@ -245,10 +247,8 @@ impl SuiTransactionBlockResponse {
     /// Deserialized from the [`Self::raw_effects`] BCS bytes. If those bytes are empty, the result
     /// is `None`.
     pub fn sui_effects(&self) -> Result<Option<sui_sdk_types::TransactionEffects>, ToEffectsError> {
-        self.raw_effects
-            .len()
-            .ge(&0)
-            .then_some(bcs::from_bytes(&self.raw_effects))
+        (!self.raw_effects.is_empty())
+            .then(|| bcs::from_bytes(&self.raw_effects))
             .transpose()
             .map_err(From::from)
             .map_err(ToEffectsError::Generic)
@@ -490,6 +490,24 @@ pub fn get_new_package_upgrade_cap_from_response(
     })
 }
 
+/// The `UpgradeCap` of the package published or upgraded by the transaction, at its new version.
+///
+/// Unlike [`get_new_package_upgrade_cap_from_response`], this also finds the cap mutated by an
+/// upgrade.
+pub fn get_upgrade_cap_from_response(response: &SuiTransactionBlockResponse) -> Option<ObjectRef> {
+    response.object_changes.as_ref()?.iter().find_map(|change| {
+        let (ObjectChange::Created { object_type, .. } | ObjectChange::Mutated { object_type, .. }) =
+            change
+        else {
+            return None;
+        };
+        (object_type.address == SUI_FRAMEWORK_ADDRESS
+            && object_type.module.as_str() == "package"
+            && object_type.name.as_str() == "UpgradeCap")
+            .then(|| change.object_ref())
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename = "TransactionBlockKind", tag = "kind")]
 #[non_exhaustive]
@@ -1061,6 +1079,7 @@ impl Display for SuiExecutionStatus {
 
 impl SuiExecutionStatus {
     const MOVE_ABORT_PATTERN: &str = r#"MoveAbort\(MoveLocation \{ module: ModuleId \{ address: ([[:alnum:]]+), name: Identifier\("([[:word:]]+)"\) \}, function: (\d+), instruction: (\d+), function_name: Some\("([[:word:]]+)"\) \}, (\d+)\)"#;
+    const COMMAND_PATTERN: &str = r" in command (\d+)$";
 
     pub fn is_ok(&self) -> bool {
         matches!(self, SuiExecutionStatus::Success)
@@ -1094,6 +1113,32 @@ impl SuiExecutionStatus {
             matches.get(6)?.as_str().parse().ok()?,
         ))
     }
+
+    /// The structured failure, if any.
+    ///
+    /// The node only reports the error's text, so [`ExecutionFailure::error`] is only recovered
+    /// for [`MoveAbort`]s. The command index is recovered for all errors.
+    ///
+    /// [`MoveAbort`]: af_sui_types::ExecutionError::MoveAbort
+    pub fn as_execution_failure(&self) -> Option<ExecutionFailure> {
+        let Self::Failure { error } = self else {
+            return None;
+        };
+        let re = regex::Regex::new(Self::COMMAND_PATTERN).expect("Tested below");
+        let command = re
+            .captures(error)
+            .and_then(|matches| matches.get(1)?.as_str().parse().ok());
+        let failure = match self.as_move_abort() {
+            Some((location, code)) => {
+                ExecutionFailure::new(ExecutionError::MoveAbort { location, code }, command)
+            }
+            None => {
+                let message = re.replace(error, "").into_owned();
+                ExecutionFailure::unparsed(message, command)
+            }
+        };
+        Some(failure.with_raw(error.clone()))
+    }
 }
 
 #[serde_as]
@@ -1755,12 +1800,89 @@ mod tests {
         r#"MoveAbort(MoveLocation { module: ModuleId { address: 72a8715095cdc8442b4316f78802d7aefa2e6f0c3c6fac256ce81554034b0d4b, name: Identifier("clearing_house") }, function: 53, instruction: 32, function_name: Some("settled_liquidated_position") }, 2001) in command 3"#,
     ];
 
+    #[test]
+    fn upgrade_results() -> Result<()> {
+        let package_id = ObjectId::new([1; 32]);
+        let cap_id = ObjectId::new([2; 32]);
+        let sender = SuiAddress::ZERO;
+        let response = SuiTransactionBlockResponse {
+            object_changes: Some(vec![
+                ObjectChange::Mutated {
+                    sender,
+                    owner: Owner::AddressOwner(sender),
+                    object_type: "0x2::coin::Coin<0x2::sui::SUI>".parse()?,
+                    object_id: ObjectId::new([3; 32]),
+                    version: 5,
+                    previous_version: 4,
+                    digest: ObjectDigest::ZERO,
+                },
+                ObjectChange::Mutated {
+                    sender,
+                    owner: Owner::AddressOwner(sender),
+                    object_type: "0x2::package::UpgradeCap".parse()?,
+                    object_id: cap_id,
+                    version: 5,
+                    previous_version: 4,
+                    digest: ObjectDigest::ZERO,
+                },
+                ObjectChange::Published {
+                    package_id,
+                    version: 2,
+                    digest: ObjectDigest::ZERO,
+                    modules: vec!["example".into()],
+                },
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            get_new_package_obj_from_response(&response),
+            Some((package_id, 2, ObjectDigest::ZERO))
+        );
+        assert_eq!(get_new_package_upgrade_cap_from_response(&response), None);
+        assert_eq!(
+            get_upgrade_cap_from_response(&response),
+            Some((cap_id, 5, ObjectDigest::ZERO))
+        );
+        Ok(())
+    }
+
     #[test]
     fn move_abort_regex_is_valid() -> Result<()> {
         regex::Regex::new(SuiExecutionStatus::MOVE_ABORT_PATTERN)?;
+        regex::Regex::new(SuiExecutionStatus::COMMAND_PATTERN)?;
         Ok(())
     }
 
+    #[test]
+    fn execution_failure_extracts() {
+        let failures: Vec<_> = MOVE_ABORT_ERRORS
+            .into_iter()
+            .map(|msg| {
+                SuiExecutionStatus::Failure { error: msg.into() }
+                    .as_execution_failure()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(failures[0].command, None);
+        assert_eq!(failures[1].move_abort().unwrap().1, 4);
+        assert_eq!(failures[2].command, Some(3));
+        assert_eq!(failures[2].move_abort().unwrap().1, 2001);
+        assert_eq!(failures[2].raw.as_deref(), Some(MOVE_ABORT_ERRORS[2]));
+
+        let status = SuiExecutionStatus::Failure {
+            error: "InsufficientCoinBalance in command 1".into(),
+        };
+        let failure = status.as_execution_failure().unwrap();
+        assert_eq!(failure.error, None);
+        assert_eq!(failure.command, Some(1));
+        assert_eq!(failure.to_string(), "InsufficientCoinBalance in command 1");
+        assert_eq!(
+            failure.raw.as_deref(),
+            Some("InsufficientCoinBalance in command 1")
+        );
+        assert_eq!(SuiExecutionStatus::Success.as_execution_failure(), None);
+    }
+
     #[test]
     fn move_abort_extracts() -> Result<()> {
         let expected = [
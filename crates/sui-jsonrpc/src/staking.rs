@@ -0,0 +1,444 @@
+//! Staking and governance workflows on top of the [`GovernanceReadApi`].
+//!
+//! - [`ValidatorStats`] gathers what matters when choosing a validator: APY, commission and share
+//!   of the total stake;
+//! - [`ValidatorSelection`] filters and ranks validators by those;
+//! - [`StakingPtbExt`] adds the `sui_system` staking calls to a [`ProgrammableTransactionBuilder`];
+//! - [`RewardsEstimator`] projects the rewards of a [`DelegatedStake`] over future epochs.
+//!
+//! [`GovernanceReadApi`]: crate::api::GovernanceReadApiClient
+use af_ptbuilder::{ProgrammableTransactionBuilder, ptbuilder};
+use af_sui_types::{
+    Address as SuiAddress,
+    Argument,
+    EpochId,
+    ObjectArg,
+    ObjectId,
+    SUI_SYSTEM_PACKAGE_ID,
+};
+
+use crate::msgs::{DelegatedStake, StakeStatus, SuiSystemStateSummary, ValidatorApys};
+
+/// Minimum amount of MIST in a stake request, i.e., 1 SUI.
+pub const MIN_STAKE: u64 = 1_000_000_000;
+
+/// Commission rates are in basis points.
+const BPS: u64 = 10_000;
+
+const MS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// Metrics of an active validator relevant to stakers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidatorStats {
+    pub address: SuiAddress,
+    pub name: String,
+    pub staking_pool_id: ObjectId,
+    /// Annual percentage yield for stakers, as a fraction (`0.05` is 5%).
+    ///
+    /// Zero if the node didn't report it.
+    pub apy: f64,
+    /// Commission rate in basis points.
+    pub commission_rate: u64,
+    /// Commission rate in basis points starting next epoch.
+    pub next_epoch_commission_rate: u64,
+    /// SUI in the validator's staking pool.
+    pub stake: u64,
+    /// Fraction of the total stake in the validator's pool.
+    pub stake_share: f64,
+    pub voting_power: u64,
+    /// For how many epochs the validator has been below the low stake threshold, if it is.
+    pub at_risk_epochs: Option<u64>,
+    /// Whether the validator asked to leave the active set.
+    pub pending_removal: bool,
+}
+
+impl ValidatorStats {
+    /// The stats of every active validator in `system_state`.
+    pub fn from_system_state(
+        system_state: &SuiSystemStateSummary,
+        apys: &ValidatorApys,
+    ) -> Vec<Self> {
+        let total_stake = system_state.total_stake;
+        system_state
+            .active_validators
+            .iter()
+            .enumerate()
+            .map(|(i, validator)| {
+                let address = validator.sui_address;
+                let apy = apys
+                    .apys
+                    .iter()
+                    .find(|a| a.address == address)
+                    .map_or(0.0, |a| a.apy);
+                let at_risk_epochs = system_state
+                    .at_risk_validators
+                    .iter()
+                    .find(|(a, _)| *a == address)
+                    .map(|(_, epochs)| *epochs);
+                Self {
+                    address,
+                    name: validator.name.clone(),
+                    staking_pool_id: validator.staking_pool_id,
+                    apy,
+                    commission_rate: validator.commission_rate,
+                    next_epoch_commission_rate: validator.next_epoch_commission_rate,
+                    stake: validator.staking_pool_sui_balance,
+                    stake_share: share(validator.staking_pool_sui_balance, total_stake),
+                    voting_power: validator.voting_power,
+                    at_risk_epochs,
+                    pending_removal: system_state.pending_removals.contains(&(i as u64)),
+                }
+            })
+            .collect()
+    }
+
+    /// The commission rate as a fraction (`0.02` is 2%).
+    pub fn commission(&self) -> f64 {
+        self.commission_rate as f64 / BPS as f64
+    }
+}
+
+fn share(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    part as f64 / total as f64
+}
+
+/// Criteria for choosing validators to stake with.
+///
+/// The default accepts every validator that isn't at risk of removal or leaving the active set.
+#[derive(Clone, Debug, Default)]
+pub struct ValidatorSelection {
+    /// Maximum commission rate, in basis points, this epoch and the next.
+    pub max_commission_rate: Option<u64>,
+    /// Maximum fraction of the total stake, to avoid concentrating stake in few validators.
+    pub max_stake_share: Option<f64>,
+    /// Minimum APY, as a fraction.
+    pub min_apy: Option<f64>,
+    /// Whether to accept validators below the low stake threshold or leaving the active set.
+    pub include_at_risk: bool,
+}
+
+impl ValidatorSelection {
+    pub fn accepts(&self, validator: &ValidatorStats) -> bool {
+        let Self {
+            max_commission_rate,
+            max_stake_share,
+            min_apy,
+            include_at_risk,
+        } = self;
+        let commission_rate = validator
+            .commission_rate
+            .max(validator.next_epoch_commission_rate);
+        if max_commission_rate.is_some_and(|max| commission_rate > max) {
+            return false;
+        }
+        if max_stake_share.is_some_and(|max| validator.stake_share > max) {
+            return false;
+        }
+        if min_apy.is_some_and(|min| validator.apy < min) {
+            return false;
+        }
+        *include_at_risk || (validator.at_risk_epochs.is_none() && !validator.pending_removal)
+    }
+
+    /// The accepted validators, highest APY first.
+    ///
+    /// Ties go to the validator with the smaller share of the total stake.
+    pub fn select<'a>(&self, validators: &'a [ValidatorStats]) -> Vec<&'a ValidatorStats> {
+        let mut selected: Vec<_> = validators.iter().filter(|v| self.accepts(v)).collect();
+        selected.sort_by(|a, b| {
+            b.apy
+                .total_cmp(&a.apy)
+                .then(a.stake_share.total_cmp(&b.stake_share))
+        });
+        selected
+    }
+}
+
+/// Staking calls to the `sui_system` package.
+#[extension_traits::extension(pub trait StakingPtbExt)]
+impl ProgrammableTransactionBuilder {
+    /// Stake `coin`, a `Coin<SUI>` with at least [`MIN_STAKE`], with `validator`.
+    ///
+    /// The `StakedSui` receipt is sent to the transaction sender.
+    fn request_add_stake(
+        &mut self,
+        coin: Argument,
+        validator: SuiAddress,
+    ) -> Result<(), af_ptbuilder::Error> {
+        ptbuilder!(self {
+            package sui_system: SUI_SYSTEM_PACKAGE_ID;
+            input obj system_state: ObjectArg::SYSTEM_STATE_MUT;
+            input pure validator: &validator;
+            sui_system::sui_system::request_add_stake(system_state, coin, validator);
+        });
+        Ok(())
+    }
+
+    /// Stake `amount` of the gas coin with `validator`.
+    fn stake_from_gas(
+        &mut self,
+        amount: u64,
+        validator: SuiAddress,
+    ) -> Result<(), af_ptbuilder::Error> {
+        let amount = self.pure(&amount)?;
+        let coin = self.split_coin(Argument::Gas, amount)?;
+        self.request_add_stake(coin, validator)
+    }
+
+    /// Withdraw the stake in `staked_sui`, the owned object reference of a `StakedSui`.
+    ///
+    /// The principal and its rewards are sent to the transaction sender.
+    fn request_withdraw_stake(&mut self, staked_sui: ObjectArg) -> Result<(), af_ptbuilder::Error> {
+        ptbuilder!(self {
+            package sui_system: SUI_SYSTEM_PACKAGE_ID;
+            input obj system_state: ObjectArg::SYSTEM_STATE_MUT;
+            input obj staked_sui;
+            sui_system::sui_system::request_withdraw_stake(system_state, staked_sui);
+        });
+        Ok(())
+    }
+}
+
+/// Projects staking rewards assuming a constant APY.
+///
+/// Rewards are compounded every epoch, as the staking pools do.
+#[derive(Clone, Copy, Debug)]
+pub struct RewardsEstimator {
+    /// Annual percentage yield, as a fraction.
+    pub apy: f64,
+    pub current_epoch: EpochId,
+    pub epoch_duration_ms: u64,
+}
+
+impl RewardsEstimator {
+    /// Estimator for the current epoch with the APY of one validator.
+    pub const fn from_system_state(system_state: &SuiSystemStateSummary, apy: f64) -> Self {
+        Self {
+            apy,
+            current_epoch: system_state.epoch,
+            epoch_duration_ms: system_state.epoch_duration_ms,
+        }
+    }
+
+    /// The rate at which stakes grow every epoch.
+    pub fn epoch_rate(&self) -> f64 {
+        (1.0 + self.apy).powf(self.epoch_duration_ms as f64 / MS_PER_YEAR) - 1.0
+    }
+
+    /// The rewards of `delegated` after `epochs` more epochs.
+    ///
+    /// Pending stakes only start earning once they are active; unstaked ones are ignored.
+    pub fn estimate(&self, delegated: &DelegatedStake, epochs: u64) -> RewardsEstimate {
+        let growth = 1.0 + self.epoch_rate();
+        let mut estimate = RewardsEstimate {
+            validator_address: delegated.validator_address,
+            principal: 0,
+            rewards: 0,
+            projected_rewards: 0,
+        };
+        for stake in &delegated.stakes {
+            let (rewards, earning_epochs) = match stake.status {
+                StakeStatus::Unstaked => continue,
+                StakeStatus::Active { estimated_reward } => (estimated_reward, epochs),
+                StakeStatus::Pending => {
+                    let wait = stake.stake_active_epoch.saturating_sub(self.current_epoch);
+                    (0, epochs.saturating_sub(wait))
+                }
+            };
+            let value = stake.principal.saturating_add(rewards);
+            let exponent = i32::try_from(earning_epochs).unwrap_or(i32::MAX);
+            let projected = value as f64 * (growth.powi(exponent) - 1.0);
+            estimate.principal = estimate.principal.saturating_add(stake.principal);
+            estimate.rewards = estimate.rewards.saturating_add(rewards);
+            estimate.projected_rewards =
+                estimate.projected_rewards.saturating_add(projected as u64);
+        }
+        estimate
+    }
+}
+
+/// Projected rewards of a [`DelegatedStake`], in MIST.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RewardsEstimate {
+    pub validator_address: SuiAddress,
+    /// Total principal of the pending and active stakes.
+    pub principal: u64,
+    /// Rewards earned so far, as estimated by the node.
+    pub rewards: u64,
+    /// Rewards to be earned over the projected epochs.
+    pub projected_rewards: u64,
+}
+
+impl RewardsEstimate {
+    /// The value of the stakes at the end of the projected epochs.
+    pub const fn total(&self) -> u64 {
+        self.principal
+            .saturating_add(self.rewards)
+            .saturating_add(self.projected_rewards)
+    }
+}
+
+#[cfg(feature = "client")]
+impl crate::client::SuiClient {
+    /// The stats of the active validators, from the latest system state.
+    pub async fn validator_stats(
+        &self,
+    ) -> Result<Vec<ValidatorStats>, crate::error::JsonRpcClientError> {
+        use crate::api::GovernanceReadApiClient as _;
+
+        let system_state = self.http().get_latest_sui_system_state().await?;
+        let apys = self.http().get_validators_apy().await?;
+        Ok(ValidatorStats::from_system_state(&system_state, &apys))
+    }
+
+    /// Project the rewards of `owner`'s stakes over the next `epochs`, using the current APY of
+    /// each validator.
+    pub async fn estimate_stake_rewards(
+        &self,
+        owner: SuiAddress,
+        epochs: u64,
+    ) -> Result<Vec<RewardsEstimate>, crate::error::JsonRpcClientError> {
+        use crate::api::GovernanceReadApiClient as _;
+
+        let system_state = self.http().get_latest_sui_system_state().await?;
+        let apys = self.http().get_validators_apy().await?;
+        let stakes = self.http().get_stakes(owner).await?;
+        Ok(stakes
+            .iter()
+            .map(|delegated| {
+                let apy = apys
+                    .apys
+                    .iter()
+                    .find(|a| a.address == delegated.validator_address)
+                    .map_or(0.0, |a| a.apy);
+                RewardsEstimator::from_system_state(&system_state, apy).estimate(delegated, epochs)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use af_sui_types::Command;
+    use sui_sdk_types::Input;
+
+    use super::*;
+    use crate::msgs::Stake;
+
+    fn validator(n: u8, apy: f64, commission_rate: u64, stake_share: f64) -> ValidatorStats {
+        ValidatorStats {
+            address: SuiAddress::new([n; 32]),
+            name: format!("validator-{n}"),
+            staking_pool_id: ObjectId::new([n; 32]),
+            apy,
+            commission_rate,
+            next_epoch_commission_rate: commission_rate,
+            stake: 0,
+            stake_share,
+            voting_power: 0,
+            at_risk_epochs: None,
+            pending_removal: false,
+        }
+    }
+
+    #[test]
+    fn select_validators() {
+        let mut at_risk = validator(4, 0.09, 0, 0.01);
+        at_risk.at_risk_epochs = Some(2);
+        let validators = [
+            validator(1, 0.03, 200, 0.05),
+            validator(2, 0.04, 1000, 0.05),
+            validator(3, 0.04, 200, 0.02),
+            at_risk,
+            validator(5, 0.05, 200, 0.30),
+        ];
+        let selection = ValidatorSelection {
+            max_commission_rate: Some(500),
+            max_stake_share: Some(0.1),
+            ..Default::default()
+        };
+        let selected: Vec<_> = selection
+            .select(&validators)
+            .into_iter()
+            .map(|v| v.name.as_str())
+            .collect();
+        assert_eq!(selected, ["validator-3", "validator-1"]);
+
+        let selection = ValidatorSelection {
+            include_at_risk: true,
+            ..Default::default()
+        };
+        assert_eq!(selection.select(&validators)[0].name, "validator-4");
+    }
+
+    #[test]
+    fn stake_ptb() {
+        let validator = SuiAddress::new([1; 32]);
+        let staked_sui =
+            ObjectArg::ImmOrOwnedObject((ObjectId::new([2; 32]), 1, Default::default()));
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.stake_from_gas(MIN_STAKE, validator).unwrap();
+        builder.request_withdraw_stake(staked_sui).unwrap();
+        let ptb = builder.finish();
+
+        // System state, amount, validator and staked SUI
+        assert_eq!(ptb.inputs.len(), 4);
+        assert!(matches!(ptb.inputs[0], Input::Pure { .. }));
+        assert!(matches!(ptb.inputs[1], Input::Shared { mutable: true, .. }));
+        let calls: Vec<_> = ptb
+            .commands
+            .iter()
+            .filter_map(|c| match c {
+                Command::MoveCall(call) => Some(call.function.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls, ["request_add_stake", "request_withdraw_stake"]);
+        let Command::MoveCall(call) = &ptb.commands[1] else {
+            panic!("Not a Move call")
+        };
+        assert_eq!(call.package, SUI_SYSTEM_PACKAGE_ID);
+        assert_eq!(call.arguments[1], Argument::NestedResult(0, 0));
+    }
+
+    #[test]
+    fn estimate_rewards() {
+        let estimator = RewardsEstimator {
+            apy: 0.05,
+            current_epoch: 10,
+            // A year per epoch
+            epoch_duration_ms: MS_PER_YEAR as u64,
+        };
+        let stake = |principal, stake_active_epoch, status| Stake {
+            staked_sui_id: ObjectId::ZERO,
+            stake_request_epoch: 0,
+            stake_active_epoch,
+            principal,
+            status,
+        };
+        let delegated = DelegatedStake {
+            validator_address: SuiAddress::new([1; 32]),
+            staking_pool: ObjectId::ZERO,
+            stakes: vec![
+                stake(
+                    1_000,
+                    5,
+                    StakeStatus::Active {
+                        estimated_reward: 100,
+                    },
+                ),
+                stake(1_000, 11, StakeStatus::Pending),
+                stake(5_000, 1, StakeStatus::Unstaked),
+            ],
+        };
+        let estimate = estimator.estimate(&delegated, 2);
+        assert_eq!(estimate.principal, 2_000);
+        assert_eq!(estimate.rewards, 100);
+        // 1100 * (1.05^2 - 1) + 1000 * (1.05 - 1)
+        assert_eq!(estimate.projected_rewards, 112 + 50);
+        assert_eq!(estimate.total(), 2_262);
+    }
+}